@@ -1,6 +1,7 @@
 #![no_main]
 
-use kyte::{Compose, Delta, LastWriteWins, Transform};
+use kyte::testing::assert_tp1;
+use kyte::{Delta, LastWriteWins};
 use libfuzzer_sys::fuzz_target;
 
 fuzz_target!(|data: (
@@ -12,15 +13,5 @@ fuzz_target!(|data: (
     let alice = data.1.into_iter().collect::<Delta<_, _>>();
     let bob = data.2.into_iter().collect::<Delta<_, _>>();
 
-    let alice_bob = before
-        .clone()
-        .compose(alice.clone())
-        .compose(alice.clone().transform(bob.clone(), true));
-
-    let bob_alice = before
-        .clone()
-        .compose(bob.clone())
-        .compose(bob.clone().transform(alice.clone(), false));
-
-    assert_eq!(alice_bob, bob_alice);
+    assert_tp1(&before, &alice, &bob);
 });
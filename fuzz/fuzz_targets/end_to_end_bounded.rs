@@ -0,0 +1,15 @@
+#![no_main]
+
+use kyte::testing::assert_tp1;
+use kyte::{BoundedDelta, LastWriteWins};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: (
+    BoundedDelta::<String, LastWriteWins<u8>>,
+    BoundedDelta::<String, LastWriteWins<u8>>,
+    BoundedDelta::<String, LastWriteWins<u8>>,
+)| {
+    let (BoundedDelta(before), BoundedDelta(alice), BoundedDelta(bob)) = data;
+
+    assert_tp1(&before, &alice, &bob);
+});
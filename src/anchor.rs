@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::mem::take;
+
+use super::{Len, Op};
+
+/// Handle of an anchor that a [`Document`](crate::Document) keeps up to date
+/// (see [`Document::create_anchor`](crate::Document::create_anchor)). Handles
+/// only refer to anchors of the document that created them and its clones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AnchorId(u64);
+
+/// Determines which side of its position an anchor sticks to, i.e. where it
+/// ends up when an element is inserted at that position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bias {
+    /// Sticks to the element before the anchor, so that inserts at its
+    /// position end up after it (e.g. the end of a comment's range).
+    Left,
+
+    /// Sticks to the element after the anchor, so that inserts at its position
+    /// end up before it (e.g. a cursor, or the start of a comment's range).
+    Right,
+}
+
+/// Position of an anchor in a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Anchor {
+    position: usize,
+    bias: Bias,
+}
+
+/// Anchors of a document, together with the anchors whose elements were
+/// deleted since they were last taken.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Anchors {
+    next: u64,
+    anchors: BTreeMap<AnchorId, Anchor>,
+    deleted: Vec<AnchorId>,
+}
+
+impl Anchors {
+    pub(crate) fn create(&mut self, position: usize, bias: Bias) -> AnchorId {
+        let id = AnchorId(self.next);
+        self.next += 1;
+        self.anchors.insert(id, Anchor { position, bias });
+        id
+    }
+
+    pub(crate) fn resolve(&self, id: AnchorId) -> Option<usize> {
+        self.anchors.get(&id).map(|anchor| anchor.position)
+    }
+
+    pub(crate) fn remove(&mut self, id: AnchorId) -> Option<usize> {
+        let anchor = self.anchors.remove(&id)?;
+        self.deleted.retain(|deleted| *deleted != id);
+        Some(anchor.position)
+    }
+
+    pub(crate) fn take_deleted(&mut self) -> Vec<AnchorId> {
+        take(&mut self.deleted)
+    }
+
+    /// Returns a shift that moves these anchors through the ops of a change
+    /// that's about to be applied, which must then be passed back to
+    /// [`Anchors::finish`].
+    pub(crate) fn shift(&self) -> Shift {
+        let mut pending = self
+            .anchors
+            .iter()
+            .map(|(&id, anchor)| Pending {
+                id,
+                bias: anchor.bias,
+                position: anchor.position as i128,
+                deleted: false,
+            })
+            .collect::<Vec<_>>();
+
+        pending.sort_unstable_by_key(|pending| (pending.position, pending.bias));
+
+        Shift {
+            pending,
+            done: 0,
+            old: 0,
+            new: 0,
+        }
+    }
+
+    /// Moves these anchors to the positions of the given (fully stepped)
+    /// shift and records the anchors whose elements it deleted.
+    pub(crate) fn finish(&mut self, mut shift: Shift) {
+        shift.settle();
+
+        for pending in shift.pending {
+            if let Some(anchor) = self.anchors.get_mut(&pending.id) {
+                anchor.position = pending.position as usize;
+            }
+
+            if pending.deleted {
+                self.deleted.push(pending.id);
+            }
+        }
+    }
+
+    /// Moves these anchors through the given ops at once.
+    pub(crate) fn update<'a, T, A>(&mut self, ops: impl IntoIterator<Item = &'a Op<T, A>>)
+    where
+        T: Len + 'a,
+        A: 'a,
+    {
+        if self.anchors.is_empty() {
+            return;
+        }
+
+        let mut shift = self.shift();
+
+        for op in ops {
+            shift.step(op);
+        }
+
+        self.finish(shift);
+    }
+}
+
+/// Anchor (or other position) that's being moved through a change.
+#[derive(Clone, Copy, Debug)]
+struct Pending<I> {
+    id: I,
+    bias: Bias,
+    position: i128,
+    deleted: bool,
+}
+
+/// State of anchors while they're moved through a change one op at a time,
+/// like [`Delta::transform_positions_through`](crate::Delta::transform_positions_through)
+/// but with a priority per anchor that follows from its bias. Positions without
+/// an anchor (e.g. the newlines of a [`LineIndex`](crate::LineIndex)) use
+/// `Shift<()>`.
+#[derive(Clone, Debug)]
+pub(crate) struct Shift<I = AnchorId> {
+    /// Contains the anchors sorted by their position and bias. Anchors before
+    /// `done` have their final positions, the others are still at their
+    /// original positions, which map to the result by adding `new - old`.
+    pending: Vec<Pending<I>>,
+    done: usize,
+
+    /// Contains the offsets that the ops so far span in the original document
+    /// and in the result.
+    old: i128,
+    new: i128,
+}
+
+impl Shift<()> {
+    /// Returns a shift that moves the given sorted positions of elements, which
+    /// stick to their elements like anchors with [`Bias::Right`].
+    pub(crate) fn new(positions: impl IntoIterator<Item = usize>) -> Shift<()> {
+        Shift {
+            pending: positions
+                .into_iter()
+                .map(|position| Pending {
+                    id: (),
+                    bias: Bias::Right,
+                    position: position as i128,
+                    deleted: false,
+                })
+                .collect(),
+            done: 0,
+            old: 0,
+            new: 0,
+        }
+    }
+
+    /// Returns the positions of this (fully stepped) shift in order, except for
+    /// those whose elements it deleted.
+    pub(crate) fn into_retained(mut self) -> impl Iterator<Item = usize> {
+        self.settle();
+
+        self.pending
+            .into_iter()
+            .filter(|pending| !pending.deleted)
+            .map(|pending| pending.position as usize)
+    }
+}
+
+impl<I> Shift<I> {
+    /// Moves the positions that the ops so far haven't reached to the result.
+    fn settle(&mut self) {
+        let offset = self.new - self.old;
+
+        for pending in &mut self.pending[self.done..] {
+            pending.position += offset;
+        }
+
+        self.done = self.pending.len();
+    }
+
+    pub(crate) fn step<T, A>(&mut self, op: &Op<T, A>)
+    where
+        T: Len,
+    {
+        let len = op.len() as i128;
+        let (old, new) = (self.old, self.new);
+        let pending = &mut self.pending[self.done..];
+
+        match op {
+            Op::Insert(_) => {
+                // Deletes move the anchors that they cover to the same position
+                // regardless of their bias, so the anchors at this position are
+                // sorted by their bias again before the left ones are finalized.
+                let count = pending
+                    .iter()
+                    .take_while(|anchor| anchor.position == old)
+                    .count();
+                let at = &mut pending[..count];
+                at.sort_by_key(|anchor| anchor.bias);
+
+                for anchor in at.iter_mut() {
+                    match anchor.bias {
+                        Bias::Left => anchor.position = new,
+                        Bias::Right => break,
+                    }
+
+                    self.done += 1;
+                }
+
+                self.new += len;
+            }
+            Op::Retain(_) => {
+                for anchor in pending.iter_mut() {
+                    match anchor.position < old + len {
+                        true => anchor.position += new - old,
+                        false => break,
+                    }
+
+                    self.done += 1;
+                }
+
+                self.old += len;
+                self.new += len;
+            }
+            Op::Delete(_) => {
+                for anchor in pending.iter_mut() {
+                    if anchor.position > old + len {
+                        break;
+                    }
+
+                    anchor.deleted |= match anchor.bias {
+                        Bias::Left => anchor.position > old,
+                        Bias::Right => anchor.position < old + len,
+                    };
+                    anchor.position = old + len;
+                }
+
+                self.old += len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use serde_json::json;
+
+    use super::Bias;
+    use crate::{ArbitraryDocument, Delta, Document, LastWriteWins, Len, Op, Transform};
+
+    #[test]
+    fn test_anchors() {
+        let mut document =
+            Document::from_delta(Delta::new().insert("Hello World\n".to_owned(), ())).unwrap();
+
+        let start = document.create_anchor(6, Bias::Right);
+        let end = document.create_anchor(11, Bias::Left);
+        let cursor = document.create_anchor(11, Bias::Right);
+        let clamped = document.create_anchor(42, Bias::Right);
+
+        document.apply(Delta::new().retain(11, ()).insert("!".to_owned(), ()));
+        document.apply(Delta::new().retain(6, ()).insert("big ".to_owned(), ()));
+
+        assert_eq!(document.resolve(start), Some(10));
+        assert_eq!(document.resolve(end), Some(15));
+        assert_eq!(document.resolve(cursor), Some(16));
+        assert_eq!(document.resolve(clamped), Some(17));
+        assert_eq!(document.take_deleted_anchors(), vec![]);
+
+        document.apply(Delta::new().retain(5, ()).delete(11));
+
+        assert_eq!(document.resolve(start), Some(5));
+        assert_eq!(document.resolve(end), Some(5));
+        assert_eq!(document.resolve(cursor), Some(5));
+        assert_eq!(document.take_deleted_anchors(), vec![start, end]);
+        assert_eq!(document.take_deleted_anchors(), vec![]);
+
+        document.apply(Delta::new().delete(5));
+        assert_eq!(document.remove_anchor(cursor), Some(0));
+        assert_eq!(document.take_deleted_anchors(), vec![end]);
+        assert_eq!(document.resolve(cursor), None);
+        assert_eq!(document.remove_anchor(cursor), None);
+    }
+
+    #[test]
+    fn test_apply_from() {
+        let mut document =
+            Document::from_delta(Delta::new().insert("Hello World\n".to_owned(), ())).unwrap();
+
+        let cursor = document.create_anchor(11, Bias::Right);
+        let word = document.create_anchor(7, Bias::Left);
+
+        let document = document
+            .apply_from(json!([{ "retain": 6 }, { "delete": 5 }, { "insert": "Kyte" }]))
+            .unwrap();
+
+        assert_eq!(document.resolve(cursor), Some(10));
+        assert_eq!(document.resolve(word), Some(6));
+    }
+
+    #[test]
+    fn test_anchors_arbitrary() {
+        let bytes = (0..1 << 16)
+            .map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&bytes);
+
+        while !u.is_empty() {
+            let arbitrary =
+                ArbitraryDocument::<String, LastWriteWins<u8>>::arbitrary(&mut u).unwrap();
+            let change = arbitrary.edit().arbitrary(&mut u).unwrap();
+            let ArbitraryDocument(mut document) = arbitrary;
+            let len = document.len();
+
+            let mut deleted = vec![false; len];
+            let mut offset = 0;
+
+            for op in change.ops() {
+                match op {
+                    Op::Insert(_) => continue,
+                    Op::Retain(_) => {}
+                    Op::Delete(_) => deleted[offset..offset + op.len()].fill(true),
+                }
+
+                offset += op.len();
+            }
+
+            let anchors = (0..=len)
+                .flat_map(|position| [(position, Bias::Left), (position, Bias::Right)])
+                .map(|(position, bias)| (document.create_anchor(position, bias), position, bias))
+                .collect::<Vec<_>>();
+
+            document.apply(change.clone());
+
+            let mut expected = vec![];
+
+            for &(anchor, position, bias) in &anchors {
+                let element = match bias {
+                    Bias::Left => position.checked_sub(1),
+                    Bias::Right => Some(position).filter(|&position| position < len),
+                };
+
+                if element.is_some_and(|element| deleted[element]) {
+                    expected.push(anchor);
+                }
+
+                assert_eq!(
+                    document.resolve(anchor),
+                    Some((&change).transform(position, bias == Bias::Left))
+                );
+            }
+
+            let mut actual = document.take_deleted_anchors();
+            actual.sort();
+
+            assert_eq!(actual, expected);
+        }
+    }
+}
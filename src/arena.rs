@@ -0,0 +1,267 @@
+//! Deltas whose operations are allocated in a bump arena.
+//!
+//! This module is only available with the `bumpalo` feature enabled. An
+//! [`ArenaDelta`] stores its operations in a [`Bump`] arena instead of on the
+//! heap, and [`Delta::compose_in`], [`Delta::transform_in`] and the
+//! corresponding methods of [`ArenaDelta`] allocate their results from the
+//! given arena. A server that processes a batch of changes can compose and
+//! transform them in an arena, convert the results that it keeps with
+//! [`ArenaDelta::to_delta`] and then reset the arena, instead of allocating
+//! (and freeing) a buffer in the global allocator for every intermediate
+//! delta. Note that the values of inserts (e.g. strings) are still allocated
+//! by their own types.
+
+use std::fmt::Debug;
+
+use bumpalo::collections::vec::IntoIter;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+
+use super::compose::compose_ops;
+use super::delta::{push_op, OpVec};
+use super::ops::Retain;
+use super::transform::transform_ops;
+use super::{Compose, Cursor, Delta, Op, OrderPolicy, OverflowPolicy, Seq};
+
+/// Series of insert, retain and delete operations that are allocated in a
+/// bump arena. Like [`Delta`], an arena delta that's built with
+/// [`ArenaDelta::push`] is always in canonical form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArenaDelta<'bump, T, A> {
+    ops: Vec<'bump, Op<T, A>>,
+}
+
+impl<'bump, T, A> ArenaDelta<'bump, T, A> {
+    /// Returns a new empty delta that allocates its operations in the given
+    /// arena.
+    pub fn new_in(bump: &'bump Bump) -> ArenaDelta<'bump, T, A> {
+        ArenaDelta {
+            ops: Vec::new_in(bump),
+        }
+    }
+
+    /// Returns the operations of this delta.
+    pub fn ops(&self) -> &[Op<T, A>] {
+        &self.ops
+    }
+
+    /// Returns the arena that this delta allocates its operations in.
+    pub fn bump(&self) -> &'bump Bump {
+        self.ops.bump()
+    }
+
+    /// Copies the operations of this delta into a [`Delta`] that's allocated
+    /// on the heap, so that it outlives the arena.
+    pub fn to_delta(&self) -> Delta<T, A>
+    where
+        T: Clone,
+        A: Clone,
+    {
+        Delta::from_ops(self.ops.to_vec())
+    }
+}
+
+impl<'bump, T, A> ArenaDelta<'bump, T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Clone + PartialEq,
+{
+    /// Moves the operations of the given delta into a new delta that's
+    /// allocated in the given arena.
+    pub fn from_delta_in(delta: Delta<T, A>, bump: &'bump Bump) -> ArenaDelta<'bump, T, A>
+    where
+        A: Default,
+    {
+        let mut result = ArenaDelta::new_in(bump);
+        result.extend(delta);
+        result
+    }
+
+    /// Appends the given operation to this series like [`Delta::push`].
+    pub fn push(&mut self, op: Op<T, A>) {
+        push_op(
+            &mut self.ops,
+            op,
+            OrderPolicy::InsertFirst,
+            OverflowPolicy::Split,
+        )
+    }
+
+    fn chop_in_place(&mut self) {
+        while let Some(Op::Retain(Retain {
+            attributes: None, ..
+        })) = self.ops.last()
+        {
+            self.ops.pop();
+        }
+    }
+}
+
+impl<'bump, T, A> ArenaDelta<'bump, T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Composes the given delta with this delta like [`Compose`] and allocates
+    /// the result in the arena of this delta.
+    pub fn compose(self, rhs: ArenaDelta<'bump, T, A>) -> ArenaDelta<'bump, T, A> {
+        let mut result = ArenaDelta::new_in(self.bump());
+        result
+            .ops
+            .reserve(self.ops.len().saturating_add(rhs.ops.len()));
+        compose_ops(self.into_iter(), rhs.into_iter(), &mut result);
+        result.chop_in_place();
+        result
+    }
+
+    /// Transforms the given delta with this delta like
+    /// [`Transform`](crate::Transform) and allocates the result in the arena of
+    /// this delta.
+    pub fn transform(
+        self,
+        rhs: ArenaDelta<'bump, T, A>,
+        priority: bool,
+    ) -> ArenaDelta<'bump, T, A> {
+        let mut result = ArenaDelta::new_in(self.bump());
+        transform_ops(self.into_iter(), rhs.into_iter(), priority, &mut result);
+        result.chop_in_place();
+        result
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Composes the given delta with this delta like [`Compose`], but
+    /// allocates the result in the given arena.
+    pub fn compose_in<'bump>(self, rhs: Delta<T, A>, bump: &'bump Bump) -> ArenaDelta<'bump, T, A> {
+        let mut result = ArenaDelta::new_in(bump);
+        result
+            .ops
+            .reserve(self.ops_len().saturating_add(rhs.ops_len()));
+        compose_ops(self.into_iter(), rhs.into_iter(), &mut result);
+        result.chop_in_place();
+        result
+    }
+
+    /// Transforms the given delta with this delta like
+    /// [`Transform`](crate::Transform), but allocates the result in the given
+    /// arena.
+    pub fn transform_in<'bump>(
+        self,
+        rhs: Delta<T, A>,
+        priority: bool,
+        bump: &'bump Bump,
+    ) -> ArenaDelta<'bump, T, A> {
+        let mut result = ArenaDelta::new_in(bump);
+        transform_ops(self.into_iter(), rhs.into_iter(), priority, &mut result);
+        result.chop_in_place();
+        result
+    }
+}
+
+impl<'bump, T, A> OpVec<T, A> for Vec<'bump, Op<T, A>> {
+    fn last_mut(&mut self) -> Option<&mut Op<T, A>> {
+        <[Op<T, A>]>::last_mut(self)
+    }
+
+    fn push(&mut self, op: Op<T, A>) {
+        Vec::push(self, op)
+    }
+
+    fn pop(&mut self) -> Option<Op<T, A>> {
+        Vec::pop(self)
+    }
+}
+
+impl<'bump, T, A> Extend<Op<T, A>> for ArenaDelta<'bump, T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Clone + PartialEq,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = Op<T, A>>,
+    {
+        let iter = iter.into_iter();
+        self.ops.reserve(iter.size_hint().0);
+        iter.for_each(|op| self.push(op))
+    }
+}
+
+impl<'bump, T, A> IntoIterator for ArenaDelta<'bump, T, A>
+where
+    T: Clone + Default + Seq,
+    A: Clone + Default,
+{
+    type Item = Op<T, A>;
+
+    type IntoIter = Cursor<Op<T, A>, IntoIter<'bump, Op<T, A>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Cursor::new(self.ops.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::ArenaDelta;
+    use crate::{Compose, Delta, Transform};
+
+    #[test]
+    fn test_compose_in() {
+        let bump = Bump::new();
+        let a = Delta::new().insert("Hello".to_owned(), ());
+        let b = Delta::new().retain(5, None).insert(" World".to_owned(), ());
+
+        assert_eq!(
+            a.clone().compose_in(b.clone(), &bump).to_delta(),
+            a.compose(b)
+        );
+    }
+
+    #[test]
+    fn test_transform_in() {
+        let bump = Bump::new();
+        let a = Delta::new().retain(5, None).insert(",".to_owned(), ());
+        let b = Delta::new().retain(11, None).insert("!".to_owned(), ());
+
+        assert_eq!(
+            a.clone().transform_in(b.clone(), true, &bump).to_delta(),
+            a.transform(b, true)
+        );
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut bump = Bump::new();
+        let changes = [
+            Delta::new().insert("a".to_owned(), ()),
+            Delta::new().retain(1, None).insert("b".to_owned(), ()),
+            Delta::new()
+                .delete(1)
+                .retain(1, None)
+                .insert("c".to_owned(), ()),
+        ];
+
+        let composed = changes
+            .iter()
+            .map(|change| ArenaDelta::from_delta_in(change.clone(), &bump))
+            .reduce(ArenaDelta::compose)
+            .unwrap()
+            .to_delta();
+
+        assert_eq!(composed, Delta::new().insert("bc".to_owned(), ()));
+        assert_eq!(
+            composed,
+            changes.into_iter().reduce(Delta::compose).unwrap()
+        );
+
+        bump.reset();
+        assert!(ArenaDelta::<String, ()>::new_in(&bump).ops().is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+//! Dynamic attribute values for applications that don't want to define their
+//! own attribute types.
+
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+
+use super::validate::{AttributeKeys, AttributeValues, ValueRef};
+use super::{Compose, Diff, Hashable, HeapSize};
+
+/// Attributes that map formats to dynamic values. These compose key-wise like
+/// Quill's attributes: composing two attribute maps keeps all formats of the
+/// first map unless they're overwritten by the second map. A
+/// [`AttrValue::Null`] value removes the corresponding format.
+pub type Attributes = BTreeMap<String, AttrValue>;
+
+/// Dynamically typed attribute value that (de)serializes to and from the
+/// corresponding JSON values.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum AttrValue {
+    /// Represents the absence of a value, which removes a format.
+    #[default]
+    Null,
+
+    /// Represents a boolean value (e.g. `bold: true`).
+    Bool(bool),
+
+    /// Represents an integer value (e.g. `header: 1`).
+    Int(i64),
+
+    /// Represents a floating point value.
+    Float(f64),
+
+    /// Represents a string value (e.g. `color: "#ff0000"`).
+    String(String),
+
+    /// Represents a nested map of values. Like Quill, nested maps are not
+    /// merged when composed, but replaced as a whole.
+    Map(BTreeMap<String, AttrValue>),
+}
+
+impl AttrValue {
+    /// Returns true if this value is [`AttrValue::Null`].
+    pub fn is_null(&self) -> bool {
+        matches!(self, AttrValue::Null)
+    }
+}
+
+impl From<bool> for AttrValue {
+    fn from(value: bool) -> Self {
+        AttrValue::Bool(value)
+    }
+}
+
+impl From<i64> for AttrValue {
+    fn from(value: i64) -> Self {
+        AttrValue::Int(value)
+    }
+}
+
+impl From<f64> for AttrValue {
+    fn from(value: f64) -> Self {
+        AttrValue::Float(value)
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(value: String) -> Self {
+        AttrValue::String(value)
+    }
+}
+
+impl From<&str> for AttrValue {
+    fn from(value: &str) -> Self {
+        AttrValue::String(value.to_owned())
+    }
+}
+
+impl From<BTreeMap<String, AttrValue>> for AttrValue {
+    fn from(value: BTreeMap<String, AttrValue>) -> Self {
+        AttrValue::Map(value)
+    }
+}
+
+/// Replaces the value with the given value.
+impl Compose<AttrValue> for AttrValue {
+    type Output = AttrValue;
+
+    fn compose(self, rhs: AttrValue) -> Self::Output {
+        rhs
+    }
+}
+
+/// Merges the attributes key-wise, where the given attributes take precedence.
+/// [`AttrValue::Null`] values are kept so that they continue to remove formats
+/// when the composed attributes are applied.
+impl Compose<Attributes> for Attributes {
+    type Output = Attributes;
+
+    fn compose(mut self, rhs: Attributes) -> Self::Output {
+        self.extend(rhs);
+        self
+    }
+}
+
+/// Returns the formats whose values differ, where formats that are only set in
+/// these attributes are removed with [`AttrValue::Null`].
+impl Diff for Attributes {
+    fn diff(&self, other: &Self) -> Option<Self> {
+        let mut attributes = Attributes::new();
+
+        for (key, value) in self {
+            if other.get(key) != Some(value) {
+                attributes.insert(
+                    key.clone(),
+                    other.get(key).cloned().unwrap_or(AttrValue::Null),
+                );
+            }
+        }
+
+        for (key, value) in other {
+            if !self.contains_key(key) {
+                attributes.insert(key.clone(), value.clone());
+            }
+        }
+
+        Some(attributes).filter(|attributes| !attributes.is_empty())
+    }
+}
+
+impl AttributeKeys for Attributes {
+    fn keys(&self) -> Vec<&str> {
+        BTreeMap::keys(self).map(String::as_str).collect()
+    }
+
+    fn remove_key(&mut self, key: &str) {
+        self.remove(key);
+    }
+}
+
+impl AttributeValues for Attributes {
+    fn value(&self, key: &str) -> Option<ValueRef<'_>> {
+        self.get(key).map(|value| match value {
+            AttrValue::Null => ValueRef::Null,
+            AttrValue::Bool(value) => ValueRef::Bool(*value),
+            AttrValue::Int(value) => ValueRef::Int(*value),
+            AttrValue::Float(value) => ValueRef::Float(*value),
+            AttrValue::String(value) => ValueRef::String(value),
+            AttrValue::Map(_) => ValueRef::Map,
+        })
+    }
+}
+
+/// Hashes the variant of this value followed by its contents, so that e.g.
+/// `Int(1)` and `Float(1.0)` hash differently.
+impl Hashable for AttrValue {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            AttrValue::Null => state.write_u8(0),
+            AttrValue::Bool(value) => {
+                state.write_u8(1);
+                value.hash_content(state);
+            }
+            AttrValue::Int(value) => {
+                state.write_u8(2);
+                value.hash_content(state);
+            }
+            AttrValue::Float(value) => {
+                state.write_u8(3);
+                value.hash_content(state);
+            }
+            AttrValue::String(value) => {
+                state.write_u8(4);
+                value.hash_content(state);
+            }
+            AttrValue::Map(value) => {
+                state.write_u8(5);
+                value.hash_content(state);
+            }
+        }
+    }
+}
+
+impl HeapSize for AttrValue {
+    fn heap_size(&self) -> usize {
+        match self {
+            AttrValue::Null | AttrValue::Bool(_) | AttrValue::Int(_) | AttrValue::Float(_) => 0,
+            AttrValue::String(value) => value.heap_size(),
+            AttrValue::Map(value) => value.heap_size(),
+        }
+    }
+}
+
+/// Returns attributes with a single format, for use in tests.
+#[cfg(test)]
+pub(crate) fn attributes(key: &str, value: impl Into<AttrValue>) -> Attributes {
+    [(key.to_owned(), value.into())].into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{AttrValue, Attributes};
+    use crate::{Compose, Diff};
+
+    #[test]
+    fn test_serde() {
+        let attributes: Attributes = serde_json::from_value(json!({
+            "bold": true,
+            "header": 1,
+            "size": 1.5,
+            "color": "red",
+            "font": null,
+            "image": { "width": 100 },
+        }))
+        .unwrap();
+
+        assert_eq!(attributes["bold"], AttrValue::Bool(true));
+        assert_eq!(attributes["header"], AttrValue::Int(1));
+        assert_eq!(attributes["size"], AttrValue::Float(1.5));
+        assert_eq!(attributes["color"], AttrValue::from("red"));
+        assert_eq!(attributes["font"], AttrValue::Null);
+        assert_eq!(
+            attributes["image"],
+            AttrValue::Map([("width".to_owned(), AttrValue::Int(100))].into())
+        );
+
+        assert_eq!(
+            serde_json::to_value(&attributes).unwrap(),
+            json!({
+                "bold": true,
+                "color": "red",
+                "font": null,
+                "header": 1,
+                "image": { "width": 100 },
+                "size": 1.5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs = Attributes::from([
+            ("bold".to_owned(), true.into()),
+            ("color".to_owned(), "red".into()),
+        ]);
+        let rhs = Attributes::from([
+            ("color".to_owned(), AttrValue::Null),
+            ("italic".to_owned(), true.into()),
+        ]);
+
+        assert_eq!(
+            lhs.compose(rhs),
+            Attributes::from([
+                ("bold".to_owned(), true.into()),
+                ("color".to_owned(), AttrValue::Null),
+                ("italic".to_owned(), true.into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let lhs: Attributes = [("bold", true), ("italic", true)]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.into()))
+            .collect();
+        let rhs: Attributes = [("bold", true), ("underline", true)]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.into()))
+            .collect();
+
+        assert_eq!(
+            lhs.diff(&rhs),
+            Some(Attributes::from([
+                ("italic".to_owned(), AttrValue::Null),
+                ("underline".to_owned(), AttrValue::Bool(true)),
+            ]))
+        );
+        assert_eq!(lhs.diff(&lhs), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Serde representation of deltas as a bare sequence of operations.
+//!
+//! By default, a delta serializes as an object with an `ops` field, like
+//! Quill's `Delta` class. A lot of Quill tooling stores the operations on their
+//! own instead (e.g. `JSON.stringify(delta.ops)`):
+//!
+//! ```json
+//! [{ "insert": "Hello" }, { "retain": 5, "attributes": { "bold": true } }]
+//! ```
+//!
+//! Use [`Bare`] to serialize a delta on its own in this form, or this module
+//! with `#[serde(with = "kyte::bare")]` for a delta that's part of another
+//! type. Either way, deltas still deserialize from both forms, so stored
+//! deltas can be migrated from one form to the other.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Delta, Len};
+
+/// Wrapper that serializes a delta as a bare sequence of operations and
+/// deserializes it from either form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bare<T, A>(pub Delta<T, A>);
+
+impl<T, A> Serialize for Bare<T, A>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T, A> Deserialize<'de> for Bare<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Bare)
+    }
+}
+
+impl<T, A> From<Delta<T, A>> for Bare<T, A> {
+    fn from(delta: Delta<T, A>) -> Self {
+        Bare(delta)
+    }
+}
+
+/// Serializes the given delta as a bare sequence of operations.
+pub fn serialize<T, A, S>(delta: &Delta<T, A>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    A: Serialize,
+    S: Serializer,
+{
+    serializer.collect_seq(delta.ops())
+}
+
+/// Deserializes a delta from either a bare sequence of operations or an object
+/// with an `ops` field, like the default implementation of [`Deserialize`].
+pub fn deserialize<'de, T, A, D>(deserializer: D) -> Result<Delta<T, A>, D::Error>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Delta::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::Delta;
+
+    use super::Bare;
+
+    fn delta() -> Delta<String, Attributes> {
+        Delta::new().insert("Hello".to_owned(), None).retain(
+            5,
+            Attributes::from([("bold".to_owned(), AttrValue::Bool(true))]),
+        )
+    }
+
+    #[test]
+    fn test_bare() {
+        let bare = json!([{ "insert": "Hello" }, { "retain": 5, "attributes": { "bold": true } }]);
+
+        assert_eq!(serde_json::to_value(Bare(delta())).unwrap(), bare);
+        assert_eq!(
+            serde_json::from_value::<Bare<_, _>>(bare.clone()).unwrap(),
+            Bare(delta())
+        );
+        assert_eq!(
+            serde_json::from_value::<Bare<_, _>>(json!({ "ops": bare })).unwrap(),
+            Bare(delta())
+        );
+    }
+
+    #[test]
+    fn test_with() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Change {
+            version: usize,
+            #[serde(with = "crate::bare")]
+            delta: Delta<String, Attributes>,
+        }
+
+        let change = Change {
+            version: 42,
+            delta: delta(),
+        };
+        let json = serde_json::to_value(&change).unwrap();
+
+        assert!(json["delta"].is_array());
+        assert_eq!(serde_json::from_value::<Change>(json).unwrap(), change);
+        assert_eq!(
+            serde_json::from_value::<Change>(json!({
+                "version": 42,
+                "delta": serde_json::to_value(delta()).unwrap(),
+            }))
+            .unwrap(),
+            change
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Conversions between deltas and [CBOR](https://cbor.io).
+//!
+//! This module is only available with the `ciborium` feature enabled. Deltas
+//! are encoded with the same structure as their JSON representation (i.e. maps
+//! with `insert`, `retain`, `delete` and `attributes` fields), so that they can
+//! be stored and decoded by any CBOR implementation without an intermediate
+//! representation.
+
+use std::io;
+
+use ciborium::{de, ser};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{Delta, Len};
+
+/// Encodes the given delta as CBOR.
+pub fn to_vec<T, A>(delta: &Delta<T, A>) -> Result<Vec<u8>, ser::Error<io::Error>>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    let mut bytes = Vec::new();
+    ciborium::into_writer(delta, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decodes a delta from the given CBOR, which may use any of the structures
+/// that a delta deserializes from.
+pub fn from_slice<T, A>(bytes: &[u8]) -> Result<Delta<T, A>, de::Error<io::Error>>
+where
+    T: Len + DeserializeOwned,
+    A: DeserializeOwned,
+{
+    ciborium::from_reader(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use crate::attributes::Attributes;
+    use crate::{Delta, Len};
+
+    use super::{from_slice, to_vec};
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Text(String),
+        Embed(Attributes),
+    }
+
+    impl Len for Value {
+        fn len(&self) -> usize {
+            match self {
+                Value::Text(text) => text.len(),
+                Value::Embed(_) => 1,
+            }
+        }
+    }
+
+    fn attributes() -> Attributes {
+        serde_json::from_value(json!({
+            "bold": true,
+            "header": 1,
+            "size": 1.5,
+            "font": null,
+            "link": { "href": "https://example.com", "target": -1 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let delta = Delta::new()
+            .retain(3, None)
+            .delete(3)
+            .retain(2, attributes())
+            .insert("Hello".to_owned(), attributes())
+            .insert(" World".to_owned(), None);
+
+        let bytes = to_vec(&delta).unwrap();
+
+        assert_eq!(from_slice::<String, Attributes>(&bytes).unwrap(), delta);
+        assert_eq!(
+            ciborium::from_reader::<serde_json::Value, _>(bytes.as_slice()).unwrap(),
+            serde_json::to_value(&delta).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_embeds() {
+        let delta: Delta<Value, Attributes> = serde_json::from_value(json!([
+            { "insert": "Hello" },
+            { "insert": { "image": "cat.png" }, "attributes": { "width": 100 } },
+            { "retain": 1, "attributes": attributes() },
+            { "delete": 1 },
+        ]))
+        .unwrap();
+
+        let bytes = to_vec(&delta).unwrap();
+
+        assert_eq!(from_slice::<Value, Attributes>(&bytes).unwrap(), delta);
+    }
+
+    #[test]
+    fn test_from_slice_invalid() {
+        let bytes = to_vec(&Delta::<String, ()>::new().retain(3, None)).unwrap();
+
+        assert!(from_slice::<String, ()>(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,891 @@
+use std::collections::HashMap;
+
+use super::attributes::{AttrValue, Attributes};
+use super::de::DeltaRepr;
+use super::ops::{Delete, Insert, Retain};
+use super::{Compose, Delta, Error, Len, Op};
+
+/// Version of the compact encoding that [`Delta::encode_compact`] produces.
+const VERSION: u8 = 1;
+
+/// Flag that is set if the attributes are stored in a dictionary.
+const DICTIONARY: u8 = 1;
+
+const INSERT: u8 = 0;
+const RETAIN: u8 = 1;
+const DELETE: u8 = 2;
+const ATTRIBUTES: u8 = 4;
+
+/// Flag of an op in a batch that has the same attributes as the previous op
+/// with attributes in its delta.
+const SAME_ATTRIBUTES: u8 = 8;
+
+/// Flag that is set if the body of a batch is compressed with zstd.
+const ZSTD: u8 = 1;
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const INT: u8 = 3;
+const FLOAT: u8 = 4;
+const STRING: u8 = 5;
+const MAP: u8 = 6;
+
+impl Delta<String, Attributes> {
+    /// Encodes this delta into a compact binary format that's meant for
+    /// high-frequency traffic (e.g. a delta per keystroke), where it's usually
+    /// several times smaller than JSON. Use [`Delta::decode_compact`] to decode
+    /// it.
+    ///
+    /// The encoding starts with a version byte and a flags byte, followed by
+    /// each op as a tag byte and its LEB128-encoded length (or the UTF-8 length
+    /// and bytes of an insert). Attributes are encoded inline, unless the same
+    /// attributes occur often enough that storing them once in a dictionary
+    /// after the flags byte and referring to them by index is smaller.
+    pub fn encode_compact(&self) -> Vec<u8> {
+        let mut dictionary = Dictionary::default();
+        let attributes =
+            self.ops()
+                .filter_map(|op| match op {
+                    Op::Insert(Insert { attributes, .. })
+                    | Op::Retain(Retain { attributes, .. }) => attributes.as_ref(),
+                    Op::Delete(_) => None,
+                })
+                .map(|attributes| dictionary.index(attributes))
+                .collect::<Vec<_>>();
+
+        let inline_len = attributes
+            .iter()
+            .map(|&index| dictionary.entries[index].len())
+            .sum::<usize>();
+        let dictionary_len = dictionary.len()
+            + attributes
+                .iter()
+                .map(|&index| varint_len(index as u64))
+                .sum::<usize>();
+        let use_dictionary = dictionary_len < inline_len;
+        let mut bytes = vec![VERSION];
+
+        if use_dictionary {
+            bytes.push(DICTIONARY);
+            dictionary.write(&mut bytes);
+        } else {
+            bytes.push(0);
+        }
+
+        let mut indices = attributes.into_iter();
+
+        for op in self.ops() {
+            let (tag, op_attributes) = match op {
+                Op::Insert(insert) => (INSERT, insert.attributes.as_ref()),
+                Op::Retain(retain) => (RETAIN, retain.attributes.as_ref()),
+                Op::Delete(_) => (DELETE, None),
+            };
+
+            match op_attributes {
+                Some(_) => bytes.push(tag | ATTRIBUTES),
+                None => bytes.push(tag),
+            }
+
+            match op {
+                Op::Insert(insert) => write_str(&mut bytes, insert.insert()),
+                Op::Retain(retain) => write_varint(&mut bytes, retain.retain as u64),
+                Op::Delete(delete) => write_varint(&mut bytes, delete.delete as u64),
+            }
+
+            match (op_attributes, op_attributes.and_then(|_| indices.next())) {
+                (Some(_), Some(index)) if use_dictionary => write_varint(&mut bytes, index as u64),
+                (Some(op_attributes), _) => write_attributes(&mut bytes, op_attributes),
+                (None, _) => {}
+            }
+        }
+
+        bytes
+    }
+
+    /// Decodes a delta that was encoded with [`Delta::encode_compact`]. The ops
+    /// are returned as encoded (i.e. they're not normalized). Returns
+    /// [`Error::InvalidEncoding`] if the given bytes are malformed or were
+    /// encoded with an unsupported version.
+    pub fn decode_compact(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.byte()? != VERSION {
+            return Err(Error::InvalidEncoding { offset: 0 });
+        }
+
+        let dictionary = match reader.byte()? {
+            0 => None,
+            DICTIONARY => Some(
+                (0..reader.varint()?)
+                    .map(|_| reader.attributes())
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            _ => return Err(Error::InvalidEncoding { offset: 1 }),
+        };
+
+        let mut ops = vec![];
+
+        while !reader.is_empty() {
+            let offset = reader.offset;
+            let tag = reader.byte()?;
+
+            ops.push(match tag & !ATTRIBUTES {
+                INSERT => Op::Insert(Insert::new(
+                    reader.string()?,
+                    reader.op_attributes(tag, dictionary.as_deref())?,
+                )),
+                RETAIN => Op::Retain(Retain {
+                    retain: reader.len()?,
+                    attributes: reader.op_attributes(tag, dictionary.as_deref())?,
+                }),
+                DELETE if tag & ATTRIBUTES == 0 => Op::Delete(Delete {
+                    delete: reader.len()?,
+                }),
+                _ => return Err(Error::InvalidEncoding { offset }),
+            });
+        }
+
+        Ok(DeltaRepr::Seq(ops).into())
+    }
+
+    /// Encodes the given batch of deltas (e.g. a burst of keystrokes that's
+    /// broadcast to many clients) into a single message. Like
+    /// [`Delta::encode_compact`], but the attributes of all deltas are stored
+    /// once in a shared dictionary, an op that has the same attributes as the
+    /// previous op with attributes refers to them with a flag instead of an
+    /// index, and each delta only stores the bytes after the prefix it has in
+    /// common with the previous delta. With the `zstd` feature enabled, the
+    /// result is also compressed with zstd if that makes it smaller. Use
+    /// [`Delta::decompress_batch`] to decode it.
+    pub fn compress_batch(deltas: &[Self]) -> Vec<u8> {
+        let mut dictionary = Dictionary::default();
+        let encoded = deltas
+            .iter()
+            .map(|delta| {
+                let mut bytes = vec![];
+                let mut previous = None;
+
+                for op in delta.ops() {
+                    let (tag, attributes) = match op {
+                        Op::Insert(insert) => (INSERT, insert.attributes.as_ref()),
+                        Op::Retain(retain) => (RETAIN, retain.attributes.as_ref()),
+                        Op::Delete(_) => (DELETE, None),
+                    };
+                    let index = attributes.map(|attributes| dictionary.index(attributes));
+
+                    match index {
+                        Some(index) if previous == Some(index) => {
+                            bytes.push(tag | ATTRIBUTES | SAME_ATTRIBUTES)
+                        }
+                        Some(_) => bytes.push(tag | ATTRIBUTES),
+                        None => bytes.push(tag),
+                    }
+
+                    match op {
+                        Op::Insert(insert) => write_str(&mut bytes, insert.insert()),
+                        Op::Retain(retain) => write_varint(&mut bytes, retain.retain as u64),
+                        Op::Delete(delete) => write_varint(&mut bytes, delete.delete as u64),
+                    }
+
+                    if let Some(index) = index {
+                        if previous != Some(index) {
+                            write_varint(&mut bytes, index as u64);
+                        }
+
+                        previous = Some(index);
+                    }
+                }
+
+                bytes
+            })
+            .collect::<Vec<_>>();
+
+        let mut body = vec![];
+        dictionary.write(&mut body);
+        write_varint(&mut body, encoded.len() as u64);
+
+        let mut last: &[u8] = &[];
+
+        for bytes in &encoded {
+            let shared = last.iter().zip(bytes).take_while(|(a, b)| a == b).count();
+
+            write_varint(&mut body, shared as u64);
+            write_varint(&mut body, (bytes.len() - shared) as u64);
+            body.extend(&bytes[shared..]);
+            last = bytes;
+        }
+
+        #[cfg(feature = "zstd")]
+        if let Ok(compressed) = zstd::bulk::compress(&body, 0) {
+            if compressed.len() < body.len() {
+                return [&[VERSION, ZSTD][..], &compressed].concat();
+            }
+        }
+
+        [&[VERSION, 0][..], &body].concat()
+    }
+
+    /// Decodes a batch of deltas that was encoded with
+    /// [`Delta::compress_batch`]. Returns [`Error::InvalidEncoding`] if the
+    /// given bytes are malformed, were encoded with an unsupported version or
+    /// are compressed with zstd while the `zstd` feature is disabled. If the
+    /// batch is compressed, the offset of that error refers to the decompressed
+    /// batch (after the version and flags bytes).
+    pub fn decompress_batch(bytes: &[u8]) -> Result<Vec<Self>, Error> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.byte()? != VERSION {
+            return Err(Error::InvalidEncoding { offset: 0 });
+        }
+
+        let decompressed: Vec<u8>;
+        let mut reader = match reader.byte()? {
+            0 => reader,
+            ZSTD => {
+                decompressed = decompress(&bytes[2..])?;
+
+                Reader {
+                    bytes: &decompressed,
+                    offset: 0,
+                }
+            }
+            _ => return Err(Error::InvalidEncoding { offset: 1 }),
+        };
+
+        let dictionary = (0..reader.varint()?)
+            .map(|_| reader.attributes())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut deltas = vec![];
+        let mut last = vec![];
+
+        for _ in 0..reader.varint()? {
+            let offset = reader.offset;
+            let shared = reader.len()?;
+            let len = reader.len()?;
+            let rest = reader.take(len)?;
+
+            if shared > last.len() {
+                return Err(Error::InvalidEncoding { offset });
+            }
+
+            last.truncate(shared);
+            last.extend(rest);
+
+            deltas.push(
+                decode_batch_ops(&last, &dictionary)
+                    .map_err(|_| Error::InvalidEncoding { offset })?,
+            );
+        }
+
+        match reader.is_empty() {
+            true => Ok(deltas),
+            false => Err(reader.error()),
+        }
+    }
+
+    /// Splits this delta into a sequence of deltas that compose back into this
+    /// delta (see [`Delta::from_chunks`]), each of which is at most
+    /// `max_encoded_size` bytes when encoded with [`Delta::encode_compact`].
+    /// This is meant for messages that can't exceed a maximum size (e.g. a
+    /// WebSocket frame), where a large paste would otherwise be rejected.
+    ///
+    /// Each chunk starts with a retain of the text that the previous chunks
+    /// produced, and large inserts are split at character boundaries. A chunk
+    /// only exceeds the maximum if a single op (or a single character of an
+    /// insert) with its attributes doesn't fit on its own.
+    pub fn chunk(&self, max_encoded_size: usize) -> Vec<Self> {
+        let mut chunks = vec![];
+        let mut ops = vec![];
+        let mut start = 0;
+        let mut position = 0;
+        let mut size = chunk_header_len(start);
+
+        let mut flush = |ops: &mut Vec<Op<String, Attributes>>, position: usize| {
+            let chunk = Delta::new().retain(start, None);
+            chunks.push(ops.drain(..).fold(chunk, |mut chunk, op| {
+                chunk.push(op);
+                chunk
+            }));
+            start = position;
+            chunk_header_len(start)
+        };
+
+        for op in self.ops() {
+            let attributes = match op {
+                Op::Insert(Insert { attributes, .. }) | Op::Retain(Retain { attributes, .. }) => {
+                    attributes.as_ref()
+                }
+                Op::Delete(_) => None,
+            };
+            let attributes_len = attributes.map_or(0, |attributes| {
+                let mut bytes = vec![];
+                write_attributes(&mut bytes, attributes);
+                bytes.len()
+            });
+
+            let Op::Insert(insert) = op else {
+                let (len, advance) = match op {
+                    Op::Retain(retain) => (retain.retain, retain.retain),
+                    _ => (op.len(), 0),
+                };
+                let op_len = 1 + varint_len(len as u64) + attributes_len;
+
+                if size + op_len > max_encoded_size && !ops.is_empty() {
+                    size = flush(&mut ops, position);
+                }
+
+                ops.push(op.clone());
+                size += op_len;
+                position += advance;
+                continue;
+            };
+
+            let mut rest = insert.insert().as_str();
+
+            while !rest.is_empty() {
+                let available = max_encoded_size.saturating_sub(size + 1 + attributes_len);
+                let mut len = fit(rest, available);
+
+                if len == 0 {
+                    if !ops.is_empty() {
+                        size = flush(&mut ops, position);
+                        continue;
+                    }
+
+                    len = rest.chars().next().map_or(0, char::len_utf8);
+                }
+
+                let (value, after) = rest.split_at(len);
+                rest = after;
+
+                ops.push(Op::Insert(Insert::new(
+                    value.to_owned(),
+                    attributes.cloned(),
+                )));
+                size += 1 + varint_len(len as u64) + len + attributes_len;
+                position += value.chars().count();
+            }
+        }
+
+        if !ops.is_empty() {
+            flush(&mut ops, position);
+        }
+
+        chunks
+    }
+
+    /// Reassembles a delta from the chunks that [`Delta::chunk`] returned, by
+    /// composing them in order.
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        chunks
+            .into_iter()
+            .fold(Delta::new(), |delta, chunk| delta.compose(chunk))
+    }
+}
+
+/// Decodes the ops of a delta in a batch, with the given shared dictionary.
+fn decode_batch_ops(
+    bytes: &[u8],
+    dictionary: &[Attributes],
+) -> Result<Delta<String, Attributes>, Error> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let mut ops = vec![];
+    let mut previous = None;
+
+    while !reader.is_empty() {
+        let offset = reader.offset;
+        let tag = reader.byte()?;
+
+        if tag & SAME_ATTRIBUTES != 0 && (tag & ATTRIBUTES == 0 || previous.is_none()) {
+            return Err(Error::InvalidEncoding { offset });
+        }
+
+        ops.push(match tag & !(ATTRIBUTES | SAME_ATTRIBUTES) {
+            INSERT => Op::Insert(Insert::new(
+                reader.string()?,
+                reader.batch_attributes(tag, dictionary, &mut previous)?,
+            )),
+            RETAIN => Op::Retain(Retain {
+                retain: reader.len()?,
+                attributes: reader.batch_attributes(tag, dictionary, &mut previous)?,
+            }),
+            DELETE if tag & ATTRIBUTES == 0 => Op::Delete(Delete {
+                delete: reader.len()?,
+            }),
+            _ => return Err(Error::InvalidEncoding { offset }),
+        });
+    }
+
+    Ok(DeltaRepr::Seq(ops).into())
+}
+
+#[cfg(feature = "zstd")]
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    zstd::stream::decode_all(bytes).map_err(|_| Error::InvalidEncoding { offset: 2 })
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    Err(Error::InvalidEncoding { offset: 1 })
+}
+
+/// Dictionary of the encoded attributes of a delta or a batch of deltas, in
+/// order of their first occurrence.
+#[derive(Default)]
+struct Dictionary {
+    indices: HashMap<Vec<u8>, usize>,
+    entries: Vec<Vec<u8>>,
+}
+
+impl Dictionary {
+    /// Returns the index of the given attributes, adding them if necessary.
+    fn index(&mut self, attributes: &Attributes) -> usize {
+        let mut bytes = vec![];
+        write_attributes(&mut bytes, attributes);
+
+        *self.indices.entry(bytes).or_insert_with_key(|bytes| {
+            self.entries.push(bytes.clone());
+            self.entries.len() - 1
+        })
+    }
+
+    /// Returns the number of bytes that [`Dictionary::write`] writes.
+    fn len(&self) -> usize {
+        varint_len(self.entries.len() as u64) + self.entries.iter().map(Vec::len).sum::<usize>()
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_varint(bytes, self.entries.len() as u64);
+
+        for entry in &self.entries {
+            bytes.extend(entry);
+        }
+    }
+}
+
+/// Returns the number of bytes of the version and flags bytes and the leading
+/// retain of a chunk that starts at the given position.
+fn chunk_header_len(start: usize) -> usize {
+    match start {
+        0 => 2,
+        start => 3 + varint_len(start as u64),
+    }
+}
+
+/// Returns the length in bytes of the longest prefix of the given text that
+/// fits in the given number of bytes with its length, without splitting a
+/// character.
+fn fit(text: &str, available: usize) -> usize {
+    let mut len = available
+        .saturating_sub(varint_len(available as u64))
+        .min(text.len());
+
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    len
+}
+
+/// Returns the number of bytes that [`write_varint`] writes for the given value.
+fn varint_len(value: u64) -> usize {
+    (64 - value.leading_zeros() as usize).max(1).div_ceil(7)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+    write_varint(bytes, value.len() as u64);
+    bytes.extend(value.as_bytes());
+}
+
+fn write_attributes(bytes: &mut Vec<u8>, attributes: &Attributes) {
+    write_varint(bytes, attributes.len() as u64);
+
+    for (key, value) in attributes {
+        write_str(bytes, key);
+        write_value(bytes, value);
+    }
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: &AttrValue) {
+    match value {
+        AttrValue::Null => bytes.push(NULL),
+        AttrValue::Bool(false) => bytes.push(FALSE),
+        AttrValue::Bool(true) => bytes.push(TRUE),
+        AttrValue::Int(value) => {
+            bytes.push(INT);
+            write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+        }
+        AttrValue::Float(value) => {
+            bytes.push(FLOAT);
+            bytes.extend(value.to_le_bytes());
+        }
+        AttrValue::String(value) => {
+            bytes.push(STRING);
+            write_str(bytes, value);
+        }
+        AttrValue::Map(value) => {
+            bytes.push(MAP);
+            write_attributes(bytes, value);
+        }
+    }
+}
+
+/// Reads the compact encoding, keeping track of the offset for errors.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn is_empty(&self) -> bool {
+        self.offset == self.bytes.len()
+    }
+
+    fn error(&self) -> Error {
+        Error::InvalidEncoding {
+            offset: self.offset,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let byte = *self.bytes.get(self.offset).ok_or_else(|| self.error())?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], Error> {
+        let end = self.offset.checked_add(len).ok_or_else(|| self.error())?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or_else(|| self.error())?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let offset = self.offset;
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return match shift == 63 && byte > 1 {
+                    true => Err(Error::InvalidEncoding { offset }),
+                    false => Ok(value),
+                };
+            }
+        }
+
+        Err(Error::InvalidEncoding { offset })
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        let offset = self.offset;
+        usize::try_from(self.varint()?).map_err(|_| Error::InvalidEncoding { offset })
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.len()?;
+        let offset = self.offset;
+
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| Error::InvalidEncoding { offset })
+    }
+
+    /// Reads the attributes of an op with the given tag, which are either
+    /// inline or an index into the given dictionary.
+    fn op_attributes(
+        &mut self,
+        tag: u8,
+        dictionary: Option<&[Attributes]>,
+    ) -> Result<Option<Attributes>, Error> {
+        if tag & ATTRIBUTES == 0 {
+            return Ok(None);
+        }
+
+        let Some(dictionary) = dictionary else {
+            return self.attributes().map(Some);
+        };
+
+        let offset = self.offset;
+        let index = self.len()?;
+
+        match dictionary.get(index) {
+            Some(attributes) => Ok(Some(attributes.clone())),
+            None => Err(Error::InvalidEncoding { offset }),
+        }
+    }
+
+    /// Reads the attributes of an op with the given tag in a batch, which are
+    /// either an index into the given dictionary or the given previous
+    /// attributes.
+    fn batch_attributes<'a>(
+        &mut self,
+        tag: u8,
+        dictionary: &'a [Attributes],
+        previous: &mut Option<&'a Attributes>,
+    ) -> Result<Option<Attributes>, Error> {
+        if tag & ATTRIBUTES == 0 {
+            return Ok(None);
+        }
+
+        if tag & SAME_ATTRIBUTES == 0 {
+            let offset = self.offset;
+            let index = self.len()?;
+
+            *previous = Some(
+                dictionary
+                    .get(index)
+                    .ok_or(Error::InvalidEncoding { offset })?,
+            );
+        }
+
+        Ok(previous.cloned())
+    }
+
+    fn attributes(&mut self) -> Result<Attributes, Error> {
+        let mut attributes = Attributes::new();
+
+        for _ in 0..self.varint()? {
+            let key = self.string()?;
+            attributes.insert(key, self.value()?);
+        }
+
+        Ok(attributes)
+    }
+
+    fn value(&mut self) -> Result<AttrValue, Error> {
+        let offset = self.offset;
+
+        Ok(match self.byte()? {
+            NULL => AttrValue::Null,
+            FALSE => AttrValue::Bool(false),
+            TRUE => AttrValue::Bool(true),
+            INT => {
+                let value = self.varint()?;
+                AttrValue::Int((value >> 1) as i64 ^ -((value & 1) as i64))
+            }
+            FLOAT => {
+                let bytes = self.take(8)?;
+                AttrValue::Float(f64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+            }
+            STRING => AttrValue::String(self.string()?),
+            MAP => AttrValue::Map(self.attributes()?),
+            _ => return Err(Error::InvalidEncoding { offset }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::attributes::Attributes;
+    use crate::{Compose, Delta, Error};
+
+    fn attributes() -> Attributes {
+        serde_json::from_value(json!({
+            "bold": true,
+            "italic": false,
+            "header": -2,
+            "size": 1.5,
+            "font": null,
+            "color": "#ff0000",
+            "link": { "href": "https://example.com" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let delta = Delta::new()
+            .retain(300, None)
+            .insert("Héllo".to_owned(), attributes())
+            .delete(1 << 40)
+            .retain(2, Attributes::new());
+
+        let bytes = delta.encode_compact();
+
+        assert_eq!(bytes[1], 0);
+        assert_eq!(Delta::decode_compact(&bytes), Ok(delta));
+    }
+
+    #[test]
+    fn test_round_trip_dictionary() {
+        let delta = Delta::new()
+            .insert("a".to_owned(), attributes())
+            .insert("b".to_owned(), None)
+            .insert("c".to_owned(), attributes())
+            .retain(2, attributes());
+
+        let bytes = delta.encode_compact();
+
+        assert_eq!(bytes[1], super::DICTIONARY);
+        assert_eq!(Delta::decode_compact(&bytes), Ok(delta));
+    }
+
+    #[test]
+    fn test_size() {
+        let delta = Delta::<String, Attributes>::new()
+            .retain(1234, None)
+            .insert("a".to_owned(), None);
+
+        assert_eq!(
+            delta.encode_compact(),
+            [super::VERSION, 0, 1, 0xd2, 0x09, 0, 1, b'a']
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = Delta::<String, Attributes>::new()
+            .retain(1234, None)
+            .insert("a".to_owned(), attributes())
+            .encode_compact();
+
+        assert!(matches!(
+            Delta::decode_compact(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidEncoding { .. })
+        ));
+        assert_eq!(
+            Delta::decode_compact(&[2, 0]),
+            Err(Error::InvalidEncoding { offset: 0 })
+        );
+        assert_eq!(
+            Delta::decode_compact(&[super::VERSION, 0, 7, 1]),
+            Err(Error::InvalidEncoding { offset: 2 })
+        );
+    }
+
+    fn burst() -> Vec<Delta<String, Attributes>> {
+        let bold = Attributes::from([("bold".to_owned(), true.into())]);
+
+        "Hello World"
+            .chars()
+            .enumerate()
+            .map(|(index, char)| {
+                Delta::new()
+                    .retain(1000 + index, None)
+                    .insert(char.to_string(), bold.clone())
+                    .retain(5, bold.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_round_trip() {
+        let deltas = burst();
+        let bytes = Delta::compress_batch(&deltas);
+
+        assert!(
+            bytes.len()
+                < deltas
+                    .iter()
+                    .map(|delta| delta.encode_compact().len())
+                    .sum::<usize>()
+        );
+        assert_eq!(Delta::decompress_batch(&bytes), Ok(deltas));
+        assert_eq!(
+            Delta::decompress_batch(&Delta::compress_batch(&[])),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_batch_zstd() {
+        let deltas = (0..100).flat_map(|_| burst()).collect::<Vec<_>>();
+        let bytes = Delta::compress_batch(&deltas);
+
+        assert_eq!(bytes[1], super::ZSTD);
+        assert_eq!(Delta::decompress_batch(&bytes), Ok(deltas));
+    }
+
+    #[test]
+    fn test_batch_invalid() {
+        // The first delta can't share a prefix with the previous delta.
+        assert_eq!(
+            Delta::decompress_batch(&[super::VERSION, 0, 0, 1, 1, 0]),
+            Err(Error::InvalidEncoding { offset: 4 })
+        );
+
+        // The first op with attributes can't refer to the previous attributes.
+        assert_eq!(
+            Delta::decompress_batch(&[super::VERSION, 0, 0, 1, 0, 2, 13, 1]),
+            Err(Error::InvalidEncoding { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn test_chunk() {
+        let bold = Attributes::from([("bold".to_owned(), true.into())]);
+        let base = Delta::new().insert("x".repeat(20), None);
+        let delta = Delta::new()
+            .retain(10, None)
+            .insert("é".repeat(1000) + "abc", bold.clone())
+            .delete(3)
+            .retain(2, bold);
+
+        let chunks = delta.chunk(64);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.encode_compact().len() <= 64));
+        assert_eq!(
+            base.clone().compose(Delta::from_chunks(chunks.clone())),
+            base.clone().compose(delta.clone())
+        );
+        assert_eq!(
+            chunks.into_iter().fold(base.clone(), Compose::compose),
+            base.compose(delta)
+        );
+    }
+
+    #[test]
+    fn test_chunk_small() {
+        let delta = Delta::new()
+            .retain(10, None)
+            .insert("Hello".to_owned(), attributes());
+
+        assert_eq!(delta.chunk(1024), vec![delta.clone()]);
+        assert_eq!(Delta::<String, Attributes>::new().chunk(1024), vec![]);
+    }
+
+    #[test]
+    fn test_chunk_oversized() {
+        let delta = Delta::new()
+            .insert("ab".to_owned(), attributes())
+            .retain(2, attributes());
+
+        let chunks = delta.chunk(8);
+
+        assert_eq!(
+            chunks,
+            vec![
+                Delta::new().insert("a".to_owned(), attributes()),
+                Delta::new()
+                    .retain(1, None)
+                    .insert("b".to_owned(), attributes()),
+                Delta::new().retain(2, None).retain(2, attributes()),
+            ]
+        );
+        assert_eq!(Delta::from_chunks(chunks), delta);
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt::Debug;
 use std::mem::take;
+use std::ops::AddAssign;
 
 use arbitrary::Arbitrary;
 
 use super::op::split;
 use super::ops::{Delete, Insert, Retain};
-use super::{Delta, Op, Seq};
+use super::{ApplyMode, Cursor, Delta, Error, Iter, Len, Op, OrderPolicy, Seq, Transform};
 
 /// Implemented by types that can apply a series of operations in sequence.
 ///
@@ -36,6 +37,39 @@ pub trait Compose<Rhs> {
     /// Applies the given series of operations to the receiver and returns the
     /// result.
     fn compose(self, rhs: Rhs) -> Self::Output;
+
+    /// Applies the given attributes of a retain to the attributes of an insert
+    /// (if any) and returns the attributes of the resulting insert, or `None`
+    /// if it doesn't have any. Attributes that can remove formats (e.g. with
+    /// `null` values, like Quill's) can override this to drop those removals,
+    /// because the inserted elements don't have any formats to remove. By
+    /// default, this is the same as [`Compose::compose`].
+    fn compose_insert(lhs: Option<Self>, rhs: Rhs) -> Option<Self::Output>
+    where
+        Self: Sized,
+        Rhs: Into<Self::Output>,
+    {
+        Some(match lhs {
+            Some(lhs) => lhs.compose(rhs),
+            None => rhs.into(),
+        })
+    }
+
+    /// Transforms the attributes of Bob's retain (`rhs`) against the attributes
+    /// of Alice's retain of the same selection (`lhs`) and returns the
+    /// attributes of Bob's transformed retain, or `None` if it doesn't have any.
+    /// Attributes that consist of individual formats (like Quill's) can
+    /// override this to only drop the formats that Alice also sets. By default,
+    /// the attributes of whoever has `priority` win entirely.
+    fn transform_retain(lhs: Option<Self>, rhs: Option<Self>, priority: bool) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        match priority {
+            true => lhs.or(rhs),
+            false => rhs.or(lhs),
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -82,12 +116,13 @@ where
     type Output = Insert<T, A>;
 
     fn compose(self, rhs: &mut Retain<A>) -> Self::Output {
-        let (lhs, rhs) = split(self, rhs);
+        let (mut lhs, rhs) = split(self, rhs);
 
-        Insert {
-            insert: lhs.insert,
-            attributes: lhs.attributes.compose(rhs.attributes),
+        if let Some(rhs) = rhs.attributes {
+            lhs.attributes = A::compose_insert(lhs.attributes, rhs);
         }
+
+        lhs
     }
 }
 
@@ -130,13 +165,13 @@ where
     fn compose(self, rhs: &mut Delete) -> Self::Output {
         let (_lhs, rhs) = split(self, rhs);
 
-        rhs.into()
+        rhs
     }
 }
 
 impl<A, T, U> Compose<&mut Insert<T, A>> for U
 where
-    T: Default,
+    T: Default + Len,
     A: Default,
 {
     type Output = Insert<T, A>;
@@ -198,13 +233,269 @@ where
     type Output = Self;
 
     fn compose(self, rhs: Delta<T, A>) -> Self {
+        let mut result = Delta::new();
+        self.compose_into(rhs, &mut result);
+        result
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Composes the given delta with this delta like [`Compose`], but writes
+    /// the result into the given delta, whose previous operations are
+    /// discarded. The buffer of that delta is reused, so a server that keeps a
+    /// scratch delta around (e.g. per batch of submissions) doesn't allocate a
+    /// new buffer for every composition.
+    pub fn compose_into(self, rhs: Delta<T, A>, out: &mut Delta<T, A>) {
+        self.compose_untrimmed_into(rhs, out);
+        out.chop_in_place();
+    }
+
+    /// Composes the given delta with this delta like [`Compose`], but keeps
+    /// trailing retains that don't update any attributes, so that the
+    /// [`base_len`](Delta::base_len) of the result is that of this delta (or
+    /// more, if the given delta retains past its end). Use [`Delta::trim`] to
+    /// remove them later.
+    pub fn compose_untrimmed(self, rhs: Delta<T, A>) -> Self {
+        let mut result = Delta::new();
+        self.compose_untrimmed_into(rhs, &mut result);
+        result
+    }
+
+    fn compose_untrimmed_into(self, rhs: Delta<T, A>, out: &mut Delta<T, A>) {
+        out.clear();
+        out.reserve(self.ops_len().saturating_add(rhs.ops_len()));
+
+        compose_ops(self.into_iter(), rhs.into_iter(), out);
+    }
+
+    /// Composes the given delta with this delta in place, i.e. replaces this
+    /// delta with its composition with the given delta (see [`Compose`]).
+    ///
+    /// The operations of this delta that precede the position of the first
+    /// change of the given delta (i.e. those covered by its leading retain
+    /// without attributes) are left in place, and only the rest of this delta
+    /// is composed. This makes folding the next edit into a buffered delta
+    /// cheap when that edit is made near its end, e.g. while typing.
+    pub fn compose_assign(&mut self, rhs: Delta<T, A>) {
+        let mut rhs_iter = rhs.into_iter();
+
+        let retain = match rhs_iter.next_mut() {
+            Some(Op::Retain(Retain {
+                retain,
+                attributes: None,
+            })) => *retain,
+            _ => 0,
+        };
+
+        let mut kept = 0usize;
+        let mut index = 0;
+
+        for op in self.ops() {
+            let len = match op {
+                Op::Delete(_) => 0,
+                op => op.len(),
+            };
+
+            match kept.checked_add(len) {
+                Some(len) if len <= retain => kept = len,
+                _ => break,
+            }
+
+            index += 1;
+        }
+
+        if let Some(Op::Retain(rhs)) = rhs_iter.next_mut() {
+            rhs.retain -= kept;
+        }
+
+        let mut self_iter = self.split_off(index).into_iter();
+
+        self.extend(self_iter.zip_mut(&mut rhs_iter, |a, b| a.compose(b)));
+        self.extend(self_iter.chain(rhs_iter));
+        self.chop_in_place();
+    }
+
+    /// Composes the given deltas in order, i.e. returns the delta that applies
+    /// all of them in sequence (or an empty delta if there are none).
+    ///
+    /// Unlike folding them with [`Compose`], which composes every delta with
+    /// the (ever growing) composition of all deltas before it, this composes
+    /// adjacent deltas pairwise (like a merge sort), so that each op takes
+    /// part in a logarithmic number of compositions. This makes composing a
+    /// long history of small deltas (e.g. to take a snapshot or to catch up a
+    /// client) roughly linear instead of quadratic. At most a logarithmic
+    /// number of intermediate compositions is kept in memory at once.
+    pub fn compose_many<I>(deltas: I) -> Delta<T, A>
+    where
+        I: IntoIterator<Item = Delta<T, A>>,
+    {
+        let mut stack: Vec<(u32, Delta<T, A>)> = vec![];
+
+        for delta in deltas {
+            let mut level = 0;
+            let mut delta = delta;
+
+            while matches!(stack.last(), Some((other, _)) if *other == level) {
+                let (_, lhs) = stack.pop().expect("stack isn't empty");
+                delta = lhs.compose(delta);
+                level += 1;
+            }
+
+            stack.push((level, delta));
+        }
+
+        stack
+            .into_iter()
+            .map(|(_, delta)| delta)
+            .reduce(|lhs, rhs| lhs.compose(rhs))
+            .unwrap_or_default()
+    }
+
+    /// Transforms the given incoming delta with the given concurrent delta and
+    /// composes the result with this delta, in a single pass over all three
+    /// deltas. Returns both the composition and the transformed delta. This is
+    /// the canonical step of a server, where this delta is the document (or
+    /// history) that the concurrent delta was already composed onto, and is
+    /// equivalent to (but faster than):
+    ///
+    /// ```ignore
+    /// let transformed = concurrent.transform(incoming, true);
+    /// (self.compose(transformed.clone()), transformed)
+    /// ```
+    pub fn apply_concurrent(
+        self,
+        concurrent: Delta<T, A>,
+        incoming: Delta<T, A>,
+    ) -> (Delta<T, A>, Delta<T, A>) {
+        let mut base_iter = self.into_iter();
+        let mut concurrent_iter = concurrent.into_iter();
+        let mut incoming_iter = incoming.into_iter();
+
+        let mut composed = Delta::new();
+        let mut transformed = Delta::new();
+
+        let mut step = |op: Op<T, A>| {
+            transformed.push(op.clone());
+            compose_op(&mut base_iter, &mut composed, op);
+        };
+
+        concurrent_iter
+            .zip_mut(&mut incoming_iter, |a, b| a.transform(b, true))
+            .for_each(&mut step);
+        incoming_iter.for_each(step);
+
+        composed.extend(base_iter);
+
+        (composed.chop(), transformed.chop())
+    }
+}
+
+/// Composes the ops of `rhs` with the ops of `lhs` (see [`Compose`]) and
+/// appends the untrimmed result to the given ops.
+pub(crate) fn compose_ops<T, A, I, E>(
+    mut lhs: Cursor<Op<T, A>, I>,
+    mut rhs: Cursor<Op<T, A>, I>,
+    out: &mut E,
+) where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq + Compose<A, Output = A>,
+    I: Iterator<Item = Op<T, A>>,
+    E: Extend<Op<T, A>>,
+{
+    out.extend(lhs.zip_mut(&mut rhs, |a, b| a.compose(b)));
+    out.extend(lhs.chain(rhs));
+}
+
+/// Composes the given delta with this delta in place. See
+/// [`Delta::compose_assign`].
+impl<T, A> AddAssign<Delta<T, A>> for Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    fn add_assign(&mut self, rhs: Delta<T, A>) {
+        self.compose_assign(rhs);
+    }
+}
+
+/// Composes the given op with the remainder of the given (partially consumed)
+/// delta, like a single step of [`Compose`], and pushes the result.
+fn compose_op<T, A>(base: &mut Iter<T, A>, result: &mut Delta<T, A>, mut op: Op<T, A>)
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq + Compose<A, Output = A>,
+{
+    if op.is_empty() {
+        return;
+    }
+
+    while let Some(base) = base.next_mut() {
+        result.push(base.compose(&mut op));
+
+        if op.is_empty() {
+            return;
+        }
+    }
+
+    result.push(op);
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Strict counterpart of [`Compose`] that applies the given delta to this
+    /// delta, which in turn is applied to a document of the given length (pass
+    /// `0` if this delta is a document itself). Unlike [`Compose`], which
+    /// silently clips retains and deletes that extend past the end of the
+    /// document, this function returns an error if either delta consumes more
+    /// elements than its base document contains or isn't well-formed (see
+    /// [`Delta::check_applicable`]).
+    pub fn try_compose(self, rhs: Delta<T, A>, base_len: usize) -> Result<Self, Error> {
+        self.check_applicable(base_len)?;
+        rhs.check_applicable(self.target_len(base_len))?;
+
+        Ok(self.compose(rhs))
+    }
+
+    /// Applies the given delta to this delta using the given mode, where this
+    /// delta is applied to a document of the given length. In
+    /// [`ApplyMode::Strict`], this is equivalent to [`Delta::try_compose`]. In
+    /// [`ApplyMode::Lenient`], this is equivalent to [`Compose`] and never
+    /// returns an error.
+    pub fn compose_with(
+        self,
+        rhs: Delta<T, A>,
+        base_len: usize,
+        mode: ApplyMode,
+    ) -> Result<Self, Error> {
+        match mode {
+            ApplyMode::Strict => self.try_compose(rhs, base_len),
+            ApplyMode::Lenient => Ok(self.compose(rhs)),
+        }
+    }
+
+    /// Composes the given delta with this delta like [`Compose`], but pushes
+    /// the resulting operations with the given policy (see
+    /// [`Delta::push_with`]). With [`OrderPolicy::Preserve`], a delete that's
+    /// produced before an insert stays in front of it.
+    pub fn compose_with_order(self, rhs: Delta<T, A>, order: OrderPolicy) -> Self {
         let mut self_iter = self.into_iter();
         let mut other_iter = rhs.into_iter();
-
         let mut result = Delta::new();
 
-        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| a.compose(b)));
-        result.extend(self_iter.chain(other_iter));
+        for op in self_iter.zip_mut(&mut other_iter, |a, b| a.compose(b)) {
+            result.push_with(op, order);
+        }
+
+        for op in self_iter.chain(other_iter) {
+            result.push_with(op, order);
+        }
 
         result.chop()
     }
@@ -212,7 +503,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{Compose, Delta};
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::{ApplyMode, Compose, Delete, Delta, Error, Insert, Op, OrderPolicy, Retain};
+    use crate::{LastWriteWins, Transform};
 
     #[derive(Clone, Debug, Default, PartialEq, Eq)]
     pub struct Attributes {
@@ -318,6 +612,105 @@ mod tests {
         assert_eq!(a.compose(b), Delta::new().delete(3));
     }
 
+    #[test]
+    fn test_try_compose() {
+        let a = Delta::new().insert("Hello".to_owned(), ());
+        let b = Delta::new().retain(3, ()).delete(2);
+
+        assert_eq!(
+            a.clone().try_compose(b, 0),
+            Ok(Delta::new().insert("Hel".to_owned(), ()))
+        );
+        assert_eq!(
+            a.try_compose(Delta::new().retain(3, ()).delete(3), 0),
+            Err(Error::LengthMismatch {
+                index: 1,
+                expected: 5,
+                actual: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_compose_change() {
+        let a = Delta::<_, ()>::new()
+            .retain(2, None)
+            .insert("AB".to_owned(), None);
+        let b = Delta::new().retain(4, None).delete(1);
+
+        assert_eq!(
+            a.clone().try_compose(b.clone(), 3),
+            Ok(Delta::new()
+                .retain(2, None)
+                .insert("AB".to_owned(), None)
+                .delete(1))
+        );
+        assert_eq!(
+            a.clone().try_compose(b, 2),
+            Err(Error::LengthMismatch {
+                index: 1,
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(
+            a.try_compose(Delta::new(), 1),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 1,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_compose_with() {
+        let a = Delta::new().insert("Hello".to_owned(), ());
+        let b = Delta::new().retain(3, ()).delete(5);
+
+        assert_eq!(
+            a.clone().compose_with(b.clone(), 0, ApplyMode::Lenient),
+            Ok(Delta::new().insert("Hel".to_owned(), ()).delete(3))
+        );
+        assert_eq!(
+            a.compose_with(b, 0, ApplyMode::Strict),
+            Err(Error::LengthMismatch {
+                index: 1,
+                expected: 5,
+                actual: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_compose_with_order() {
+        let a = Delta::new().retain(2, ());
+        let mut b = Delta::new().retain(1, ()).delete(1);
+        b.push_with(
+            Op::Insert(Insert::new("X".to_owned(), ())),
+            OrderPolicy::Preserve,
+        );
+
+        assert_eq!(
+            a.clone()
+                .compose_with_order(b.clone(), OrderPolicy::InsertFirst),
+            a.clone().compose(b.clone())
+        );
+        assert_eq!(
+            a.compose_with_order(b, OrderPolicy::Preserve)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                Op::Retain(Retain {
+                    retain: 1,
+                    attributes: Some(())
+                }),
+                Op::Delete(Delete { delete: 1 }),
+                Op::Insert(Insert::new("X".to_owned(), ())),
+            ]
+        );
+    }
+
     #[test]
     fn test_insert_mid() {
         let a = Delta::new().insert("Hello".to_owned(), ());
@@ -421,4 +814,163 @@ mod tests {
                 .insert("F".to_owned(), None)
         );
     }
+
+    #[test]
+    fn test_compose_into() {
+        let a = Delta::new().insert("AB".to_owned(), None);
+        let b = Delta::new().retain(1, Attributes::bold()).delete(1);
+
+        let mut out = Delta::new()
+            .insert("stale".to_owned(), None)
+            .retain(3, None);
+        a.clone().compose_into(b.clone(), &mut out);
+
+        assert_eq!(out, a.compose(b));
+    }
+
+    #[test]
+    fn test_compose_untrimmed() {
+        let a = Delta::<_, Attributes>::new()
+            .retain(2, None)
+            .insert("x".to_owned(), None)
+            .retain(3, None);
+        let b = Delta::new().delete(1);
+
+        let mut composed = a.clone().compose_untrimmed(b.clone());
+
+        assert_eq!(
+            composed,
+            Delta::new()
+                .delete(1)
+                .retain(1, None)
+                .insert("x".to_owned(), None)
+                .retain(3, None)
+        );
+        assert_eq!(composed.base_len(), a.base_len());
+
+        composed.trim();
+        assert_eq!(composed, a.compose(b));
+    }
+
+    #[test]
+    fn test_apply_concurrent() {
+        let base = Delta::new().insert("Hello World".to_owned(), None);
+        let concurrent = Delta::new().retain(5, None).delete(6);
+        let incoming = Delta::new()
+            .retain(6, Attributes::bold())
+            .insert("there".to_owned(), None);
+
+        let transformed = concurrent.clone().transform(incoming.clone(), true);
+
+        assert_eq!(
+            base.clone()
+                .compose(concurrent.clone())
+                .apply_concurrent(concurrent, incoming),
+            (
+                Delta::new()
+                    .insert("Hello".to_owned(), Attributes::bold())
+                    .insert("there".to_owned(), None),
+                transformed
+            )
+        );
+    }
+
+    /// Returns bytes from a xorshift generator, which deterministically feed
+    /// [`Arbitrary`] in place of a fuzzer.
+    fn pseudo_random_bytes(mut state: u64) -> Vec<u8> {
+        (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_concurrent_arbitrary() {
+        let bytes = pseudo_random_bytes(0x2545f4914f6cdd1d);
+        let mut data = Unstructured::new(&bytes);
+
+        for _ in 0..1000 {
+            let [base, concurrent, incoming] = [(); 3].map(|_| {
+                Delta::<String, LastWriteWins<usize>>::arbitrary(&mut data)
+                    .unwrap()
+                    .normalize()
+            });
+
+            let transformed = concurrent.clone().transform(incoming.clone(), true);
+            let (composed, actual) = base.clone().apply_concurrent(concurrent, incoming);
+
+            assert!(composed.semantic_eq(&base.compose(transformed.clone())));
+            assert!(actual.semantic_eq(&transformed));
+        }
+    }
+
+    #[test]
+    fn test_compose_many() {
+        let deltas = (0..100)
+            .map(|index| match index % 3 {
+                0 => Delta::new()
+                    .retain(index / 2, None)
+                    .insert(index.to_string(), Attributes::bold()),
+                1 => Delta::new().retain(index / 3, None).delete(2),
+                _ => Delta::new().insert("ab".to_owned(), None),
+            })
+            .collect::<Vec<_>>();
+
+        for len in [0, 1, 2, 7, 64, 100] {
+            assert_eq!(
+                Delta::compose_many(deltas[..len].to_vec()),
+                deltas[..len]
+                    .iter()
+                    .cloned()
+                    .fold(Delta::new(), |lhs, rhs| lhs.compose(rhs))
+            );
+        }
+    }
+
+    #[test]
+    fn test_compose_assign() {
+        let mut delta = Delta::new()
+            .insert("Hello".to_owned(), Attributes::bold())
+            .retain(3, None)
+            .delete(2)
+            .insert(" World".to_owned(), None);
+
+        let edits = [
+            Delta::new().retain(14, None).insert("!".to_owned(), None),
+            Delta::new().retain(5, None).delete(2),
+            Delta::new().retain(2, None).retain(4, Attributes::bold()),
+            Delta::new().insert(">".to_owned(), None),
+            Delta::new().retain(100, None),
+        ];
+
+        for edit in edits {
+            let expected = delta.clone().compose(edit.clone());
+            delta += edit;
+
+            assert_eq!(delta, expected);
+        }
+    }
+
+    #[test]
+    fn test_compose_assign_arbitrary() {
+        let bytes = pseudo_random_bytes(0x9e3779b97f4a7c15);
+        let mut data = Unstructured::new(&bytes);
+
+        for _ in 0..1000 {
+            let [mut lhs, rhs] = [(); 2].map(|_| {
+                Delta::<String, LastWriteWins<usize>>::arbitrary(&mut data)
+                    .unwrap()
+                    .normalize()
+            });
+
+            let expected = lhs.clone().compose(rhs.clone());
+            lhs.compose_assign(rhs);
+
+            assert_eq!(lhs, expected);
+        }
+    }
 }
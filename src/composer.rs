@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+use std::mem::take;
+use std::time::{Duration, Instant};
+
+use super::{Compose, Delta, Seq};
+
+/// Buffer that composes rapid local changes (e.g. individual keystrokes) into
+/// fewer, larger changes before they're submitted.
+///
+/// A composer emits its buffered changes as a single delta once the first of
+/// them has been buffered for `max_delay`, or once it contains `max_changes`
+/// changes, whichever comes first. It doesn't keep time itself: callers pass
+/// the current time to [`push`](Composer::push) and [`poll`](Composer::poll),
+/// and can use [`deadline`](Composer::deadline) to schedule the next poll.
+///
+/// Changes that are emitted by a composer are regular local changes, so they
+/// should be submitted (or buffered behind a change that's still in flight)
+/// just like any other change. The composer itself must be
+/// [`flush`](Composer::flush)ed before a remote change is applied locally,
+/// because its buffer isn't transformed against remote changes.
+#[derive(Clone, Debug)]
+pub struct Composer<T, A> {
+    max_delay: Duration,
+    max_changes: usize,
+    buffer: Delta<T, A>,
+    changes: usize,
+    since: Option<Instant>,
+}
+
+impl<T, A> Composer<T, A> {
+    /// Returns a new empty composer with the given time and size window.
+    pub fn new(max_delay: Duration, max_changes: usize) -> Composer<T, A> {
+        Composer {
+            max_delay,
+            max_changes,
+            buffer: Delta::default(),
+            changes: 0,
+            since: None,
+        }
+    }
+
+    /// Returns true if this composer doesn't contain any changes.
+    pub fn is_empty(&self) -> bool {
+        self.changes == 0
+    }
+
+    /// Returns the time at which the buffered changes should be emitted, or
+    /// `None` if this composer is empty.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.since.map(|since| since + self.max_delay)
+    }
+
+    /// Returns the composition of the buffered changes if either end of the
+    /// window has been reached at the given time, and empties this composer.
+    pub fn poll(&mut self, now: Instant) -> Option<Delta<T, A>> {
+        let elapsed = self.deadline().is_some_and(|deadline| now >= deadline);
+
+        match elapsed || (self.changes > 0 && self.changes >= self.max_changes) {
+            true => self.flush(),
+            false => None,
+        }
+    }
+
+    /// Returns the composition of the buffered changes (or `None` if there
+    /// are none) regardless of the window, and empties this composer.
+    pub fn flush(&mut self) -> Option<Delta<T, A>> {
+        self.since = None;
+
+        match take(&mut self.changes) {
+            0 => None,
+            _ => Some(take(&mut self.buffer)),
+        }
+    }
+}
+
+impl<T, A> Composer<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Buffers the given change, which applies after the buffered changes, at
+    /// the given time. Returns the composition of the buffered changes if
+    /// either end of the window has been reached (see
+    /// [`poll`](Composer::poll)).
+    pub fn push(&mut self, change: Delta<T, A>, now: Instant) -> Option<Delta<T, A>> {
+        self.buffer = take(&mut self.buffer).compose(change);
+        self.changes += 1;
+        self.since.get_or_insert(now);
+        self.poll(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Composer;
+    use crate::Delta;
+
+    fn typed(index: usize, text: &str) -> Delta<String, ()> {
+        Delta::new()
+            .retain(index, None)
+            .insert(text.to_owned(), None)
+    }
+
+    #[test]
+    fn test_max_delay() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut composer = Composer::new(Duration::from_millis(100), 10);
+
+        assert_eq!(composer.deadline(), None);
+        assert_eq!(composer.push(typed(0, "a"), at(0)), None);
+        assert_eq!(composer.push(typed(1, "b"), at(50)), None);
+        assert_eq!(composer.deadline(), Some(at(100)));
+        assert_eq!(composer.poll(at(99)), None);
+        assert_eq!(composer.poll(at(100)), Some(typed(0, "ab")));
+        assert!(composer.is_empty());
+        assert_eq!(composer.poll(at(200)), None);
+
+        assert_eq!(composer.push(typed(2, "c"), at(250)), None);
+        assert_eq!(composer.push(typed(3, "d"), at(400)), Some(typed(2, "cd")));
+    }
+
+    #[test]
+    fn test_max_changes() {
+        let now = Instant::now();
+        let mut composer = Composer::new(Duration::from_secs(1), 3);
+
+        assert_eq!(composer.push(typed(0, "a"), now), None);
+        assert_eq!(composer.push(typed(1, "b"), now), None);
+        assert_eq!(composer.push(typed(2, "c"), now), Some(typed(0, "abc")));
+        assert_eq!(composer.push(typed(3, "d"), now), None);
+        assert_eq!(composer.flush(), Some(typed(3, "d")));
+        assert_eq!(composer.flush(), None);
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use super::ops::{Delete, Insert, Retain};
+use super::{Len, Op};
+
+/// Deserializes any of the representations of a delta that Quill emits: either
+/// an object with an `ops` field or a bare sequence of operations.
+///
+/// This (like [`Op`]) is deserialized by hand rather than with
+/// `#[serde(untagged)]`, which would replace the errors of the operations
+/// (e.g. of a negative length) with a generic error.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema), schemars(untagged))]
+pub enum DeltaRepr<T, A> {
+    Ops { ops: Vec<Op<T, A>> },
+    Seq(Vec<Op<T, A>>),
+}
+
+impl<'de, T, A> Deserialize<'de> for DeltaRepr<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ReprVisitor(PhantomData))
+    }
+}
+
+struct ReprVisitor<T, A>(PhantomData<(T, A)>);
+
+impl<'de, T, A> Visitor<'de> for ReprVisitor<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    type Value = DeltaRepr<T, A>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object with an `ops` field or a sequence of operations")
+    }
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        let mut ops = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+        while let Some(op) = seq.next_element()? {
+            ops.push(op);
+        }
+
+        Ok(DeltaRepr::Seq(ops))
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut ops = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "ops" if ops.is_some() => return Err(de::Error::duplicate_field("ops")),
+                "ops" => ops = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        match ops {
+            Some(ops) => Ok(DeltaRepr::Ops { ops }),
+            None => Err(de::Error::missing_field("ops")),
+        }
+    }
+}
+
+/// Fields of any kind of operation. An operation is an insert if it has an
+/// `insert` field, a retain if it has a `retain` field and a delete otherwise.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>, A: Deserialize<'de>"))]
+struct RawOp<T, A> {
+    #[serde(default, deserialize_with = "some")]
+    insert: Option<T>,
+
+    #[serde(default, deserialize_with = "some_retain")]
+    retain: Option<usize>,
+
+    #[serde(default, deserialize_with = "some_delete")]
+    delete: Option<usize>,
+
+    attributes: Option<A>,
+}
+
+impl<'de, T, A> Deserialize<'de> for Op<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let RawOp {
+            insert,
+            retain,
+            delete,
+            attributes,
+        } = RawOp::deserialize(deserializer)?;
+
+        match (insert, retain, delete) {
+            (Some(insert), _, _) => Ok(Op::Insert(Insert::new(insert, attributes))),
+            (None, Some(retain), _) => Ok(Op::Retain(Retain { retain, attributes })),
+            (None, None, Some(delete)) => Ok(Op::Delete(Delete { delete })),
+            (None, None, None) => Err(de::Error::custom(
+                "expected an operation with an `insert`, `retain` or `delete` field",
+            )),
+        }
+    }
+}
+
+/// Fields of an insert operation, whose length is counted once it's
+/// deserialized.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>, A: Deserialize<'de>"))]
+struct RawInsert<T, A> {
+    insert: T,
+    attributes: Option<A>,
+}
+
+impl<'de, T, A> Deserialize<'de> for Insert<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let RawInsert { insert, attributes } = RawInsert::deserialize(deserializer)?;
+
+        Ok(Insert::new(insert, attributes))
+    }
+}
+
+fn some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn some_retain<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    retain(deserializer).map(Some)
+}
+
+fn some_delete<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    delete(deserializer).map(Some)
+}
+
+struct LenVisitor {
+    retain: bool,
+}
+
+impl<'de> Visitor<'de> for LenVisitor {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.retain {
+            true => formatter.write_str("a non-negative integer or an object"),
+            false => formatter.write_str("a non-negative integer"),
+        }
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        usize::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        usize::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match value.fract() == 0.0 && value >= 0.0 && value < usize::MAX as f64 {
+            true => Ok(value as usize),
+            false => Err(E::invalid_value(de::Unexpected::Float(value), &self)),
+        }
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        if !self.retain {
+            return Err(de::Error::invalid_type(de::Unexpected::Map, &self));
+        }
+
+        // Quill (since Delta 5) uses objects to retain a single embed and
+        // apply changes to it. kyte can't represent these changes, so the
+        // object becomes a retain of that one embed (which keeps the
+        // attributes of the op).
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+
+        Ok(1)
+    }
+}
+
+/// Deserializes the length of a retain. Apart from integers, this accepts
+/// floats without a fractional part (as emitted by JavaScript) and objects,
+/// which Quill (since Delta 5) uses to retain a single embed and deserialize as
+/// a retain of one element.
+pub fn retain<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(LenVisitor { retain: true })
+}
+
+/// Deserializes the length of a delete. Apart from integers, this accepts
+/// floats without a fractional part (as emitted by JavaScript).
+pub fn delete<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(LenVisitor { retain: false })
+}
+
+/// Deserializes an optional value that may be `null`, where a missing value
+/// (which requires `#[serde(default)]`) deserializes to `None` and `null`
+/// deserializes to `Some(None)`.
+#[cfg(feature = "quill")]
+pub fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Delta;
+
+    fn parse(value: serde_json::Value) -> Delta<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_ops_object() {
+        assert_eq!(
+            parse(json!({ "ops": [{ "retain": 5 }, { "delete": 3 }] })),
+            Delta::new().retain(5, None).delete(3)
+        );
+    }
+
+    #[test]
+    fn test_ops_seq() {
+        assert_eq!(
+            parse(json!([{ "insert": "a" }, { "delete": 3 }])),
+            Delta::new().insert("a".to_owned(), None).delete(3)
+        );
+    }
+
+    #[test]
+    fn test_null_attributes() {
+        assert_eq!(
+            parse(json!({ "ops": [{ "retain": 1, "attributes": null }] })),
+            Delta::new().retain(1, None)
+        );
+        assert_eq!(
+            parse(json!({ "ops": [{ "retain": 1, "attributes": { "bold": null } }] })),
+            Delta::new().retain(1, json!({ "bold": null }))
+        );
+    }
+
+    #[test]
+    fn test_float_lengths() {
+        assert_eq!(
+            parse(json!({ "ops": [{ "retain": 5.0 }, { "delete": 3.0 }] })),
+            Delta::new().retain(5, None).delete(3)
+        );
+        assert!(serde_json::from_value::<Delta<String, ()>>(json!({
+            "ops": [{ "delete": 1.5 }]
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_object_retain() {
+        assert_eq!(
+            parse(json!({
+                "ops": [
+                    { "retain": { "image": { "width": 100 } }, "attributes": { "alt": "logo" } },
+                    { "retain": 2, "attributes": { "bold": true } },
+                ]
+            })),
+            Delta::new()
+                .retain(1, json!({ "alt": "logo" }))
+                .retain(2, json!({ "bold": true }))
+        );
+        assert!(serde_json::from_value::<Delta<String, ()>>(json!({
+            "ops": [{ "delete": {} }]
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_float_overflow() {
+        assert!(serde_json::from_value::<Delta<String, ()>>(json!({
+            "ops": [{ "retain": usize::MAX as f64 }]
+        }))
+        .is_err());
+    }
+}
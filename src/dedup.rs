@@ -0,0 +1,184 @@
+//! Operation identifiers and deduplication of resubmitted deltas.
+//!
+//! Clients that retry a submission after a timeout can't know whether the
+//! server already applied it, so the server may receive the same delta more
+//! than once. Applying it twice would duplicate the edit. To make submissions
+//! idempotent, each client numbers its deltas with consecutive sequence numbers
+//! (starting at zero) and sends them as a [`Submission`] with an [`OpId`]. The
+//! server then records every id in a [`DedupWindow`] before applying the
+//! corresponding delta, and drops (but still acknowledges) the ones that it has
+//! seen before.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Delta, Len};
+
+/// Identifier of a delta that's unique among all deltas that are submitted to
+/// a document.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OpId {
+    /// Contains the identifier of the client that submitted the delta.
+    pub client: String,
+
+    /// Contains the sequence number of the delta among those submitted by the
+    /// same client.
+    pub seq: u64,
+}
+
+/// Delta that's submitted by a client, optionally with an identifier that
+/// makes resubmitting it idempotent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"))]
+pub struct Submission<T, A> {
+    /// Contains the identifier of the delta, or `None` if the client doesn't
+    /// need deduplication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<OpId>,
+
+    /// Contains the delta itself.
+    pub delta: Delta<T, A>,
+}
+
+/// Set of the [`OpId`]s that a server has seen, which remembers a bounded
+/// number of sequence numbers per client.
+///
+/// For each client, the window tracks the sequence number below which it has
+/// seen every delta, along with the (at most `capacity`) sequence numbers
+/// above it that arrived out of order. If more arrive out of order, the window
+/// gives up on the oldest missing sequence numbers, which are treated as seen
+/// from then on. Deltas that are resubmitted that late are therefore dropped
+/// rather than applied twice.
+#[derive(Clone, Debug)]
+pub struct DedupWindow {
+    capacity: usize,
+    clients: HashMap<String, ClientWindow>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ClientWindow {
+    floor: u64,
+    seen: BTreeSet<u64>,
+}
+
+impl DedupWindow {
+    /// Returns a new empty window that remembers up to `capacity` sequence
+    /// numbers per client that arrived out of order.
+    pub fn new(capacity: usize) -> DedupWindow {
+        DedupWindow {
+            capacity,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the given id has been seen (or has expired) before.
+    pub fn contains(&self, id: &OpId) -> bool {
+        self.clients
+            .get(&id.client)
+            .is_some_and(|window| id.seq < window.floor || window.seen.contains(&id.seq))
+    }
+
+    /// Records the given id and returns true if it wasn't seen before, i.e. if
+    /// the corresponding delta should be applied.
+    pub fn insert(&mut self, id: &OpId) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+
+        let window = self.clients.entry(id.client.clone()).or_default();
+        window.seen.insert(id.seq);
+
+        loop {
+            if window.seen.remove(&window.floor) {
+                window.floor += 1;
+            } else if window.seen.len() > self.capacity {
+                let oldest = window.seen.pop_first().unwrap_or_default();
+                window.floor = oldest + 1;
+            } else {
+                break;
+            }
+        }
+
+        true
+    }
+
+    /// Forgets everything about the given client (e.g. once its session ends
+    /// for good).
+    pub fn forget(&mut self, client: &str) {
+        self.clients.remove(client);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{DedupWindow, OpId, Submission};
+    use crate::Delta;
+
+    fn id(client: &str, seq: u64) -> OpId {
+        OpId {
+            client: client.to_owned(),
+            seq,
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut window = DedupWindow::new(2);
+
+        assert!(window.insert(&id("alice", 0)));
+        assert!(window.insert(&id("bob", 0)));
+        assert!(!window.insert(&id("alice", 0)));
+
+        assert!(window.insert(&id("alice", 2)));
+        assert!(!window.insert(&id("alice", 2)));
+        assert!(window.insert(&id("alice", 1)));
+        assert!(!window.insert(&id("alice", 1)));
+        assert!(!window.contains(&id("alice", 3)));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut window = DedupWindow::new(2);
+
+        assert!(window.insert(&id("alice", 2)));
+        assert!(window.insert(&id("alice", 3)));
+        assert!(window.insert(&id("alice", 5)));
+
+        assert!(window.contains(&id("alice", 0)));
+        assert!(window.contains(&id("alice", 1)));
+        assert!(!window.contains(&id("alice", 4)));
+        assert!(window.insert(&id("alice", 4)));
+
+        window.forget("alice");
+        assert!(!window.contains(&id("alice", 0)));
+    }
+
+    #[test]
+    fn test_submission_serde() {
+        let submission = Submission {
+            id: Some(id("alice", 3)),
+            delta: Delta::<String, ()>::new().retain(1, None).delete(1),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&submission).unwrap(),
+            json!({
+                "id": { "client": "alice", "seq": 3 },
+                "delta": { "ops": [{ "retain": 1 }, { "delete": 1 }] },
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Submission<String, ()>>(json!({
+                "delta": [{ "insert": "a" }],
+            }))
+            .unwrap(),
+            Submission {
+                id: None,
+                delta: Delta::new().insert("a".to_owned(), None),
+            }
+        );
+    }
+}
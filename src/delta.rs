@@ -1,11 +1,41 @@
+use std::ops::Range;
+
 use arbitrary::Arbitrary;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use super::de::DeltaRepr;
+use super::iter::RefIter;
 use super::ops::{Delete, Insert, Retain};
-use super::{Iter, Len, Op, Seq};
+use super::{Error, Iter, Len, Op, OrderPolicy, OverflowPolicy, Seq};
 
 /// Series of insert, retain and delete operations.
+///
+/// Deltas that are built with [`Delta::push`] (and therefore also with the
+/// builder functions and [`Compose`](crate::Compose) and
+/// [`Transform`](crate::Transform)) are always in canonical form, which upholds
+/// the following invariants:
+///
+/// 1. No operation has a length of zero.
+/// 2. Adjacent operations of the same kind are merged if they have the same
+///    attributes (unless their combined length would overflow `usize::MAX`).
+/// 3. An insert never directly follows a delete.
+///
+/// Deltas that are constructed in other ways (e.g. deserialized) may not
+/// uphold these invariants. Use [`Delta::normalize`] to convert these deltas
+/// into canonical form, or [`Delta::deserialize_normalized`] to do so during
+/// deserialization.
+///
+/// Deltas deserialize from any of the representations that Quill emits: an
+/// object with an `ops` field or a bare sequence of operations. They serialize
+/// as an object with an `ops` field, or as a bare sequence with
+/// [`bare`](crate::bare).
 #[derive(Arbitrary, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(
+    from = "DeltaRepr<T, A>",
+    bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>")
+)]
+#[arbitrary(bound = "T: Arbitrary<'arbitrary> + Len, A: Arbitrary<'arbitrary>")]
 pub struct Delta<T, A> {
     ops: Vec<Op<T, A>>,
 }
@@ -26,10 +56,35 @@ where
     /// optional attributes. Pass `None` if you don't want this operation to
     /// affect any attributes. See [`Insert::attributes`] for more information.
     pub fn insert(mut self, insert: T, attributes: impl Into<Option<A>>) -> Self {
-        self.push(Op::Insert(Insert {
-            insert,
-            attributes: attributes.into(),
-        }));
+        self.push(Op::Insert(Insert::new(insert, attributes)));
+
+        self
+    }
+
+    /// Returns a new delta with an insert of the given single element (e.g. a
+    /// `char` for a `String`) with the given optional attributes, like
+    /// [`Delta::insert`]. If the insert that this delta ends with has the same
+    /// attributes, the element is appended to its value in place, so typing a
+    /// run of characters only allocates when that value has to grow. Otherwise,
+    /// a new value is built from the element (see [`Op::insert_one`]), which
+    /// doesn't allocate for values that store a single element inline (e.g.
+    /// [`InlineString`](crate::InlineString)).
+    pub fn insert_one<I>(mut self, item: I, attributes: impl Into<Option<A>>) -> Self
+    where
+        T: Extend<I> + FromIterator<I>,
+    {
+        let attributes = attributes.into();
+
+        // Inserts move before a trailing delete, like in `push`.
+        let last_insert = match self.ops.as_mut_slice() {
+            [.., Op::Insert(insert)] | [.., Op::Insert(insert), Op::Delete(_)] => Some(insert),
+            _ => None,
+        };
+
+        match last_insert {
+            Some(insert) if insert.attributes == attributes => insert.push(item),
+            _ => self.push(Op::insert_one(item, attributes)),
+        }
 
         self
     }
@@ -53,11 +108,12 @@ where
         self
     }
 
-    pub(crate) fn ops(&self) -> impl Iterator<Item = &Op<T, A>> {
-        <[_]>::iter(&self.ops)
+    pub(crate) fn chop(mut self) -> Self {
+        self.chop_in_place();
+        self
     }
 
-    pub(crate) fn chop(mut self) -> Self {
+    pub(crate) fn chop_in_place(&mut self) {
         while let Some(Op::Retain(Retain { attributes, .. })) = self.ops.last() {
             if attributes.is_some() {
                 break;
@@ -65,8 +121,80 @@ where
 
             self.ops.pop();
         }
+    }
 
-        self
+    /// Returns the canonical form of this delta, i.e. the delta that is
+    /// obtained by pushing each of its operations into an empty delta in
+    /// order. Deltas that only differ in zero-length operations, in how
+    /// adjacent operations are split or in the order of adjacent deletes and
+    /// inserts compare equal after normalization. This is equivalent to
+    /// [`Delta::compact`] followed by moving every insert in front of the
+    /// deletes that directly precede it.
+    pub fn normalize(self) -> Self {
+        self.normalize_with(OrderPolicy::InsertFirst)
+    }
+
+    /// Returns the canonical form of this delta like [`Delta::normalize`], but
+    /// pushes its operations with the given policy (see [`Delta::push_with`]).
+    pub fn normalize_with(self, order: OrderPolicy) -> Self {
+        let mut delta = Delta::new();
+
+        for op in self.ops {
+            delta.push_with(op, order);
+        }
+
+        delta
+    }
+
+    /// Returns this delta with adjacent operations merged wherever
+    /// [`Delta::push`] would have merged them (e.g. two inserts with equal
+    /// attributes or two consecutive retains) and without operations that have
+    /// a length of zero. Unlike [`Delta::normalize`], this never reorders any
+    /// operations, so it's safe to use on deltas that are built with
+    /// [`OrderPolicy::Preserve`], e.g. to shrink a deserialized or assembled
+    /// delta before serializing it again.
+    pub fn compact(self) -> Self {
+        self.normalize_with(OrderPolicy::Preserve)
+    }
+
+    /// Deserializes a delta and converts it into canonical form. This function
+    /// can be used with `#[serde(deserialize_with = "...")]` to enforce the
+    /// canonical form invariants on deserialized deltas.
+    pub fn deserialize_normalized<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+        A: Deserialize<'de>,
+    {
+        Delta::deserialize(deserializer).map(Delta::normalize)
+    }
+
+    /// Returns this delta with trailing retains that don't update any
+    /// attributes removed. This is an alias of [`Delta::trim`] that takes and
+    /// returns the delta by value, so an identity delta (e.g. a plain
+    /// `retain(n)`) collapses into an empty delta that clients can skip
+    /// broadcasting.
+    pub fn simplify(self) -> Self {
+        self.chop()
+    }
+
+    /// Removes trailing retains that don't update any attributes from this
+    /// delta, like [`Compose`](crate::Compose) and
+    /// [`Transform`](crate::Transform) do with their results (see
+    /// [`Delta::compose_untrimmed`] to skip that).
+    pub fn trim(&mut self) {
+        self.chop_in_place()
+    }
+
+    /// Returns true if this delta has the same effect as the given delta. Unlike
+    /// [`PartialEq`], which compares deltas structurally, this compares the
+    /// [`normalized`](Delta::normalize) forms of both deltas and ignores
+    /// trailing retains that don't update any attributes.
+    pub fn semantic_eq(&self, other: &Delta<T, A>) -> bool
+    where
+        T: PartialEq,
+    {
+        self.clone().normalize().chop() == other.clone().normalize().chop()
     }
 
     /// Appends the given operation to this series. If possible, this function
@@ -78,70 +206,459 @@ where
     ///
     /// [1]: #impl-Compose<Delta<T,+A>>-for-Delta<T,+A>
     pub fn push(&mut self, op: Op<T, A>) {
-        if op.len() == 0 {
-            return;
-        }
+        self.push_with(op, OrderPolicy::InsertFirst)
+    }
+
+    /// Appends the given operation to this series like [`Delta::push`], but
+    /// only moves an insert in front of a preceding delete if the given policy
+    /// is [`OrderPolicy::InsertFirst`]. Deltas that are built with
+    /// [`OrderPolicy::Preserve`] don't uphold the third invariant of the
+    /// canonical form, but otherwise behave the same.
+    pub fn push_with(&mut self, op: Op<T, A>, order: OrderPolicy) {
+        self.push_inner(op, order, OverflowPolicy::Split)
+    }
 
-        let Some(last_op) = self.ops.last_mut() else {
-            self.ops.push(op);
-            return;
+    /// Appends the given operation to this series like [`Delta::push`], but
+    /// handles a retain or delete whose combined length with the preceding
+    /// operation overflows `usize::MAX` according to the given policy.
+    pub fn push_with_overflow(&mut self, op: Op<T, A>, overflow: OverflowPolicy) {
+        self.push_inner(op, OrderPolicy::InsertFirst, overflow)
+    }
+
+    /// Appends the given operation to this series like [`Delta::push`], or
+    /// returns an error (and leaves this delta unchanged) if it's a retain or
+    /// delete whose combined length with the preceding operation would
+    /// overflow `usize::MAX`.
+    pub fn try_push(&mut self, op: Op<T, A>) -> Result<(), Error> {
+        let overflows = match (self.ops.last(), &op) {
+            (Some(Op::Retain(last)), Op::Retain(retain)) => {
+                last.attributes == retain.attributes
+                    && last.retain.checked_add(retain.retain).is_none()
+            }
+            (Some(Op::Delete(last)), Op::Delete(delete)) => {
+                last.delete.checked_add(delete.delete).is_none()
+            }
+            _ => false,
         };
 
-        match last_op {
-            Op::Insert(Insert {
-                insert: last_insert,
-                attributes: last_attributes,
-            }) => match op {
-                Op::Insert(Insert {
-                    insert,
-                    ref attributes,
-                }) if last_attributes == attributes => {
-                    last_insert.extend([insert]);
+        match overflows {
+            true => Err(Error::LengthOverflow {
+                index: self.ops.len() - 1,
+            }),
+            false => {
+                self.push(op);
+                Ok(())
+            }
+        }
+    }
+
+    /// Checked counterpart of [`Delta::retain`] that returns an error if the
+    /// retain overflows (see [`Delta::try_push`]).
+    pub fn try_retain(
+        mut self,
+        retain: usize,
+        attributes: impl Into<Option<A>>,
+    ) -> Result<Self, Error> {
+        self.try_push(Op::Retain(Retain {
+            retain,
+            attributes: attributes.into(),
+        }))?;
+
+        Ok(self)
+    }
+
+    /// Checked counterpart of [`Delta::delete`] that returns an error if the
+    /// delete overflows (see [`Delta::try_push`]).
+    pub fn try_delete(mut self, delete: usize) -> Result<Self, Error> {
+        self.try_push(Op::Delete(Delete { delete }))?;
+        Ok(self)
+    }
+
+    fn push_inner(&mut self, op: Op<T, A>, order: OrderPolicy, overflow: OverflowPolicy) {
+        push_op(&mut self.ops, op, order, overflow)
+    }
+}
+
+/// Buffer of operations that [`push_op`] appends to, i.e. the ops of a
+/// [`Delta`] or of an arena-allocated delta.
+pub(crate) trait OpVec<T, A> {
+    fn last_mut(&mut self) -> Option<&mut Op<T, A>>;
+
+    fn push(&mut self, op: Op<T, A>);
+
+    fn pop(&mut self) -> Option<Op<T, A>>;
+}
+
+impl<T, A> OpVec<T, A> for Vec<Op<T, A>> {
+    fn last_mut(&mut self) -> Option<&mut Op<T, A>> {
+        <[Op<T, A>]>::last_mut(self)
+    }
+
+    fn push(&mut self, op: Op<T, A>) {
+        Vec::push(self, op)
+    }
+
+    fn pop(&mut self) -> Option<Op<T, A>> {
+        Vec::pop(self)
+    }
+}
+
+/// Appends the given operation to the given ops like [`Delta::push_with`] and
+/// [`Delta::push_with_overflow`].
+pub(crate) fn push_op<T, A, V>(
+    ops: &mut V,
+    op: Op<T, A>,
+    order: OrderPolicy,
+    overflow: OverflowPolicy,
+) where
+    T: Seq + Extend<T>,
+    A: PartialEq,
+    V: OpVec<T, A>,
+{
+    if op.is_empty() {
+        return;
+    }
+
+    let Some(last_op) = ops.last_mut() else {
+        ops.push(op);
+        return;
+    };
+
+    match last_op {
+        Op::Insert(last_insert) => match op {
+            Op::Insert(insert) if last_insert.attributes == insert.attributes => {
+                last_insert.extend([insert.into_parts().0]);
+            }
+            Op::Insert { .. } | Op::Retain { .. } | Op::Delete { .. } => {
+                ops.push(op);
+            }
+        },
+        Op::Retain(Retain {
+            retain: last_retain,
+            attributes: last_attributes,
+        }) => match op {
+            Op::Retain(Retain { retain, attributes }) if last_attributes == &attributes => {
+                if let Some(retain) = add_len(last_retain, retain, overflow) {
+                    ops.push(Op::Retain(Retain { retain, attributes }))
                 }
-                Op::Insert { .. } | Op::Retain { .. } | Op::Delete { .. } => {
-                    self.ops.push(op);
+            }
+            Op::Insert { .. } | Op::Retain { .. } | Op::Delete { .. } => {
+                ops.push(op);
+            }
+        },
+        Op::Delete(Delete {
+            delete: last_delete,
+        }) => match op {
+            Op::Insert { .. } if order == OrderPolicy::InsertFirst => {
+                if let Some(delete) = ops.pop() {
+                    push_op(ops, op, order, overflow);
+                    push_op(ops, delete, order, overflow);
                 }
-            },
-            Op::Retain(Retain {
-                retain: last_retain,
-                attributes: last_attributes,
-            }) => match op {
-                Op::Retain(Retain { retain, attributes }) if last_attributes == &attributes => {
-                    match last_retain.overflowing_add(retain) {
-                        (retain, false) => *last_retain = retain,
-                        (retain, true) => {
-                            *last_retain = usize::MAX;
-                            self.ops.push(Op::Retain(Retain {
-                                retain: retain + 1,
-                                attributes,
-                            }))
-                        }
-                    }
+            }
+            Op::Insert { .. } | Op::Retain { .. } => {
+                ops.push(op);
+            }
+            Op::Delete(Delete { delete }) => {
+                if let Some(delete) = add_len(last_delete, delete, overflow) {
+                    ops.push(Op::Delete(Delete { delete }))
                 }
-                Op::Insert { .. } | Op::Retain { .. } | Op::Delete { .. } => {
-                    self.ops.push(op);
+            }
+        },
+    }
+}
+
+/// Adds the given length to the length of the preceding operation and returns
+/// the remainder that should be appended as a separate operation if the sum
+/// overflows (see [`OverflowPolicy`]).
+fn add_len(last: &mut usize, len: usize, overflow: OverflowPolicy) -> Option<usize> {
+    let (sum, overflowed) = last.overflowing_add(len);
+
+    if !overflowed {
+        *last = sum;
+        return None;
+    }
+
+    *last = usize::MAX;
+
+    match overflow {
+        OverflowPolicy::Split => Some(sum + 1),
+        OverflowPolicy::Saturate => None,
+        OverflowPolicy::DebugPanic => {
+            debug_assert!(false, "length of operation overflows usize::MAX");
+            Some(sum + 1)
+        }
+    }
+}
+
+impl<T, A> Delta<T, A> {
+    #[cfg(feature = "bumpalo")]
+    pub(crate) fn from_ops(ops: Vec<Op<T, A>>) -> Delta<T, A> {
+        Delta { ops }
+    }
+
+    pub(crate) fn ops(&self) -> impl Iterator<Item = &Op<T, A>> {
+        <[_]>::iter(&self.ops)
+    }
+
+    pub(crate) fn ops_len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub(crate) fn ops_capacity(&self) -> usize {
+        self.ops.capacity()
+    }
+
+    pub(crate) fn split_off(&mut self, index: usize) -> Delta<T, A> {
+        Delta {
+            ops: self.ops.split_off(index),
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.ops.reserve(additional);
+    }
+
+    /// Removes all operations from this delta, but keeps the memory that was
+    /// allocated for them so that it can be reused.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// Returns true if this delta only consists of inserts, i.e. if it
+    /// represents the contents of a document rather than a change to one. An
+    /// empty delta is an (empty) document.
+    pub fn is_document(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, Op::Insert(_)))
+    }
+
+    /// Returns whether this delta is a document or a change (see
+    /// [`Delta::is_document`]).
+    pub fn kind(&self) -> DeltaKind {
+        match self.is_document() {
+            true => DeltaKind::Document,
+            false => DeltaKind::Change,
+        }
+    }
+
+    /// Verifies that this delta is a document, or returns an error with the
+    /// index of its first retain or delete. Use this before passing a delta
+    /// from an untrusted source to a function that expects a document, such as
+    /// [`LineIndex::new`](crate::LineIndex::new) or `Delta::diff`.
+    pub fn check_document(&self) -> Result<(), Error> {
+        match self.ops.iter().position(|op| !matches!(op, Op::Insert(_))) {
+            Some(index) => Err(Error::NotADocument { index }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Distinguishes deltas that represent the contents of a document from deltas
+/// that represent a change to a document (see [`Delta::kind`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeltaKind {
+    /// The delta only consists of inserts (or is empty).
+    Document,
+
+    /// The delta contains at least one retain or delete.
+    Change,
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Len,
+{
+    /// Returns the ranges of the resulting document (i.e. after applying this
+    /// delta) that differ from the base document. Inserts and retains that
+    /// update attributes invalidate the elements they cover, whereas deletes
+    /// produce an empty range at the position where elements were removed.
+    /// Adjacent and overlapping ranges are merged, so the returned ranges are
+    /// sorted and disjoint.
+    pub fn changed_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let mut position = 0usize;
+
+        for op in self.ops() {
+            let range = match op {
+                Op::Insert(insert) => position..position.saturating_add(insert.len()),
+                Op::Retain(Retain {
+                    retain,
+                    attributes: None,
+                }) => {
+                    position = position.saturating_add(*retain);
+                    continue;
                 }
-            },
-            Op::Delete(Delete {
-                delete: last_delete,
-            }) => match op {
-                Op::Insert { .. } => {
-                    if let Some(delete) = self.ops.pop() {
-                        self.push(op);
-                        self.push(delete);
-                    }
+                Op::Retain(retain) => position..position.saturating_add(retain.len()),
+                Op::Delete(_) => position..position,
+            };
+
+            position = range.end;
+
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                Some(_) | None => ranges.push(range),
+            }
+        }
+
+        ranges
+    }
+
+    /// Returns a single range of the resulting document that bounds all of
+    /// the [`changed ranges`](Delta::changed_ranges), or `None` if this delta
+    /// doesn't change anything.
+    pub fn affected_range(&self) -> Option<Range<usize>> {
+        let ranges = self.changed_ranges();
+
+        match (ranges.first(), ranges.last()) {
+            (Some(first), Some(last)) => Some(first.start..last.end),
+            _ => None,
+        }
+    }
+
+    /// Returns the maximal runs of this (document) delta whose attributes
+    /// match the given predicate (e.g. all links or all text by an author),
+    /// along with their attributes. Adjacent inserts with equal attributes are
+    /// coalesced into a single run, elements without attributes never match,
+    /// and retains and deletes are skipped. The returned ranges are sorted and
+    /// disjoint.
+    pub fn ranges_with<F>(&self, mut predicate: F) -> Vec<(Range<usize>, &A)>
+    where
+        A: PartialEq,
+        F: FnMut(&A) -> bool,
+    {
+        let mut ranges: Vec<(Range<usize>, &A)> = vec![];
+        let mut position = 0usize;
+
+        for op in self.ops() {
+            let Op::Insert(insert) = op else {
+                continue;
+            };
+
+            let range = position..position.saturating_add(insert.len());
+            position = range.end;
+
+            let Some(attributes) = insert.attributes.as_ref().filter(|&a| predicate(a)) else {
+                continue;
+            };
+
+            match ranges.last_mut() {
+                Some((last, last_attributes))
+                    if last.end == range.start && *last_attributes == attributes =>
+                {
+                    last.end = range.end
                 }
-                Op::Retain { .. } => {
-                    self.ops.push(op);
+                Some(_) | None => ranges.push((range, attributes)),
+            }
+        }
+
+        ranges
+    }
+
+    /// Returns the number of elements that this delta consumes, i.e. the sum
+    /// of the lengths of its retains and deletes. This is the minimum length
+    /// of a document that this delta can be applied to without clipping.
+    pub fn base_len(&self) -> usize {
+        self.ops()
+            .filter(|op| !matches!(op, Op::Insert(_)))
+            .fold(0usize, |len, op| len.saturating_add(op.len()))
+    }
+
+    /// Returns the length of the document that is obtained by applying this
+    /// delta to a document of the given length.
+    pub fn target_len(&self, base_len: usize) -> usize {
+        self.ops().fold(base_len, |len, op| match op {
+            Op::Insert(insert) => len.saturating_add(insert.len()),
+            Op::Retain(_) => len,
+            Op::Delete(delete) => len.saturating_sub(delete.len()),
+        })
+    }
+
+    /// Verifies that this delta is well-formed and can be applied to a
+    /// document of the given length. A delta is well-formed if none of its
+    /// operations has a length of zero and no insert directly follows a
+    /// delete. A delta can be applied to a document if it doesn't retain or
+    /// delete more elements than that document contains.
+    ///
+    /// Servers should use this as the first gate on every submitted delta.
+    /// Deltas that are built with [`OrderPolicy::Preserve`] must be checked
+    /// with [`Delta::check_applicable_with`] instead.
+    pub fn check_applicable(&self, base_len: usize) -> Result<(), Error> {
+        self.check_applicable_with(base_len, OrderPolicy::InsertFirst)
+    }
+
+    /// Verifies that this delta is well-formed and can be applied to a
+    /// document of the given length like [`Delta::check_applicable`], but only
+    /// rejects an insert that directly follows a delete if the given policy is
+    /// [`OrderPolicy::InsertFirst`] (see [`Delta::push_with`]).
+    pub fn check_applicable_with(&self, base_len: usize, order: OrderPolicy) -> Result<(), Error> {
+        let mut consumed = 0usize;
+        let mut last_op: Option<&Op<T, A>> = None;
+
+        for (index, op) in self.ops().enumerate() {
+            if op.is_empty() {
+                return Err(Error::ZeroLength { index });
+            }
+
+            match (last_op, op) {
+                (Some(Op::Delete(_)), Op::Insert(_)) if order == OrderPolicy::InsertFirst => {
+                    return Err(Error::InsertAfterDelete { index })
                 }
-                Op::Delete(Delete { delete }) => match last_delete.overflowing_add(delete) {
-                    (delete, false) => *last_delete = delete,
-                    (delete, true) => {
-                        *last_delete = usize::MAX;
-                        self.ops.push(Op::Delete(Delete { delete: delete + 1 }))
+                (_, Op::Insert(_)) => {}
+                (_, Op::Retain(_) | Op::Delete(_)) => {
+                    consumed = consumed.saturating_add(op.len());
+
+                    if consumed > base_len {
+                        return Err(Error::LengthMismatch {
+                            index,
+                            expected: base_len,
+                            actual: consumed,
+                        });
                     }
-                },
-            },
+                }
+            }
+
+            last_op = Some(op);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if applying this delta to a document of the given length
+    /// doesn't change that document. This is the case if this delta doesn't
+    /// insert anything and doesn't delete or update the attributes of any of
+    /// the first `base_len` elements. Retains and deletes past the end of the
+    /// document don't affect any elements and are therefore ignored.
+    pub fn is_noop(&self, base_len: usize) -> bool {
+        let mut position = 0usize;
+
+        for op in self.ops() {
+            match op {
+                Op::Insert(_) => return false,
+                Op::Retain(Retain {
+                    attributes: None, ..
+                }) => {}
+                Op::Retain(_) | Op::Delete(_) if position < base_len => return false,
+                Op::Retain(_) | Op::Delete(_) => {}
+            }
+
+            position = position.saturating_add(op.len());
+        }
+
+        true
+    }
+}
+
+impl<T, A> From<DeltaRepr<T, A>> for Delta<T, A> {
+    fn from(value: DeltaRepr<T, A>) -> Self {
+        match value {
+            DeltaRepr::Ops { ops } | DeltaRepr::Seq(ops) => Delta { ops },
+        }
+    }
+}
+
+impl<T, A> Default for Delta<T, A> {
+    fn default() -> Self {
+        Delta {
+            ops: Default::default(),
         }
     }
 }
@@ -155,7 +672,9 @@ where
     where
         I: IntoIterator<Item = Op<T, A>>,
     {
-        iter.into_iter().for_each(|op| self.push(op))
+        let iter = iter.into_iter();
+        self.ops.reserve(iter.size_hint().0);
+        iter.for_each(|op| self.push(op))
     }
 }
 
@@ -174,6 +693,19 @@ where
     }
 }
 
+impl<T, A> Delta<T, A>
+where
+    T: Clone + Seq,
+    A: Clone,
+{
+    /// Returns a cursor over the ops of this delta that clones each op once
+    /// it's reached, rather than consuming this delta. See
+    /// [`OpPairCursor`](crate::OpPairCursor) for walking two deltas pairwise.
+    pub fn cursor(&self) -> RefIter<'_, T, A> {
+        RefIter::new(<[_]>::iter(&self.ops).cloned())
+    }
+}
+
 impl<T, A> IntoIterator for Delta<T, A>
 where
     T: Clone + Default + Seq,
@@ -190,7 +722,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{Delete, Delta, Insert, Op, Retain};
+    use super::{Delete, Delta, DeltaKind, Error, Insert, Op, OrderPolicy, OverflowPolicy, Retain};
 
     #[test]
     fn test_push_insert_insert_same() {
@@ -201,10 +733,7 @@ mod tests {
 
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "ab".to_owned(),
-                attributes: None
-            }))
+            Some(Op::Insert(Insert::new("ab".to_owned(), None)))
         );
         assert_eq!(iter.next(), None);
     }
@@ -218,17 +747,11 @@ mod tests {
 
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: Some(false)
-            }))
+            Some(Op::Insert(Insert::new("a".to_owned(), false)))
         );
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "b".to_owned(),
-                attributes: Some(true)
-            }))
+            Some(Op::Insert(Insert::new("b".to_owned(), true)))
         );
         assert_eq!(iter.next(), None);
     }
@@ -242,10 +765,7 @@ mod tests {
 
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: Some(())
-            }))
+            Some(Op::Insert(Insert::new("a".to_owned(), ())))
         );
         assert_eq!(
             iter.next(),
@@ -266,10 +786,7 @@ mod tests {
 
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: None
-            }))
+            Some(Op::Insert(Insert::new("a".to_owned(), None)))
         );
         assert_eq!(iter.next(), Some(Op::Delete(Delete { delete: 1 })));
         assert_eq!(iter.next(), None);
@@ -291,10 +808,7 @@ mod tests {
         );
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: None
-            }))
+            Some(Op::Insert(Insert::new("a".to_owned(), None)))
         );
         assert_eq!(iter.next(), None);
     }
@@ -367,10 +881,7 @@ mod tests {
 
         assert_eq!(
             iter.next(),
-            Some(Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: None
-            }))
+            Some(Op::Insert(Insert::new("a".to_owned(), None)))
         );
         assert_eq!(iter.next(), Some(Op::Delete(Delete { delete: 1 })));
         assert_eq!(iter.next(), None);
@@ -402,6 +913,312 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_insert_one() {
+        let delta = Delta::<String, bool>::new()
+            .retain(1, None)
+            .insert_one('a', None)
+            .delete(2)
+            .insert_one('b', None)
+            .insert_one('c', true);
+
+        assert_eq!(
+            delta,
+            Delta::new()
+                .retain(1, None)
+                .insert("ab".to_owned(), None)
+                .insert("c".to_owned(), true)
+                .delete(2)
+        );
+
+        let delta = "hello"
+            .chars()
+            .fold(Delta::<String, ()>::new(), |delta, c| {
+                delta.insert_one(c, None)
+            });
+
+        assert_eq!(delta, Delta::new().insert("hello".to_owned(), None));
+        assert_eq!(
+            Op::<Vec<u8>, ()>::insert_one(7, None),
+            Op::Insert(Insert::new(vec![7], None))
+        );
+    }
+
+    #[test]
+    fn test_changed_ranges() {
+        let delta = Delta::new()
+            .retain(2, None)
+            .insert("ab".to_owned(), None)
+            .retain(3, None)
+            .delete(2)
+            .retain(1, true)
+            .retain(4, None)
+            .retain(1, true);
+
+        assert_eq!(delta.changed_ranges(), vec![2..4, 7..8, 12..13]);
+        assert_eq!(delta.affected_range(), Some(2..13));
+    }
+
+    #[test]
+    fn test_changed_ranges_merge() {
+        let delta = Delta::<_, ()>::new()
+            .insert("ab".to_owned(), None)
+            .delete(3)
+            .retain(1, ())
+            .retain(1, None)
+            .delete(1);
+
+        assert_eq!(delta.changed_ranges(), vec![0..3, 4..4]);
+        assert_eq!(Delta::<String, ()>::new().affected_range(), None);
+    }
+
+    #[test]
+    fn test_ranges_with() {
+        let delta = Delta::new()
+            .insert("abc".to_owned(), Some(1))
+            .insert("d".to_owned(), None)
+            .insert("ef".to_owned(), Some(2))
+            .insert("g".to_owned(), Some(3))
+            .insert("h".to_owned(), Some(1));
+
+        assert_eq!(
+            delta.ranges_with(|&a| a != 2),
+            vec![(0..3, &1), (6..7, &3), (7..8, &1)]
+        );
+        assert_eq!(delta.ranges_with(|&a| a == 2), vec![(4..6, &2)]);
+        assert_eq!(delta.ranges_with(|_| false), vec![]);
+    }
+
+    #[test]
+    fn test_ranges_with_coalesce() {
+        let delta = Delta::<String, u8> {
+            ops: vec![
+                Op::Insert(Insert::new("a".to_owned(), 1)),
+                Op::Insert(Insert::new("b".to_owned(), 1)),
+            ],
+        };
+
+        assert_eq!(delta.ranges_with(|_| true), vec![(0..2, &1)]);
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let delta = Delta::<String, ()> {
+            ops: vec![
+                Op::Insert(Insert::new("a".to_owned(), None)),
+                Op::Insert(Insert::new("b".to_owned(), None)),
+                Op::Retain(Retain {
+                    retain: 3,
+                    attributes: None,
+                }),
+            ],
+        };
+
+        assert_ne!(delta, Delta::new().insert("ab".to_owned(), None));
+        assert!(delta.semantic_eq(&Delta::new().insert("ab".to_owned(), None)));
+        assert!(!delta.semantic_eq(&Delta::new().insert("ab".to_owned(), ())));
+    }
+
+    #[test]
+    fn test_check_applicable() {
+        let delta = Delta::<_, ()>::new()
+            .retain(2, None)
+            .insert("ab".to_owned(), None)
+            .delete(3);
+
+        assert_eq!(delta.check_applicable(5), Ok(()));
+        assert_eq!(
+            delta.check_applicable(4),
+            Err(Error::LengthMismatch {
+                index: 2,
+                expected: 4,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_applicable_preserve() {
+        let mut delta = Delta::<String, ()>::new();
+
+        for op in [
+            Op::Delete(Delete { delete: 2 }),
+            Op::Insert(Insert::new("ab".to_owned(), None)),
+        ] {
+            delta.push_with(op, OrderPolicy::Preserve);
+        }
+
+        assert_eq!(
+            delta.check_applicable(2),
+            Err(Error::InsertAfterDelete { index: 1 })
+        );
+        assert_eq!(
+            delta.check_applicable_with(2, OrderPolicy::Preserve),
+            Ok(())
+        );
+        assert_eq!(
+            delta.check_applicable_with(1, OrderPolicy::Preserve),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 1,
+                actual: 2
+            })
+        );
+        assert_eq!(delta.normalize().check_applicable(2), Ok(()));
+    }
+
+    #[test]
+    fn test_check_applicable_malformed() {
+        let delta = Delta::<String, ()> {
+            ops: vec![
+                Op::Delete(Delete { delete: 1 }),
+                Op::Insert(Insert::new("a".to_owned(), None)),
+            ],
+        };
+
+        assert_eq!(
+            delta.check_applicable(1),
+            Err(Error::InsertAfterDelete { index: 1 })
+        );
+
+        let delta = Delta::<String, ()> {
+            ops: vec![Op::Retain(Retain {
+                retain: 0,
+                attributes: None,
+            })],
+        };
+
+        assert_eq!(
+            delta.check_applicable(1),
+            Err(Error::ZeroLength { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_is_noop() {
+        assert!(Delta::<String, ()>::new().is_noop(0));
+        assert!(Delta::<String, ()>::new().retain(5, None).is_noop(3));
+        assert!(Delta::<String, _>::new()
+            .retain(3, None)
+            .retain(2, true)
+            .delete(1)
+            .is_noop(3));
+        assert!(!Delta::<String, _>::new().retain(2, true).is_noop(3));
+        assert!(!Delta::<String, ()>::new()
+            .retain(2, None)
+            .delete(1)
+            .is_noop(3));
+        assert!(!Delta::<_, ()>::new()
+            .insert("a".to_owned(), None)
+            .is_noop(0));
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(
+            Delta::<String, ()>::new().retain(5, None).simplify(),
+            Delta::new()
+        );
+        assert_eq!(
+            Delta::new()
+                .retain(1, None)
+                .retain(1, true)
+                .retain(5, None)
+                .simplify(),
+            Delta::<String, _>::new().retain(1, None).retain(1, true)
+        );
+
+        let delta = Delta::<String, _>::new().retain(1, true).retain(2, None);
+        let mut trimmed = delta.clone();
+        trimmed.trim();
+        assert_eq!(delta.simplify(), trimmed);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let delta = Delta::<String, ()> {
+            ops: vec![
+                Op::Retain(Retain {
+                    retain: 0,
+                    attributes: None,
+                }),
+                Op::Delete(Delete { delete: 1 }),
+                Op::Delete(Delete { delete: 2 }),
+                Op::Insert(Insert::new("a".to_owned(), None)),
+                Op::Insert(Insert::new("b".to_owned(), None)),
+                Op::Insert(Insert::new("".to_owned(), None)),
+            ],
+        };
+
+        assert_eq!(
+            delta.normalize(),
+            Delta::new().insert("ab".to_owned(), None).delete(3)
+        );
+    }
+
+    #[test]
+    fn test_kind() {
+        let document = Delta::<_, ()>::new().insert("a".to_owned(), None);
+        let change = document.clone().retain(1, None).delete(1);
+
+        assert_eq!(Delta::<String, ()>::new().kind(), DeltaKind::Document);
+        assert_eq!(document.kind(), DeltaKind::Document);
+        assert_eq!(document.check_document(), Ok(()));
+        assert_eq!(change.kind(), DeltaKind::Change);
+        assert!(!change.is_document());
+        assert_eq!(
+            change.check_document(),
+            Err(Error::NotADocument { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let delta: Delta<String, bool> = serde_json::from_value(serde_json::json!([
+            { "retain": 1 },
+            { "retain": 2 },
+            { "retain": 1, "attributes": true },
+            { "delete": 1 },
+            { "insert": "a" },
+            { "insert": "" },
+            { "insert": "b" },
+            { "delete": 2 },
+            { "delete": 3 },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(delta.compact()).unwrap(),
+            serde_json::json!({ "ops": [
+                { "retain": 3 },
+                { "retain": 1, "attributes": true },
+                { "delete": 1 },
+                { "insert": "ab" },
+                { "delete": 5 },
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_normalize_with_preserve() {
+        let delta = Delta::<String, ()> {
+            ops: vec![
+                Op::Delete(Delete { delete: 1 }),
+                Op::Delete(Delete { delete: 2 }),
+                Op::Insert(Insert::new("a".to_owned(), None)),
+                Op::Insert(Insert::new("b".to_owned(), None)),
+            ],
+        };
+
+        assert_eq!(
+            delta.normalize_with(OrderPolicy::Preserve).ops,
+            vec![
+                Op::Delete(Delete { delete: 3 }),
+                Op::Insert(Insert::new("ab".to_owned(), None)),
+            ]
+        );
+    }
+
     #[test]
     fn test_overflow() {
         let mut x = Delta::<String, ()>::new();
@@ -432,4 +1249,99 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_overflow_policy() {
+        let retain = |retain| {
+            Op::Retain(Retain {
+                retain,
+                attributes: None,
+            })
+        };
+
+        let mut saturated = Delta::<String, ()>::new().retain(usize::MAX - 4, None);
+        saturated.push_with_overflow(retain(8), OverflowPolicy::Saturate);
+        assert_eq!(saturated.ops, vec![retain(usize::MAX)]);
+
+        let mut split = Delta::<String, ()>::new().delete(usize::MAX);
+        split.push_with_overflow(Op::Delete(Delete { delete: 2 }), OverflowPolicy::Split);
+        assert_eq!(
+            split.ops,
+            vec![
+                Op::Delete(Delete { delete: usize::MAX }),
+                Op::Delete(Delete { delete: 2 })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_push() {
+        let delta = Delta::<String, ()>::new().retain(usize::MAX - 4, None);
+
+        assert_eq!(
+            delta.clone().try_retain(8, None),
+            Err(Error::LengthOverflow { index: 0 })
+        );
+        assert_eq!(
+            delta.clone().try_retain(8, ()).map(|delta| delta.ops.len()),
+            Ok(2)
+        );
+        assert_eq!(
+            delta
+                .insert("a".to_owned(), None)
+                .delete(usize::MAX)
+                .try_delete(1),
+            Err(Error::LengthOverflow { index: 2 })
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "overflows usize::MAX")]
+    fn test_overflow_debug_panic() {
+        let mut delta = Delta::<String, ()>::new().delete(usize::MAX);
+        delta.push_with_overflow(Op::Delete(Delete { delete: 1 }), OverflowPolicy::DebugPanic);
+    }
+
+    #[test]
+    #[cfg(feature = "schemars")]
+    fn test_json_schema() {
+        use schemars::generate::SchemaSettings;
+        use serde_json::json;
+
+        use crate::attributes::Attributes;
+
+        let schema = SchemaSettings::default()
+            .for_serialize()
+            .into_generator()
+            .into_root_schema_for::<Delta<String, Attributes>>();
+
+        assert_eq!(schema.get("required"), Some(&json!(["ops"])));
+        assert_eq!(
+            schema.get("properties"),
+            Some(&json!({ "ops": { "type": "array", "items": { "$ref": "#/$defs/Op" } } }))
+        );
+
+        let schema = SchemaSettings::default()
+            .for_deserialize()
+            .into_generator()
+            .into_root_schema_for::<Delta<String, Attributes>>();
+
+        assert_eq!(
+            schema
+                .get("anyOf")
+                .and_then(|schema| schema.as_array())
+                .map(Vec::len),
+            Some(2)
+        );
+        assert_eq!(
+            schema
+                .get("$defs")
+                .and_then(|defs| defs.get("Op"))
+                .and_then(|op| op.get("anyOf"))
+                .and_then(|schema| schema.as_array())
+                .map(Vec::len),
+            Some(3)
+        );
+    }
 }
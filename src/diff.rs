@@ -0,0 +1,574 @@
+#[cfg(feature = "similar")]
+use similar::{DiffTag, TextDiff};
+
+use super::{Delta, Op};
+
+/// Implemented by attributes that can describe the difference between two
+/// values, so that `Delta::diff` (which requires the `similar` feature) can
+/// turn formatting changes into retains.
+pub trait Diff: Sized {
+    /// Returns the attributes that turn these attributes into the given
+    /// attributes when composed with them, or `None` if both are equal.
+    fn diff(&self, other: &Self) -> Option<Self>;
+}
+
+impl Diff for () {
+    fn diff(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+}
+
+/// Part of a diff between two texts, which is converted from and into an op of
+/// a delta.
+#[derive(Clone, Debug)]
+enum Chunk<A> {
+    Equal(Vec<char>, Option<A>),
+    Delete(Vec<char>),
+    Insert(Vec<char>, Option<A>),
+}
+
+impl<A> Chunk<A> {
+    fn text(&self) -> &[char] {
+        match self {
+            Chunk::Equal(text, _) | Chunk::Delete(text) | Chunk::Insert(text, _) => text,
+        }
+    }
+
+    fn text_mut(&mut self) -> &mut Vec<char> {
+        match self {
+            Chunk::Equal(text, _) | Chunk::Delete(text) | Chunk::Insert(text, _) => text,
+        }
+    }
+}
+
+impl<A> Delta<String, A>
+where
+    A: Clone + PartialEq,
+{
+    /// Returns a delta that has the same effect as this delta on the given text
+    /// (which must be the text that this delta is applied to), but whose
+    /// changes are easier for humans to read. Like diff-match-patch's
+    /// `diff_cleanupSemantic`, this merges short retains that are surrounded by
+    /// larger changes into those changes (e.g. `mouse` to `sofas` becomes a
+    /// single replacement rather than 4 separate ones), and then shifts each
+    /// remaining change to align with word boundaries (e.g. inserting
+    /// `"ow and the c"` after `"The c"` becomes inserting `"cow and the "`
+    /// after `"The "`).
+    ///
+    /// This is meant for deltas that are generated by a diff algorithm. Retains
+    /// with attributes are never merged into the changes around them, but text
+    /// that is merged is reinserted without the attributes that it had in the
+    /// document.
+    pub fn cleanup_semantic(self, base: &str) -> Self {
+        let mut base = base.chars();
+        let mut diffs = self
+            .ops()
+            .map(|op| match op {
+                Op::Insert(insert) => {
+                    Chunk::Insert(insert.insert().chars().collect(), insert.attributes.clone())
+                }
+                Op::Retain(retain) => Chunk::Equal(
+                    base.by_ref().take(retain.retain).collect(),
+                    retain.attributes.clone(),
+                ),
+                Op::Delete(delete) => Chunk::Delete(base.by_ref().take(delete.delete).collect()),
+            })
+            .collect::<Vec<_>>();
+        diffs.push(Chunk::Equal(base.collect(), None));
+        diffs.retain(|diff| !diff.text().is_empty());
+
+        eliminate_equalities(&mut diffs);
+        merge(&mut diffs);
+        shift_edits(&mut diffs);
+
+        diffs
+            .into_iter()
+            .fold(Delta::new(), |delta, diff| match diff {
+                Chunk::Equal(text, attributes) => delta.retain(text.len(), attributes),
+                Chunk::Delete(text) => delta.delete(text.len()),
+                Chunk::Insert(text, attributes) => {
+                    delta.insert(text.into_iter().collect(), attributes)
+                }
+            })
+            .chop()
+    }
+}
+
+#[cfg(feature = "similar")]
+/// Converts the changes of the given diff into a delta without attributes,
+/// which turns the old text of the diff into its new text. This requires the
+/// `similar` feature. Deltas count characters, so the diff can be made at any
+/// granularity (e.g. with [`TextDiff::from_lines`] or
+/// [`TextDiff::from_chars`]), where a coarser diff is faster but results in
+/// larger deletes and inserts.
+impl<A> From<&TextDiff<'_, '_, '_, str>> for Delta<String, A>
+where
+    A: Clone + PartialEq,
+{
+    fn from(diff: &TextDiff<'_, '_, '_, str>) -> Self {
+        let old = diff.old_slices();
+        let new = diff.new_slices();
+
+        diff.ops().iter().fold(Delta::new(), |delta, op| {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            let old_len = old[old_range]
+                .iter()
+                .map(|slice| slice.chars().count())
+                .sum();
+
+            match tag {
+                DiffTag::Equal => delta.retain(old_len, None),
+                DiffTag::Delete => delta.delete(old_len),
+                DiffTag::Insert => delta.insert(new[new_range].concat(), None),
+                DiffTag::Replace => delta.delete(old_len).insert(new[new_range].concat(), None),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "similar")]
+impl<A> Delta<String, A>
+where
+    A: Clone + Default + PartialEq + Diff,
+{
+    /// Returns the delta that turns this (document) delta into the given
+    /// (document) delta. This requires the `similar` feature. Text that both
+    /// documents contain is retained, with the [`Diff`] between its old and new
+    /// attributes, so that formatting changes don't result in a delete and
+    /// insert. Use [`Delta::cleanup_semantic`] to make the result easier for
+    /// humans to read.
+    ///
+    /// Both deltas must be documents (see [`Delta::check_document`]), which is
+    /// asserted in debug builds.
+    pub fn diff(&self, other: &Self) -> Self {
+        debug_assert!(self.is_document(), "diff of a delta that isn't a document");
+        debug_assert!(
+            other.is_document(),
+            "diff with a delta that isn't a document"
+        );
+
+        let old = self.plain_text();
+        let new = other.plain_text();
+        let old_attributes = char_attributes(self);
+        let new_attributes = char_attributes(other);
+        let default = A::default();
+        let diff = TextDiff::from_chars(&old, &new);
+
+        diff.ops()
+            .iter()
+            .fold(Delta::new(), |mut delta, op| {
+                let (tag, old_range, new_range) = op.as_tag_tuple();
+
+                match tag {
+                    DiffTag::Equal => {
+                        let pairs = old_attributes[old_range]
+                            .iter()
+                            .zip(&new_attributes[new_range]);
+
+                        for (lhs, rhs) in pairs {
+                            delta = delta.retain(
+                                1,
+                                match (lhs, rhs) {
+                                    (None, None) => None,
+                                    (lhs, rhs) => {
+                                        lhs.unwrap_or(&default).diff(rhs.unwrap_or(&default))
+                                    }
+                                },
+                            );
+                        }
+                    }
+                    DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                        delta = delta.delete(old_range.len());
+
+                        for (&value, attributes) in diff.new_slices()[new_range.clone()]
+                            .iter()
+                            .zip(&new_attributes[new_range])
+                        {
+                            delta = delta.insert(value.to_owned(), attributes.cloned());
+                        }
+                    }
+                }
+
+                delta
+            })
+            .chop()
+    }
+}
+
+/// Returns the attributes of each character that the given document contains.
+#[cfg(feature = "similar")]
+fn char_attributes<A>(document: &Delta<String, A>) -> Vec<Option<&A>> {
+    document
+        .ops()
+        .flat_map(|op| match op {
+            Op::Insert(insert) => {
+                std::iter::repeat(insert.attributes.as_ref()).take(insert.insert().chars().count())
+            }
+            Op::Retain(_) | Op::Delete(_) => std::iter::repeat(None).take(0),
+        })
+        .collect()
+}
+
+/// Replaces each equality without attributes that is no longer than the
+/// changes on both sides of it with a delete and an insert of its text.
+fn eliminate_equalities<A>(diffs: &mut Vec<Chunk<A>>)
+where
+    A: Clone,
+{
+    let mut equalities = Vec::<usize>::new();
+    let mut last_equality = None;
+    let mut before = (0, 0);
+    let mut after = (0, 0);
+    let mut pointer = 0;
+
+    while pointer < diffs.len() {
+        match &diffs[pointer] {
+            Chunk::Equal(text, attributes) => {
+                equalities.push(pointer);
+                before = after;
+                after = (0, 0);
+                last_equality = attributes.is_none().then_some(text.len());
+            }
+            Chunk::Delete(text) => after.0 += text.len(),
+            Chunk::Insert(text, _) => after.1 += text.len(),
+        }
+
+        if let Some(len) = last_equality.filter(|_| !matches!(diffs[pointer], Chunk::Equal(..))) {
+            if len <= before.0.max(before.1) && len <= after.0.max(after.1) {
+                let index = equalities.pop().unwrap_or_default();
+                let text = diffs[index].text().to_vec();
+
+                diffs[index] = Chunk::Insert(text.clone(), None);
+                diffs.insert(index, Chunk::Delete(text));
+
+                // The previous equality needs to be reevaluated.
+                equalities.pop();
+                pointer = equalities.last().map_or(0, |&index| index + 1);
+                last_equality = None;
+                before = (0, 0);
+                after = (0, 0);
+                continue;
+            }
+        }
+
+        pointer += 1;
+    }
+}
+
+/// Merges each run of deletes and inserts between two equalities into a single
+/// delete followed by its inserts, and moves any text that the delete and an
+/// insert without attributes have in common into the surrounding equalities.
+fn merge<A>(diffs: &mut Vec<Chunk<A>>)
+where
+    A: PartialEq,
+{
+    let mut merged = Vec::with_capacity(diffs.len());
+    let mut deleted = Vec::new();
+    let mut inserts = Vec::<Chunk<A>>::new();
+
+    for diff in diffs.drain(..) {
+        match diff {
+            Chunk::Equal(text, attributes) => {
+                flush(&mut merged, &mut deleted, &mut inserts);
+                push_equal(&mut merged, text, attributes);
+            }
+            Chunk::Delete(text) => deleted.extend(text),
+            Chunk::Insert(text, attributes) => match inserts.last_mut() {
+                Some(Chunk::Insert(last, last_attributes)) if last_attributes == &attributes => {
+                    last.extend(text)
+                }
+                _ => inserts.push(Chunk::Insert(text, attributes)),
+            },
+        }
+    }
+
+    flush(&mut merged, &mut deleted, &mut inserts);
+    *diffs = merged;
+}
+
+/// Pushes the given delete and inserts (and their common prefix and suffix)
+/// onto the given diffs.
+fn flush<A>(diffs: &mut Vec<Chunk<A>>, deleted: &mut Vec<char>, inserts: &mut Vec<Chunk<A>>)
+where
+    A: PartialEq,
+{
+    let mut suffix = vec![];
+
+    if let [Chunk::Insert(inserted, None)] = inserts.as_mut_slice() {
+        let len = common_prefix(deleted, inserted);
+        inserted.drain(..len);
+        push_equal(diffs, deleted.drain(..len).collect(), None);
+
+        let len = common_suffix(deleted, inserted);
+        inserted.truncate(inserted.len() - len);
+        suffix = deleted.split_off(deleted.len() - len);
+    }
+
+    if !deleted.is_empty() {
+        diffs.push(Chunk::Delete(std::mem::take(deleted)));
+    }
+
+    diffs.extend(inserts.drain(..).filter(|diff| !diff.text().is_empty()));
+    push_equal(diffs, suffix, None);
+}
+
+/// Pushes the given equality onto the given diffs, merging it with the last
+/// equality if their attributes are equal.
+fn push_equal<A>(diffs: &mut Vec<Chunk<A>>, text: Vec<char>, attributes: Option<A>)
+where
+    A: PartialEq,
+{
+    match diffs.last_mut() {
+        _ if text.is_empty() => {}
+        Some(Chunk::Equal(last, last_attributes)) if last_attributes == &attributes => {
+            last.extend(text)
+        }
+        _ => diffs.push(Chunk::Equal(text, attributes)),
+    }
+}
+
+/// Shifts each single delete or insert between two equalities without
+/// attributes to the position where it aligns best with word and line
+/// boundaries.
+fn shift_edits<A>(diffs: &mut Vec<Chunk<A>>) {
+    for index in 1..diffs.len().saturating_sub(1) {
+        if let [Chunk::Equal(before, None), edit, Chunk::Equal(after, None)] =
+            &mut diffs[index - 1..=index + 1]
+        {
+            if !matches!(edit, Chunk::Equal(..)) && !before.is_empty() && !after.is_empty() {
+                shift(before, edit.text_mut(), after);
+            }
+        }
+    }
+
+    diffs.retain(|diff| !diff.text().is_empty());
+}
+
+/// Shifts the given edit between the given equalities.
+fn shift(before: &mut Vec<char>, edit: &mut Vec<char>, after: &mut Vec<char>) {
+    // First shift the edit as far to the left as possible.
+    let len = common_suffix(before, edit);
+    let shifted = edit.split_off(edit.len() - len);
+    before.truncate(before.len() - len);
+    edit.splice(0..0, shifted.iter().copied());
+    after.splice(0..0, shifted);
+
+    // Then step to the right one character at a time, keeping the best fit.
+    let mut best_score = score(before, edit) + score(edit, after);
+    let mut best = (before.clone(), edit.clone(), after.clone());
+
+    while !edit.is_empty() && edit.first() == after.first() {
+        before.push(edit.remove(0));
+        edit.push(after.remove(0));
+
+        let score = score(before, edit) + score(edit, after);
+
+        if score >= best_score {
+            best_score = score;
+            best = (before.clone(), edit.clone(), after.clone());
+        }
+    }
+
+    (*before, *edit, *after) = best;
+}
+
+/// Returns how well the boundary between the given texts falls on a logical
+/// boundary, from 6 (the edge of the text) to 0 (the middle of a word).
+fn score(lhs: &[char], rhs: &[char]) -> usize {
+    let (Some(&last), Some(&first)) = (lhs.last(), rhs.first()) else {
+        return 6;
+    };
+
+    let non_alphanumeric = (!last.is_alphanumeric(), !first.is_alphanumeric());
+    let whitespace = (
+        non_alphanumeric.0 && last.is_whitespace(),
+        non_alphanumeric.1 && first.is_whitespace(),
+    );
+    let line_break = (
+        whitespace.0 && matches!(last, '\r' | '\n'),
+        whitespace.1 && matches!(first, '\r' | '\n'),
+    );
+    let blank_line = (
+        line_break.0 && ends_with_blank_line(lhs),
+        line_break.1 && starts_with_blank_line(rhs),
+    );
+
+    if blank_line.0 || blank_line.1 {
+        5
+    } else if line_break.0 || line_break.1 {
+        4
+    } else if non_alphanumeric.0 && !whitespace.0 && whitespace.1 {
+        3
+    } else if whitespace.0 || whitespace.1 {
+        2
+    } else if non_alphanumeric.0 || non_alphanumeric.1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Returns true if the given text ends with two line breaks.
+fn ends_with_blank_line(text: &[char]) -> bool {
+    let Some(text) = text.strip_suffix(&['\n']) else {
+        return false;
+    };
+
+    let text = text.strip_suffix(&['\r']).unwrap_or(text);
+    text.ends_with(&['\n'])
+}
+
+/// Returns true if the given text starts with two line breaks.
+fn starts_with_blank_line(text: &[char]) -> bool {
+    fn strip_line_break(text: &[char]) -> Option<&[char]> {
+        text.strip_prefix(&['\r'])
+            .unwrap_or(text)
+            .strip_prefix(&['\n'])
+    }
+
+    strip_line_break(text).and_then(strip_line_break).is_some()
+}
+
+/// Returns the length of the common prefix of the given texts.
+fn common_prefix(lhs: &[char], rhs: &[char]) -> usize {
+    lhs.iter()
+        .zip(rhs)
+        .take_while(|(lhs, rhs)| lhs == rhs)
+        .count()
+}
+
+/// Returns the length of the common suffix of the given texts.
+fn common_suffix(lhs: &[char], rhs: &[char]) -> usize {
+    lhs.iter()
+        .rev()
+        .zip(rhs.iter().rev())
+        .take_while(|(lhs, rhs)| lhs == rhs)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "similar")]
+    use similar::TextDiff;
+
+    #[cfg(feature = "similar")]
+    use crate::attributes::{AttrValue, Attributes};
+    #[cfg(feature = "similar")]
+    use crate::Compose;
+    use crate::Delta;
+
+    #[test]
+    #[cfg(feature = "similar")]
+    fn test_from_text_diff() {
+        let old = "Hello World\nHow are you?\n";
+        let new = "Hello, World\nHow is it going?\nBye\n";
+        let before = Delta::<String, Attributes>::new().insert(old.to_owned(), None);
+
+        for diff in [
+            TextDiff::from_lines(old, new),
+            TextDiff::from_words(old, new),
+            TextDiff::from_chars(old, new),
+        ] {
+            let delta = Delta::from(&diff);
+
+            assert_eq!(delta.base_len(), old.chars().count());
+            assert_eq!(before.clone().compose(delta).plain_text(), new);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "similar")]
+    fn test_from_text_diff_chars() {
+        let diff = TextDiff::from_chars("The cat", "The bat!");
+
+        assert_eq!(
+            Delta::<String, ()>::from(&diff),
+            Delta::new()
+                .retain(4, None)
+                .delete(1)
+                .insert("b".to_owned(), None)
+                .retain(2, None)
+                .insert("!".to_owned(), None)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "similar")]
+    fn test_diff() {
+        let bold = Attributes::from([("bold".to_owned(), true.into())]);
+        let italic = Attributes::from([("italic".to_owned(), true.into())]);
+        let old = Delta::new()
+            .insert("Hello".to_owned(), None)
+            .insert(" World".to_owned(), italic.clone());
+        let new = Delta::new()
+            .insert("Hello".to_owned(), bold.clone())
+            .insert(" World".to_owned(), None)
+            .insert("!".to_owned(), italic);
+
+        let delta = old.diff(&new);
+
+        assert_eq!(
+            delta,
+            Delta::new()
+                .retain(5, bold)
+                .retain(
+                    6,
+                    Attributes::from([("italic".to_owned(), AttrValue::Null)])
+                )
+                .insert(
+                    "!".to_owned(),
+                    Attributes::from([("italic".to_owned(), true.into())])
+                )
+        );
+        assert_eq!(old.compose(delta).plain_text(), new.plain_text());
+    }
+
+    #[test]
+    fn test_cleanup_semantic() {
+        let delta = Delta::<String, bool>::new()
+            .insert("s".to_owned(), None)
+            .delete(1)
+            .retain(1, None)
+            .insert("fas".to_owned(), None)
+            .delete(3);
+
+        assert_eq!(
+            delta.clone().cleanup_semantic("mouse"),
+            Delta::new().insert("sofas".to_owned(), None).delete(5)
+        );
+
+        let delta = Delta::<String, bool>::new()
+            .insert("s".to_owned(), None)
+            .delete(1)
+            .retain(1, true)
+            .insert("fas".to_owned(), None)
+            .delete(3);
+
+        assert_eq!(delta.clone().cleanup_semantic("mouse"), delta);
+    }
+
+    #[test]
+    fn test_cleanup_semantic_shift() {
+        let delta = Delta::<String, bool>::new()
+            .retain(5, None)
+            .insert("ow and the c".to_owned(), None);
+
+        assert_eq!(
+            delta.cleanup_semantic("The cat."),
+            Delta::new()
+                .retain(4, None)
+                .insert("cow and the ".to_owned(), None)
+        );
+
+        let delta = Delta::<String, bool>::new()
+            .retain(1, None)
+            .insert("\n\nb".to_owned(), true);
+
+        assert_eq!(
+            delta.cleanup_semantic("a\n\nc"),
+            Delta::new()
+                .retain(3, None)
+                .insert("b\n\n".to_owned(), true)
+        );
+    }
+}
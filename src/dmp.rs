@@ -0,0 +1,424 @@
+use std::fmt::{self, Debug};
+
+use super::seq::split_at;
+use super::{Compose, Delta, Error, Len, Op};
+
+impl<A> Delta<String, A>
+where
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Returns the delta that has the same effect as the given
+    /// [diff-match-patch](https://github.com/google/diff-match-patch) patches,
+    /// in the textual format that `patch_toText` produces. Like `patch_apply`,
+    /// this applies the patches in order, so that each patch starts at its
+    /// position in the text that results from applying the previous patches.
+    /// Unlike `patch_apply`, this doesn't look for the context of each patch
+    /// near its expected position, so the patches must have been made for the
+    /// text that this delta is applied to.
+    ///
+    /// Positions and lengths count characters rather than the UTF-16 code units
+    /// that the JavaScript implementation uses. Returns [`Error::InvalidPatch`]
+    /// if the patches can't be parsed or a header doesn't match its diffs.
+    pub fn from_dmp_patches(patches: &str) -> Result<Self, Error> {
+        let mut delta = Delta::new();
+        let mut lines = patches.lines().enumerate().peekable();
+
+        while let Some((line, header)) = lines.next() {
+            if header.is_empty() {
+                continue;
+            }
+
+            let (start, lhs_len, rhs_len) =
+                parse_header(header).ok_or(Error::InvalidPatch { line })?;
+
+            let mut change = Delta::new().retain(start, None);
+            let mut consumed = 0usize;
+            let mut produced = 0usize;
+
+            while let Some((index, diff)) =
+                lines.next_if(|(_, diff)| !diff.is_empty() && !diff.starts_with('@'))
+            {
+                let text = decode(&diff[1..]).ok_or(Error::InvalidPatch { line: index })?;
+                let len = text.chars().count();
+
+                change = match &diff[..1] {
+                    " " => {
+                        consumed += len;
+                        produced += len;
+                        change.retain(len, None)
+                    }
+                    "-" => {
+                        consumed += len;
+                        change.delete(len)
+                    }
+                    "+" => {
+                        produced += len;
+                        change.insert(text, None)
+                    }
+                    _ => return Err(Error::InvalidPatch { line: index }),
+                };
+            }
+
+            if consumed != lhs_len || produced != rhs_len {
+                return Err(Error::InvalidPatch { line });
+            }
+
+            delta = delta.compose(change);
+        }
+
+        Ok(delta)
+    }
+}
+
+impl<A> Delta<String, A> {
+    /// Returns the diff-match-patch patches (in the textual format that
+    /// `patch_fromText` parses) that have the same effect as this delta when
+    /// applied to the given text. Like `patch_make`, each change is surrounded
+    /// by 4 characters of context (or more if necessary to make its context
+    /// unique), so that `patch_apply` can find it. Attributes are dropped. See
+    /// [`Delta::from_dmp_patches`].
+    pub fn to_dmp_patches(&self, base: &str) -> String {
+        let mut diffs = vec![];
+        let mut deleted = String::new();
+        let mut inserted = String::new();
+        let mut rest = base;
+
+        for op in self.ops() {
+            match op {
+                Op::Insert(insert) => inserted.push_str(insert.insert()),
+                Op::Retain(retain) => {
+                    let (kept, after) = split_at(rest, retain.len());
+                    rest = after;
+
+                    diffs.push((DELETE, std::mem::take(&mut deleted)));
+                    diffs.push((INSERT, std::mem::take(&mut inserted)));
+                    diffs.push((EQUAL, kept.to_owned()));
+                }
+                Op::Delete(delete) => {
+                    let (removed, after) = split_at(rest, delete.len());
+                    deleted.push_str(removed);
+                    rest = after;
+                }
+            }
+        }
+
+        diffs.push((DELETE, deleted));
+        diffs.push((INSERT, inserted));
+        diffs.push((EQUAL, rest.to_owned()));
+        diffs.retain(|(_, text)| !text.is_empty());
+
+        make_patches(base, &diffs)
+            .iter()
+            .map(Patch::to_string)
+            .collect()
+    }
+}
+
+/// Number of characters of context that `patch_make` adds around each change.
+const MARGIN: usize = 4;
+
+/// Maximum length of the context of a patch (`Match_MaxBits`).
+const MAX_BITS: usize = 32;
+
+const DELETE: char = '-';
+const INSERT: char = '+';
+const EQUAL: char = ' ';
+
+/// Patch that consists of a series of diffs (with their context), which is
+/// applied to the text that results from applying the previous patches.
+#[derive(Default)]
+struct Patch {
+    diffs: Vec<(char, String)>,
+    lhs_start: usize,
+    rhs_start: usize,
+    lhs_len: usize,
+    rhs_len: usize,
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "@@ -{} +{} @@",
+            format_range(self.lhs_start, self.lhs_len),
+            format_range(self.rhs_start, self.rhs_len)
+        )?;
+
+        for (op, text) in &self.diffs {
+            writeln!(f, "{op}{}", encode(text))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Groups the given diffs of the given text into patches like `patch_make`.
+fn make_patches(text: &str, diffs: &[(char, String)]) -> Vec<Patch> {
+    let mut patches = vec![];
+    let mut patch = Patch::default();
+    let mut prepatch = text.chars().collect::<Vec<_>>();
+    let mut postpatch = prepatch.clone();
+    let mut lhs_position = 0usize;
+    let mut rhs_position = 0usize;
+
+    for (index, (op, text)) in diffs.iter().enumerate() {
+        let chars = text.chars().collect::<Vec<_>>();
+
+        if patch.diffs.is_empty() && *op != EQUAL {
+            patch.lhs_start = lhs_position;
+            patch.rhs_start = rhs_position;
+        }
+
+        match *op {
+            INSERT => {
+                patch.diffs.push((*op, text.clone()));
+                patch.rhs_len += chars.len();
+                postpatch.splice(rhs_position..rhs_position, chars.iter().copied());
+            }
+            DELETE => {
+                patch.diffs.push((*op, text.clone()));
+                patch.lhs_len += chars.len();
+                postpatch.drain(rhs_position..rhs_position + chars.len());
+            }
+            _ => {
+                if chars.len() <= 2 * MARGIN && !patch.diffs.is_empty() && index + 1 != diffs.len()
+                {
+                    patch.diffs.push((*op, text.clone()));
+                    patch.lhs_len += chars.len();
+                    patch.rhs_len += chars.len();
+                } else if chars.len() >= 2 * MARGIN && !patch.diffs.is_empty() {
+                    add_context(&mut patch, &prepatch);
+                    patches.push(std::mem::take(&mut patch));
+                    prepatch = postpatch.clone();
+                    lhs_position = rhs_position;
+                }
+            }
+        }
+
+        if *op != INSERT {
+            lhs_position += chars.len();
+        }
+
+        if *op != DELETE {
+            rhs_position += chars.len();
+        }
+    }
+
+    if !patch.diffs.is_empty() {
+        add_context(&mut patch, &prepatch);
+        patches.push(patch);
+    }
+
+    patches
+}
+
+/// Surrounds the given patch with enough context to make it unique in the
+/// given text (up to [`MAX_BITS`]) like `patch_addContext`.
+fn add_context(patch: &mut Patch, text: &[char]) {
+    if text.is_empty() {
+        return;
+    }
+
+    let start = patch.rhs_start;
+    let end = start + patch.lhs_len;
+    let mut padding = 0;
+    let mut pattern = &text[start..end];
+
+    while find(text, pattern) != rfind(text, pattern) && pattern.len() < MAX_BITS - 2 * MARGIN {
+        padding += MARGIN;
+        pattern = &text[start.saturating_sub(padding)..(end + padding).min(text.len())];
+    }
+
+    padding += MARGIN;
+
+    let prefix = text[start.saturating_sub(padding)..start]
+        .iter()
+        .collect::<String>();
+    let suffix = text[end..(end + padding).min(text.len())]
+        .iter()
+        .collect::<String>();
+    let prefix_len = prefix.chars().count();
+    let suffix_len = suffix.chars().count();
+
+    if !prefix.is_empty() {
+        patch.diffs.insert(0, (EQUAL, prefix));
+    }
+
+    if !suffix.is_empty() {
+        patch.diffs.push((EQUAL, suffix));
+    }
+
+    patch.lhs_start -= prefix_len;
+    patch.rhs_start -= prefix_len;
+    patch.lhs_len += prefix_len + suffix_len;
+    patch.rhs_len += prefix_len + suffix_len;
+}
+
+/// Returns the index of the first occurrence of the given pattern in the given
+/// text.
+fn find(text: &[char], pattern: &[char]) -> Option<usize> {
+    (0..=text.len().checked_sub(pattern.len())?).find(|&i| text[i..].starts_with(pattern))
+}
+
+/// Returns the index of the last occurrence of the given pattern in the given
+/// text.
+fn rfind(text: &[char], pattern: &[char]) -> Option<usize> {
+    (0..=text.len().checked_sub(pattern.len())?)
+        .rev()
+        .find(|&i| text[i..].starts_with(pattern))
+}
+
+/// Formats the given range like `patch_toText`, which uses one-based positions
+/// except for empty ranges.
+fn format_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        len => format!("{},{len}", start + 1),
+    }
+}
+
+/// Parses a header (e.g. `@@ -1,8 +1,7 @@`) into the (zero-based) position of
+/// the patch in the text that results from applying the previous patches and
+/// the lengths of the text before and after applying the patch.
+fn parse_header(header: &str) -> Option<(usize, usize, usize)> {
+    let ranges = header.strip_prefix("@@ -")?.strip_suffix(" @@")?;
+    let (lhs, rhs) = ranges.split_once(" +")?;
+    let (_, lhs_len) = parse_range(lhs)?;
+    let (start, rhs_len) = parse_range(rhs)?;
+
+    Some((start, lhs_len, rhs_len))
+}
+
+/// Parses a range (e.g. `1,8`) into a zero-based position and a length.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        None => Some((range.parse::<usize>().ok()?.checked_sub(1)?, 1)),
+        Some((start, "0")) => Some((start.parse().ok()?, 0)),
+        Some((start, len)) => Some((
+            start.parse::<usize>().ok()?.checked_sub(1)?,
+            len.parse().ok()?,
+        )),
+    }
+}
+
+/// Encodes the given text like `patch_toText`, which uses `encodeURI` but
+/// keeps spaces.
+fn encode(text: &str) -> String {
+    let mut encoded = String::new();
+
+    for c in text.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => encoded.push(c),
+            ' ' | '-' | '_' | '.' | '!' | '~' | '*' | '\'' | '(' | ')' | ';' | '/' | '?' | ':'
+            | '@' | '&' | '=' | '+' | '$' | ',' | '#' => encoded.push(c),
+            c => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    encoded.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes the percent-encoded UTF-8 in the given text.
+fn decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::{Compose, Delta, Error};
+
+    fn bold() -> Attributes {
+        [("bold".to_owned(), AttrValue::Bool(true))]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_from_dmp_patches() {
+        let patches = "@@ -1,7 +1,8 @@\n Th\n-e\n+at\n  qui\n@@ -10,0 +11,2 @@\n+%C3%A9%0A\n";
+        let before = Delta::new()
+            .insert("The ".to_owned(), None)
+            .insert("quick".to_owned(), bold());
+
+        assert_eq!(
+            before.compose(Delta::from_dmp_patches(patches).unwrap()),
+            Delta::new()
+                .insert("That ".to_owned(), None)
+                .insert("quick".to_owned(), bold())
+                .insert("é\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_to_dmp_patches() {
+        let delta = Delta::<String, ()>::new()
+            .retain(2, None)
+            .insert("at".to_owned(), None)
+            .delete(1)
+            .retain(16, None)
+            .insert("ö".to_owned(), None);
+
+        assert_eq!(
+            delta.to_dmp_patches("The quick brown fox"),
+            "@@ -1,7 +1,8 @@\n Th\n-e\n+at\n  qui\n@@ -13,8 +13,9 @@\n rown fox\n+%C3%B6\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let base = "Hello World\nHow are you?\n";
+        let before = Delta::<String, ()>::new().insert(base.to_owned(), None);
+        let delta = Delta::new()
+            .delete(1)
+            .insert("J".to_owned(), None)
+            .retain(5, None)
+            .delete(6)
+            .insert("100% ✓".to_owned(), None)
+            .retain(5, None)
+            .delete(3);
+
+        let patches = delta.to_dmp_patches(base);
+
+        assert_eq!(
+            before
+                .clone()
+                .compose(Delta::from_dmp_patches(&patches).unwrap()),
+            before.compose(delta)
+        );
+    }
+
+    #[test]
+    fn test_invalid_dmp_patches() {
+        assert_eq!(
+            Delta::<String, ()>::from_dmp_patches("@@ -1,3 +1,3 @@\n a\n"),
+            Err(Error::InvalidPatch { line: 0 })
+        );
+        assert_eq!(
+            Delta::<String, ()>::from_dmp_patches("@@ -1 +1 @@\n-%ZZ\n"),
+            Err(Error::InvalidPatch { line: 1 })
+        );
+        assert_eq!(
+            Delta::<String, ()>::from_dmp_patches("@@ -1 @@\n"),
+            Err(Error::InvalidPatch { line: 0 })
+        );
+    }
+}
@@ -0,0 +1,736 @@
+use std::fmt::Debug;
+use std::hash::Hasher;
+use std::mem::take;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::anchor::Anchors;
+use super::hash::ContentHasher;
+use super::{AnchorId, Bias, Compose, Delta, Error, Hashable, Len, Op, Seq, Transform};
+
+/// Delta that only consists of inserts, i.e. the contents of a document.
+///
+/// Unlike a plain [`Delta`], a [`Document`] guarantees that it never contains
+/// any retains or deletes, so that its [`len`](Document::len) is always the
+/// number of elements in the document.
+///
+/// A document can also hold the provisional text of an IME composition (see
+/// [`Document::begin_composition`]). That text is part of its contents, so
+/// it's included when the document is serialized, converted into a delta or
+/// hashed (see [`Document::content_hash`]). Only the state of the composition
+/// isn't serialized, so a deserialized document isn't composing.
+///
+/// Finally, a document keeps its anchors (see [`Document::create_anchor`]) up
+/// to date as changes are applied. Like the state of the composition, they
+/// aren't serialized. Documents are only equal if they also have the same
+/// composition and anchors.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(try_from = "Delta<T, A>", into = "Delta<T, A>")]
+#[serde(bound(
+    serialize = "T: Clone + Serialize, A: Clone + Serialize",
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub struct Document<T, A> {
+    delta: Delta<T, A>,
+    composition: Option<Composition<T, A>>,
+    anchors: Anchors,
+}
+
+/// State of an active IME composition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Composition<T, A> {
+    /// Change from the document without the composition to the document with
+    /// it, which retains up to the composition, inserts the composed text and
+    /// deletes the range that it replaces. It never contains any other inserts,
+    /// because transforming it against remote changes turns their inserts into
+    /// retains.
+    provisional: Delta<T, A>,
+
+    /// True once the provisional change has been applied to the document,
+    /// which only happens with the first update, so that a composition that's
+    /// never updated doesn't delete the range that it was started with.
+    applied: bool,
+}
+
+impl<T, A> Document<T, A> {
+    /// Returns a new empty document.
+    pub fn new() -> Document<T, A> {
+        Document {
+            delta: Delta::default(),
+            composition: None,
+            anchors: Anchors::default(),
+        }
+    }
+
+    /// Returns the delta that represents this document.
+    pub fn as_delta(&self) -> &Delta<T, A> {
+        &self.delta
+    }
+
+    /// Returns the position that the given anchor currently refers to, or
+    /// `None` if it was removed.
+    pub fn resolve(&self, anchor: AnchorId) -> Option<usize> {
+        self.anchors.resolve(anchor)
+    }
+
+    /// Removes the given anchor and returns its last position, if it wasn't
+    /// removed already.
+    pub fn remove_anchor(&mut self, anchor: AnchorId) -> Option<usize> {
+        self.anchors.remove(anchor)
+    }
+
+    /// Returns the anchors whose elements were deleted since the last call (in
+    /// the order in which they were deleted), e.g. to resolve the comments
+    /// that they belong to. An anchor's element is the one before it if its
+    /// bias is [`Bias::Left`], or the one after it otherwise.
+    ///
+    /// These anchors aren't removed: they stay at the position where their
+    /// element was deleted until they're [removed](Document::remove_anchor).
+    pub fn take_deleted_anchors(&mut self) -> Vec<AnchorId> {
+        self.anchors.take_deleted()
+    }
+
+    pub(crate) fn anchors_mut(&mut self) -> &mut Anchors {
+        &mut self.anchors
+    }
+
+    /// Returns the delta that represents this document.
+    pub fn into_delta(self) -> Delta<T, A> {
+        self.delta
+    }
+}
+
+impl<T, A> Document<T, A>
+where
+    T: Len,
+{
+    /// Returns a document with the contents of the given delta, or an error if
+    /// that delta contains any retains or deletes.
+    pub fn from_delta(delta: Delta<T, A>) -> Result<Document<T, A>, Error> {
+        delta.check_document()?;
+
+        Ok(Document {
+            delta,
+            composition: None,
+            anchors: Anchors::default(),
+        })
+    }
+
+    /// Returns the number of elements in this document.
+    pub fn len(&self) -> usize {
+        self.delta
+            .ops()
+            .fold(0usize, |len, op| len.saturating_add(op.len()))
+    }
+
+    /// Returns true if this document doesn't contain any elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Creates an anchor at the given position (clamped to the length of this
+    /// document) with the given bias and returns its handle.
+    ///
+    /// Unlike a raw position, which every caller has to transform against
+    /// every change, this document moves its anchors along with each change
+    /// that it applies (in any way, e.g. [`Document::apply`] or
+    /// [`Document::apply_remote`]), like [`Transform<usize>`] with priority for
+    /// [`Bias::Left`]. Anchors whose elements are deleted are reported by
+    /// [`Document::take_deleted_anchors`].
+    pub fn create_anchor(&mut self, position: usize, bias: Bias) -> AnchorId {
+        let position = position.min(self.len());
+        self.anchors.create(position, bias)
+    }
+}
+
+impl<T, A> Document<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change to this document. Like [`Compose`], this
+    /// silently clips retains and deletes that extend past the end of this
+    /// document.
+    pub fn apply(&mut self, change: Delta<T, A>) {
+        self.anchors.update(change.ops());
+
+        let delta = take(&mut self.delta).compose(change);
+
+        self.delta = delta
+            .into_iter()
+            .filter(|op| matches!(op, Op::Insert(_)))
+            .collect();
+    }
+
+    /// Applies the given change to this document, or returns an error if that
+    /// change isn't well-formed or extends past the end of this document. In
+    /// that case, this document is left unchanged.
+    pub fn try_apply(&mut self, change: Delta<T, A>) -> Result<(), Error> {
+        change.check_applicable(self.len())?;
+        self.apply(change);
+        Ok(())
+    }
+
+    /// Applies the given change to this document like [`Document::apply`], but
+    /// lets every insert without attributes inherit the attributes of the
+    /// element that precedes it (e.g. to keep typing in bold). The given
+    /// function receives the attributes of the preceding element and returns
+    /// the attributes to inherit, so that e.g. links don't extend as text is
+    /// typed after them. Inserts at the start of this document or after an
+    /// element without attributes are left unchanged.
+    ///
+    /// Returns the change with the inherited attributes, which should be sent
+    /// to other clients instead of the given change so that they converge.
+    pub fn apply_sticky<F>(&mut self, change: Delta<T, A>, mut inherit: F) -> Delta<T, A>
+    where
+        F: FnMut(&A) -> Option<A>,
+    {
+        let mut after = self.clone();
+        after.apply(change.clone());
+
+        let mut inserts = after.delta.ops().filter_map(|op| match op {
+            Op::Insert(insert) => Some(insert),
+            Op::Retain(_) | Op::Delete(_) => None,
+        });
+        let mut current = inserts.next();
+        let mut start = 0usize;
+        let mut attributes_at = |index: usize| {
+            while let Some(insert) = current {
+                if index < start.saturating_add(insert.len()) {
+                    return insert.attributes.as_ref();
+                }
+
+                start = start.saturating_add(insert.len());
+                current = inserts.next();
+            }
+
+            None
+        };
+
+        let mut position = 0usize;
+        let change = change
+            .into_iter()
+            .map(|op| {
+                let op = match op {
+                    Op::Insert(mut insert) if insert.attributes.is_none() && position > 0 => {
+                        insert.attributes = attributes_at(position - 1).and_then(&mut inherit);
+                        Op::Insert(insert)
+                    }
+                    op => op,
+                };
+
+                if !matches!(op, Op::Delete(_)) {
+                    position = position.saturating_add(op.len());
+                }
+
+                op
+            })
+            .collect::<Delta<T, A>>();
+
+        self.apply(change.clone());
+        change
+    }
+
+    /// Starts an IME composition that replaces the given range of this document
+    /// (e.g. the selection, or an empty range at the cursor). The range is
+    /// clamped to the length of this document and is only replaced once the
+    /// composition is first [updated](Document::update_composition).
+    ///
+    /// Returns the change of the composition that was still active, if any (see
+    /// [`Document::commit_composition`]).
+    pub fn begin_composition(&mut self, range: Range<usize>) -> Option<Delta<T, A>> {
+        let committed = self.commit_composition();
+        let len = self.len();
+        let start = range.start.min(len);
+        let end = range.end.clamp(start, len);
+
+        self.composition = Some(Composition {
+            provisional: Delta::new().retain(start, None).delete(end - start),
+            applied: false,
+        });
+
+        committed
+    }
+
+    /// Replaces the provisional text of the active composition with the given
+    /// text (without attributes) and returns the change that was applied to
+    /// this document, e.g. to update the editor. Does nothing and returns an
+    /// empty delta if there's no active composition.
+    ///
+    /// The provisional text must not be sent to other clients: only the result
+    /// of [`Document::commit_composition`] should.
+    pub fn update_composition(&mut self, text: T) -> Delta<T, A> {
+        let Some(composition) = self.composition.as_mut() else {
+            return Delta::new();
+        };
+
+        let mut ops = take(&mut composition.provisional).into_iter().peekable();
+        let start = ops.next_if(|op| matches!(op, Op::Retain(_)));
+        let start = start.map_or(0, |op| op.len());
+        let composed = ops.next_if(|op| matches!(op, Op::Insert(_)));
+        let composed = composed.map_or(0, |op| op.len());
+        let mut provisional = Delta::new().retain(start, None).insert(text.clone(), None);
+
+        provisional.extend(ops);
+
+        let change = match composition.applied {
+            true => Delta::new()
+                .retain(start, None)
+                .insert(text, None)
+                .delete(composed),
+            false => provisional.clone(),
+        };
+
+        composition.provisional = provisional;
+        composition.applied = true;
+
+        self.apply(change.clone());
+        change
+    }
+
+    /// Ends the active composition and returns its change, i.e. the change that
+    /// replaces the range that the composition started with with its composed
+    /// text, which should be sent to other clients. Returns `None` if there's
+    /// no active composition or it was never updated.
+    ///
+    /// The change applies after every change that was passed to
+    /// [`Document::apply_remote`] during the composition.
+    pub fn commit_composition(&mut self) -> Option<Delta<T, A>> {
+        self.composition
+            .take()
+            .filter(|composition| composition.applied)
+            .map(|composition| composition.provisional.chop())
+    }
+
+    /// Returns true if there's an active composition.
+    pub fn is_composing(&self) -> bool {
+        self.composition.is_some()
+    }
+
+    /// Applies the given remote change, which doesn't know about the active
+    /// composition (if any), to this document and returns it as it was
+    /// applied, e.g. to update the editor.
+    ///
+    /// The change is transformed against the provisional text of the
+    /// composition, whose change is rebased onto the given change in turn. The
+    /// remote change has priority, because the server already applied it before
+    /// it will receive the composed text. Without an active composition, this
+    /// is the same as [`Document::apply`].
+    pub fn apply_remote(&mut self, change: Delta<T, A>) -> Delta<T, A> {
+        let change = match self.composition.as_mut() {
+            Some(composition) => {
+                let provisional = take(&mut composition.provisional);
+                composition.provisional = change.clone().transform(provisional.clone(), true);
+
+                match composition.applied {
+                    true => provisional.transform(change, false),
+                    false => change,
+                }
+            }
+            None => change,
+        };
+
+        self.apply(change.clone());
+        change
+    }
+}
+
+impl<T, A> Document<T, A>
+where
+    T: Hashable,
+    A: Hashable,
+{
+    /// Returns a digest of the contents of this document that's stable across
+    /// platforms and versions of this library (see [`Hashable`]). Clients and
+    /// servers can exchange it after a reconnect to cheaply detect whether
+    /// their documents diverged.
+    ///
+    /// The hash covers every insert and its attributes in order, so documents
+    /// only hash the same if they also split their contents into the same
+    /// inserts. That's always the case for documents that are built with
+    /// [`Delta::insert`] and [`Document::apply`], which merge adjacent inserts
+    /// with equal attributes.
+    ///
+    /// This is not a cryptographic hash and must not be used to detect
+    /// deliberate tampering.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+
+        for op in self.delta.ops() {
+            if let Op::Insert(insert) = op {
+                insert.insert().hash_content(&mut hasher);
+                insert.attributes.hash_content(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+impl<T, A> Default for Document<T, A> {
+    fn default() -> Self {
+        Document::new()
+    }
+}
+
+impl<T, A> TryFrom<Delta<T, A>> for Document<T, A>
+where
+    T: Len,
+{
+    type Error = Error;
+
+    fn try_from(value: Delta<T, A>) -> Result<Self, Self::Error> {
+        Document::from_delta(value)
+    }
+}
+
+impl<T, A> From<Document<T, A>> for Delta<T, A> {
+    fn from(value: Document<T, A>) -> Self {
+        value.delta
+    }
+}
+
+impl<A> Document<String, A>
+where
+    A: Clone + PartialEq,
+{
+    /// Verifies that this document ends with a newline, which Quill requires of
+    /// every document (even empty ones).
+    pub fn check_trailing_newline(&self) -> Result<(), Error> {
+        match self.delta.ops().last() {
+            Some(Op::Insert(insert)) if insert.insert().ends_with('\n') => Ok(()),
+            _ => Err(Error::MissingTrailingNewline),
+        }
+    }
+
+    /// Appends a newline (without attributes) to this document if it doesn't
+    /// end with a newline yet. Like any other change, this moves the anchors at
+    /// the end of this document that have a [`Bias::Right`] past the newline.
+    pub fn ensure_trailing_newline(&mut self) {
+        if self.check_trailing_newline().is_err() {
+            let change = Delta::<String, A>::new()
+                .retain(self.len(), None)
+                .insert("\n".to_owned(), None);
+
+            self.anchors.update(change.ops());
+            self.delta = take(&mut self.delta).insert("\n".to_owned(), None);
+        }
+    }
+}
+
+impl<A> Document<String, A>
+where
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change to this document and restores the trailing
+    /// newline if that change removed it, so that this document remains a
+    /// valid Quill document.
+    pub fn apply_preserving_newline(&mut self, change: Delta<String, A>) {
+        self.apply(change);
+        self.ensure_trailing_newline();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attributes::{AttrValue, Attributes};
+
+    use super::{Bias, Delta, Document, Error};
+
+    #[test]
+    fn test_from_delta() {
+        assert_eq!(
+            Document::from_delta(Delta::<_, ()>::new().insert("a".to_owned(), None))
+                .map(|document| document.len()),
+            Ok(1)
+        );
+        assert_eq!(
+            Document::from_delta(
+                Delta::<_, ()>::new()
+                    .insert("a".to_owned(), None)
+                    .retain(1, None)
+            ),
+            Err(Error::NotADocument { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut document =
+            Document::from_delta(Delta::new().insert("Hello\n".to_owned(), ())).unwrap();
+
+        document.apply(Delta::new().retain(5, ()).insert("!".to_owned(), ()));
+        assert_eq!(
+            document.as_delta(),
+            &Delta::new().insert("Hello!\n".to_owned(), ())
+        );
+
+        assert_eq!(
+            document.try_apply(Delta::new().delete(8)),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 7,
+                actual: 8
+            })
+        );
+        assert_eq!(document.len(), 7);
+    }
+
+    #[test]
+    fn test_apply_sticky() {
+        let attributes = |key: &str| Attributes::from([(key.to_owned(), AttrValue::Bool(true))]);
+        let not_link = |attributes: &Attributes| {
+            let mut attributes = attributes.clone();
+            attributes.remove("link");
+            Some(attributes).filter(|attributes| !attributes.is_empty())
+        };
+
+        let mut document = Document::from_delta(
+            Delta::new()
+                .insert("ab".to_owned(), attributes("bold"))
+                .insert("cd".to_owned(), attributes("link"))
+                .insert("\n".to_owned(), None),
+        )
+        .unwrap();
+
+        let change = document.apply_sticky(
+            Delta::new()
+                .insert("x".to_owned(), None)
+                .retain(2, None)
+                .insert("y".to_owned(), None)
+                .retain(2, None)
+                .insert("z".to_owned(), None)
+                .retain(1, None)
+                .insert("w".to_owned(), None),
+            not_link,
+        );
+
+        assert_eq!(
+            change,
+            Delta::new()
+                .insert("x".to_owned(), None)
+                .retain(2, None)
+                .insert("y".to_owned(), attributes("bold"))
+                .retain(2, None)
+                .insert("z".to_owned(), None)
+                .retain(1, None)
+                .insert("w".to_owned(), None)
+        );
+        assert_eq!(
+            document.as_delta(),
+            &Delta::new()
+                .insert("x".to_owned(), None)
+                .insert("aby".to_owned(), attributes("bold"))
+                .insert("cd".to_owned(), attributes("link"))
+                .insert("z\nw".to_owned(), None)
+        );
+
+        let change = document.apply_sticky(
+            Delta::new()
+                .retain(1, None)
+                .delete(2)
+                .insert("v".to_owned(), None),
+            |attributes| Some(attributes.clone()),
+        );
+
+        assert_eq!(
+            change,
+            Delta::new()
+                .retain(1, None)
+                .insert("v".to_owned(), None)
+                .delete(2)
+        );
+    }
+
+    #[test]
+    fn test_composition() {
+        let text = |text: &str| Delta::<String, ()>::new().insert(text.to_owned(), None);
+        let mut server = Document::from_delta(text("Hello\n")).unwrap();
+        let mut client = server.clone();
+
+        assert_eq!(client.begin_composition(5..5), None);
+        assert!(client.is_composing());
+        assert_eq!(
+            client.update_composition("k".to_owned()),
+            Delta::new().retain(5, None).insert("k".to_owned(), None)
+        );
+        assert_eq!(
+            client.update_composition("か".to_owned()),
+            Delta::new()
+                .retain(5, None)
+                .insert("か".to_owned(), None)
+                .delete(1)
+        );
+
+        let remote = Delta::new().insert("Oh, ".to_owned(), None);
+        server.apply(remote.clone());
+        assert_eq!(client.apply_remote(remote.clone()), remote);
+
+        client.update_composition("かな".to_owned());
+
+        let remote = Delta::new().retain(9, None).insert("X".to_owned(), None);
+        server.apply(remote.clone());
+        assert_eq!(client.apply_remote(remote.clone()), remote);
+        assert_eq!(client.as_delta(), &text("Oh, HelloXかな\n"));
+
+        let change = client.commit_composition().unwrap();
+        assert_eq!(
+            change,
+            Delta::new()
+                .retain(10, None)
+                .insert("かな".to_owned(), None)
+        );
+        assert!(!client.is_composing());
+
+        server.apply(change);
+        assert_eq!(client, server);
+    }
+
+    #[test]
+    fn test_composition_replace() {
+        let text = |text: &str| Delta::<String, ()>::new().insert(text.to_owned(), None);
+        let mut server = Document::from_delta(text("abcdef\n")).unwrap();
+        let mut client = server.clone();
+
+        client.begin_composition(1..5);
+
+        let remote = Delta::new().retain(3, None).insert("X".to_owned(), None);
+        server.apply(remote.clone());
+        assert_eq!(client.apply_remote(remote.clone()), remote);
+        assert_eq!(client.as_delta(), server.as_delta());
+
+        assert_eq!(
+            client.update_composition("y".to_owned()),
+            Delta::new()
+                .retain(1, None)
+                .insert("y".to_owned(), None)
+                .delete(2)
+                .retain(1, None)
+                .delete(2)
+        );
+
+        let remote = Delta::new().delete(2).insert("Z".to_owned(), None);
+        server.apply(remote.clone());
+        assert_eq!(
+            client.apply_remote(remote),
+            Delta::new().insert("Z".to_owned(), None).delete(1)
+        );
+
+        client.update_composition("yz".to_owned());
+        assert_eq!(client.as_delta(), &text("ZyzXf\n"));
+
+        let change = client.begin_composition(0..0).unwrap();
+        server.apply(change);
+        assert_eq!(client.as_delta(), server.as_delta());
+        assert_eq!(client.commit_composition(), None);
+    }
+
+    #[test]
+    fn test_composition_contents() {
+        let text = |text: &str| Delta::<String, ()>::new().insert(text.to_owned(), None);
+        let mut document = Document::from_delta(text("Hello\n")).unwrap();
+
+        document.begin_composition(5..5);
+        document.update_composition("かな".to_owned());
+
+        let contents = Document::from_delta(text("Helloかな\n")).unwrap();
+        let json = serde_json::to_value(&document).unwrap();
+
+        assert_eq!(json, serde_json::to_value(&contents).unwrap());
+        assert_eq!(document.content_hash(), contents.content_hash());
+        assert_eq!(Delta::from(document.clone()), text("Helloかな\n"));
+
+        let deserialized: Document<String, ()> = serde_json::from_value(json).unwrap();
+        assert!(!deserialized.is_composing());
+        assert_eq!(deserialized, contents);
+        assert_ne!(document, contents);
+
+        document.commit_composition();
+        assert_eq!(document, contents);
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let mut document = Document::<String, ()>::new();
+
+        assert_eq!(
+            document.check_trailing_newline(),
+            Err(Error::MissingTrailingNewline)
+        );
+
+        document.ensure_trailing_newline();
+        assert_eq!(document.check_trailing_newline(), Ok(()));
+        assert_eq!(
+            document.as_delta(),
+            &Delta::new().insert("\n".to_owned(), None)
+        );
+
+        document.apply_preserving_newline(Delta::new().insert("a".to_owned(), None).delete(1));
+        assert_eq!(
+            document.as_delta(),
+            &Delta::new().insert("a\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_trailing_newline_anchors() {
+        let mut document =
+            Document::<String, ()>::from_delta(Delta::new().insert("abc".to_owned(), None))
+                .unwrap();
+
+        let left = document.create_anchor(3, Bias::Left);
+        let right = document.create_anchor(3, Bias::Right);
+        let start = document.create_anchor(0, Bias::Right);
+
+        document.ensure_trailing_newline();
+
+        assert_eq!(document.resolve(left), Some(3));
+        assert_eq!(document.resolve(right), Some(4));
+        assert_eq!(document.resolve(start), Some(0));
+
+        document.ensure_trailing_newline();
+        assert_eq!(document.resolve(right), Some(4));
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let bold = |value: AttrValue| Attributes::from([("bold".to_owned(), value)]);
+        let document = |delta: Delta<String, Attributes>| Document::from_delta(delta).unwrap();
+
+        let mut lhs = document(Delta::new().insert("Hello\n".to_owned(), None));
+        lhs.apply(
+            Delta::new()
+                .retain(5, None)
+                .insert(" World".to_owned(), None),
+        );
+
+        let rhs = document(
+            Delta::new()
+                .insert("Hello".to_owned(), None)
+                .insert(" World\n".to_owned(), None),
+        );
+
+        assert_eq!(lhs.content_hash(), rhs.content_hash());
+        assert_eq!(lhs.content_hash(), 0x402c13e4cdc18d81);
+
+        let hashes = [
+            document(Delta::new().insert("Hello World".to_owned(), None)),
+            document(Delta::new().insert("Hello World\n".to_owned(), bold(true.into()))),
+            document(Delta::new().insert("Hello World\n".to_owned(), bold(1.into()))),
+            document(Delta::new().insert("Hello World\n".to_owned(), bold(1.0.into()))),
+            document(
+                Delta::new()
+                    .insert("Hello".to_owned(), bold(true.into()))
+                    .insert(" World\n".to_owned(), None),
+            ),
+        ]
+        .map(|document| document.content_hash());
+
+        for (index, hash) in hashes.iter().enumerate() {
+            assert_ne!(*hash, lhs.content_hash());
+            assert!(!hashes[..index].contains(hash));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+/// Error returned by the fallible (i.e. strict) counterparts of
+/// [`Compose`](crate::Compose) and [`Transform`](crate::Transform), such as
+/// [`Delta::try_compose`](crate::Delta::try_compose).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Returned when a delta retains or deletes more elements than the
+    /// document it's applied to contains.
+    LengthMismatch {
+        /// Contains the index of the first operation that extends past the end
+        /// of the document.
+        index: usize,
+
+        /// Contains the length of the document that the delta is applied to.
+        expected: usize,
+
+        /// Contains the number of elements that the delta retains or deletes
+        /// up to and including the offending operation.
+        actual: usize,
+    },
+
+    /// Returned when a delta contains an operation with a length of zero.
+    ZeroLength {
+        /// Contains the index of the offending operation.
+        index: usize,
+    },
+
+    /// Returned when a delta contains an insert that directly follows a
+    /// delete, which [`Delta::push`](crate::Delta::push) never produces.
+    InsertAfterDelete {
+        /// Contains the index of the offending insert.
+        index: usize,
+    },
+
+    /// Returned when a delta that is expected to be a document contains a
+    /// retain or delete.
+    NotADocument {
+        /// Contains the index of the first retain or delete.
+        index: usize,
+    },
+
+    /// Returned when a text document doesn't end with a newline, which Quill
+    /// requires.
+    MissingTrailingNewline,
+
+    /// Returned when an operation of a [`TreeDelta`](crate::tree::TreeDelta)
+    /// refers to a node that doesn't exist.
+    InvalidPath {
+        /// Contains the index of the offending operation.
+        index: usize,
+    },
+
+    /// Returned when an operation of a [`TableDelta`](crate::table::TableDelta)
+    /// refers to a row, column or cell that doesn't exist.
+    OutOfBounds {
+        /// Contains the index of the offending operation.
+        index: usize,
+    },
+
+    /// Returned when an operation of another format can't be converted into a
+    /// delta (e.g. a ShareDB json0 operation that replaces a string).
+    Unsupported {
+        /// Contains the index of the offending operation.
+        index: usize,
+    },
+
+    /// Returned when an Etherpad changeset can't be parsed or its header
+    /// doesn't match its operations.
+    InvalidChangeset {
+        /// Contains the byte offset in the changeset at which it's malformed.
+        offset: usize,
+    },
+
+    /// Returned when a diff-match-patch patch can't be parsed or its header
+    /// doesn't match its diffs.
+    InvalidPatch {
+        /// Contains the (zero-based) index of the line at which the patch is
+        /// malformed.
+        line: usize,
+    },
+
+    /// Returned when a delta that's encoded with
+    /// [`Delta::encode_compact`](crate::Delta::encode_compact) can't be
+    /// decoded.
+    InvalidEncoding {
+        /// Contains the byte offset at which the encoding is malformed.
+        offset: usize,
+    },
+
+    /// Returned by [`Delta::try_push`](crate::Delta::try_push) (and the checked
+    /// builders) when a retain or delete can't be merged into the preceding
+    /// operation because their combined length would overflow `usize::MAX`.
+    LengthOverflow {
+        /// Contains the index of the operation that would overflow.
+        index: usize,
+    },
+
+    /// Returned by [`Limits`](crate::validate::Limits) when a delta has too
+    /// many operations, too long an insert or would make the document too
+    /// long.
+    LimitExceeded {
+        /// Contains the index of the first operation that exceeds a limit.
+        index: usize,
+    },
+
+    /// Returned by [`ProtectedRanges`](crate::validate::ProtectedRanges) when a
+    /// delta changes a protected range of the document.
+    Protected {
+        /// Contains the index of the offending operation.
+        index: usize,
+    },
+
+    /// Returned by a custom [`DeltaValidator`](crate::validate::DeltaValidator)
+    /// that rejects a delta for another reason.
+    Rejected {
+        /// Contains a description of the reason, which may be shown to users.
+        reason: String,
+    },
+
+    /// Returned by [`AttributeFilter`](crate::validate::AttributeFilter) when
+    /// a delta sets a format that the submitting client isn't allowed to set.
+    ForbiddenAttribute {
+        /// Contains the index of the offending operation.
+        index: usize,
+
+        /// Contains the key of the format that isn't allowed.
+        key: String,
+    },
+
+    /// Returned by [`AttributeSchema`](crate::validate::AttributeSchema) when
+    /// a delta sets a format to a value of the wrong type or out of range.
+    InvalidAttribute {
+        /// Contains the index of the offending operation.
+        index: usize,
+
+        /// Contains the key of the format with the invalid value.
+        key: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LengthMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "op {index} consumes {actual} elements, but the base length is {expected}"
+            ),
+            Error::ZeroLength { index } => write!(f, "op {index} has a length of zero"),
+            Error::InsertAfterDelete { index } => {
+                write!(f, "op {index} is an insert that directly follows a delete")
+            }
+            Error::NotADocument { index } => {
+                write!(
+                    f,
+                    "op {index} is not an insert, so the delta is not a document"
+                )
+            }
+            Error::MissingTrailingNewline => write!(f, "document doesn't end with a newline"),
+            Error::InvalidPath { index } => {
+                write!(f, "op {index} refers to a node that doesn't exist")
+            }
+            Error::OutOfBounds { index } => {
+                write!(
+                    f,
+                    "op {index} refers to a row, column or cell that doesn't exist"
+                )
+            }
+            Error::Unsupported { index } => {
+                write!(f, "op {index} can't be converted into a delta")
+            }
+            Error::InvalidChangeset { offset } => {
+                write!(f, "changeset is malformed at byte {offset}")
+            }
+            Error::InvalidPatch { line } => write!(f, "patch is malformed at line {line}"),
+            Error::InvalidEncoding { offset } => {
+                write!(f, "compact encoding is malformed at byte {offset}")
+            }
+            Error::LengthOverflow { index } => {
+                write!(
+                    f,
+                    "op {index} can't be extended without overflowing its length"
+                )
+            }
+            Error::LimitExceeded { index } => write!(f, "op {index} exceeds a limit"),
+            Error::Protected { index } => write!(f, "op {index} changes a protected range"),
+            Error::Rejected { reason } => write!(f, "delta was rejected: {reason}"),
+            Error::ForbiddenAttribute { index, key } => {
+                write!(f, "op {index} sets attribute {key:?}, which isn't allowed")
+            }
+            Error::InvalidAttribute { index, key } => {
+                write!(f, "op {index} sets attribute {key:?} to an invalid value")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,420 @@
+//! Extension point for custom operations.
+//!
+//! The [`Op`] enum only contains inserts, retains and deletes. Protocols that
+//! need other kinds of operations (e.g. an operation that transforms the
+//! elements it spans) can define those as a type that implements
+//! [`Extension`] and use an [`ExtDelta`], which contains both regular ops and
+//! custom ops. Pairs of regular ops are composed and transformed by kyte
+//! itself, while every pair that involves a custom op is handed to the
+//! extension.
+//!
+//! Both are driven by the same zip machinery as [`Delta`] (see [`Cursor`]), so
+//! an extension only needs to describe how to (partially) consume a single pair
+//! of ops.
+
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use super::ops::{Delete, Insert, Retain};
+use super::{Compose, Cursor, Delta, Len, Op, Seq, Split, Transform};
+
+/// Implemented by custom operations that extend the built-in [`Op`] kinds.
+///
+/// Both functions are only called for pairs of ops where at least one op is
+/// custom. Like [`Compose`] and [`Transform`] for regular ops, they may consume
+/// either op partially (by [splitting](Split) it), but must consume at least
+/// one element of either op, and return the op that results from the part that
+/// was consumed.
+pub trait Extension<T, A>: Clone + Len + Split + Sized {
+    /// Composes the given op with the op that is applied after it.
+    fn compose(lhs: &mut ExtOp<T, A, Self>, rhs: &mut ExtOp<T, A, Self>) -> ExtOp<T, A, Self>;
+
+    /// Transforms the given `rhs` op with the `lhs` op that was applied
+    /// concurrently. See [`Transform`] for the meaning of `priority`.
+    fn transform(
+        lhs: &mut ExtOp<T, A, Self>,
+        rhs: &mut ExtOp<T, A, Self>,
+        priority: bool,
+    ) -> ExtOp<T, A, Self>;
+}
+
+/// Either a regular [`Op`] or a custom op of an [`Extension`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    untagged,
+    bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>, X: Deserialize<'de>")
+)]
+pub enum ExtOp<T, A, X> {
+    /// Regular insert, retain or delete operation.
+    Op(Op<T, A>),
+
+    /// Custom operation.
+    Custom(X),
+}
+
+impl<T, A, X> Len for ExtOp<T, A, X>
+where
+    T: Len,
+    X: Len,
+{
+    fn len(&self) -> usize {
+        match self {
+            ExtOp::Op(op) => op.len(),
+            ExtOp::Custom(custom) => custom.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            ExtOp::Op(op) => op.is_empty(),
+            ExtOp::Custom(custom) => custom.is_empty(),
+        }
+    }
+}
+
+impl<T, A, X> Split for ExtOp<T, A, X>
+where
+    T: Clone + Seq,
+    A: Clone,
+    X: Split,
+{
+    fn split(&mut self, len: usize) -> Self {
+        match self {
+            ExtOp::Op(op) => ExtOp::Op(op.split(len)),
+            ExtOp::Custom(custom) => ExtOp::Custom(custom.split(len)),
+        }
+    }
+}
+
+impl<T, A, X> From<Op<T, A>> for ExtOp<T, A, X> {
+    fn from(op: Op<T, A>) -> Self {
+        ExtOp::Op(op)
+    }
+}
+
+/// Series of regular and custom operations.
+///
+/// Adjacent regular ops are kept in the same canonical form as a [`Delta`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>, X: Deserialize<'de>"
+))]
+pub struct ExtDelta<T, A, X> {
+    ops: Vec<ExtOp<T, A, X>>,
+}
+
+impl<T, A, X> ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+    X: Len,
+{
+    /// Returns a new empty delta.
+    pub fn new() -> ExtDelta<T, A, X> {
+        ExtDelta { ops: vec![] }
+    }
+
+    /// Returns a new delta with an additional insert. See [`Delta::insert`].
+    pub fn insert(mut self, insert: T, attributes: impl Into<Option<A>>) -> Self {
+        self.push(ExtOp::Op(Op::Insert(Insert::new(insert, attributes))));
+
+        self
+    }
+
+    /// Returns a new delta with an additional retain. See [`Delta::retain`].
+    pub fn retain(mut self, retain: usize, attributes: impl Into<Option<A>>) -> Self {
+        self.push(ExtOp::Op(Op::Retain(Retain {
+            retain,
+            attributes: attributes.into(),
+        })));
+
+        self
+    }
+
+    /// Returns a new delta with an additional delete. See [`Delta::delete`].
+    pub fn delete(mut self, delete: usize) -> Self {
+        self.push(ExtOp::Op(Op::Delete(Delete { delete })));
+        self
+    }
+
+    /// Returns a new delta with an additional custom op.
+    pub fn custom(mut self, custom: X) -> Self {
+        self.push(ExtOp::Custom(custom));
+        self
+    }
+
+    /// Returns the operations in this delta.
+    pub fn ops(&self) -> &[ExtOp<T, A, X>] {
+        &self.ops
+    }
+
+    /// Appends the given operation to this series. Regular ops are merged with
+    /// the last op like [`Delta::push`] does, while custom ops are always
+    /// appended as is (unless they're empty).
+    pub fn push(&mut self, op: ExtOp<T, A, X>) {
+        if op.is_empty() {
+            return;
+        }
+
+        match (self.ops.pop(), op) {
+            (Some(ExtOp::Op(last)), ExtOp::Op(op)) => {
+                let mut delta = Delta::new();
+                delta.push(last);
+                delta.push(op);
+
+                self.ops.extend(delta.into_iter().map(ExtOp::Op));
+            }
+            (last, op) => {
+                self.ops.extend(last);
+                self.ops.push(op);
+            }
+        }
+    }
+
+    /// Removes trailing retains that don't update any attributes.
+    fn chop(mut self) -> Self {
+        while let Some(ExtOp::Op(Op::Retain(Retain {
+            attributes: None, ..
+        }))) = self.ops.last()
+        {
+            self.ops.pop();
+        }
+
+        self
+    }
+}
+
+impl<T, A, X> Default for ExtDelta<T, A, X> {
+    fn default() -> Self {
+        ExtDelta { ops: vec![] }
+    }
+}
+
+impl<T, A, X> From<Delta<T, A>> for ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq,
+    A: Default + Clone,
+{
+    fn from(delta: Delta<T, A>) -> Self {
+        ExtDelta {
+            ops: delta.into_iter().map(ExtOp::Op).collect(),
+        }
+    }
+}
+
+impl<T, A, X> Extend<ExtOp<T, A, X>> for ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+    X: Len,
+{
+    fn extend<I: IntoIterator<Item = ExtOp<T, A, X>>>(&mut self, iter: I) {
+        for op in iter {
+            self.push(op);
+        }
+    }
+}
+
+impl<T, A, X> FromIterator<ExtOp<T, A, X>> for ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+    X: Len,
+{
+    fn from_iter<I: IntoIterator<Item = ExtOp<T, A, X>>>(iter: I) -> Self {
+        let mut delta = ExtDelta::new();
+        delta.extend(iter);
+        delta
+    }
+}
+
+impl<T, A, X> IntoIterator for ExtDelta<T, A, X>
+where
+    T: Len,
+    X: Len,
+{
+    type Item = ExtOp<T, A, X>;
+    type IntoIter = Cursor<ExtOp<T, A, X>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Cursor::new(self.ops.into_iter())
+    }
+}
+
+impl<T, A, X> Compose<ExtDelta<T, A, X>> for ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq + Compose<A, Output = A>,
+    X: Extension<T, A>,
+{
+    type Output = Self;
+
+    fn compose(self, rhs: ExtDelta<T, A, X>) -> Self {
+        let mut self_iter = self.into_iter();
+        let mut other_iter = rhs.into_iter();
+
+        let mut result = ExtDelta::new();
+
+        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| match (a, b) {
+            (ExtOp::Op(a), ExtOp::Op(b)) => ExtOp::Op(a.compose(b)),
+            (a, b) => X::compose(a, b),
+        }));
+        result.extend(self_iter.chain(other_iter));
+
+        result.chop()
+    }
+}
+
+impl<T, A, X> Transform<ExtDelta<T, A, X>> for ExtDelta<T, A, X>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq + Compose<A, Output = A>,
+    X: Extension<T, A>,
+{
+    type Output = Self;
+
+    fn transform(self, rhs: ExtDelta<T, A, X>, priority: bool) -> Self {
+        let mut self_iter = self.into_iter();
+        let mut other_iter = rhs.into_iter();
+
+        let mut result = ExtDelta::new();
+
+        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| match (a, b) {
+            (ExtOp::Op(a), ExtOp::Op(b)) => ExtOp::Op(a.transform(b, priority)),
+            (a, b) => X::transform(a, b, priority),
+        }));
+        result.extend(other_iter);
+
+        result.chop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::min;
+
+    use crate::ops::{Delete, Insert, Retain};
+    use crate::{Compose, Delta, Len, Op, Split, Transform};
+
+    use super::{ExtDelta, ExtOp, Extension};
+
+    /// Custom op that uppercases the elements it spans.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Upper(usize);
+
+    impl Len for Upper {
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    impl Split for Upper {
+        fn split(&mut self, len: usize) -> Self {
+            let len = min(self.0, len);
+            self.0 -= len;
+            Upper(len)
+        }
+    }
+
+    type Upp = ExtOp<String, (), Upper>;
+
+    fn split(lhs: &mut Upp, rhs: &mut Upp) -> (Upp, Upp) {
+        let len = min(lhs.len(), rhs.len());
+        (lhs.split(len), rhs.split(len))
+    }
+
+    fn retain(len: usize) -> Upp {
+        ExtOp::Op(Op::Retain(Retain {
+            retain: len,
+            attributes: None,
+        }))
+    }
+
+    impl Extension<String, ()> for Upper {
+        fn compose(lhs: &mut Upp, rhs: &mut Upp) -> Upp {
+            match (lhs, rhs) {
+                (lhs @ ExtOp::Op(Op::Delete(_)), _) => lhs.split(lhs.len()),
+                (_, rhs @ ExtOp::Op(Op::Insert(_))) => rhs.split(rhs.len()),
+                (lhs, rhs) => match split(lhs, rhs) {
+                    (ExtOp::Op(Op::Insert(insert)), _) => ExtOp::Op(Op::Insert(Insert::new(
+                        insert.insert().to_uppercase(),
+                        None,
+                    ))),
+                    (_, delete @ ExtOp::Op(Op::Delete(_))) => delete,
+                    (lhs, _) => ExtOp::Custom(Upper(lhs.len())),
+                },
+            }
+        }
+
+        fn transform(lhs: &mut Upp, rhs: &mut Upp, _priority: bool) -> Upp {
+            match (lhs, rhs) {
+                (lhs @ ExtOp::Op(Op::Insert(_)), _) => retain(lhs.split(lhs.len()).len()),
+                (_, rhs @ ExtOp::Op(Op::Insert(_))) => rhs.split(rhs.len()),
+                (lhs, rhs) => match split(lhs, rhs) {
+                    (ExtOp::Op(Op::Delete(_)), _) => ExtOp::Op(Op::Delete(Delete { delete: 0 })),
+                    (ExtOp::Custom(_), rhs @ ExtOp::Custom(_)) => retain(rhs.len()),
+                    (_, rhs) => rhs,
+                },
+            }
+        }
+    }
+
+    fn doc(text: &str) -> ExtDelta<String, (), Upper> {
+        Delta::new().insert(text.to_owned(), None).into()
+    }
+
+    #[test]
+    fn test_compose() {
+        let change = ExtDelta::new().retain(1, None).custom(Upper(2)).delete(1);
+
+        assert_eq!(doc("abcd").compose(change), doc("aBC"));
+    }
+
+    #[test]
+    fn test_push() {
+        let delta = ExtDelta::<String, (), Upper>::new()
+            .retain(1, None)
+            .retain(1, None)
+            .custom(Upper(0))
+            .custom(Upper(1))
+            .delete(1)
+            .insert("a".to_owned(), None);
+
+        assert_eq!(
+            delta.ops(),
+            &[
+                retain(2),
+                ExtOp::Custom(Upper(1)),
+                ExtOp::Op(Op::Insert(Insert::new("a".to_owned(), None))),
+                ExtOp::Op(Op::Delete(Delete { delete: 1 })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let changes = [
+            ExtDelta::new().custom(Upper(2)),
+            ExtDelta::new().retain(1, None).custom(Upper(3)),
+            ExtDelta::new().retain(1, None).insert("x".to_owned(), None),
+            ExtDelta::new().retain(1, None).delete(2),
+            ExtDelta::new().delete(1).custom(Upper(1)),
+        ];
+
+        for alice in &changes {
+            for bob in &changes {
+                for priority in [false, true] {
+                    let alice_bob = doc("abcd")
+                        .compose(alice.clone())
+                        .compose(alice.clone().transform(bob.clone(), priority));
+                    let bob_alice = doc("abcd")
+                        .compose(bob.clone())
+                        .compose(bob.clone().transform(alice.clone(), !priority));
+
+                    assert_eq!(alice_bob, bob_alice, "{alice:?} {bob:?} {priority}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Preservation of the fields of ops that Kyte doesn't know about.
+//!
+//! This module is only available with the `extra-fields` feature enabled. Some
+//! systems attach additional fields to the ops of a delta, e.g. the author of
+//! an insert:
+//!
+//! ```json
+//! { "ops": [{ "retain": 5 }, { "insert": "!", "author": "alice" }] }
+//! ```
+//!
+//! A [`Delta`] ignores these fields when it's deserialized. To pass them on,
+//! deserialize a [`WithExtra`] instead, which keeps them in a side channel next
+//! to the delta, process the delta with [`WithExtra::map`] and serialize the
+//! result, which puts the fields back on their ops.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use serde::de::{self, DeserializeOwned};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use super::de::DeltaRepr;
+use super::{Delta, Len, Op};
+
+/// Fields of an op that Kyte doesn't know about.
+pub type Extra = Map<String, Value>;
+
+/// Delta together with the fields of its ops that aren't part of the op
+/// itself (i.e. anything but `insert`, `retain`, `delete` and `attributes`).
+///
+/// Serializes like a delta with these fields added to their ops, and
+/// deserializes from both forms that a delta deserializes from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WithExtra<T, A> {
+    /// Contains the delta.
+    pub delta: Delta<T, A>,
+
+    /// Contains the unknown fields of the ops of the delta by index. Ops
+    /// without unknown fields don't have an entry.
+    pub extra: BTreeMap<usize, Extra>,
+}
+
+impl<T, A> WithExtra<T, A> {
+    /// Returns the given delta without any unknown fields.
+    pub fn new(delta: Delta<T, A>) -> WithExtra<T, A> {
+        WithExtra {
+            delta,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the unknown fields of the op with the given index, if any.
+    pub fn get(&self, index: usize) -> Option<&Extra> {
+        self.extra.get(&index)
+    }
+
+    /// Replaces the delta with the result of the given function and moves the
+    /// unknown fields along: the fields of the n-th insert (or retain or
+    /// delete) move to the n-th insert (or retain or delete) of the result.
+    /// This keeps them on the right ops for processing that doesn't add or
+    /// remove ops of a kind, e.g. transforming a delta (which never splits or
+    /// drops its inserts) or filtering its attributes. Fields of ops without a
+    /// counterpart in the result are dropped.
+    pub fn map<F>(self, f: F) -> WithExtra<T, A>
+    where
+        F: FnOnce(Delta<T, A>) -> Delta<T, A>,
+    {
+        match self.try_map(|delta| Ok::<_, Infallible>(f(delta))) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Fallible counterpart of [`WithExtra::map`], e.g. to run a
+    /// [`DeltaValidator`](crate::validate::DeltaValidator).
+    pub fn try_map<F, E>(mut self, f: F) -> Result<WithExtra<T, A>, E>
+    where
+        F: FnOnce(Delta<T, A>) -> Result<Delta<T, A>, E>,
+    {
+        let mut by_kind = BTreeMap::new();
+
+        for (index, op) in ordinals(&self.delta).enumerate() {
+            if let Some(extra) = self.extra.remove(&index) {
+                by_kind.insert(op, extra);
+            }
+        }
+
+        let delta = f(self.delta)?;
+        let extra = ordinals(&delta)
+            .enumerate()
+            .filter_map(|(index, op)| Some((index, by_kind.remove(&op)?)))
+            .collect();
+
+        Ok(WithExtra { delta, extra })
+    }
+}
+
+impl<T, A> From<Delta<T, A>> for WithExtra<T, A> {
+    fn from(delta: Delta<T, A>) -> Self {
+        WithExtra::new(delta)
+    }
+}
+
+/// Returns the kind of each op of the given delta, together with the number
+/// of ops of the same kind before it.
+fn ordinals<T, A>(delta: &Delta<T, A>) -> impl Iterator<Item = (u8, usize)> + '_ {
+    let mut counts = [0; 3];
+
+    delta.ops().map(move |op| {
+        let kind = match op {
+            Op::Insert(_) => 0,
+            Op::Retain(_) => 1,
+            Op::Delete(_) => 2,
+        };
+
+        counts[kind] += 1;
+        (kind as u8, counts[kind] - 1)
+    })
+}
+
+/// Like [`DeltaRepr`], but with the raw fields of each op.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Ops { ops: Vec<Extra> },
+    Seq(Vec<Extra>),
+}
+
+const KNOWN_FIELDS: [&str; 4] = ["insert", "retain", "delete", "attributes"];
+
+impl<T, A> Serialize for WithExtra<T, A>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ops = self
+            .delta
+            .ops()
+            .enumerate()
+            .map(|(index, op)| {
+                let mut value = serde_json::to_value(op).map_err(ser::Error::custom)?;
+
+                if let (Value::Object(fields), Some(extra)) = (&mut value, self.extra.get(&index)) {
+                    for (key, extra) in extra {
+                        if !KNOWN_FIELDS.contains(&key.as_str()) {
+                            fields.insert(key.clone(), extra.clone());
+                        }
+                    }
+                }
+
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, S::Error>>()?;
+
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("ops", &ops)?;
+        map.end()
+    }
+}
+
+impl<'de, T, A> Deserialize<'de> for WithExtra<T, A>
+where
+    T: Len + DeserializeOwned,
+    A: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (Repr::Ops { ops } | Repr::Seq(ops)) = Repr::deserialize(deserializer)?;
+
+        let mut extra = BTreeMap::new();
+        let ops = ops
+            .into_iter()
+            .enumerate()
+            .map(|(index, fields)| {
+                let (known, unknown): (Extra, Extra) = fields
+                    .into_iter()
+                    .partition(|(key, _)| KNOWN_FIELDS.contains(&key.as_str()));
+
+                if !unknown.is_empty() {
+                    extra.insert(index, unknown);
+                }
+
+                Op::deserialize(Value::Object(known)).map_err(de::Error::custom)
+            })
+            .collect::<Result<Vec<Op<T, A>>, D::Error>>()?;
+
+        Ok(WithExtra {
+            delta: DeltaRepr::Seq(ops).into(),
+            extra,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::WithExtra;
+    use crate::attributes::Attributes;
+    use crate::validate::AttributeFilter;
+    use crate::{Delta, Transform};
+
+    #[test]
+    fn test_round_trip() {
+        let json = json!({ "ops": [
+            { "retain": 5, "attributes": { "bold": true }, "author": "alice" },
+            { "insert": "!", "author": "bob", "time": 42 },
+            { "delete": 1 },
+        ] });
+
+        let change = serde_json::from_value::<WithExtra<String, Attributes>>(json.clone()).unwrap();
+
+        assert_eq!(change.delta.ops_len(), 3);
+        assert_eq!(change.get(1).unwrap()["author"], "bob");
+        assert_eq!(change.get(2), None);
+        assert_eq!(serde_json::to_value(&change).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<WithExtra<String, Attributes>>(json["ops"].clone()).unwrap(),
+            change
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        let change = serde_json::from_value::<WithExtra<String, Attributes>>(json!([
+            { "retain": 2, "attributes": { "locked": true }, "author": "alice" },
+            { "insert": "a", "author": "alice" },
+            { "retain": 1 },
+            { "insert": "b", "author": "bob" },
+        ]))
+        .unwrap();
+
+        let concurrent = Delta::new().retain(3, None).insert("xyz".to_owned(), None);
+        let filter = AttributeFilter::strip(|key: &str| key != "locked");
+
+        let change = change
+            .map(|delta| concurrent.transform(delta, true))
+            .try_map(|delta| filter.filter(delta))
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            json!({ "ops": [
+                { "retain": 2, "author": "alice" },
+                { "insert": "a", "author": "alice" },
+                { "retain": 4 },
+                { "insert": "b", "author": "bob" },
+            ] })
+        );
+    }
+}
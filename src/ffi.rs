@@ -0,0 +1,276 @@
+//! C ABI for [`QuillDelta`], so that native applications (e.g. C++ or Swift
+//! through C) can embed the same implementation of operational transformation
+//! as a Rust server.
+//!
+//! This module is only available with the `ffi` feature enabled. Build the
+//! library as a `staticlib` or `cdylib` (e.g. with `cargo rustc --release
+//! --features ffi --crate-type staticlib`) and generate the matching header
+//! with [cbindgen](https://github.com/mozilla/cbindgen) using the
+//! `cbindgen.toml` in the root of this repository.
+//!
+//! Deltas are passed around as opaque [`KyteDelta`] handles. Every function
+//! that returns a handle or a string transfers ownership to the caller, who
+//! must release it with [`kyte_delta_free`] or [`kyte_string_free`]
+//! respectively. Functions never take ownership of the handles they're given.
+//! Deltas and attributes are exchanged as NUL-terminated UTF-8 JSON strings in
+//! the format that Quill produces, and functions that can fail return a null
+//! pointer or `false`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::mem::take;
+use std::ptr::null_mut;
+
+use super::json::JsonAttributes;
+use super::quill::QuillDelta;
+use super::{Compose, Transform};
+
+/// Opaque handle to a delta with text values and JSON attributes.
+#[derive(Debug, Default)]
+pub struct KyteDelta(QuillDelta);
+
+/// Returns a new empty delta.
+#[no_mangle]
+pub extern "C" fn kyte_delta_new() -> *mut KyteDelta {
+    into_handle(QuillDelta::new())
+}
+
+/// Parses a delta from the given JSON (an object with an `ops` field or an
+/// array of ops). Returns a null pointer if the JSON isn't a valid delta.
+///
+/// # Safety
+///
+/// `json` must be a valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_from_json(json: *const c_char) -> *mut KyteDelta {
+    match parse(json) {
+        Some(delta) => into_handle(delta),
+        None => null_mut(),
+    }
+}
+
+/// Returns the JSON representation of the given delta, which must be released
+/// with [`kyte_string_free`].
+///
+/// # Safety
+///
+/// `delta` must be a valid handle returned by one of the functions of this
+/// module that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_to_json(delta: *const KyteDelta) -> *mut c_char {
+    let json = serde_json::to_string(&(*delta).0).expect("delta serializes");
+
+    CString::new(json)
+        .expect("JSON doesn't contain NUL characters")
+        .into_raw()
+}
+
+/// Appends an insert of the given text with the given attributes (a JSON
+/// object, or a null pointer for none) to the given delta. Returns false (and
+/// leaves the delta unchanged) if either string isn't valid.
+///
+/// # Safety
+///
+/// `delta` must be a valid handle and `text` and `attributes` (unless null)
+/// must be valid pointers to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_insert(
+    delta: *mut KyteDelta,
+    text: *const c_char,
+    attributes: *const c_char,
+) -> bool {
+    let (Some(text), Some(attributes)) = (to_str(text), parse_attributes(attributes)) else {
+        return false;
+    };
+
+    let delta = &mut (*delta).0;
+    *delta = take(delta).insert(text.to_owned(), attributes);
+    true
+}
+
+/// Appends a retain of the given length with the given attributes (a JSON
+/// object, or a null pointer for none) to the given delta. Returns false (and
+/// leaves the delta unchanged) if the attributes aren't valid.
+///
+/// # Safety
+///
+/// `delta` must be a valid handle and `attributes` (unless null) must be a
+/// valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_retain(
+    delta: *mut KyteDelta,
+    len: usize,
+    attributes: *const c_char,
+) -> bool {
+    let Some(attributes) = parse_attributes(attributes) else {
+        return false;
+    };
+
+    let delta = &mut (*delta).0;
+    *delta = take(delta).retain(len, attributes);
+    true
+}
+
+/// Appends a delete of the given length to the given delta.
+///
+/// # Safety
+///
+/// `delta` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_delete(delta: *mut KyteDelta, len: usize) {
+    let delta = &mut (*delta).0;
+    *delta = take(delta).delete(len);
+}
+
+/// Returns the composition of `a` and `b` (which applies after `a`) as a new
+/// delta.
+///
+/// # Safety
+///
+/// `a` and `b` must be valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_compose(
+    a: *const KyteDelta,
+    b: *const KyteDelta,
+) -> *mut KyteDelta {
+    into_handle((*a).0.clone().compose((*b).0.clone()))
+}
+
+/// Returns `b` transformed against `a` as a new delta, where `a` has priority
+/// if `priority` is true.
+///
+/// # Safety
+///
+/// `a` and `b` must be valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_transform(
+    a: *const KyteDelta,
+    b: *const KyteDelta,
+    priority: bool,
+) -> *mut KyteDelta {
+    into_handle((*a).0.clone().transform((*b).0.clone(), priority))
+}
+
+/// Returns the given index transformed against the given delta, where the
+/// delta has priority if `priority` is true.
+///
+/// # Safety
+///
+/// `delta` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_transform_position(
+    delta: *const KyteDelta,
+    index: usize,
+    priority: bool,
+) -> usize {
+    (&(*delta).0).transform(index, priority)
+}
+
+/// Releases the given delta. Does nothing if `delta` is null.
+///
+/// # Safety
+///
+/// `delta` must be null or a valid handle, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_delta_free(delta: *mut KyteDelta) {
+    if !delta.is_null() {
+        drop(Box::from_raw(delta));
+    }
+}
+
+/// Releases a string returned by one of the functions of this module. Does
+/// nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this module, which must not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn kyte_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+fn into_handle(delta: QuillDelta) -> *mut KyteDelta {
+    Box::into_raw(Box::new(KyteDelta(delta)))
+}
+
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    CStr::from_ptr(string).to_str().ok()
+}
+
+unsafe fn parse(json: *const c_char) -> Option<QuillDelta> {
+    serde_json::from_str(to_str(json)?).ok()
+}
+
+/// Returns `Some(None)` if `attributes` is null and `None` if it isn't valid.
+unsafe fn parse_attributes(attributes: *const c_char) -> Option<Option<JsonAttributes>> {
+    match attributes.is_null() {
+        true => Some(None),
+        false => serde_json::from_str(to_str(attributes)?).ok().map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::ptr::null;
+
+    use super::{
+        kyte_delta_compose, kyte_delta_delete, kyte_delta_free, kyte_delta_from_json,
+        kyte_delta_insert, kyte_delta_new, kyte_delta_retain, kyte_delta_to_json,
+        kyte_delta_transform, kyte_delta_transform_position, kyte_string_free, KyteDelta,
+    };
+
+    unsafe fn json(delta: *const KyteDelta) -> String {
+        let json = kyte_delta_to_json(delta);
+        let string = CStr::from_ptr(json).to_str().unwrap().to_owned();
+        kyte_string_free(json);
+        string
+    }
+
+    #[test]
+    fn test_ffi() {
+        unsafe {
+            let a = kyte_delta_new();
+            let text = CString::new("Hello").unwrap();
+            assert!(kyte_delta_insert(a, text.as_ptr(), null()));
+
+            let b = kyte_delta_new();
+            let bold = CString::new(r#"{"bold":true}"#).unwrap();
+            assert!(kyte_delta_retain(b, 4, bold.as_ptr()));
+            kyte_delta_delete(b, 1);
+
+            let composed = kyte_delta_compose(a, b);
+            assert_eq!(
+                json(composed),
+                r#"{"ops":[{"insert":"Hell","attributes":{"bold":true}}]}"#
+            );
+
+            let transformed = kyte_delta_transform(a, b, true);
+            assert_eq!(
+                json(transformed),
+                r#"{"ops":[{"retain":5},{"retain":4,"attributes":{"bold":true}},{"delete":1}]}"#
+            );
+            assert_eq!(kyte_delta_transform_position(a, 0, false), 5);
+
+            for delta in [a, b, composed, transformed] {
+                kyte_delta_free(delta);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ffi_invalid() {
+        unsafe {
+            let invalid = CString::new("{").unwrap();
+            assert!(kyte_delta_from_json(invalid.as_ptr()).is_null());
+
+            let delta = kyte_delta_from_json(CString::new("[]").unwrap().as_ptr());
+            assert!(!kyte_delta_retain(delta, 1, invalid.as_ptr()));
+            assert_eq!(json(delta), r#"{"ops":[]}"#);
+
+            kyte_delta_free(delta);
+        }
+    }
+}
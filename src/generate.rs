@@ -0,0 +1,259 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{Delta, Document, Seq};
+
+/// Limits of [`BoundedDelta`] and other bounded generators, which keep the
+/// inputs that a fuzzer minimizes small enough to read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    /// Contains the maximum number of ops in a generated delta.
+    pub max_ops: usize,
+
+    /// Contains the maximum length of a generated insert, retain or delete.
+    pub max_len: usize,
+
+    /// Contains the characters that generated text is made of.
+    pub alphabet: &'static str,
+}
+
+impl Bounds {
+    /// Generates a delta within these bounds, whose ops (like the [`Arbitrary`]
+    /// impl of [`Delta`]) don't necessarily apply to any particular document.
+    pub fn delta<'a, T, A>(&self, u: &mut Unstructured<'a>) -> Result<Delta<T, A>>
+    where
+        T: BoundedValue + Default + Clone + Seq + Extend<T>,
+        A: Arbitrary<'a> + Default + Clone + PartialEq,
+    {
+        let mut delta = Delta::new();
+
+        for _ in 0..u.int_in_range(0..=self.max_ops)? {
+            delta = match u.int_in_range(0..=2)? {
+                0 => delta.insert(T::arbitrary_bounded(u, self)?, Option::<A>::arbitrary(u)?),
+                1 => delta.retain(
+                    u.int_in_range(1..=self.max_len)?,
+                    Option::<A>::arbitrary(u)?,
+                ),
+                _ => delta.delete(u.int_in_range(1..=self.max_len)?),
+            };
+        }
+
+        Ok(delta)
+    }
+}
+
+/// Readable defaults: at most 8 ops of at most 8 elements each, with text
+/// made of `a`, `b`, `c` and newlines.
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds {
+            max_ops: 8,
+            max_len: 8,
+            alphabet: "abc\n",
+        }
+    }
+}
+
+/// Implemented by values that can be generated within the given [`Bounds`].
+pub trait BoundedValue: Sized {
+    /// Generates a non-empty value that's at most [`Bounds::max_len`] long.
+    fn arbitrary_bounded(u: &mut Unstructured<'_>, bounds: &Bounds) -> Result<Self>;
+}
+
+impl BoundedValue for String {
+    fn arbitrary_bounded(u: &mut Unstructured<'_>, bounds: &Bounds) -> Result<Self> {
+        let alphabet = bounds.alphabet.chars().collect::<Vec<_>>();
+
+        (0..u.int_in_range(1..=bounds.max_len)?)
+            .map(|_| u.choose(&alphabet).copied())
+            .collect()
+    }
+}
+
+impl<T> BoundedValue for Vec<T>
+where
+    T: for<'a> Arbitrary<'a>,
+{
+    fn arbitrary_bounded(u: &mut Unstructured<'_>, bounds: &Bounds) -> Result<Self> {
+        (0..u.int_in_range(1..=bounds.max_len)?)
+            .map(|_| T::arbitrary(u))
+            .collect()
+    }
+}
+
+/// Delta that's generated within the default [`Bounds`], for fuzz targets
+/// whose minimized inputs should be easy to read. Unlike the [`Arbitrary`]
+/// impl of [`Delta`], this never produces huge retains and deletes or long
+/// inserts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundedDelta<T, A>(pub Delta<T, A>);
+
+impl<'a, T, A> Arbitrary<'a> for BoundedDelta<T, A>
+where
+    T: BoundedValue + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Bounds::default().delta(u).map(BoundedDelta)
+    }
+}
+
+/// Document with arbitrary contents, for fuzzing invariants that involve
+/// documents and the changes that apply to them (e.g. applying, inverting or
+/// diffing changes).
+///
+/// Unlike the [`Arbitrary`] impl of [`Delta`], which produces arbitrary
+/// sequences of ops, this always produces a valid document. Use
+/// [`edit`](ArbitraryDocument::edit) to generate changes that apply to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArbitraryDocument<T, A>(pub Document<T, A>);
+
+impl<T, A> ArbitraryDocument<T, A> {
+    /// Returns a generator of changes that apply to this document.
+    pub fn edit(&self) -> ArbitraryEditOf<'_, T, A> {
+        ArbitraryEditOf::new(&self.0)
+    }
+}
+
+impl<'a, T, A> Arbitrary<'a> for ArbitraryDocument<T, A>
+where
+    T: Arbitrary<'a> + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let document = Document::from_delta(document(u)?).expect("delta only contains inserts");
+
+        Ok(ArbitraryDocument(document))
+    }
+}
+
+/// Generator of arbitrary changes that apply to a given document, i.e. whose
+/// retains and deletes never extend past the end of that document (see
+/// [`Delta::check_applicable`]).
+#[derive(Debug)]
+pub struct ArbitraryEditOf<'d, T, A> {
+    document: &'d Document<T, A>,
+}
+
+impl<'d, T, A> ArbitraryEditOf<'d, T, A> {
+    /// Returns a generator of changes that apply to the given document.
+    pub fn new(document: &'d Document<T, A>) -> ArbitraryEditOf<'d, T, A> {
+        ArbitraryEditOf { document }
+    }
+
+    /// Returns the document that generated changes apply to.
+    pub fn document(&self) -> &'d Document<T, A> {
+        self.document
+    }
+}
+
+impl<T, A> ArbitraryEditOf<'_, T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+{
+    /// Generates an arbitrary change that applies to the document of this
+    /// generator from the given data.
+    pub fn arbitrary<'a>(&self, u: &mut Unstructured<'a>) -> Result<Delta<T, A>>
+    where
+        T: Arbitrary<'a>,
+        A: Arbitrary<'a>,
+    {
+        change(u, self.document.len())
+    }
+}
+
+/// Generates a delta that only contains inserts.
+pub(crate) fn document<'a, T, A>(u: &mut Unstructured<'a>) -> Result<Delta<T, A>>
+where
+    T: Arbitrary<'a> + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    let mut delta = Delta::new();
+
+    for _ in 0..u.arbitrary_len::<(T, Option<A>)>()? {
+        delta = delta.insert(T::arbitrary(u)?, Option::<A>::arbitrary(u)?);
+    }
+
+    Ok(delta)
+}
+
+/// Generates a delta that applies to a document of the given length.
+pub(crate) fn change<'a, T, A>(u: &mut Unstructured<'a>, base_len: usize) -> Result<Delta<T, A>>
+where
+    T: Arbitrary<'a> + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    let mut delta = Delta::new();
+    let mut remaining = base_len;
+
+    for _ in 0..u.arbitrary_len::<(u8, T, Option<A>)>()? {
+        delta = match u.int_in_range(0..=2)? {
+            0 => delta.insert(T::arbitrary(u)?, Option::<A>::arbitrary(u)?),
+            _ if remaining == 0 => continue,
+            1 => {
+                let len = u.int_in_range(1..=remaining)?;
+                remaining -= len;
+                delta.retain(len, Option::<A>::arbitrary(u)?)
+            }
+            _ => {
+                let len = u.int_in_range(1..=remaining)?;
+                remaining -= len;
+                delta.delete(len)
+            }
+        };
+    }
+
+    Ok(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::{ArbitraryDocument, BoundedDelta, Bounds};
+    use crate::{LastWriteWins, Len, Op};
+
+    #[test]
+    fn test_arbitrary_edit_of() {
+        let bytes = (0..1 << 16)
+            .map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&bytes);
+
+        while !u.is_empty() {
+            let arbitrary =
+                ArbitraryDocument::<String, LastWriteWins<u8>>::arbitrary(&mut u).unwrap();
+            let change = arbitrary.edit().arbitrary(&mut u).unwrap();
+            let ArbitraryDocument(mut document) = arbitrary;
+            let len = change.target_len(document.len());
+
+            assert_eq!(change.check_applicable(document.len()), Ok(()));
+            assert_eq!(document.try_apply(change), Ok(()));
+            assert_eq!(document.len(), len);
+        }
+    }
+
+    #[test]
+    fn test_bounded_delta() {
+        let bytes = (0..1 << 16)
+            .map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&bytes);
+        let bounds = Bounds::default();
+
+        while !u.is_empty() {
+            let BoundedDelta(delta) =
+                BoundedDelta::<String, LastWriteWins<u8>>::arbitrary(&mut u).unwrap();
+
+            assert!(delta.clone().into_iter().count() <= bounds.max_ops);
+
+            for op in delta {
+                if let Op::Insert(insert) = &op {
+                    assert!(insert.insert().chars().all(|c| bounds.alphabet.contains(c)));
+                }
+
+                assert!(op.len() <= bounds.max_len * bounds.max_ops);
+            }
+        }
+    }
+}
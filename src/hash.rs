@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+/// Implemented by values and attributes that contribute to the
+/// [`content_hash`](crate::Document::content_hash) of a document.
+///
+/// Unlike [`Hash`](std::hash::Hash), whose output for the types of the
+/// standard library may change between Rust versions, implementations of this
+/// trait must feed the same bytes into the hasher on every platform and in
+/// every version, so that a server and its clients agree on the hash of the
+/// same contents. Integers written with the methods of [`Hasher`] are hashed in
+/// little-endian order regardless of the platform.
+pub trait Hashable {
+    /// Feeds the contents of this value into the given hasher.
+    fn hash_content<H: Hasher>(&self, state: &mut H);
+}
+
+impl Hashable for () {
+    fn hash_content<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl Hashable for bool {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(*self as u8);
+    }
+}
+
+impl Hashable for i64 {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_i64(*self);
+    }
+}
+
+impl Hashable for u64 {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(*self);
+    }
+}
+
+/// Hashes the bits of this number, so that `0.0` and `-0.0` hash differently
+/// (just like they serialize differently).
+impl Hashable for f64 {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.to_bits());
+    }
+}
+
+impl Hashable for str {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.len() as u64);
+        state.write(self.as_bytes());
+    }
+}
+
+impl Hashable for String {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash_content(state);
+    }
+}
+
+impl<T> Hashable for Option<T>
+where
+    T: Hashable,
+{
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Some(value) => {
+                state.write_u8(1);
+                value.hash_content(state);
+            }
+            None => state.write_u8(0),
+        }
+    }
+}
+
+impl<T> Hashable for Vec<T>
+where
+    T: Hashable,
+{
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.len() as u64);
+        self.iter().for_each(|value| value.hash_content(state));
+    }
+}
+
+impl<K, V> Hashable for BTreeMap<K, V>
+where
+    K: Hashable,
+    V: Hashable,
+{
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.len() as u64);
+
+        for (key, value) in self {
+            key.hash_content(state);
+            value.hash_content(state);
+        }
+    }
+}
+
+/// 64-bit FNV-1a hasher that writes integers in little-endian order, which
+/// [`content_hash`](crate::Document::content_hash) uses because its output is
+/// fully specified (unlike that of `DefaultHasher`).
+pub(crate) struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> ContentHasher {
+        ContentHasher(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+
+    use super::ContentHasher;
+
+    #[test]
+    fn test_fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = ContentHasher::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_little_endian() {
+        let mut lhs = ContentHasher::new();
+        lhs.write_usize(0x0102);
+
+        let mut rhs = ContentHasher::new();
+        rhs.write(&[2, 1, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(lhs.finish(), rhs.finish());
+    }
+}
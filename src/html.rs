@@ -0,0 +1,563 @@
+//! Import of (a constrained subset of) HTML into Quill documents.
+//!
+//! This module is only available with the `html` feature enabled. It converts
+//! the HTML that rich text editors (including Quill itself) typically produce
+//! into a document delta with [`QuillAttributes`]. The following elements are
+//! supported:
+//!
+//! | Element                              | Format                       |
+//! |:-------------------------------------|:-----------------------------|
+//! | `<b>`, `<strong>`                    | `bold`                       |
+//! | `<i>`, `<em>`                        | `italic`                     |
+//! | `<u>`                                | `underline`                  |
+//! | `<s>`, `<strike>`, `<del>`           | `strike`                     |
+//! | `<code>`                             | `code`                       |
+//! | `<a href="...">`                     | `link`                       |
+//! | `<sub>`, `<sup>`                     | `script`                     |
+//! | `<h1>` to `<h6>`                     | `header`                     |
+//! | `<ul>`, `<ol>` with `<li>`           | `list` and `indent`          |
+//! | `<blockquote>`                       | `blockquote`                 |
+//! | `<pre>`                              | `code-block`                 |
+//! | `<p>`, `<div>`, `<br>`               | (line breaks)                |
+//!
+//! Other elements are ignored, but their text content is kept (except for the
+//! contents of `<script>`, `<style>` and `<head>`). Like Quill documents, the
+//! resulting document always ends with a newline.
+
+use std::mem::take;
+
+use super::quill::{CodeBlock, List, QuillAttributes, Script};
+use super::{Compose, Delta};
+
+enum Token<'a> {
+    Text(&'a str),
+    Start(String, Vec<(String, String)>),
+    End(String),
+}
+
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "input" | "meta" | "link" | "col" | "wbr" | "source" | "area"
+    )
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "li"
+            | "blockquote"
+            | "pre"
+            | "ul"
+            | "ol"
+    )
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or_default();
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        match tag.strip_prefix('/') {
+            Some(name) => tokens.push(Token::End(name.trim().to_ascii_lowercase())),
+            None => {
+                let tag = tag.strip_suffix('/').unwrap_or(tag);
+                let name_len = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+                let name = tag[..name_len].to_ascii_lowercase();
+                let attributes = parse_attributes(&tag[name_len..]);
+
+                tokens.push(Token::Start(name, attributes));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_attributes(mut input: &str) -> Vec<(String, String)> {
+    let mut attributes = vec![];
+
+    loop {
+        input = input.trim_start();
+
+        if input.is_empty() {
+            break;
+        }
+
+        let name_len = input
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(input.len());
+        let name = input[..name_len].to_ascii_lowercase();
+        input = input[name_len..].trim_start();
+
+        let value = match input.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+
+                match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let value = &value[1..];
+                        let end = value.find(quote).unwrap_or(value.len());
+                        input = value.get(end + 1..).unwrap_or_default();
+                        &value[..end]
+                    }
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        input = &value[end..];
+                        &value[..end]
+                    }
+                }
+            }
+            None => "",
+        };
+
+        attributes.push((name, decode_entities(value)));
+    }
+
+    attributes
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    Some(decimal) => decimal.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn inline_format(name: &str, attributes: &[(String, String)]) -> Option<QuillAttributes> {
+    let format = match name {
+        "b" | "strong" => QuillAttributes {
+            bold: Some(Some(true)),
+            ..Default::default()
+        },
+        "i" | "em" => QuillAttributes {
+            italic: Some(Some(true)),
+            ..Default::default()
+        },
+        "u" => QuillAttributes {
+            underline: Some(Some(true)),
+            ..Default::default()
+        },
+        "s" | "strike" | "del" => QuillAttributes {
+            strike: Some(Some(true)),
+            ..Default::default()
+        },
+        "code" => QuillAttributes {
+            code: Some(Some(true)),
+            ..Default::default()
+        },
+        "sub" => QuillAttributes {
+            script: Some(Some(Script::Sub)),
+            ..Default::default()
+        },
+        "sup" => QuillAttributes {
+            script: Some(Some(Script::Super)),
+            ..Default::default()
+        },
+        "a" => QuillAttributes {
+            link: attributes
+                .iter()
+                .find(|(name, _)| name == "href")
+                .map(|(_, href)| Some(href.clone())),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+struct Importer {
+    delta: Delta<String, QuillAttributes>,
+    inline: Vec<(String, QuillAttributes)>,
+    blocks: Vec<(String, QuillAttributes)>,
+    lists: Vec<List>,
+    line_has_content: bool,
+    ends_with_newline: bool,
+    skip: usize,
+}
+
+impl Importer {
+    fn attributes(formats: &[(String, QuillAttributes)]) -> Option<QuillAttributes> {
+        let attributes = formats
+            .iter()
+            .map(|(_, format)| format.clone())
+            .fold(QuillAttributes::default(), Compose::compose);
+
+        match attributes == QuillAttributes::default() {
+            true => None,
+            false => Some(attributes),
+        }
+    }
+
+    fn in_pre(&self) -> bool {
+        self.blocks.iter().any(|(name, _)| name == "pre")
+    }
+
+    fn insert(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.ends_with_newline = false;
+        self.line_has_content = true;
+        self.delta = take(&mut self.delta).insert(text, Self::attributes(&self.inline));
+    }
+
+    fn newline(&mut self) {
+        self.delta = take(&mut self.delta).insert("\n".to_owned(), Self::attributes(&self.blocks));
+        self.ends_with_newline = true;
+        self.line_has_content = false;
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip > 0 {
+            return;
+        }
+
+        let text = decode_entities(text);
+
+        if self.in_pre() {
+            for (index, line) in text.split('\n').enumerate() {
+                if index > 0 {
+                    self.newline();
+                }
+
+                self.insert(line.to_owned());
+            }
+
+            return;
+        }
+
+        let mut collapsed = String::with_capacity(text.len());
+        let mut whitespace = !self.line_has_content;
+
+        for c in text.chars() {
+            match c.is_whitespace() && c != '\u{a0}' {
+                true if whitespace => {}
+                true => {
+                    collapsed.push(' ');
+                    whitespace = true;
+                }
+                false => {
+                    collapsed.push(c);
+                    whitespace = false;
+                }
+            }
+        }
+
+        self.insert(collapsed);
+    }
+
+    fn start(&mut self, name: String, attributes: Vec<(String, String)>) {
+        if matches!(name.as_str(), "script" | "style" | "head") {
+            self.skip += 1;
+            return;
+        }
+
+        if self.skip > 0 {
+            return;
+        }
+
+        if name == "br" {
+            self.newline();
+            return;
+        }
+
+        if is_block(&name) && self.line_has_content {
+            self.newline();
+        }
+
+        let block = match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => QuillAttributes {
+                header: name[1..].parse().ok().map(Some),
+                ..Default::default()
+            },
+            "blockquote" => QuillAttributes {
+                blockquote: Some(Some(true)),
+                ..Default::default()
+            },
+            "pre" => QuillAttributes {
+                code_block: Some(Some(CodeBlock::Plain(true))),
+                ..Default::default()
+            },
+            "ul" | "ol" => {
+                self.lists.push(match name.as_str() {
+                    "ol" => List::Ordered,
+                    _ => List::Bullet,
+                });
+                return;
+            }
+            "li" => QuillAttributes {
+                list: Some(self.lists.last().copied().or(Some(List::Bullet))),
+                indent: match self.lists.len() {
+                    0 | 1 => None,
+                    depth => Some(u8::try_from(depth - 1).ok()),
+                },
+                ..Default::default()
+            },
+            "p" | "div" => QuillAttributes::default(),
+            _ => {
+                if let Some(format) = inline_format(&name, &attributes) {
+                    self.inline.push((name, format));
+                }
+
+                return;
+            }
+        };
+
+        if !is_void(&name) {
+            self.blocks.push((name, block));
+        }
+    }
+
+    fn end(&mut self, name: String) {
+        if matches!(name.as_str(), "script" | "style" | "head") {
+            self.skip = self.skip.saturating_sub(1);
+            return;
+        }
+
+        if self.skip > 0 {
+            return;
+        }
+
+        if matches!(name.as_str(), "ul" | "ol") {
+            self.lists.pop();
+            return;
+        }
+
+        if is_block(&name) {
+            if self.line_has_content {
+                self.newline();
+            }
+
+            if let Some(index) = self.blocks.iter().rposition(|(block, _)| block == &name) {
+                self.blocks.truncate(index);
+            }
+        } else if let Some(index) = self.inline.iter().rposition(|(inline, _)| inline == &name) {
+            self.inline.truncate(index);
+        }
+    }
+}
+
+/// Converts the given HTML into a document delta with Quill's attributes. See
+/// the [module documentation](self) for the supported elements.
+pub fn from_html(html: &str) -> Delta<String, QuillAttributes> {
+    let mut importer = Importer {
+        delta: Delta::new(),
+        inline: vec![],
+        blocks: vec![],
+        lists: vec![],
+        line_has_content: false,
+        ends_with_newline: false,
+        skip: 0,
+    };
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => importer.text(text),
+            Token::Start(name, attributes) => importer.start(name, attributes),
+            Token::End(name) => importer.end(name),
+        }
+    }
+
+    if !importer.ends_with_newline {
+        importer.blocks.clear();
+        importer.newline();
+    }
+
+    importer.delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_html;
+    use crate::quill::{CodeBlock, List, QuillAttributes};
+    use crate::Delta;
+
+    fn bold() -> QuillAttributes {
+        QuillAttributes {
+            bold: Some(Some(true)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        assert_eq!(
+            from_html("<p>Hello <b>World</b>!</p>\n<p>Second &amp; last</p>"),
+            Delta::new()
+                .insert("Hello ".to_owned(), None)
+                .insert("World".to_owned(), bold())
+                .insert("!\nSecond & last\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            from_html("Hello"),
+            Delta::new().insert("Hello\n".to_owned(), None)
+        );
+        assert_eq!(from_html(""), Delta::new().insert("\n".to_owned(), None));
+    }
+
+    #[test]
+    fn test_nested_inline() {
+        assert_eq!(
+            from_html("<a href=\"https://example.com\"><strong>a</strong>b</a>"),
+            Delta::new()
+                .insert(
+                    "a".to_owned(),
+                    QuillAttributes {
+                        bold: Some(Some(true)),
+                        link: Some(Some("https://example.com".to_owned())),
+                        ..Default::default()
+                    }
+                )
+                .insert(
+                    "b".to_owned(),
+                    QuillAttributes {
+                        link: Some(Some("https://example.com".to_owned())),
+                        ..Default::default()
+                    }
+                )
+                .insert("\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_headers_and_lists() {
+        assert_eq!(
+            from_html("<h2>Title</h2><ul><li>a<ol><li>b</li></ol></li></ul>"),
+            Delta::new()
+                .insert("Title".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        header: Some(Some(2)),
+                        ..Default::default()
+                    }
+                )
+                .insert("a".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        list: Some(Some(List::Bullet)),
+                        ..Default::default()
+                    }
+                )
+                .insert("b".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        list: Some(Some(List::Ordered)),
+                        indent: Some(Some(1)),
+                        ..Default::default()
+                    }
+                )
+        );
+    }
+
+    #[test]
+    fn test_pre() {
+        let code = QuillAttributes {
+            code_block: Some(Some(CodeBlock::Plain(true))),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            from_html("<pre>fn  main()\n{}</pre>"),
+            Delta::new()
+                .insert("fn  main()".to_owned(), None)
+                .insert("\n".to_owned(), code.clone())
+                .insert("{}".to_owned(), None)
+                .insert("\n".to_owned(), code)
+        );
+    }
+
+    #[test]
+    fn test_ignored() {
+        assert_eq!(
+            from_html("<head><title>x</title></head><!-- y --><span>z</span><br>"),
+            Delta::new().insert("z\n".to_owned(), None)
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::{from_utf8, Chars};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::seq::{char_count, split_at};
+use super::{Hashable, HeapSize, Len, Seq};
+
+/// Number of bytes that an [`InlineString`] stores without allocating.
+const INLINE_CAPACITY: usize = 22;
+
+/// String that stores short text (up to 22 bytes) inline and only allocates
+/// once it grows past that. Use `Delta<InlineString, A>` instead of
+/// `Delta<String, A>` to make the inserts of single characters (see
+/// [`Delta::insert_one`](crate::Delta::insert_one)) and of short words, which
+/// make up most of the changes of per-keystroke editing, avoid allocating
+/// their values. Inline strings (de)serialize, hash and compare like the
+/// strings they contain.
+#[derive(Clone, Default)]
+pub struct InlineString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(String),
+}
+
+impl Default for Repr {
+    fn default() -> Self {
+        Repr::Inline {
+            len: 0,
+            bytes: [0; INLINE_CAPACITY],
+        }
+    }
+}
+
+impl InlineString {
+    /// Returns a new empty string, which doesn't allocate.
+    pub fn new() -> InlineString {
+        InlineString::default()
+    }
+
+    /// Returns the contents of this string.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Inline { len, bytes } => {
+                from_utf8(&bytes[..*len as usize]).expect("inline bytes are valid UTF-8")
+            }
+            Repr::Heap(string) => string,
+        }
+    }
+
+    /// Returns true if this string stores its contents inline (i.e. without an
+    /// allocation).
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Appends the given character to this string.
+    pub fn push(&mut self, char: char) {
+        self.push_str(char.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Appends the given text to this string, which only allocates if the
+    /// result doesn't fit inline.
+    pub fn push_str(&mut self, text: &str) {
+        match &mut self.0 {
+            Repr::Inline { len, bytes } if *len as usize + text.len() <= INLINE_CAPACITY => {
+                let start = *len as usize;
+                bytes[start..start + text.len()].copy_from_slice(text.as_bytes());
+                *len += text.len() as u8;
+            }
+            Repr::Inline { .. } => {
+                let mut string = String::with_capacity(self.as_str().len() + text.len());
+                string.push_str(self.as_str());
+                string.push_str(text);
+                self.0 = Repr::Heap(string);
+            }
+            Repr::Heap(string) => string.push_str(text),
+        }
+    }
+}
+
+impl Deref for InlineString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for InlineString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for InlineString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for InlineString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for InlineString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for InlineString {}
+
+impl PartialEq<str> for InlineString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for InlineString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for InlineString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl From<&str> for InlineString {
+    fn from(text: &str) -> Self {
+        let mut string = InlineString::new();
+        string.push_str(text);
+        string
+    }
+}
+
+/// Moves the given string's allocation into the inline string, unless its text
+/// fits inline.
+impl From<String> for InlineString {
+    fn from(text: String) -> Self {
+        match text.len() <= INLINE_CAPACITY {
+            true => InlineString::from(text.as_str()),
+            false => InlineString(Repr::Heap(text)),
+        }
+    }
+}
+
+impl From<InlineString> for String {
+    fn from(text: InlineString) -> Self {
+        match text.0 {
+            Repr::Inline { .. } => text.as_str().to_owned(),
+            Repr::Heap(string) => string,
+        }
+    }
+}
+
+impl Len for InlineString {
+    fn len(&self) -> usize {
+        char_count(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        str::is_empty(self)
+    }
+}
+
+impl Seq for InlineString {
+    type Iterator<'a> = Chars<'a>;
+
+    fn iter(&self) -> Self::Iterator<'_> {
+        self.chars()
+    }
+
+    fn split_prefix(&mut self, len: usize) -> Self {
+        let (prefix, rest) = split_at(self, len);
+        let (prefix, rest) = (InlineString::from(prefix), InlineString::from(rest));
+        *self = rest;
+        prefix
+    }
+}
+
+impl FromIterator<char> for InlineString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut string = InlineString::new();
+        string.extend(iter);
+        string
+    }
+}
+
+impl Extend<char> for InlineString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|char| self.push(char));
+    }
+}
+
+impl Extend<InlineString> for InlineString {
+    fn extend<I: IntoIterator<Item = InlineString>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|text| self.push_str(&text));
+    }
+}
+
+impl Hashable for InlineString {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash_content(state);
+    }
+}
+
+impl HeapSize for InlineString {
+    fn heap_size(&self) -> usize {
+        match &self.0 {
+            Repr::Inline { .. } => 0,
+            Repr::Heap(string) => string.heap_size(),
+        }
+    }
+}
+
+impl Serialize for InlineString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for InlineString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(InlineString::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineString;
+    use crate::{Compose, Delta, Len, Seq};
+
+    #[test]
+    fn test_push() {
+        let mut text = InlineString::new();
+
+        text.push('H');
+        text.push_str("é🌍llo");
+        assert!(text.is_inline());
+        assert_eq!(text, "Hé🌍llo");
+        assert_eq!(text.len(), 6);
+
+        text.push_str(", world! How are you?");
+        assert!(!text.is_inline());
+        assert_eq!(text, "Hé🌍llo, world! How are you?");
+    }
+
+    #[test]
+    fn test_split_prefix() {
+        let mut text = InlineString::from("Hé🌍llo, world! How are you?".to_owned());
+
+        assert_eq!(text.split_prefix(3), "Hé🌍");
+        assert_eq!(text, "llo, world! How are you?");
+        assert!(!text.is_inline());
+        assert_eq!(text.split_prefix(15), "llo, world! How");
+        assert!(text.is_inline());
+    }
+
+    #[test]
+    fn test_insert_one() {
+        let delta = "Hello"
+            .chars()
+            .fold(Delta::<InlineString, ()>::new(), |delta, char| {
+                delta.insert_one(char, None)
+            });
+
+        assert_eq!(
+            delta,
+            Delta::new().insert(InlineString::from("Hello"), None)
+        );
+        assert_eq!(
+            delta.compose(Delta::new().retain(1, None).delete(3)),
+            Delta::new().insert(InlineString::from("Ho"), None)
+        );
+        assert_eq!(
+            serde_json::to_string(&Delta::<InlineString, ()>::new().insert_one('a', None)).unwrap(),
+            r#"{"ops":[{"insert":"a"}]}"#
+        );
+    }
+}
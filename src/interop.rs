@@ -0,0 +1,50 @@
+//! Conversions between deltas and the operation formats of other collaborative
+//! editing systems.
+//!
+//! Each submodule is only available with the feature of the same name enabled.
+//!
+//! # Yjs
+//!
+//! [Yjs](https://yjs.dev) (and its Rust port yrs) already describes the
+//! contents of and changes to its text types with Quill deltas (see
+//! `YText.toDelta()` and the `delta` of a `YTextEvent`). These deserialize into
+//! a [`Delta`](crate::Delta) with [`Attributes`](crate::attributes::Attributes)
+//! as is.
+
+#[cfg(feature = "automerge")]
+pub mod automerge;
+#[cfg(feature = "etherpad")]
+pub mod etherpad;
+#[cfg(feature = "sharedb")]
+pub mod sharedb;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::attributes::Attributes;
+    use crate::{Compose, Delta};
+
+    #[test]
+    fn test_yjs_delta() {
+        let contents: Delta<String, Attributes> = serde_json::from_value(json!([
+            { "insert": "Hello", "attributes": { "bold": true } },
+            { "insert": " World\n" },
+        ]))
+        .unwrap();
+        let change: Delta<String, Attributes> = serde_json::from_value(json!([
+            { "retain": 5, "attributes": { "italic": true } },
+            { "delete": 6 },
+            { "insert": "!" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(contents.compose(change)).unwrap(),
+            json!({ "ops": [
+                { "insert": "Hello", "attributes": { "bold": true, "italic": true } },
+                { "insert": "!\n" },
+            ] })
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Conversions between deltas and the text objects of
+//! [Automerge](https://automerge.org).
+//!
+//! This module is only available with the `automerge` feature enabled. Deltas
+//! are applied to a text object as splices, while their attributes become
+//! marks. Like in a delta, inserted text only has the formatting of its own
+//! attributes: marks are created with [`ExpandMark::None`], and marks that
+//! inserted text inherits from the text around it are removed. Conversely, the patches that Automerge emits for a text object can be
+//! converted back into a delta.
+//!
+//! Both kyte and Automerge (unless built with one of its `*-indexing`
+//! features) count positions in characters.
+
+use automerge::marks::{ExpandMark, Mark};
+use automerge::transaction::Transactable;
+use automerge::{AutomergeError, ObjId, Patch, PatchAction, ScalarValue};
+
+use crate::attributes::{AttrValue, Attributes};
+use crate::{Compose, Delta, Error, Len, Op};
+
+/// Applies the given delta to the given text object. Attributes are set as
+/// marks, where [`AttrValue::Null`] removes the corresponding mark. Returns
+/// [`AutomergeError::InvalidValueType`] if an attribute has a
+/// [`AttrValue::Map`] value, which can't be stored in a mark.
+pub fn apply_delta<T>(
+    tx: &mut T,
+    text: &ObjId,
+    delta: &Delta<String, Attributes>,
+) -> Result<(), AutomergeError>
+where
+    T: Transactable,
+{
+    let mut index = 0usize;
+
+    for op in delta.ops() {
+        let len = op.len();
+
+        match op {
+            Op::Insert(insert) => {
+                tx.splice_text(text, index, 0, insert.insert())?;
+                unmark_inherited(tx, text, index..index + len, insert.attributes.as_ref())?;
+                mark(tx, text, index..index + len, insert.attributes.as_ref())?;
+                index += len;
+            }
+            Op::Retain(retain) => {
+                mark(tx, text, index..index + len, retain.attributes.as_ref())?;
+                index += len;
+            }
+            Op::Delete(_) => tx.splice_text(text, index, len as isize, "")?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the delta that has the same effect on the given text object as the
+/// given patches (e.g. the result of `AutoCommit::diff_incremental`). Patches
+/// for other objects are skipped. Returns [`Error::Unsupported`] if a patch
+/// edits the text object in a way that a delta can't represent (e.g. inserting
+/// a block marker) or contains a mark with a value that isn't a string,
+/// number, boolean or null.
+pub fn from_patches(text: &ObjId, patches: &[Patch]) -> Result<Delta<String, Attributes>, Error> {
+    let mut delta = Delta::new();
+
+    for (index, patch) in patches.iter().enumerate() {
+        if &patch.obj != text {
+            continue;
+        }
+
+        let change = match &patch.action {
+            PatchAction::SpliceText {
+                index: position,
+                value,
+                marks,
+            } => {
+                let attributes = marks
+                    .iter()
+                    .flat_map(|marks| marks.iter())
+                    .map(|(name, value)| Ok((name.to_owned(), from_scalar(value, index)?)))
+                    .collect::<Result<Attributes, Error>>()?;
+
+                Delta::new().retain(*position, None).insert(
+                    value.make_string(),
+                    Some(attributes).filter(|attributes| !attributes.is_empty()),
+                )
+            }
+            PatchAction::DeleteSeq {
+                index: position,
+                length,
+            } => Delta::new().retain(*position, None).delete(*length),
+            PatchAction::Mark { marks } => marks.iter().try_fold(Delta::new(), |delta, mark| {
+                let attributes = [(mark.name().to_owned(), from_scalar(mark.value(), index)?)];
+
+                Ok(delta.compose(
+                    Delta::new()
+                        .retain(mark.start, None)
+                        .retain(mark.end - mark.start, Attributes::from(attributes)),
+                ))
+            })?,
+            _ => return Err(Error::Unsupported { index }),
+        };
+
+        delta = delta.compose(change);
+    }
+
+    Ok(delta)
+}
+
+/// Sets or removes the marks for the given attributes in the given range.
+fn mark<T>(
+    tx: &mut T,
+    text: &ObjId,
+    range: std::ops::Range<usize>,
+    attributes: Option<&Attributes>,
+) -> Result<(), AutomergeError>
+where
+    T: Transactable,
+{
+    for (name, value) in attributes.into_iter().flatten() {
+        let value = match value {
+            AttrValue::Null => {
+                tx.unmark(text, name, range.start, range.end, ExpandMark::None)?;
+                continue;
+            }
+            AttrValue::Bool(value) => ScalarValue::Boolean(*value),
+            AttrValue::Int(value) => ScalarValue::Int(*value),
+            AttrValue::Float(value) => ScalarValue::F64(*value),
+            AttrValue::String(value) => ScalarValue::Str(value.into()),
+            AttrValue::Map(_) => {
+                return Err(AutomergeError::InvalidValueType {
+                    expected: "a scalar value".to_owned(),
+                    unexpected: "a map".to_owned(),
+                })
+            }
+        };
+
+        let mark = Mark::new(name.clone(), value, range.start, range.end);
+        tx.mark(text, mark, ExpandMark::None)?;
+    }
+
+    Ok(())
+}
+
+/// Removes the marks that the text in the given range inherited from the text
+/// around it, except for those in the given attributes.
+fn unmark_inherited<T>(
+    tx: &mut T,
+    text: &ObjId,
+    range: std::ops::Range<usize>,
+    attributes: Option<&Attributes>,
+) -> Result<(), AutomergeError>
+where
+    T: Transactable,
+{
+    let inherited = tx
+        .get_marks(text, range.start, None)?
+        .iter()
+        .map(|(name, _)| name.to_owned())
+        .filter(|name| !attributes.is_some_and(|attributes| attributes.contains_key(name)))
+        .collect::<Vec<_>>();
+
+    for name in inherited {
+        tx.unmark(text, &name, range.start, range.end, ExpandMark::None)?;
+    }
+
+    Ok(())
+}
+
+/// Converts the given value of a mark into an attribute value.
+fn from_scalar(value: &ScalarValue, index: usize) -> Result<AttrValue, Error> {
+    Ok(match value {
+        ScalarValue::Null => AttrValue::Null,
+        ScalarValue::Boolean(value) => AttrValue::Bool(*value),
+        ScalarValue::Int(value) | ScalarValue::Timestamp(value) => AttrValue::Int(*value),
+        ScalarValue::Uint(value) => match i64::try_from(*value) {
+            Ok(value) => AttrValue::Int(value),
+            Err(_) => AttrValue::Float(*value as f64),
+        },
+        ScalarValue::F64(value) => AttrValue::Float(*value),
+        ScalarValue::Str(value) => AttrValue::String(value.to_string()),
+        _ => return Err(Error::Unsupported { index }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use automerge::transaction::Transactable;
+    use automerge::{AutoCommit, ObjType, ReadDoc, ROOT};
+
+    use crate::attributes::attributes;
+    use crate::{Compose, Delta};
+
+    use super::{apply_delta, from_patches};
+
+    #[test]
+    fn test_apply_delta() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+
+        apply_delta(
+            &mut doc,
+            &text,
+            &Delta::new().insert("Hello World".to_owned(), attributes("bold", true)),
+        )
+        .unwrap();
+        apply_delta(
+            &mut doc,
+            &text,
+            &Delta::new()
+                .retain(5, None)
+                .delete(6)
+                .insert("!".to_owned(), None),
+        )
+        .unwrap();
+
+        assert_eq!(doc.text(&text).unwrap(), "Hello!");
+
+        let marks = doc.marks(&text).unwrap();
+        assert_eq!(marks.len(), 1);
+        assert_eq!(
+            (marks[0].name(), marks[0].start, marks[0].end),
+            ("bold", 0, 5)
+        );
+    }
+
+    #[test]
+    fn test_from_patches() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        let before = Delta::new().insert("Hello World".to_owned(), None);
+
+        apply_delta(&mut doc, &text, &before).unwrap();
+        doc.update_diff_cursor();
+
+        let delta = Delta::new()
+            .retain(5, attributes("bold", true))
+            .insert(",".to_owned(), None)
+            .retain(1, None)
+            .delete(5)
+            .insert("there".to_owned(), attributes("italic", true));
+
+        apply_delta(&mut doc, &text, &delta).unwrap();
+
+        let patches = doc.diff_incremental();
+
+        assert_eq!(
+            before.compose(from_patches(&text, &patches).unwrap()),
+            Delta::new()
+                .insert("Hello".to_owned(), attributes("bold", true))
+                .insert(", ".to_owned(), None)
+                .insert("there".to_owned(), attributes("italic", true))
+        );
+    }
+}
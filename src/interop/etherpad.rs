@@ -0,0 +1,431 @@
+//! Conversions between deltas and [Etherpad](https://etherpad.org)'s
+//! changesets.
+//!
+//! This module is only available with the `etherpad` feature enabled. A
+//! changeset (e.g. `Z:6>6=5*0+6$ world`) consists of a header with the lengths
+//! of the document before and after applying it, a series of keep (`=`),
+//! delete (`-`) and insert (`+`) operations with lengths in base 36, and a char
+//! bank with the inserted text. Attributes are stored as `*n` references into
+//! an [`AttributePool`], which is shared by all changesets of a pad.
+//!
+//! Etherpad attributes are key/value pairs of strings, where an empty value
+//! removes the attribute. These are converted to [`AttrValue::String`] and
+//! [`AttrValue::Null`] respectively. Lengths count characters rather than the
+//! UTF-16 code units that JavaScript uses.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::attributes::{AttrValue, Attributes};
+use crate::seq::split_at;
+use crate::{Delta, Error, Len, Op};
+
+/// Pool that maps numbers to the attributes that changesets refer to. This
+/// (de)serializes to and from the `apool` object that Etherpad stores.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributePool {
+    num_to_attrib: BTreeMap<usize, (String, String)>,
+    next_num: usize,
+}
+
+impl AttributePool {
+    /// Returns a new empty pool.
+    pub fn new() -> AttributePool {
+        AttributePool::default()
+    }
+
+    /// Returns the key and value of the attribute with the given number.
+    pub fn get(&self, num: usize) -> Option<(&str, &str)> {
+        self.num_to_attrib
+            .get(&num)
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of the attribute with the given key and value, adding
+    /// it to this pool if it doesn't exist yet.
+    pub fn put(&mut self, key: &str, value: &str) -> usize {
+        let existing = self
+            .num_to_attrib
+            .iter()
+            .find(|(_, attrib)| attrib.0 == key && attrib.1 == value);
+
+        if let Some((num, _)) = existing {
+            return *num;
+        }
+
+        let num = self.next_num;
+        self.num_to_attrib
+            .insert(num, (key.to_owned(), value.to_owned()));
+        self.next_num += 1;
+        num
+    }
+}
+
+/// Returns the changeset that has the same effect as the given delta when
+/// applied to the given text. The text is used to count the newlines in the
+/// text that the delta retains or deletes, which changesets encode. Attributes
+/// are added to the given pool. Returns [`Error::Unsupported`] if an op has an
+/// attribute with a [`AttrValue::Map`] value.
+pub fn to_changeset(
+    base: &str,
+    delta: &Delta<String, Attributes>,
+    pool: &mut AttributePool,
+) -> Result<String, Error> {
+    let mut ops = String::new();
+    let mut inserts = String::new();
+    let mut bank = String::new();
+    let mut rest = base;
+    let mut new_len = base.chars().count();
+
+    for (index, op) in delta.ops().enumerate() {
+        match op {
+            Op::Insert(insert) => {
+                let attribs = attribs(insert.attributes.as_ref(), pool, index)?;
+                push_op(&mut inserts, &attribs, '+', insert.insert());
+                bank.push_str(insert.insert());
+                new_len += insert.len();
+            }
+            Op::Retain(retain) => {
+                let attribs = attribs(retain.attributes.as_ref(), pool, index)?;
+                let (kept, after) = split_at(rest, retain.len());
+                rest = after;
+
+                ops.push_str(&std::mem::take(&mut inserts));
+                push_op(&mut ops, &attribs, '=', kept);
+            }
+            Op::Delete(delete) => {
+                let (deleted, after) = split_at(rest, delete.len());
+                rest = after;
+
+                push_op(&mut ops, "", '-', deleted);
+                new_len -= deleted.chars().count();
+            }
+        }
+    }
+
+    ops.push_str(&inserts);
+
+    let old_len = base.chars().count();
+    let (sign, diff) = match new_len >= old_len {
+        true => ('>', new_len - old_len),
+        false => ('<', old_len - new_len),
+    };
+
+    Ok(format!(
+        "Z:{}{sign}{}{ops}${bank}",
+        to_base36(old_len),
+        to_base36(diff)
+    ))
+}
+
+/// Returns the delta that has the same effect as the given changeset, looking
+/// up its attributes in the given pool. Returns [`Error::InvalidChangeset`] if
+/// the changeset can't be parsed, refers to attributes that aren't in the pool
+/// or if its header doesn't match its operations.
+pub fn from_changeset(
+    changeset: &str,
+    pool: &AttributePool,
+) -> Result<Delta<String, Attributes>, Error> {
+    let mut parser = Parser {
+        input: changeset,
+        offset: 0,
+    };
+
+    parser.expect("Z:")?;
+    let old_len = parser.number()?;
+    let new_len = match parser.next() {
+        Some('>') => old_len.checked_add(parser.number()?),
+        Some('<') => old_len.checked_sub(parser.number()?),
+        _ => return Err(parser.error()),
+    }
+    .ok_or(Error::InvalidChangeset { offset: 0 })?;
+
+    let mut ops = vec![];
+
+    while !parser.eat('$') {
+        let mut attributes = Attributes::new();
+
+        while parser.eat('*') {
+            let start = parser.offset;
+            let (key, value) = pool
+                .get(parser.number()?)
+                .ok_or(Error::InvalidChangeset { offset: start })?;
+
+            let value = match value {
+                "" => AttrValue::Null,
+                value => AttrValue::from(value),
+            };
+
+            attributes.insert(key.to_owned(), value);
+        }
+
+        if parser.eat('|') {
+            parser.number()?;
+        }
+
+        let opcode = parser.next().ok_or(parser.error())?;
+        let len = parser.number()?;
+        let attributes = Some(attributes).filter(|attributes| !attributes.is_empty());
+
+        ops.push((parser.offset, opcode, len, attributes));
+    }
+
+    let end = parser.offset - 1;
+    let mut bank = changeset[parser.offset..].chars();
+    let mut delta = Delta::new();
+    let mut consumed = 0usize;
+    let mut produced = 0usize;
+
+    for (offset, opcode, len, attributes) in ops {
+        match opcode {
+            '+' => {
+                let insert = bank.by_ref().take(len).collect::<String>();
+
+                if insert.chars().count() != len {
+                    return Err(Error::InvalidChangeset { offset });
+                }
+
+                delta = delta.insert(insert, attributes);
+                produced = produced.saturating_add(len);
+            }
+            '-' => {
+                delta = delta.delete(len);
+                consumed = consumed.saturating_add(len);
+            }
+            '=' => {
+                delta = delta.retain(len, attributes);
+                consumed = consumed.saturating_add(len);
+                produced = produced.saturating_add(len);
+            }
+            _ => return Err(Error::InvalidChangeset { offset }),
+        }
+    }
+
+    match consumed <= old_len && produced + (old_len - consumed) == new_len {
+        true if bank.next().is_none() => Ok(delta),
+        _ => Err(Error::InvalidChangeset { offset: end }),
+    }
+}
+
+/// Returns the `*n` references to the given attributes, which are added to the
+/// given pool.
+fn attribs(
+    attributes: Option<&Attributes>,
+    pool: &mut AttributePool,
+    index: usize,
+) -> Result<String, Error> {
+    let mut attribs = String::new();
+
+    for (key, value) in attributes.into_iter().flatten() {
+        let value = match value {
+            AttrValue::Null => String::new(),
+            AttrValue::Bool(value) => value.to_string(),
+            AttrValue::Int(value) => value.to_string(),
+            AttrValue::Float(value) => value.to_string(),
+            AttrValue::String(value) => value.clone(),
+            AttrValue::Map(_) => return Err(Error::Unsupported { index }),
+        };
+
+        attribs.push('*');
+        attribs.push_str(&to_base36(pool.put(key, &value)));
+    }
+
+    Ok(attribs)
+}
+
+/// Appends the operation with the given opcode that spans the given text to
+/// the given ops. Etherpad requires the part of the text up to and including
+/// its last newline to be a separate operation that is prefixed with the
+/// number of newlines.
+fn push_op(ops: &mut String, attribs: &str, opcode: char, text: &str) {
+    let (lines, rest) = match text.rfind('\n') {
+        Some(index) => text.split_at(index + 1),
+        None => ("", text),
+    };
+
+    if !lines.is_empty() {
+        let newlines = lines.matches('\n').count();
+        ops.push_str(&format!(
+            "{attribs}|{}{opcode}{}",
+            to_base36(newlines),
+            to_base36(lines.chars().count())
+        ));
+    }
+
+    if !rest.is_empty() {
+        ops.push_str(&format!(
+            "{attribs}{opcode}{}",
+            to_base36(rest.chars().count())
+        ));
+    }
+}
+
+/// Returns the (lowercase) base 36 representation of the given number.
+fn to_base36(mut value: usize) -> String {
+    let mut digits = vec![];
+
+    loop {
+        digits.push(std::char::from_digit((value % 36) as u32, 36).unwrap_or('0'));
+        value /= 36;
+
+        if value == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().collect()
+}
+
+/// Parser for the operations in a changeset.
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> Error {
+        Error::InvalidChangeset {
+            offset: self.offset,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.offset..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let next = self.peek()?;
+        self.offset += next.len_utf8();
+        Some(next)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matches = self.peek() == Some(expected);
+
+        if matches {
+            self.offset += expected.len_utf8();
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), Error> {
+        match self.input[self.offset..].starts_with(expected) {
+            true => {
+                self.offset += expected.len();
+                Ok(())
+            }
+            false => Err(self.error()),
+        }
+    }
+
+    /// Parses a (lowercase) base 36 number.
+    fn number(&mut self) -> Result<usize, Error> {
+        let start = self.offset;
+        let digits = self.input[start..]
+            .find(|c: char| !c.is_ascii_digit() && !c.is_ascii_lowercase())
+            .map_or(self.input.len(), |index| start + index);
+
+        self.offset = digits;
+
+        usize::from_str_radix(&self.input[start..digits], 36)
+            .map_err(|_| Error::InvalidChangeset { offset: start })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attributes::{attributes, AttrValue};
+    use crate::{Compose, Delta, Error};
+
+    use super::{from_changeset, to_changeset, AttributePool};
+
+    #[test]
+    fn test_to_changeset() {
+        let mut pool = AttributePool::new();
+        let delta = Delta::new()
+            .retain(5, None)
+            .insert(" world".to_owned(), attributes("bold", true));
+
+        assert_eq!(
+            to_changeset("hello\n", &delta, &mut pool),
+            Ok("Z:6>6=5*0+6$ world".to_owned())
+        );
+        assert_eq!(pool.get(0), Some(("bold", "true")));
+    }
+
+    #[test]
+    fn test_to_changeset_newlines() {
+        let delta = Delta::new().insert("x\ny".to_owned(), None).delete(4);
+
+        assert_eq!(
+            to_changeset("ab\ncd\n", &delta, &mut AttributePool::new()),
+            Ok("Z:6<1|1-3-1|1+2+1$x\ny".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_from_changeset() {
+        let mut pool = AttributePool::new();
+        pool.put("author", "a.1");
+        pool.put("bold", "");
+
+        assert_eq!(
+            from_changeset("Z:6>3|1=3*1=1*0+3$xyz", &pool),
+            Ok(Delta::new()
+                .retain(3, None)
+                .retain(1, attributes("bold", AttrValue::Null))
+                .insert("xyz".to_owned(), attributes("author", "a.1")))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut pool = AttributePool::new();
+        let base = Delta::new().insert("ab\ncd\nef\n".to_owned(), None);
+        let delta = Delta::new()
+            .retain(1, None)
+            .insert("é\n".to_owned(), attributes("italic", "true"))
+            .delete(3)
+            .retain(4, attributes("align", "center"));
+
+        let changeset = to_changeset("ab\ncd\nef\n", &delta, &mut pool).unwrap();
+
+        assert_eq!(
+            base.clone()
+                .compose(from_changeset(&changeset, &pool).unwrap()),
+            base.compose(delta)
+        );
+    }
+
+    #[test]
+    fn test_invalid_changeset() {
+        let pool = AttributePool::new();
+
+        assert_eq!(
+            from_changeset("Z:6>3*0+3$xyz", &pool),
+            Err(Error::InvalidChangeset { offset: 6 })
+        );
+        assert_eq!(
+            from_changeset("Z:6>3+2$xyz", &pool),
+            Err(Error::InvalidChangeset { offset: 7 })
+        );
+        assert_eq!(
+            from_changeset("Z:6>3=7$", &pool),
+            Err(Error::InvalidChangeset { offset: 7 })
+        );
+    }
+
+    #[test]
+    fn test_pool_serde() {
+        let mut pool = AttributePool::new();
+        pool.put("bold", "true");
+
+        assert_eq!(
+            serde_json::to_value(&pool).unwrap(),
+            serde_json::json!({ "numToAttrib": { "0": ["bold", "true"] }, "nextNum": 1 })
+        );
+    }
+}
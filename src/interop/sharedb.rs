@@ -0,0 +1,320 @@
+//! Conversions between deltas and [ShareDB](https://github.com/share/sharedb)'s
+//! `text0` and `json0` operations.
+//!
+//! This module is only available with the `sharedb` feature enabled. A `text0`
+//! operation is a list of components that each insert or delete a string at a
+//! position in the document that results from applying the previous
+//! components. A `json0` operation edits strings in the same way, but each
+//! component has a path that ends with the position in the string.
+//!
+//! Neither format supports attributes, so attributes are ignored when
+//! converting deltas and never set when converting operations. Positions count
+//! characters (like [`Len`] does for strings) rather than the
+//! UTF-16 code units that JavaScript uses.
+
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::seq::split_at;
+use crate::{Compose, Delta, Error, Len, Op};
+
+/// Component of a `text0` operation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Text0Component {
+    /// Inserts the given string at the given position.
+    Insert {
+        /// Position at which to insert the string.
+        p: usize,
+
+        /// String to insert.
+        i: String,
+    },
+
+    /// Deletes the given string, which starts at the given position.
+    Delete {
+        /// Position of the first character to delete.
+        p: usize,
+
+        /// String to delete.
+        d: String,
+    },
+}
+
+/// Component of a `json0` operation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Json0Component {
+    /// Path of the value that this component edits. Each segment is either an
+    /// object key or a list index.
+    pub p: Vec<Value>,
+
+    /// Edit that this component makes.
+    #[serde(flatten)]
+    pub op: Json0Op,
+}
+
+/// Edit that a [`Json0Component`] makes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Json0Op {
+    /// Inserts a string into the string at the parent of the component's path.
+    /// The last segment of the path is the position in that string.
+    StringInsert {
+        /// String to insert.
+        si: String,
+    },
+
+    /// Deletes a string from the string at the parent of the component's path.
+    /// The last segment of the path is the position in that string.
+    StringDelete {
+        /// String to delete.
+        sd: String,
+    },
+
+    /// Applies an operation of a subtype (e.g. `text0`) to the value at the
+    /// component's path.
+    Subtype {
+        /// Name of the subtype.
+        t: String,
+
+        /// Operation of the subtype.
+        o: Value,
+    },
+
+    /// Any other edit (e.g. inserting into a list or replacing an object key),
+    /// which can't be represented as a delta.
+    Other(Map<String, Value>),
+}
+
+/// Returns the `text0` operation that has the same effect as the given delta
+/// when applied to the given text. The text is used to fill in the strings
+/// that the operation deletes.
+pub fn to_text0<A>(base: &str, delta: &Delta<String, A>) -> Vec<Text0Component> {
+    let mut components = vec![];
+    let mut rest = base;
+    let mut position = 0usize;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(insert) => {
+                components.push(Text0Component::Insert {
+                    p: position,
+                    i: insert.insert().clone(),
+                });
+
+                position += insert.len();
+            }
+            Op::Retain(retain) => {
+                let (_, after) = split_at(rest, retain.len());
+                position += rest.chars().count() - after.chars().count();
+                rest = after;
+            }
+            Op::Delete(delete) => {
+                let (deleted, after) = split_at(rest, delete.len());
+                rest = after;
+
+                if !deleted.is_empty() {
+                    components.push(Text0Component::Delete {
+                        p: position,
+                        d: deleted.to_owned(),
+                    });
+                }
+            }
+        }
+    }
+
+    components
+}
+
+/// Returns the delta that has the same effect as the given `text0` operation.
+pub fn from_text0<A>(components: &[Text0Component]) -> Delta<String, A>
+where
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    components
+        .iter()
+        .fold(Delta::new(), |delta, component| match component {
+            Text0Component::Insert { p, i } => {
+                delta.compose(Delta::new().retain(*p, None).insert(i.clone(), None))
+            }
+            Text0Component::Delete { p, d } => {
+                delta.compose(Delta::new().retain(*p, None).delete(d.chars().count()))
+            }
+        })
+}
+
+/// Returns the `json0` operation that has the same effect as the given delta
+/// when applied to the given text, which is stored at the given path.
+pub fn to_json0<A>(path: &[Value], base: &str, delta: &Delta<String, A>) -> Vec<Json0Component> {
+    to_text0(base, delta)
+        .into_iter()
+        .map(|component| {
+            let (p, op) = match component {
+                Text0Component::Insert { p, i } => (p, Json0Op::StringInsert { si: i }),
+                Text0Component::Delete { p, d } => (p, Json0Op::StringDelete { sd: d }),
+            };
+
+            let mut path = path.to_vec();
+            path.push(p.into());
+
+            Json0Component { p: path, op }
+        })
+        .collect()
+}
+
+/// Returns the delta that has the same effect on the string at the given path
+/// as the given `json0` operation. Components that edit strings or apply a
+/// `text0` operation at that path are converted, while components with
+/// unrelated paths are skipped. Returns [`Error::Unsupported`] if a component
+/// edits that string (or one of its ancestors) in any other way.
+///
+/// Note that components that move the string (e.g. by inserting into a list
+/// that contains it) are considered unrelated, so the given path must be the
+/// path of the string at the time the operation is applied.
+pub fn from_json0<A>(
+    path: &[Value],
+    components: &[Json0Component],
+) -> Result<Delta<String, A>, Error>
+where
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let mut delta = Delta::new();
+
+    for (index, component) in components.iter().enumerate() {
+        if !component.p.starts_with(path) && !path.starts_with(&component.p) {
+            continue;
+        }
+
+        let position = match &component.p[..] {
+            [parent @ .., position] if parent == path => position.as_u64(),
+            _ => None,
+        };
+
+        let change = match (&component.op, position) {
+            (Json0Op::StringInsert { si }, Some(p)) => Delta::new()
+                .retain(p as usize, None)
+                .insert(si.clone(), None),
+            (Json0Op::StringDelete { sd }, Some(p)) => Delta::new()
+                .retain(p as usize, None)
+                .delete(sd.chars().count()),
+            (Json0Op::Subtype { t, o }, _) if t == "text0" && component.p == path => {
+                let components = serde_json::from_value::<Vec<Text0Component>>(o.clone())
+                    .map_err(|_| Error::Unsupported { index })?;
+
+                from_text0(&components)
+            }
+            _ => return Err(Error::Unsupported { index }),
+        };
+
+        delta = delta.compose(change);
+    }
+
+    Ok(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{Compose, Delta, Error};
+
+    use super::{from_json0, from_text0, to_json0, to_text0, Json0Component, Text0Component};
+
+    #[test]
+    fn test_to_text0() {
+        let delta = Delta::<String, ()>::new()
+            .retain(2, None)
+            .insert("é".to_owned(), None)
+            .delete(2)
+            .retain(1, None)
+            .insert("!".to_owned(), None);
+
+        assert_eq!(
+            serde_json::to_value(to_text0("abcde", &delta)).unwrap(),
+            json!([{ "p": 2, "i": "é" }, { "p": 3, "d": "cd" }, { "p": 4, "i": "!" }])
+        );
+    }
+
+    #[test]
+    fn test_text0_round_trip() {
+        let base = Delta::<String, ()>::new().insert("hello world".to_owned(), None);
+        let delta = Delta::new()
+            .delete(1)
+            .insert("H".to_owned(), None)
+            .retain(4, None)
+            .delete(6)
+            .insert("!".to_owned(), None);
+
+        let components = to_text0("hello world", &delta);
+
+        assert_eq!(
+            base.clone().compose(from_text0(&components)),
+            base.compose(delta)
+        );
+    }
+
+    #[test]
+    fn test_from_text0() {
+        let components: Vec<Text0Component> =
+            serde_json::from_value(json!([{ "p": 1, "d": "b" }, { "p": 0, "i": "x" }])).unwrap();
+
+        assert_eq!(
+            from_text0::<()>(&components),
+            Delta::new()
+                .insert("x".to_owned(), None)
+                .retain(1, None)
+                .delete(1)
+        );
+    }
+
+    #[test]
+    fn test_json0_round_trip() {
+        let path = [json!("notes"), json!(0)];
+        let delta = Delta::<String, ()>::new()
+            .retain(1, None)
+            .insert("x".to_owned(), None)
+            .delete(1);
+
+        let components = to_json0(&path, "abc", &delta);
+
+        assert_eq!(
+            serde_json::to_value(&components).unwrap(),
+            json!([
+                { "p": ["notes", 0, 1], "si": "x" },
+                { "p": ["notes", 0, 2], "sd": "b" },
+            ])
+        );
+        assert_eq!(from_json0(&path, &components), Ok(delta));
+    }
+
+    #[test]
+    fn test_from_json0() {
+        let components: Vec<Json0Component> = serde_json::from_value(json!([
+            { "p": ["title"], "t": "text0", "o": [{ "p": 0, "i": "a" }] },
+            { "p": ["body", 0], "si": "b" },
+            { "p": ["title", 1], "si": "c" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            from_json0::<()>(&[json!("title")], &components),
+            Ok(Delta::new().insert("ac".to_owned(), None))
+        );
+    }
+
+    #[test]
+    fn test_from_json0_unsupported() {
+        let components: Vec<Json0Component> = serde_json::from_value(json!([
+            { "p": ["title", 0], "si": "a" },
+            { "p": ["title"], "od": "a", "oi": "b" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            from_json0::<()>(&[json!("title")], &components),
+            Err(Error::Unsupported { index: 1 })
+        );
+    }
+}
@@ -1,24 +1,35 @@
-use std::{iter::from_fn, vec::IntoIter};
+use std::iter::{from_fn, Cloned, FusedIterator};
+use std::slice;
+use std::vec::IntoIter;
 
-use super::{Len, Op, Seq};
+use super::{Len, Op};
 
 /// Iterator over [`Ops`](Op) with a utility function to zip two iters together
 /// and apply a map function that supports partial consumption of either op, as
 /// used by [`Compose`](crate::Compose) and [`Transform`](crate::Transform).
-pub struct Iter<T, A> {
-    iter: IntoIter<Op<T, A>>,
-    partial: Option<Op<T, A>>,
+pub type Iter<T, A> = Cursor<Op<T, A>>;
+
+/// Iterator over the [`Ops`](Op) of a borrowed delta (see [`Delta::cursor`](
+/// crate::Delta::cursor)). Each op is only cloned once the cursor reaches it.
+pub type RefIter<'a, T, A> = Cursor<Op<T, A>, Cloned<slice::Iter<'a, Op<T, A>>>>;
+
+/// Iterator over any kind of op (including the custom ops of an
+/// [`Extension`](crate::ext::Extension)) that can partially consume its next
+/// item. See [`Iter`] for the iterator over [`Ops`](Op).
+pub struct Cursor<O, I = IntoIter<O>> {
+    iter: I,
+    partial: Option<O>,
 }
 
-impl<T, A> Iter<T, A>
+impl<O, I> Cursor<O, I>
 where
-    T: Clone + Default + Seq,
-    A: Clone + Default,
+    O: Len,
+    I: Iterator<Item = O>,
 {
-    pub(crate) fn new(iter: IntoIter<Op<T, A>>) -> Iter<T, A> {
-        Iter {
+    pub(crate) fn new(iter: I) -> Cursor<O, I> {
+        Cursor {
             iter,
-            partial: Default::default(),
+            partial: None,
         }
     }
 
@@ -26,9 +37,9 @@ where
     /// may use the mutable reference to partially consume the next op (instead
     /// of fully). For example, this is used by [`Compose`](crate::Compose) and
     /// [`Transform`](crate::Transform) to process both next items in place.
-    pub fn next_mut(&mut self) -> Option<&mut Op<T, A>> {
+    pub fn next_mut(&mut self) -> Option<&mut O> {
         match &self.partial {
-            Some(partial) if partial.len() > 0 => self.partial.as_mut(),
+            Some(partial) if !partial.is_empty() => self.partial.as_mut(),
             Some(_) | None => {
                 self.partial = self.iter.next();
                 self.partial.as_mut()
@@ -41,14 +52,15 @@ where
     /// an op. The remainder of that op will be fed to the next invocation. This
     /// will continue until either iterator is exhausted. Note that this means
     /// that the iterators are not necessarily both exhausted when this function
-    /// returns.
+    /// returns. See [`OpPairCursor`] for a cursor that walks both iterators
+    /// step by step instead.
     pub fn zip_mut<'a, F, U>(
         &'a mut self,
-        other: &'a mut Iter<T, A>,
+        other: &'a mut Cursor<O, I>,
         map_fn: F,
     ) -> impl Iterator<Item = U> + 'a
     where
-        F: for<'b> Fn(&'b mut Op<T, A>, &'b mut Op<T, A>) -> U + 'a,
+        F: for<'b> Fn(&'b mut O, &'b mut O) -> U + 'a,
     {
         from_fn(move || match (self.next_mut(), other.next_mut()) {
             (Some(self_op), Some(other_op)) => Some(map_fn(self_op, other_op)),
@@ -57,17 +69,176 @@ where
     }
 }
 
-impl<T, A> Iterator for Iter<T, A>
+impl<O, I> Iterator for Cursor<O, I>
 where
-    T: Default + Seq,
-    A: Default,
+    O: Len,
+    I: Iterator<Item = O>,
 {
-    type Item = Op<T, A>;
+    type Item = O;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.partial.take() {
-            Some(partial) if partial.len() > 0 => Some(partial),
+            Some(partial) if !partial.is_empty() => Some(partial),
             Some(_) | None => self.iter.next(),
         }
     }
+
+    /// Counts the partially consumed op (unless it's fully consumed) and the
+    /// ops that haven't been touched yet. Note that this includes ops with a
+    /// length of zero, which [`Cursor::next`] also returns.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let partial = self
+            .partial
+            .as_ref()
+            .is_some_and(|partial| !partial.is_empty()) as usize;
+        let (lower, upper) = self.iter.size_hint();
+
+        (
+            lower.saturating_add(partial),
+            upper.and_then(|upper| upper.checked_add(partial)),
+        )
+    }
+}
+
+impl<O, I> ExactSizeIterator for Cursor<O, I>
+where
+    O: Len,
+    I: ExactSizeIterator<Item = O>,
+{
+}
+
+impl<O, I> FusedIterator for Cursor<O, I>
+where
+    O: Len,
+    I: FusedIterator<Item = O>,
+{
+}
+
+/// Cursor over the ops of two deltas at once, for writing custom algorithms
+/// that walk two deltas pairwise (like [`Compose`](crate::Compose) and
+/// [`Transform`](crate::Transform) do, or e.g. a three-way merge).
+///
+/// Each call to [`next_mut`](OpPairCursor::next_mut) returns mutable references
+/// to the next op of both sides. The caller decides how much of each op to
+/// consume, typically by [`split`](crate::Split)ting one or both of them. Ops
+/// that are consumed partially are returned again (with the remainder) by the
+/// next call, and ops that are consumed fully (i.e. whose length drops to zero)
+/// are skipped. Either side may be an owned [`Iter`] or a [`RefIter`] over a
+/// borrowed delta.
+pub struct OpPairCursor<O, I = IntoIter<O>, J = I> {
+    lhs: Cursor<O, I>,
+    rhs: Cursor<O, J>,
+}
+
+impl<O, I, J> OpPairCursor<O, I, J>
+where
+    O: Len,
+    I: Iterator<Item = O>,
+    J: Iterator<Item = O>,
+{
+    /// Returns a new cursor over the ops of both given iterators.
+    pub fn new(lhs: Cursor<O, I>, rhs: Cursor<O, J>) -> OpPairCursor<O, I, J> {
+        OpPairCursor { lhs, rhs }
+    }
+
+    /// Returns mutable references to the next op of both sides, or `None` if
+    /// either side is exhausted. The remainder of the other side can then be
+    /// retrieved with [`into_inner`](OpPairCursor::into_inner).
+    pub fn next_mut(&mut self) -> Option<(&mut O, &mut O)> {
+        match (self.lhs.next_mut(), self.rhs.next_mut()) {
+            (Some(lhs), Some(rhs)) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+
+    /// Returns the cursor over the left-hand side, e.g. to consume ops that
+    /// don't have a counterpart on the other side.
+    pub fn lhs_mut(&mut self) -> &mut Cursor<O, I> {
+        &mut self.lhs
+    }
+
+    /// Returns the cursor over the right-hand side, e.g. to consume ops that
+    /// don't have a counterpart on the other side.
+    pub fn rhs_mut(&mut self) -> &mut Cursor<O, J> {
+        &mut self.rhs
+    }
+
+    /// Returns the cursors over both sides, including any partially consumed
+    /// ops.
+    pub fn into_inner(self) -> (Cursor<O, I>, Cursor<O, J>) {
+        (self.lhs, self.rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpPairCursor;
+    use crate::ops::{Insert, Retain};
+    use crate::{Delta, Len, Op, Split};
+
+    #[test]
+    fn test_len() {
+        let delta = Delta::<_, ()>::new()
+            .insert("abc".to_owned(), None)
+            .retain(2, None)
+            .delete(1);
+        let mut iter = delta.into_iter();
+
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+
+        if let Some(Op::Insert(insert)) = iter.next_mut() {
+            *insert = Insert::new("c".to_owned(), None);
+        }
+
+        assert_eq!(iter.len(), 3);
+
+        if let Some(Op::Insert(insert)) = iter.next_mut() {
+            *insert = Insert::default();
+        }
+
+        assert_eq!(iter.len(), 2);
+        assert!(matches!(iter.next(), Some(Op::Retain(_))));
+        assert_eq!(iter.len(), 1);
+        assert!(matches!(iter.next(), Some(Op::Delete(_))));
+        assert_eq!(iter.len(), 0);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_op_pair_cursor() {
+        let lhs = Delta::<String, ()>::new()
+            .retain(2, None)
+            .delete(3)
+            .retain(2, None);
+        let rhs = Delta::<String, ()>::new().retain(3, None).delete(2);
+
+        let mut cursor = OpPairCursor::new(lhs.cursor(), rhs.into_iter());
+        let mut both_deleted = 0;
+        let mut position = 0;
+
+        while let Some((lhs, rhs)) = cursor.next_mut() {
+            let len = lhs.len().min(rhs.len());
+
+            if let (Op::Delete(_), Op::Delete(_)) = (lhs.split(len), rhs.split(len)) {
+                both_deleted += len;
+            }
+
+            position += len;
+        }
+
+        assert_eq!((position, both_deleted), (5, 2));
+
+        let (mut lhs_rest, rhs_rest) = cursor.into_inner();
+
+        assert_eq!(
+            lhs_rest.next(),
+            Some(Op::Retain(Retain {
+                retain: 2,
+                attributes: None
+            }))
+        );
+        assert_eq!(rhs_rest.len(), 0);
+        assert_eq!(lhs, Delta::new().retain(2, None).delete(3).retain(2, None));
+    }
 }
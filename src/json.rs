@@ -0,0 +1,367 @@
+//! Attributes that consist of arbitrary JSON objects, using the same key-wise
+//! semantics as Quill's `AttributeMap`.
+//!
+//! This module is only available with the `json` feature enabled. With this
+//! module, a relay server can handle arbitrary attribute payloads without any
+//! custom attribute types by using [`JsonAttributes`] as the attributes of a
+//! [`Delta`](crate::Delta). A `null` value in these attributes removes the
+//! corresponding format.
+
+use std::hash::Hasher;
+use std::mem::size_of;
+
+use serde_json::{Map, Value};
+
+use super::validate::{AttributeKeys, AttributeValues, ValueRef};
+use super::{Compose, Diff, Hashable, HeapSize};
+
+/// Attributes that consist of a JSON object that maps formats to their values.
+pub type JsonAttributes = Map<String, Value>;
+
+/// Merges the attributes key-wise, where the given attributes take precedence.
+/// When composing retains, `null` values are kept so that they continue to
+/// remove formats when the composed attributes are applied. When composing an
+/// insert with a retain, they're dropped like Quill does (see [`compose`]).
+/// When transforming retains, only the formats of the other retain are dropped
+/// (see [`transform`]).
+impl Compose<Map<String, Value>> for Map<String, Value> {
+    type Output = Map<String, Value>;
+
+    fn compose(self, rhs: Map<String, Value>) -> Self::Output {
+        compose(self, rhs, true)
+    }
+
+    fn compose_insert(lhs: Option<Self>, rhs: Map<String, Value>) -> Option<Self::Output> {
+        Some(compose(lhs.unwrap_or_default(), rhs, false))
+            .filter(|attributes| !attributes.is_empty())
+    }
+
+    fn transform_retain(lhs: Option<Self>, rhs: Option<Self>, priority: bool) -> Option<Self> {
+        match lhs {
+            Some(lhs) => rhs
+                .map(|rhs| transform(&lhs, rhs, priority))
+                .filter(|attributes| !attributes.is_empty()),
+            None => rhs,
+        }
+    }
+}
+
+/// Merges the given attributes key-wise, where `rhs` takes precedence. If
+/// `keep_null` is false, the formats that `rhs` removes (i.e. sets to `null`)
+/// are dropped from the result instead, which is what Quill does when
+/// composing an insert with a retain.
+pub fn compose(mut lhs: JsonAttributes, rhs: JsonAttributes, keep_null: bool) -> JsonAttributes {
+    for (key, value) in rhs {
+        match keep_null || !value.is_null() {
+            true => lhs.insert(key, value),
+            false => lhs.remove(&key),
+        };
+    }
+
+    lhs
+}
+
+/// Returns the attributes that need to be applied to `lhs` to obtain `rhs`.
+/// Formats that are set in `lhs` but not in `rhs` are set to `null`.
+pub fn diff(lhs: &JsonAttributes, rhs: &JsonAttributes) -> JsonAttributes {
+    let mut attributes = JsonAttributes::new();
+
+    for (key, value) in lhs {
+        if rhs.get(key) != Some(value) {
+            attributes.insert(key.clone(), rhs.get(key).cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    for (key, value) in rhs {
+        if !lhs.contains_key(key) {
+            attributes.insert(key.clone(), value.clone());
+        }
+    }
+
+    attributes
+}
+
+/// Returns the [`diff`] between the attributes, or `None` if they're equal.
+impl Diff for JsonAttributes {
+    fn diff(&self, other: &Self) -> Option<Self> {
+        Some(diff(self, other)).filter(|attributes| !attributes.is_empty())
+    }
+}
+
+impl AttributeKeys for JsonAttributes {
+    fn keys(&self) -> Vec<&str> {
+        Map::keys(self).map(String::as_str).collect()
+    }
+
+    fn remove_key(&mut self, key: &str) {
+        self.remove(key);
+    }
+}
+
+/// Maps numbers to [`ValueRef::Int`] if they fit in an `i64` and to
+/// [`ValueRef::Float`] otherwise.
+impl AttributeValues for JsonAttributes {
+    fn value(&self, key: &str) -> Option<ValueRef<'_>> {
+        self.get(key).map(|value| match value {
+            Value::Null => ValueRef::Null,
+            Value::Bool(value) => ValueRef::Bool(*value),
+            Value::Number(number) => match number.as_i64() {
+                Some(value) => ValueRef::Int(value),
+                None => ValueRef::Float(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(value) => ValueRef::String(value),
+            Value::Array(_) => ValueRef::Array,
+            Value::Object(_) => ValueRef::Map,
+        })
+    }
+}
+
+/// Hashes the entries of these attributes ordered by their keys, so that the
+/// hash doesn't depend on whether serde_json's `preserve_order` feature is
+/// enabled.
+impl Hashable for JsonAttributes {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+
+        state.write_u64(entries.len() as u64);
+
+        for (key, value) in entries {
+            key.hash_content(state);
+            value.hash_content(state);
+        }
+    }
+}
+
+/// Hashes the type of this value followed by its contents. Numbers are hashed
+/// by their JSON representation, so that e.g. `1` and `1.0` hash differently.
+impl Hashable for Value {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Value::Null => state.write_u8(0),
+            Value::Bool(value) => {
+                state.write_u8(1);
+                value.hash_content(state);
+            }
+            Value::Number(value) => {
+                state.write_u8(2);
+                value.to_string().hash_content(state);
+            }
+            Value::String(value) => {
+                state.write_u8(3);
+                value.hash_content(state);
+            }
+            Value::Array(value) => {
+                state.write_u8(4);
+                value.hash_content(state);
+            }
+            Value::Object(value) => {
+                state.write_u8(5);
+                value.hash_content(state);
+            }
+        }
+    }
+}
+
+/// Estimates the size of the entries of these attributes like the
+/// [`HeapSize`] impl of `BTreeMap`.
+impl HeapSize for JsonAttributes {
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(key, value)| {
+                size_of::<String>() + size_of::<Value>() + key.heap_size() + value.heap_size()
+            })
+            .sum()
+    }
+}
+
+/// Numbers are stored inline, so only strings, arrays and objects own memory
+/// on the heap.
+impl HeapSize for Value {
+    fn heap_size(&self) -> usize {
+        match self {
+            Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+            Value::String(value) => value.heap_size(),
+            Value::Array(value) => value.heap_size(),
+            Value::Object(value) => value.heap_size(),
+        }
+    }
+}
+
+/// Returns the attributes that undo applying `attributes` to elements that had
+/// the given `base` attributes.
+pub fn invert(attributes: &JsonAttributes, base: &JsonAttributes) -> JsonAttributes {
+    let mut inverted = JsonAttributes::new();
+
+    for (key, value) in base {
+        if matches!(attributes.get(key), Some(attribute) if attribute != value) {
+            inverted.insert(key.clone(), value.clone());
+        }
+    }
+
+    for key in attributes.keys() {
+        if !base.contains_key(key) {
+            inverted.insert(key.clone(), Value::Null);
+        }
+    }
+
+    inverted
+}
+
+/// Transforms `rhs` against concurrently applied `lhs` attributes. If `lhs`
+/// has priority, formats that `lhs` sets are dropped from `rhs`.
+pub fn transform(lhs: &JsonAttributes, rhs: JsonAttributes, priority: bool) -> JsonAttributes {
+    match priority {
+        true => rhs
+            .into_iter()
+            .filter(|(key, _)| !lhs.contains_key(key))
+            .collect(),
+        false => rhs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{compose, diff, invert, transform, JsonAttributes};
+    use crate::validate::{AttributeValues, ValueRef};
+    use crate::{Compose, Delta, Document, Transform};
+
+    fn attributes(value: Value) -> JsonAttributes {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs = attributes(json!({ "bold": true, "color": "red" }));
+        let rhs = attributes(json!({ "italic": true, "color": null }));
+
+        assert_eq!(
+            lhs.clone().compose(rhs.clone()),
+            attributes(json!({ "bold": true, "italic": true, "color": null }))
+        );
+        assert_eq!(
+            compose(lhs, rhs, false),
+            attributes(json!({ "bold": true, "italic": true }))
+        );
+    }
+
+    #[test]
+    fn test_compose_insert() {
+        let insert = |value: Value| Delta::new().insert("A".to_owned(), attributes(value));
+        let retain = |value: Value| Delta::<String, _>::new().retain(1, attributes(value));
+
+        // Upstream's "insert + retain" vector.
+        assert_eq!(
+            insert(json!({})).compose(retain(
+                json!({ "bold": true, "color": "red", "font": null })
+            )),
+            insert(json!({ "bold": true, "color": "red" }))
+        );
+        assert_eq!(
+            insert(json!({ "bold": true })).compose(retain(json!({ "bold": null }))),
+            Delta::new().insert("A".to_owned(), None)
+        );
+        assert_eq!(
+            retain(json!({ "color": "blue" })).compose(retain(json!({ "font": null }))),
+            retain(json!({ "color": "blue", "font": null }))
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let lhs = attributes(json!({ "bold": true, "color": "red", "font": "serif" }));
+        let rhs = attributes(json!({ "bold": true, "color": "blue", "italic": true }));
+
+        assert_eq!(
+            diff(&lhs, &rhs),
+            attributes(json!({ "color": "blue", "font": null, "italic": true }))
+        );
+    }
+
+    #[test]
+    fn test_invert() {
+        let base = attributes(json!({ "bold": true, "color": "red", "font": "serif" }));
+        let applied = attributes(json!({ "bold": true, "color": "blue", "italic": true }));
+
+        assert_eq!(
+            invert(&applied, &base),
+            attributes(json!({ "color": "red", "italic": null }))
+        );
+        assert_eq!(
+            base.clone()
+                .compose(applied.clone())
+                .compose(invert(&applied, &base)),
+            attributes(json!({ "bold": true, "color": "red", "font": "serif", "italic": null }))
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let lhs = attributes(json!({ "color": "blue" }));
+        let rhs = attributes(json!({ "bold": true, "color": "red" }));
+
+        assert_eq!(
+            transform(&lhs, rhs.clone(), true),
+            attributes(json!({ "bold": true }))
+        );
+        assert_eq!(transform(&lhs, rhs.clone(), false), rhs);
+
+        let retain = |value: Value| Delta::<String, _>::new().retain(1, attributes(value));
+
+        // Upstream's "retain + retain" vectors.
+        assert_eq!(
+            retain(json!({ "color": "blue" }))
+                .transform(retain(json!({ "bold": true, "color": "red" })), true),
+            retain(json!({ "bold": true }))
+        );
+        assert_eq!(
+            retain(json!({ "bold": true, "color": "red" }))
+                .transform(retain(json!({ "color": "blue" })), true),
+            Delta::new()
+        );
+        assert_eq!(
+            retain(json!({ "color": "blue" }))
+                .transform(retain(json!({ "bold": true, "color": "red" })), false),
+            retain(json!({ "bold": true, "color": "red" }))
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let hash = |value: Value| {
+            Document::from_delta(Delta::new().insert("a".to_owned(), attributes(value)))
+                .unwrap()
+                .content_hash()
+        };
+
+        assert_eq!(
+            hash(json!({ "bold": true, "color": "red" })),
+            hash(json!({ "color": "red", "bold": true }))
+        );
+        assert_ne!(hash(json!({ "size": 1 })), hash(json!({ "size": 1.0 })));
+        assert_ne!(hash(json!({ "size": 1 })), hash(json!({ "size": "1" })));
+    }
+
+    #[test]
+    fn test_values() {
+        let attributes = attributes(json!({
+            "header": 1,
+            "size": 1.5,
+            "big": u64::MAX,
+            "list": [],
+            "color": null,
+        }));
+
+        assert_eq!(attributes.value("header"), Some(ValueRef::Int(1)));
+        assert_eq!(attributes.value("size"), Some(ValueRef::Float(1.5)));
+        assert_eq!(
+            attributes.value("big"),
+            Some(ValueRef::Float(u64::MAX as f64))
+        );
+        assert_eq!(attributes.value("list"), Some(ValueRef::Array));
+        assert_eq!(attributes.value("color"), Some(ValueRef::Null));
+        assert_eq!(attributes.value("bold"), None);
+    }
+}
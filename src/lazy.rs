@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+use std::sync::{Arc, OnceLock};
+
+use serde::{Serialize, Serializer};
+
+use super::{Compose, Delta, Seq, Transform};
+
+/// Chain of deltas whose composition is only computed once it's needed.
+///
+/// Composing a lazy delta with another delta merely records that delta, and
+/// the deltas themselves are shared through [`Arc`]s. The composition is
+/// computed the first time it's [`materialize`](LazyDelta::materialize)d (e.g.
+/// when it's serialized) and then cached. Clones share that cache, so a server
+/// that sends the same tail of its history to many clients only composes it
+/// once.
+#[derive(Clone, Debug)]
+pub struct LazyDelta<T, A> {
+    parts: Vec<Arc<Delta<T, A>>>,
+    composed: Arc<OnceLock<Delta<T, A>>>,
+}
+
+impl<T, A> LazyDelta<T, A> {
+    /// Returns a new lazy delta that's equal to the given delta.
+    pub fn new(delta: impl Into<Arc<Delta<T, A>>>) -> LazyDelta<T, A> {
+        LazyDelta {
+            parts: vec![delta.into()],
+            composed: Default::default(),
+        }
+    }
+
+    /// Returns a lazy delta that applies the given delta after this delta,
+    /// without computing their composition yet.
+    pub fn compose(mut self, rhs: impl Into<Arc<Delta<T, A>>>) -> LazyDelta<T, A> {
+        self.parts.push(rhs.into());
+        self.composed = Default::default();
+        self
+    }
+
+    /// Returns the deltas that make up this lazy delta, in order.
+    pub fn parts(&self) -> &[Arc<Delta<T, A>>] {
+        &self.parts
+    }
+
+    /// Returns true if the composition of this lazy delta has already been
+    /// computed.
+    pub fn is_materialized(&self) -> bool {
+        self.composed.get().is_some()
+    }
+}
+
+impl<T, A> LazyDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Returns the composition of the deltas that make up this lazy delta,
+    /// which is computed the first time this is called on this lazy delta or
+    /// any of its clones.
+    pub fn materialize(&self) -> &Delta<T, A> {
+        self.composed.get_or_init(|| {
+            let (first, rest) = self.parts.split_first().expect("at least one part");
+
+            rest.iter().fold(Delta::clone(first), |delta, part| {
+                delta.compose(Delta::clone(part))
+            })
+        })
+    }
+
+    /// Returns the composition of the deltas that make up this lazy delta.
+    pub fn into_delta(self) -> Delta<T, A> {
+        self.materialize();
+
+        match Arc::try_unwrap(self.composed) {
+            Ok(composed) => composed.into_inner().unwrap_or_default(),
+            Err(composed) => composed.get().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Transforms the given delta with the composition of this lazy delta, which
+/// is equivalent to transforming it through each of its parts in order (see
+/// [`Delta::transform_through`]). The composition is materialized by the first
+/// transform and then reused by every later transform with this lazy delta or
+/// any of its clones, so a server that rebases many deltas onto the same slice
+/// of its history only composes that slice once.
+impl<T, A> Transform<Delta<T, A>> for &LazyDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = Delta<T, A>;
+
+    fn transform(self, mut rhs: Delta<T, A>, priority: bool) -> Self::Output {
+        rhs.transform_assign(self.materialize(), priority);
+        rhs
+    }
+}
+
+impl<T, A> From<Delta<T, A>> for LazyDelta<T, A> {
+    fn from(value: Delta<T, A>) -> Self {
+        LazyDelta::new(value)
+    }
+}
+
+/// Serializes the composition of this lazy delta, which is materialized first
+/// if necessary.
+impl<T, A> Serialize for LazyDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + Serialize,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A> + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.materialize().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::LazyDelta;
+    use crate::{Compose, Delta, Transform};
+
+    #[test]
+    fn test_materialize() {
+        let tail = [
+            Arc::new(Delta::<String, ()>::new().insert("a".to_owned(), None)),
+            Arc::new(Delta::new().retain(1, None).insert("b".to_owned(), None)),
+            Arc::new(Delta::new().delete(1)),
+        ];
+
+        let lazy = tail[1..]
+            .iter()
+            .fold(LazyDelta::new(tail[0].clone()), |lazy, delta| {
+                lazy.compose(delta.clone())
+            });
+        let shared = lazy.clone();
+
+        assert!(!shared.is_materialized());
+        assert_eq!(
+            lazy.materialize(),
+            &Delta::new().insert("b".to_owned(), None)
+        );
+        assert!(shared.is_materialized());
+        assert!(Arc::ptr_eq(&shared.parts()[1], &tail[1]));
+
+        let snapshot = Delta::new().insert("Hello".to_owned(), None);
+        let client = LazyDelta::new(snapshot.clone()).compose(lazy.into_delta());
+
+        assert_eq!(
+            serde_json::to_value(&client).unwrap(),
+            serde_json::to_value(snapshot.compose(shared.into_delta())).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let history = [
+            Delta::<_, ()>::new()
+                .retain(1, None)
+                .insert("X".to_owned(), None),
+            Delta::new().retain(3, None).delete(1),
+            Delta::new().insert("Y".to_owned(), None),
+        ];
+
+        let lazy = LazyDelta::new(history[0].clone())
+            .compose(history[1].clone())
+            .compose(history[2].clone());
+
+        for delta in [
+            Delta::new().retain(2, None).insert("Z".to_owned(), None),
+            Delta::new().retain(1, None).delete(2),
+            Delta::new().retain(3, None).insert("W".to_owned(), None),
+        ] {
+            assert_eq!(
+                lazy.transform(delta.clone(), false),
+                delta.transform_through(&history, false)
+            );
+        }
+
+        assert!(lazy.is_materialized());
+    }
+}
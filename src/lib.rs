@@ -41,22 +41,121 @@
 //! testing. Simply put, this library wouldn't exist without their amazing work
 //! on Quill.
 
+mod anchor;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+pub mod attributes;
+pub mod bare;
+#[cfg(feature = "ciborium")]
+pub mod cbor;
+mod compact;
 mod compose;
+mod composer;
+mod de;
+pub mod dedup;
 mod delta;
+mod diff;
+mod dmp;
+mod document;
+mod error;
+pub mod ext;
+#[cfg(feature = "extra-fields")]
+pub mod extra;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod generate;
+mod hash;
+#[cfg(feature = "html")]
+pub mod html;
+mod inline;
+pub mod interop;
 mod iter;
+#[cfg(feature = "json")]
+pub mod json;
+mod lazy;
+mod lines;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+mod metrics;
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+mod mode;
+#[cfg(feature = "move")]
+pub mod moves;
+#[cfg(feature = "rmp-serde")]
+pub mod msgpack;
+pub mod nested;
+mod offline;
 mod op;
 pub mod ops;
+#[cfg(feature = "proptest")]
+pub mod prop;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "quill")]
+pub mod quill;
+pub mod rich;
 mod seq;
+pub mod session;
+mod size;
+#[cfg(feature = "sqlx")]
+mod sql;
+mod stats;
+mod strategy;
+mod stream;
+mod strict;
+pub mod table;
+#[cfg(feature = "tagged")]
+pub mod tagged;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod text;
+mod transaction;
 mod transform;
+pub mod tree;
+#[cfg(feature = "unicode-normalization")]
+mod unicode;
+pub mod validate;
+#[cfg(feature = "vectors")]
+pub mod vectors;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+pub use anchor::{AnchorId, Bias};
 pub use compose::Compose;
 #[doc(hidden)]
 pub use compose::LastWriteWins;
-pub use delta::Delta;
-pub use iter::Iter;
+pub use composer::Composer;
+pub use delta::{Delta, DeltaKind};
+pub use diff::Diff;
+pub use document::Document;
+pub use error::Error;
+pub use generate::{ArbitraryDocument, ArbitraryEditOf, BoundedDelta, BoundedValue, Bounds};
+pub use hash::Hashable;
+pub use inline::InlineString;
+pub use iter::{Cursor, Iter, OpPairCursor, RefIter};
+pub use lazy::LazyDelta;
+pub use lines::LineIndex;
+pub use metrics::Metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsFacade;
+pub use mode::{ApplyMode, OrderPolicy, OverflowPolicy};
+pub use offline::OfflineQueue;
 pub use op::{Op, Split};
 pub use seq::{Len, Seq};
+#[cfg(any(feature = "json", feature = "ciborium", feature = "rmp-serde"))]
+pub use size::Encoding;
+pub use size::HeapSize;
+pub use stats::Stats;
+pub use strategy::Strategy;
+pub use strict::MAX_STRICT_LEN;
+pub use transaction::Transaction;
 pub use transform::Transform;
+#[cfg(feature = "unicode-normalization")]
+pub use unicode::Normalization;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
 #[cfg(test)]
 mod tests {
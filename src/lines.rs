@@ -0,0 +1,180 @@
+use super::anchor::Shift;
+use super::seq::newlines;
+use super::{Delta, Len, Op};
+
+/// Index of the lines of a text document, which maps offsets (measured in
+/// characters, like the positions of a [`Delta`]) to zero-based lines and
+/// columns and back.
+///
+/// The index only stores the offsets of newlines, and is updated in place with
+/// [`LineIndex::apply`] as changes are applied to the document, which only
+/// scans the text that a change inserts rather than the whole document. The
+/// newlines are moved through each change like the anchors of a
+/// [`Document`](crate::Document) (see
+/// [`Document::create_anchor`](crate::Document::create_anchor)), i.e. in a
+/// single pass over the ops of the change and the sorted offsets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineIndex {
+    newlines: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    /// Returns the index of the given document, which must only contain
+    /// inserts (see [`Delta::check_document`]). This is asserted in debug
+    /// builds.
+    pub fn new<A>(document: &Delta<String, A>) -> LineIndex {
+        debug_assert!(
+            document.is_document(),
+            "index of a delta that isn't a document"
+        );
+
+        let mut index = LineIndex::default();
+        index.apply(document);
+        index
+    }
+
+    /// Returns the number of characters in the indexed document.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the indexed document is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of lines in the indexed document, which is one more
+    /// than the number of newlines (so a document that ends with a newline has
+    /// an empty last line).
+    pub fn line_count(&self) -> usize {
+        self.newlines.len() + 1
+    }
+
+    /// Returns the line and column of the given offset. Offsets past the end
+    /// of the document are clamped to its length.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.len);
+        let line = self.newlines.partition_point(|&newline| newline < offset);
+
+        (line, offset - self.line_start(line))
+    }
+
+    /// Returns the offset of the given line and column, or `None` if the line
+    /// doesn't exist or is shorter than the column (not counting its newline).
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        if line > self.newlines.len() {
+            return None;
+        }
+
+        let start = self.line_start(line);
+        let end = self.newlines.get(line).copied().unwrap_or(self.len);
+
+        start.checked_add(column).filter(|&offset| offset <= end)
+    }
+
+    /// Updates this index to reflect the given change, which is applied to the
+    /// indexed document. Like [`Delta::compose`](crate::Compose), retains and
+    /// deletes that extend past the end of the document are truncated.
+    pub fn apply<A>(&mut self, change: &Delta<String, A>) {
+        let mut shift = Shift::new(self.newlines.iter().copied());
+        let mut inserted = vec![];
+        let mut position = 0usize;
+        let mut target = 0usize;
+
+        for op in change.ops() {
+            match op {
+                Op::Insert(insert) => {
+                    inserted.extend(newlines(insert.insert()).map(|index| target + index));
+                    target += insert.len();
+                }
+                Op::Retain(retain) => {
+                    let len = retain.retain.min(self.len - position);
+                    position += len;
+                    target += len;
+                }
+                Op::Delete(delete) => position += delete.delete.min(self.len - position),
+            }
+
+            shift.step(op);
+        }
+
+        // Both the retained and the inserted newlines are sorted, so sorting
+        // their concatenation only merges the two runs.
+        self.newlines = shift.into_retained().chain(inserted).collect();
+        self.newlines.sort();
+        self.len = target + (self.len - position);
+    }
+
+    fn line_start(&self, line: usize) -> usize {
+        match line {
+            0 => 0,
+            line => self.newlines[line - 1] + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use crate::{Delta, Document};
+
+    fn document(text: &str) -> Delta<String, ()> {
+        Delta::new().insert(text.to_owned(), None)
+    }
+
+    #[test]
+    fn test_position() {
+        let index = LineIndex::new(&document("ab\nwörld\n\nc"));
+
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.position(0), (0, 0));
+        assert_eq!(index.position(2), (0, 2));
+        assert_eq!(index.position(3), (1, 0));
+        assert_eq!(index.position(8), (1, 5));
+        assert_eq!(index.position(9), (2, 0));
+        assert_eq!(index.position(11), (3, 1));
+        assert_eq!(index.position(100), (3, 1));
+
+        assert_eq!(index.offset(0, 2), Some(2));
+        assert_eq!(index.offset(0, 3), None);
+        assert_eq!(index.offset(1, 5), Some(8));
+        assert_eq!(index.offset(2, 0), Some(9));
+        assert_eq!(index.offset(3, 1), Some(11));
+        assert_eq!(index.offset(4, 0), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "isn't a document")]
+    fn test_new_change() {
+        LineIndex::new(&document("a").retain(1, None));
+    }
+
+    #[test]
+    fn test_apply() {
+        let changes = [
+            Delta::new().retain(3, None).insert("x\ny".to_owned(), None),
+            Delta::new().retain(1, None).delete(4).retain(2, ()),
+            Delta::new().insert("\n\n".to_owned(), None).delete(1),
+            Delta::new()
+                .retain(5, None)
+                .delete(1)
+                .insert("z\n".to_owned(), None),
+            Delta::new()
+                .retain(100, None)
+                .insert("end".to_owned(), None),
+            Delta::new().delete(3),
+        ];
+
+        let mut document = Document::from_delta(document("ab\ncd\n\nef\n")).unwrap();
+        let mut index = LineIndex::new(document.as_delta());
+
+        for change in changes {
+            document.apply(change.clone());
+            index.apply(&change);
+
+            assert_eq!(index, LineIndex::new(document.as_delta()), "{document:?}");
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Import of Markdown into Quill documents.
+//!
+//! This module is only available with the `markdown` feature enabled. It uses
+//! [`pulldown_cmark`] to parse CommonMark (with strikethrough and task list
+//! extensions) and converts the result into a document delta with
+//! [`QuillAttributes`]. Emphasis, strong emphasis, strikethrough, inline code
+//! and links become inline formats, whereas headings, block quotes, code
+//! blocks and (nested and task) lists become formats of the newline that ends
+//! each line. Like Quill documents, the resulting document always ends with a
+//! newline.
+
+use std::mem::take;
+
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+
+use super::quill::{CodeBlock, List, QuillAttributes};
+use super::{Compose, Delta};
+
+struct Importer {
+    delta: Delta<String, QuillAttributes>,
+    inline: Vec<QuillAttributes>,
+    blocks: Vec<QuillAttributes>,
+    lists: Vec<List>,
+    line_has_content: bool,
+}
+
+impl Importer {
+    fn attributes(formats: &[QuillAttributes]) -> Option<QuillAttributes> {
+        let attributes = formats
+            .iter()
+            .cloned()
+            .fold(QuillAttributes::default(), Compose::compose);
+
+        match attributes == QuillAttributes::default() {
+            true => None,
+            false => Some(attributes),
+        }
+    }
+
+    fn insert(&mut self, text: &str, extra: Option<QuillAttributes>) {
+        if text.is_empty() {
+            return;
+        }
+
+        let mut attributes = Self::attributes(&self.inline);
+
+        if let Some(extra) = extra {
+            attributes = Some(attributes.unwrap_or_default().compose(extra));
+        }
+
+        self.line_has_content = true;
+        self.delta = take(&mut self.delta).insert(text.to_owned(), attributes);
+    }
+
+    fn newline(&mut self) {
+        self.delta = take(&mut self.delta).insert("\n".to_owned(), Self::attributes(&self.blocks));
+        self.line_has_content = false;
+    }
+
+    fn end_line(&mut self) {
+        if self.line_has_content {
+            self.newline();
+        }
+    }
+
+    fn in_code_block(&self) -> bool {
+        self.blocks.iter().any(|block| block.code_block.is_some())
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading(level, _, _) => {
+                self.end_line();
+                self.blocks.push(QuillAttributes {
+                    header: Some(Some(match level {
+                        HeadingLevel::H1 => 1,
+                        HeadingLevel::H2 => 2,
+                        HeadingLevel::H3 => 3,
+                        HeadingLevel::H4 => 4,
+                        HeadingLevel::H5 => 5,
+                        HeadingLevel::H6 => 6,
+                    })),
+                    ..Default::default()
+                });
+            }
+            Tag::BlockQuote => {
+                self.end_line();
+                self.blocks.push(QuillAttributes {
+                    blockquote: Some(Some(true)),
+                    ..Default::default()
+                });
+            }
+            Tag::CodeBlock(kind) => {
+                self.end_line();
+                self.blocks.push(QuillAttributes {
+                    code_block: Some(Some(match kind {
+                        CodeBlockKind::Fenced(language) if !language.is_empty() => {
+                            CodeBlock::Language(language.to_string())
+                        }
+                        CodeBlockKind::Fenced(_) | CodeBlockKind::Indented => {
+                            CodeBlock::Plain(true)
+                        }
+                    })),
+                    ..Default::default()
+                });
+            }
+            Tag::List(start) => {
+                self.end_line();
+                self.lists.push(match start {
+                    Some(_) => List::Ordered,
+                    None => List::Bullet,
+                });
+            }
+            Tag::Item => {
+                self.end_line();
+                self.blocks.push(QuillAttributes {
+                    list: Some(self.lists.last().copied()),
+                    indent: match self.lists.len() {
+                        0 | 1 => None,
+                        depth => Some(u8::try_from(depth - 1).ok()),
+                    },
+                    ..Default::default()
+                });
+            }
+            Tag::Emphasis => self.inline.push(QuillAttributes {
+                italic: Some(Some(true)),
+                ..Default::default()
+            }),
+            Tag::Strong => self.inline.push(QuillAttributes {
+                bold: Some(Some(true)),
+                ..Default::default()
+            }),
+            Tag::Strikethrough => self.inline.push(QuillAttributes {
+                strike: Some(Some(true)),
+                ..Default::default()
+            }),
+            Tag::Link(_, url, _) => self.inline.push(QuillAttributes {
+                link: Some(Some(url.to_string())),
+                ..Default::default()
+            }),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.end_line(),
+            Tag::Heading(..) | Tag::BlockQuote | Tag::CodeBlock(_) | Tag::Item => {
+                self.end_line();
+                self.blocks.pop();
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+            }
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) => {
+                self.inline.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if !self.in_code_block() {
+            self.insert(text, None);
+            return;
+        }
+
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.newline();
+            }
+
+            self.insert(line, None);
+        }
+    }
+
+    fn task(&mut self, checked: bool) {
+        if let Some(item) = self.blocks.last_mut() {
+            item.list = Some(Some(match checked {
+                true => List::Checked,
+                false => List::Unchecked,
+            }));
+        }
+    }
+}
+
+/// Converts the given Markdown into a document delta with Quill's attributes.
+/// See the [module documentation](self) for the supported syntax.
+pub fn from_markdown(markdown: &str) -> Delta<String, QuillAttributes> {
+    let mut importer = Importer {
+        delta: Delta::new(),
+        inline: vec![],
+        blocks: vec![],
+        lists: vec![],
+        line_has_content: false,
+    };
+
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(tag) => importer.start(tag),
+            Event::End(tag) => importer.end(tag),
+            Event::Text(text) => importer.text(&text),
+            Event::Code(code) => importer.insert(
+                &code,
+                Some(QuillAttributes {
+                    code: Some(Some(true)),
+                    ..Default::default()
+                }),
+            ),
+            Event::SoftBreak => importer.insert(" ", None),
+            Event::HardBreak => importer.newline(),
+            Event::TaskListMarker(checked) => importer.task(checked),
+            _ => {}
+        }
+    }
+
+    importer.end_line();
+
+    if importer.delta.ops().next().is_none() {
+        importer.newline();
+    }
+
+    importer.delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_markdown;
+    use crate::quill::{CodeBlock, List, QuillAttributes};
+    use crate::Delta;
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            from_markdown("Hello **bold** and [`link`](https://example.com)"),
+            Delta::new()
+                .insert("Hello ".to_owned(), None)
+                .insert(
+                    "bold".to_owned(),
+                    QuillAttributes {
+                        bold: Some(Some(true)),
+                        ..Default::default()
+                    }
+                )
+                .insert(" and ".to_owned(), None)
+                .insert(
+                    "link".to_owned(),
+                    QuillAttributes {
+                        code: Some(Some(true)),
+                        link: Some(Some("https://example.com".to_owned())),
+                        ..Default::default()
+                    }
+                )
+                .insert("\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            from_markdown("# Title\n\n> quote\n\n```rust\nfn main() {}\n```\n"),
+            Delta::new()
+                .insert("Title".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        header: Some(Some(1)),
+                        ..Default::default()
+                    }
+                )
+                .insert("quote".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        blockquote: Some(Some(true)),
+                        ..Default::default()
+                    }
+                )
+                .insert("fn main() {}".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        code_block: Some(Some(CodeBlock::Language("rust".to_owned()))),
+                        ..Default::default()
+                    }
+                )
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            from_markdown("- a\n  1. b\n- [x] c\n"),
+            Delta::new()
+                .insert("a".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        list: Some(Some(List::Bullet)),
+                        ..Default::default()
+                    }
+                )
+                .insert("b".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        list: Some(Some(List::Ordered)),
+                        indent: Some(Some(1)),
+                        ..Default::default()
+                    }
+                )
+                .insert("c".to_owned(), None)
+                .insert(
+                    "\n".to_owned(),
+                    QuillAttributes {
+                        list: Some(Some(List::Checked)),
+                        ..Default::default()
+                    }
+                )
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            from_markdown(""),
+            Delta::new().insert("\n".to_owned(), None)
+        );
+    }
+}
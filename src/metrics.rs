@@ -0,0 +1,86 @@
+/// Hooks that the sync primitives of this crate (e.g. [`OfflineQueue`]) call
+/// into to report how operational transformation behaves at runtime.
+///
+/// Every hook does nothing by default, so implementations only need to
+/// override the events they're interested in. The unit type `()` implements
+/// this trait without recording anything and is the default everywhere. With
+/// the `metrics` feature enabled, [`MetricsFacade`] forwards every event to the
+/// [`metrics`](https://docs.rs/metrics) crate.
+///
+/// [`OfflineQueue`]: crate::OfflineQueue
+pub trait Metrics {
+    /// Called with the number of ops in each delta that is processed (e.g.
+    /// composed into a queue or transformed against one).
+    fn ops_processed(&self, ops: usize) {
+        let _ = ops;
+    }
+
+    /// Called whenever pending changes have to be transformed again because
+    /// another change was applied before them (e.g. when an offline queue is
+    /// rebased onto a missed delta).
+    fn transform_retried(&self) {}
+
+    /// Called with the number of changes that are waiting to be submitted
+    /// whenever that number changes.
+    fn queue_depth(&self, depth: usize) {
+        let _ = depth;
+    }
+
+    /// Called with the number of ops in each delta that is produced (e.g. the
+    /// composition of all pending changes).
+    fn delta_size(&self, ops: usize) {
+        let _ = ops;
+    }
+}
+
+impl Metrics for () {}
+
+impl<M> Metrics for &M
+where
+    M: Metrics + ?Sized,
+{
+    fn ops_processed(&self, ops: usize) {
+        M::ops_processed(self, ops)
+    }
+
+    fn transform_retried(&self) {
+        M::transform_retried(self)
+    }
+
+    fn queue_depth(&self, depth: usize) {
+        M::queue_depth(self, depth)
+    }
+
+    fn delta_size(&self, ops: usize) {
+        M::delta_size(self, ops)
+    }
+}
+
+/// Implementation of [`Metrics`] that forwards every event to the global
+/// recorder of the [`metrics`](https://docs.rs/metrics) crate.
+///
+/// Events are recorded as the counters `kyte_ops_processed` and
+/// `kyte_transform_retries`, the gauge `kyte_queue_depth` and the histogram
+/// `kyte_delta_ops`.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl Metrics for MetricsFacade {
+    fn ops_processed(&self, ops: usize) {
+        ::metrics::counter!("kyte_ops_processed").increment(ops as u64);
+    }
+
+    fn transform_retried(&self) {
+        ::metrics::counter!("kyte_transform_retries").increment(1);
+    }
+
+    fn queue_depth(&self, depth: usize) {
+        ::metrics::gauge!("kyte_queue_depth").set(depth as f64);
+    }
+
+    fn delta_size(&self, ops: usize) {
+        ::metrics::histogram!("kyte_delta_ops").record(ops as f64);
+    }
+}
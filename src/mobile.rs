@@ -0,0 +1,601 @@
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/) bindings that expose text
+//! deltas, documents and a client session to Swift and Kotlin.
+//!
+//! This module is only available with the `uniffi` feature enabled. Build the
+//! library as a `cdylib` or `staticlib` and generate the bindings with
+//! `uniffi-bindgen generate --library`. All objects are exported under the
+//! names they have in this module ([`Delta`], [`Document`], [`Session`] and
+//! [`Workspace`]), use the same value and attribute types as [`QuillDelta`],
+//! and exchange deltas and attributes as JSON strings in the format that Quill
+//! produces.
+//!
+//! [`Session`] and [`Workspace`] are thin wrappers around the client state
+//! machines of the same names (see [`session`](crate::session)), which keep
+//! the change that was sent to the server in flight until it's acknowledged.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::json::JsonAttributes;
+use super::quill::QuillDelta;
+
+// `Compose` and `Transform` are deliberately not imported, because the
+// scaffolding that UniFFI generates for `Delta::compose` and `Delta::transform`
+// would resolve to their methods instead.
+
+/// Error that is thrown by the exported functions.
+#[derive(Clone, Debug, PartialEq, Eq, uniffi::Error)]
+pub enum BindingError {
+    /// Thrown when a delta or attributes can't be parsed from JSON.
+    InvalidJson {
+        /// Contains the error of the JSON parser.
+        message: String,
+    },
+
+    /// Thrown when a change doesn't apply to a document (see
+    /// [`Error`](crate::Error)).
+    InvalidChange {
+        /// Contains the description of the error.
+        message: String,
+    },
+
+    /// Thrown when the session of a [`Workspace`] is used after its document
+    /// was closed.
+    Closed {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingError::InvalidJson { message } => write!(f, "invalid JSON: {message}"),
+            BindingError::InvalidChange { message } => write!(f, "invalid change: {message}"),
+            BindingError::Closed { document_id } => write!(f, "document {document_id} is closed"),
+        }
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+impl From<serde_json::Error> for BindingError {
+    fn from(error: serde_json::Error) -> Self {
+        BindingError::InvalidJson {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<crate::Error> for BindingError {
+    fn from(error: crate::Error) -> Self {
+        BindingError::InvalidChange {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Immutable delta with text values and JSON attributes. Every method that
+/// changes a delta returns a new one.
+#[derive(Debug, Default, uniffi::Object)]
+pub struct Delta(QuillDelta);
+
+#[uniffi::export]
+impl Delta {
+    /// Returns a new empty delta.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Delta> {
+        Arc::default()
+    }
+
+    /// Parses a delta from the JSON that Quill produces (i.e. an object with an
+    /// `ops` field or an array of ops).
+    #[uniffi::constructor]
+    pub fn from_json(json: String) -> Result<Arc<Delta>, BindingError> {
+        Ok(Arc::new(Delta(serde_json::from_str(&json)?)))
+    }
+
+    /// Returns the JSON representation of this delta, which Quill can parse.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("delta serializes")
+    }
+
+    /// Returns this delta followed by an insert of the given text with the
+    /// given attributes (as a JSON object).
+    pub fn insert(
+        &self,
+        text: String,
+        attributes: Option<String>,
+    ) -> Result<Arc<Delta>, BindingError> {
+        let attributes = parse_attributes(attributes)?;
+        Ok(Arc::new(Delta(self.0.clone().insert(text, attributes))))
+    }
+
+    /// Returns this delta followed by a retain of the given length with the
+    /// given attributes (as a JSON object).
+    pub fn retain(&self, len: u64, attributes: Option<String>) -> Result<Arc<Delta>, BindingError> {
+        let attributes = parse_attributes(attributes)?;
+        Ok(Arc::new(Delta(
+            self.0.clone().retain(to_usize(len), attributes),
+        )))
+    }
+
+    /// Returns this delta followed by a delete of the given length.
+    pub fn delete(&self, len: u64) -> Arc<Delta> {
+        Arc::new(Delta(self.0.clone().delete(to_usize(len))))
+    }
+
+    /// Returns the composition of this delta and the given delta, which
+    /// applies after this delta.
+    pub fn compose(&self, other: Arc<Delta>) -> Arc<Delta> {
+        Arc::new(Delta(crate::Compose::compose(
+            self.0.clone(),
+            other.0.clone(),
+        )))
+    }
+
+    /// Returns the given delta transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    pub fn transform(&self, other: Arc<Delta>, priority: bool) -> Arc<Delta> {
+        Arc::new(Delta(crate::Transform::transform(
+            self.0.clone(),
+            other.0.clone(),
+            priority,
+        )))
+    }
+
+    /// Returns the given index transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    pub fn transform_position(&self, index: u64, priority: bool) -> u64 {
+        crate::Transform::transform(&self.0, to_usize(index), priority) as u64
+    }
+}
+
+/// Text document that changes can be applied to.
+#[derive(Debug, Default, uniffi::Object)]
+pub struct Document(Mutex<crate::Document<String, JsonAttributes>>);
+
+#[uniffi::export]
+impl Document {
+    /// Returns a new empty document.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Document> {
+        Arc::default()
+    }
+
+    /// Returns a document with the contents of the given delta, which may
+    /// only contain inserts.
+    #[uniffi::constructor]
+    pub fn from_delta(delta: Arc<Delta>) -> Result<Arc<Document>, BindingError> {
+        let document = crate::Document::from_delta(delta.0.clone())?;
+        Ok(Arc::new(Document(Mutex::new(document))))
+    }
+
+    /// Returns the contents of this document as a delta.
+    pub fn contents(&self) -> Arc<Delta> {
+        Arc::new(Delta(self.lock().as_delta().clone()))
+    }
+
+    /// Returns the number of elements in this document.
+    pub fn len(&self) -> u64 {
+        self.lock().len() as u64
+    }
+
+    /// Returns true if this document doesn't contain any elements.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Applies the given change to this document, unless it extends past the
+    /// end of this document.
+    pub fn apply(&self, change: Arc<Delta>) -> Result<(), BindingError> {
+        Ok(self.lock().try_apply(change.0.clone())?)
+    }
+}
+
+impl Document {
+    fn lock(&self) -> MutexGuard<'_, crate::Document<String, JsonAttributes>> {
+        lock(&self.0)
+    }
+}
+
+/// Client state machine that keeps a local document in sync with a server
+/// (see [`crate::session::Session`]). A session that's returned by
+/// [`Workspace::open`] refers to the workspace's session of that document and
+/// throws [`BindingError::Closed`] once that document is closed.
+#[derive(Debug, uniffi::Object)]
+pub struct Session(SessionRef);
+
+#[derive(Debug)]
+enum SessionRef {
+    Owned(Mutex<crate::session::Session<String, JsonAttributes>>),
+    Workspace {
+        workspace: Arc<Mutex<crate::session::Workspace<String, JsonAttributes>>>,
+        document_id: String,
+    },
+}
+
+#[uniffi::export]
+impl Session {
+    /// Returns a new session for a local copy of the given document.
+    #[uniffi::constructor]
+    pub fn new(document: Arc<Document>) -> Arc<Session> {
+        let session = crate::session::Session::new(document.lock().clone());
+        Arc::new(Session(SessionRef::Owned(Mutex::new(session))))
+    }
+
+    /// Returns the current contents of the local document, including changes
+    /// that haven't been acknowledged yet.
+    pub fn contents(&self) -> Result<Arc<Delta>, BindingError> {
+        self.with(|session| Arc::new(Delta(session.document().as_delta().clone())))
+    }
+
+    /// Applies the given local change to the document and queues it to be
+    /// sent to the server.
+    pub fn edit(&self, change: Arc<Delta>) -> Result<(), BindingError> {
+        Ok(self.with(|session| session.edit(change.0.clone()))??)
+    }
+
+    /// Transforms the given change of another client (which the server applied
+    /// before the change in flight) against the change in flight and the
+    /// pending changes, applies it to the local document and returns it as it
+    /// was applied, e.g. to update the editor.
+    pub fn receive(&self, change: Arc<Delta>) -> Result<Arc<Delta>, BindingError> {
+        let change = self.with(|session| session.receive(change.0.clone()))??;
+        Ok(Arc::new(Delta(change)))
+    }
+
+    /// Returns the composition of the pending changes and keeps it in flight
+    /// until it's acknowledged, or nothing if there are no pending changes or
+    /// the previously sent change hasn't been acknowledged yet.
+    pub fn take_pending(&self) -> Result<Option<Arc<Delta>>, BindingError> {
+        self.with(|session| session.take_pending().map(|change| Arc::new(Delta(change))))
+    }
+
+    /// Marks the change in flight as acknowledged by the server, so that the
+    /// pending changes can be sent. Returns false if there's no change in
+    /// flight.
+    pub fn ack(&self) -> Result<bool, BindingError> {
+        self.with(|session| session.ack().is_some())
+    }
+}
+
+impl Session {
+    fn with<R>(
+        &self,
+        f: impl FnOnce(&mut crate::session::Session<String, JsonAttributes>) -> R,
+    ) -> Result<R, BindingError> {
+        match &self.0 {
+            SessionRef::Owned(session) => Ok(f(&mut lock(session))),
+            SessionRef::Workspace {
+                workspace,
+                document_id,
+            } => lock(workspace)
+                .session_mut(document_id)
+                .map(f)
+                .ok_or_else(|| BindingError::Closed {
+                    document_id: document_id.clone(),
+                }),
+        }
+    }
+}
+
+/// Message that a [`Workspace`] sends to the server (see
+/// [`crate::session::ClientMessage`]).
+#[derive(Debug, uniffi::Enum)]
+pub enum ClientMessage {
+    /// Asks the server to send the contents of the given document, followed by
+    /// the changes that other clients make to it.
+    Subscribe {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+
+    /// Asks the server to stop sending the changes to the given document.
+    Unsubscribe {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+
+    /// Contains the local changes to the given document, which the server
+    /// should acknowledge once it has applied them.
+    Change {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the change.
+        change: Arc<Delta>,
+    },
+}
+
+impl From<crate::session::ClientMessage<String, JsonAttributes>> for ClientMessage {
+    fn from(message: crate::session::ClientMessage<String, JsonAttributes>) -> Self {
+        match message {
+            crate::session::ClientMessage::Subscribe { document_id } => {
+                ClientMessage::Subscribe { document_id }
+            }
+            crate::session::ClientMessage::Unsubscribe { document_id } => {
+                ClientMessage::Unsubscribe { document_id }
+            }
+            crate::session::ClientMessage::Change {
+                document_id,
+                change,
+            } => ClientMessage::Change {
+                document_id,
+                change: Arc::new(Delta(change)),
+            },
+        }
+    }
+}
+
+/// Message that the server sends to a [`Workspace`] (see
+/// [`crate::session::ServerMessage`]).
+#[derive(Debug, uniffi::Enum)]
+pub enum ServerMessage {
+    /// Contains the contents of a document that the workspace subscribed to.
+    Snapshot {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the contents of the document, which may only contain
+        /// inserts.
+        contents: Arc<Delta>,
+    },
+
+    /// Contains a change that another client made to a document (see
+    /// [`Session::receive`]).
+    Change {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the change.
+        change: Arc<Delta>,
+    },
+
+    /// Acknowledges the last change that the workspace sent for a document
+    /// (see [`Session::ack`]).
+    Ack {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+}
+
+impl From<ServerMessage> for crate::session::ServerMessage<String, JsonAttributes> {
+    fn from(message: ServerMessage) -> Self {
+        match message {
+            ServerMessage::Snapshot {
+                document_id,
+                contents,
+            } => crate::session::ServerMessage::Snapshot {
+                document_id,
+                contents: contents.0.clone(),
+            },
+            ServerMessage::Change {
+                document_id,
+                change,
+            } => crate::session::ServerMessage::Change {
+                document_id,
+                change: change.0.clone(),
+            },
+            ServerMessage::Ack { document_id } => {
+                crate::session::ServerMessage::Ack { document_id }
+            }
+        }
+    }
+}
+
+/// Client that keeps the documents it opened in sync with a server over a
+/// single connection (see [`crate::session::Workspace`]).
+#[derive(Debug, Default, uniffi::Object)]
+pub struct Workspace(Arc<Mutex<crate::session::Workspace<String, JsonAttributes>>>);
+
+#[uniffi::export]
+impl Workspace {
+    /// Returns a new workspace without any open documents.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Workspace> {
+        Arc::default()
+    }
+
+    /// Returns the session of the given document, or nothing if the server
+    /// hasn't sent its contents yet. The first call for a document queues a
+    /// subscription to it.
+    pub fn open(&self, document_id: String) -> Option<Arc<Session>> {
+        lock(&self.0).open(&document_id)?;
+
+        Some(Arc::new(Session(SessionRef::Workspace {
+            workspace: self.0.clone(),
+            document_id,
+        })))
+    }
+
+    /// Closes the given document, which is unsubscribed from once its local
+    /// changes have been sent and acknowledged. Does nothing if the document
+    /// isn't open.
+    pub fn close(&self, document_id: String) {
+        lock(&self.0).close(&document_id);
+    }
+
+    /// Routes the given message to the session of its document and returns
+    /// the change to its contents as it was applied (i.e. the entire contents
+    /// for a snapshot), e.g. to update the editor. Returns nothing if the
+    /// message is an acknowledgement, is for a document that isn't open or is
+    /// a snapshot of a document that already has a session.
+    pub fn receive(&self, message: ServerMessage) -> Result<Option<Arc<Delta>>, BindingError> {
+        let change = lock(&self.0).receive(message.into())?;
+        Ok(change.map(|change| Arc::new(Delta(change))))
+    }
+
+    /// Returns the messages that should be sent to the server and empties the
+    /// queue (see [`crate::session::Workspace::take_outgoing`]).
+    pub fn take_outgoing(&self) -> Vec<ClientMessage> {
+        lock(&self.0)
+            .take_outgoing()
+            .into_iter()
+            .map(ClientMessage::from)
+            .collect()
+    }
+}
+
+/// Locks the given mutex, ignoring that another thread panicked while holding
+/// it, because every exported method leaves its state consistent.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+fn parse_attributes(attributes: Option<String>) -> Result<Option<JsonAttributes>, BindingError> {
+    Ok(attributes
+        .map(|attributes| serde_json::from_str(&attributes))
+        .transpose()?)
+}
+
+/// Converts a length or index from the bindings, which saturates on platforms
+/// where `usize` is smaller than 64 bits.
+fn to_usize(value: u64) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BindingError, ClientMessage, Delta, Document, ServerMessage, Session, Workspace};
+
+    #[test]
+    fn test_delta() {
+        let a = Delta::new().insert("Hello".to_owned(), None).unwrap();
+        let b = Delta::new()
+            .retain(5, None)
+            .unwrap()
+            .insert("!".to_owned(), Some(r#"{"bold":true}"#.to_owned()))
+            .unwrap();
+
+        assert_eq!(
+            a.compose(b).to_json(),
+            r#"{"ops":[{"insert":"Hello"},{"insert":"!","attributes":{"bold":true}}]}"#
+        );
+        assert_eq!(a.transform_position(0, false), 5);
+        assert!(matches!(
+            Delta::from_json("{".to_owned()),
+            Err(BindingError::InvalidJson { .. })
+        ));
+    }
+
+    #[test]
+    fn test_session() {
+        let base = Delta::new().insert("Hello\n".to_owned(), None).unwrap();
+        let session = Session::new(Document::from_delta(base).unwrap());
+
+        session
+            .edit(
+                Delta::new()
+                    .retain(5, None)
+                    .unwrap()
+                    .insert("!".to_owned(), None)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let remote = session
+            .receive(Delta::new().insert("Oh, ".to_owned(), None).unwrap())
+            .unwrap();
+
+        assert_eq!(remote.to_json(), r#"{"ops":[{"insert":"Oh, "}]}"#);
+        assert_eq!(
+            session.contents().unwrap().to_json(),
+            r#"{"ops":[{"insert":"Oh, Hello!\n"}]}"#
+        );
+        assert_eq!(
+            session.take_pending().unwrap().unwrap().to_json(),
+            r#"{"ops":[{"retain":9},{"insert":"!"}]}"#
+        );
+        assert!(session.take_pending().unwrap().is_none());
+        assert!(session.ack().unwrap());
+        assert!(!session.ack().unwrap());
+        assert!(matches!(
+            session.edit(Delta::new().retain(20, None).unwrap().delete(1)),
+            Err(BindingError::InvalidChange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_document() {
+        let document = Document::new();
+
+        document
+            .apply(Delta::new().insert("abc".to_owned(), None).unwrap())
+            .unwrap();
+
+        assert_eq!(document.len(), 3);
+        assert!(document.apply(Delta::new().delete(4)).is_err());
+        assert_eq!(
+            document.contents().to_json(),
+            r#"{"ops":[{"insert":"abc"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_workspace() {
+        let workspace = Workspace::new();
+        let contents = Delta::new().insert("Hello\n".to_owned(), None).unwrap();
+
+        assert!(workspace.open("a".to_owned()).is_none());
+        assert!(matches!(
+            &workspace.take_outgoing()[..],
+            [ClientMessage::Subscribe { document_id }] if document_id == "a"
+        ));
+
+        let snapshot = ServerMessage::Snapshot {
+            document_id: "a".to_owned(),
+            contents,
+        };
+        assert!(workspace.receive(snapshot).unwrap().is_some());
+
+        let session = workspace.open("a".to_owned()).unwrap();
+        session
+            .edit(
+                Delta::new()
+                    .retain(5, None)
+                    .unwrap()
+                    .insert("!".to_owned(), None)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let change = ServerMessage::Change {
+            document_id: "a".to_owned(),
+            change: Delta::new().insert("Oh, ".to_owned(), None).unwrap(),
+        };
+        assert_eq!(
+            workspace.receive(change).unwrap().unwrap().to_json(),
+            r#"{"ops":[{"insert":"Oh, "}]}"#
+        );
+        assert_eq!(
+            session.contents().unwrap().to_json(),
+            r#"{"ops":[{"insert":"Oh, Hello!\n"}]}"#
+        );
+
+        workspace.close("a".to_owned());
+        assert_eq!(
+            session.contents().unwrap_err(),
+            BindingError::Closed {
+                document_id: "a".to_owned()
+            }
+        );
+
+        let outgoing = workspace.take_outgoing();
+        assert!(matches!(
+            &outgoing[..],
+            [ClientMessage::Change { document_id, change }] if document_id == "a"
+                && change.to_json() == r#"{"ops":[{"retain":9},{"insert":"!"}]}"#
+        ));
+
+        let ack = ServerMessage::Ack {
+            document_id: "a".to_owned(),
+        };
+        assert!(workspace.receive(ack).unwrap().is_none());
+        assert!(matches!(
+            &workspace.take_outgoing()[..],
+            [ClientMessage::Unsubscribe { document_id }] if document_id == "a"
+        ));
+        assert!(workspace.take_outgoing().is_empty());
+    }
+}
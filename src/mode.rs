@@ -0,0 +1,55 @@
+/// Determines how [`Delta::compose_with`](crate::Delta::compose_with) and
+/// [`Delta::transform_with`](crate::Delta::transform_with) handle deltas that
+/// retain or delete more elements than their base document contains.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ApplyMode {
+    /// Rejects deltas that extend past the end of their base document or that
+    /// aren't well-formed with an [`Error`](crate::Error). This is what server
+    /// code usually wants.
+    Strict,
+
+    /// Silently clips retains and deletes that extend past the end of their
+    /// base document, like Quill does. This is the behavior of
+    /// [`Compose`](crate::Compose) and [`Transform`](crate::Transform).
+    #[default]
+    Lenient,
+}
+
+/// Determines whether [`Delta::push_with`](crate::Delta::push_with) moves an
+/// insert that directly follows a delete in front of that delete.
+///
+/// Both orders have the same effect, so the canonical form (see
+/// [`Delta`](crate::Delta)) puts inserts first, like Quill does. Systems that
+/// replay ops in strict append order (e.g. to mirror them into a log) can
+/// preserve the order in which they were produced instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OrderPolicy {
+    /// Moves inserts in front of the deletes that directly precede them. This
+    /// is the behavior of [`Delta::push`](crate::Delta::push).
+    #[default]
+    InsertFirst,
+
+    /// Appends operations in the order in which they're pushed.
+    Preserve,
+}
+
+/// Determines what [`Delta::push_with_overflow`](crate::Delta::push_with_overflow)
+/// does when the combined length of two adjacent retains or deletes would
+/// overflow `usize::MAX`. Lengths that large never occur in real documents, so
+/// they usually indicate garbage input. Use
+/// [`Delta::try_push`](crate::Delta::try_push) to get an error instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Keeps the preceding operation at `usize::MAX` and appends the remainder
+    /// as a separate operation, so that the delta keeps its effect. This is the
+    /// behavior of [`Delta::push`](crate::Delta::push).
+    #[default]
+    Split,
+
+    /// Keeps the preceding operation at `usize::MAX` and drops the remainder.
+    Saturate,
+
+    /// Panics in debug builds and splits in release builds, like arithmetic
+    /// overflow does.
+    DebugPanic,
+}
@@ -0,0 +1,607 @@
+//! Move operations as an extension of deltas.
+//!
+//! This module is only available with the `move` feature enabled. Reordering
+//! a range of a document (e.g. list items or paragraphs) with a delete and an
+//! insert loses any concurrent edits inside that range, since these edits are
+//! transformed with the delete. A [`Move`] instead relocates the range as a
+//! whole, so that concurrent edits follow it to its new position.
+//!
+//! A [`MoveDelta`] is a sequence of [`Change`]s, each of which is either a
+//! regular [`Delta`] or a [`Move`]. Moves are not part of Quill's delta format,
+//! so move deltas can't be exchanged with Quill.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::mem::take;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::ops::{Delete, Retain};
+use super::{Compose, Delta, Document, Error, Len, Op, Seq, Transform};
+
+/// Moves the range of `len` elements starting at `from` to index `to` of the
+/// document that remains after removing that range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Move {
+    /// Contains the index of the first element to move.
+    pub from: usize,
+
+    /// Contains the number of elements to move.
+    pub len: usize,
+
+    /// Contains the index that the first moved element will have.
+    pub to: usize,
+}
+
+impl Move {
+    /// Returns a new move of the range of `len` elements starting at `from` to
+    /// index `to`.
+    pub fn new(from: usize, len: usize, to: usize) -> Move {
+        Move { from, len, to }
+    }
+
+    /// Returns the move that undoes this move.
+    pub fn invert(&self) -> Move {
+        Move {
+            from: self.to,
+            len: self.len,
+            to: self.from,
+        }
+    }
+
+    fn end(&self) -> usize {
+        self.from.saturating_add(self.len)
+    }
+
+    /// Returns the index of the element at the given index after applying this
+    /// move.
+    pub fn map_index(&self, index: usize) -> usize {
+        if (self.from..self.end()).contains(&index) {
+            return self.to + (index - self.from);
+        }
+
+        let index = match index >= self.end() {
+            true => index - self.len,
+            false => index,
+        };
+
+        match index >= self.to {
+            true => index.saturating_add(self.len),
+            false => index,
+        }
+    }
+
+    /// Returns the index of the given gap between elements after applying this
+    /// move. If the moved range ends up at that gap, `priority` determines if
+    /// the moved range goes first.
+    fn map_gap(&self, gap: usize, priority: bool) -> usize {
+        if self.from < gap && gap < self.end() {
+            return self.to + (gap - self.from);
+        }
+
+        let index = match gap >= self.end() {
+            true => gap - self.len,
+            false => gap,
+        };
+
+        let after = match index.cmp(&self.to) {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => gap == self.end() || priority,
+        };
+
+        match after {
+            true => index.saturating_add(self.len),
+            false => index,
+        }
+    }
+
+    /// Returns the parts of the given range that this move keeps contiguous.
+    fn pieces(&self, range: Range<usize>) -> impl Iterator<Item = Range<usize>> {
+        let mut bounds = [self.from, self.end(), target(self)];
+        bounds.sort_unstable();
+
+        let mut start = range.start;
+        let end = range.end;
+
+        bounds
+            .into_iter()
+            .chain([end])
+            .map(move |bound| {
+                let piece = start..bound.clamp(start, end);
+                start = piece.end;
+                piece
+            })
+            .filter(|piece| piece.start < piece.end)
+    }
+}
+
+/// Individual change of a [`MoveDelta`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    untagged,
+    bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>")
+)]
+pub enum Change<T, A> {
+    /// Applies the given delta.
+    Delta(Delta<T, A>),
+
+    /// Applies the given move.
+    Move(Move),
+}
+
+/// Sequence of changes that are applied in order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"))]
+pub struct MoveDelta<T, A> {
+    changes: Vec<Change<T, A>>,
+}
+
+impl<T, A> MoveDelta<T, A> {
+    /// Returns a new empty move delta.
+    pub fn new() -> MoveDelta<T, A> {
+        MoveDelta { changes: vec![] }
+    }
+
+    /// Moves the range of `len` elements starting at `from` to index `to`.
+    pub fn move_range(mut self, from: usize, len: usize, to: usize) -> Self {
+        self.changes.push(Change::Move(Move::new(from, len, to)));
+        self
+    }
+
+    /// Returns the changes of this move delta.
+    pub fn changes(&self) -> &[Change<T, A>] {
+        &self.changes
+    }
+}
+
+impl<T, A> MoveDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given delta.
+    pub fn delta(self, delta: Delta<T, A>) -> Self {
+        self.compose(MoveDelta {
+            changes: vec![Change::Delta(delta)],
+        })
+    }
+}
+
+impl<T, A> Default for MoveDelta<T, A> {
+    fn default() -> Self {
+        MoveDelta::new()
+    }
+}
+
+impl<T, A> From<Delta<T, A>> for MoveDelta<T, A> {
+    fn from(delta: Delta<T, A>) -> Self {
+        MoveDelta {
+            changes: vec![Change::Delta(delta)],
+        }
+    }
+}
+
+/// Appends the changes of the given move delta to this move delta, where
+/// consecutive deltas are composed into a single delta.
+impl<T, A> Compose<MoveDelta<T, A>> for MoveDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = MoveDelta<T, A>;
+
+    fn compose(mut self, rhs: MoveDelta<T, A>) -> Self::Output {
+        for change in rhs.changes {
+            match (self.changes.last_mut(), change) {
+                (Some(Change::Delta(delta)), Change::Delta(rhs)) => {
+                    *delta = take(delta).compose(rhs);
+                }
+                (_, change) => self.changes.push(change),
+            }
+        }
+
+        self
+    }
+}
+
+impl<T, A> Transform<MoveDelta<T, A>> for MoveDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = MoveDelta<T, A>;
+
+    fn transform(self, rhs: MoveDelta<T, A>, priority: bool) -> Self::Output {
+        MoveDelta {
+            changes: transform_changes(self.changes, rhs.changes, priority).0,
+        }
+    }
+}
+
+type Changes<T, A> = Vec<Change<T, A>>;
+
+/// Transforms the given changes with the other (already applied) changes and
+/// returns the transformed changes, along with the other changes transformed
+/// with the given changes.
+fn transform_changes<T, A>(
+    lhs: Changes<T, A>,
+    mut rhs: Changes<T, A>,
+    priority: bool,
+) -> (Changes<T, A>, Changes<T, A>)
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    match (lhs.len(), rhs.len()) {
+        (0, _) | (_, 0) => (rhs, lhs),
+        (1, 1) => {
+            let (lhs, rhs) = (lhs.into_iter().next(), rhs.pop());
+            let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+                unreachable!()
+            };
+
+            (
+                transform_change(&lhs, rhs.clone(), priority),
+                transform_change(&rhs, lhs, !priority),
+            )
+        }
+        (1, _) => {
+            let rest = rhs.split_off(1);
+            let (first, lhs) = transform_changes(lhs, rhs, priority);
+            let (mut rest, lhs) = transform_changes(lhs, rest, priority);
+            let mut result = first;
+            result.append(&mut rest);
+            (result, lhs)
+        }
+        (_, _) => {
+            let mut lhs = lhs;
+            let lhs_rest = lhs.split_off(1);
+            let (rhs, mut first) = transform_changes(lhs, rhs, priority);
+            let (rhs, mut rest) = transform_changes(lhs_rest, rhs, priority);
+            first.append(&mut rest);
+            (rhs, first)
+        }
+    }
+}
+
+/// Transforms the given change with the given (already applied) change.
+fn transform_change<T, A>(
+    lhs: &Change<T, A>,
+    rhs: Change<T, A>,
+    priority: bool,
+) -> Vec<Change<T, A>>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    match (lhs, rhs) {
+        (Change::Delta(lhs), Change::Delta(rhs)) => {
+            vec![Change::Delta(lhs.clone().transform(rhs, priority))]
+        }
+        (Change::Delta(lhs), Change::Move(rhs)) => transform_move(lhs, rhs, priority)
+            .map(Change::Move)
+            .into_iter()
+            .collect(),
+        (Change::Move(lhs), Change::Delta(rhs)) => {
+            vec![Change::Delta(transform_delta(lhs, rhs, priority))]
+        }
+        (Change::Move(lhs), Change::Move(rhs)) => match move_move(lhs, &rhs, priority) {
+            Some(rhs) => vec![Change::Move(rhs)],
+            None if priority => vec![],
+            None => vec![Change::Move(lhs.invert()), Change::Move(rhs)],
+        },
+    }
+}
+
+/// Transforms the given move with the given (already applied) delta, or returns
+/// `None` if that delta deletes the entire range that the given move moves.
+fn transform_move<T, A>(lhs: &Delta<T, A>, rhs: Move, priority: bool) -> Option<Move>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+{
+    let from = lhs.transform(rhs.from, false);
+    let end = lhs.transform(rhs.end(), true);
+    let len = end.checked_sub(from).filter(|&len| len > 0)?;
+
+    let to = lhs.transform(target(&rhs), !priority);
+    let to = match to <= from {
+        true => to,
+        false => to.saturating_sub(len),
+    };
+
+    Some(Move { from, len, to })
+}
+
+/// Transforms the given delta with the given (already applied) move, so that
+/// the given delta's changes follow the elements that they apply to.
+fn transform_delta<T, A>(lhs: &Move, rhs: Delta<T, A>, priority: bool) -> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq,
+{
+    let mut changes = vec![];
+    let mut position = 0usize;
+
+    for op in rhs {
+        match op {
+            Op::Insert(insert) => {
+                changes.push((lhs.map_gap(position, priority), 0, Op::Insert(insert)));
+            }
+            Op::Retain(Retain { retain, attributes }) => {
+                let end = position.saturating_add(retain);
+
+                if attributes.is_some() {
+                    changes.extend(lhs.pieces(position..end).map(|piece| {
+                        let op = Op::Retain(Retain {
+                            retain: piece.len(),
+                            attributes: attributes.clone(),
+                        });
+
+                        (lhs.map_index(piece.start), 1, op)
+                    }));
+                }
+
+                position = end;
+            }
+            Op::Delete(Delete { delete }) => {
+                let end = position.saturating_add(delete);
+
+                changes.extend(lhs.pieces(position..end).map(|piece| {
+                    let op = Op::Delete(Delete {
+                        delete: piece.len(),
+                    });
+
+                    (lhs.map_index(piece.start), 1, op)
+                }));
+
+                position = end;
+            }
+        }
+    }
+
+    changes.sort_by_key(|&(position, order, _)| (position, order));
+
+    let mut result = Delta::new();
+    let mut cursor = 0usize;
+
+    for (position, _, op) in changes {
+        if position > cursor {
+            result.push(Op::Retain(Retain {
+                retain: position - cursor,
+                attributes: None,
+            }));
+            cursor = position;
+        }
+
+        if !matches!(op, Op::Insert(_)) {
+            cursor = cursor.saturating_add(op.len());
+        }
+
+        result.push(op);
+    }
+
+    result
+}
+
+/// Returns the gap of the original document that the given move moves its
+/// range to.
+fn target(change: &Move) -> usize {
+    match change.to <= change.from {
+        true => change.to,
+        false => change.to.saturating_add(change.len),
+    }
+}
+
+/// Transforms the given move with another (already applied) move, or returns
+/// `None` if the moves conflict, i.e. if their ranges overlap or if either move
+/// moves its range next to the other range or to the same position.
+fn move_move(lhs: &Move, rhs: &Move, priority: bool) -> Option<Move> {
+    if rhs.from == lhs.from && rhs.len == lhs.len {
+        return match priority {
+            true => None,
+            false => Some(Move {
+                from: lhs.to,
+                len: rhs.len,
+                to: rhs.to,
+            }),
+        };
+    }
+
+    let overlaps = rhs.from < lhs.end() && lhs.from < rhs.end();
+    let near = |gap: usize, change: &Move| (change.from..=change.end()).contains(&gap);
+    let (lhs_target, rhs_target) = (target(lhs), target(rhs));
+
+    if overlaps || lhs_target == rhs_target || near(lhs_target, rhs) || near(rhs_target, lhs) {
+        return None;
+    }
+
+    let from = lhs.map_index(rhs.from);
+    let to = lhs.map_gap(rhs_target, priority);
+    let to = match to <= from {
+        true => to,
+        false => to.saturating_sub(rhs.len),
+    };
+
+    Some(Move {
+        from,
+        len: rhs.len,
+        to,
+    })
+}
+
+impl<T, A> Document<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change (which may contain moves) to this document, or
+    /// returns an error if that change isn't well-formed or extends past the
+    /// end of this document. In that case, this document is left unchanged.
+    pub fn try_apply_moves(&mut self, change: &MoveDelta<T, A>) -> Result<(), Error> {
+        let mut document = self.clone();
+
+        for (index, change) in change.changes().iter().enumerate() {
+            match change {
+                Change::Delta(delta) => document.try_apply(delta.clone())?,
+                Change::Move(Move { from, len, to }) => {
+                    let document_len = document.len();
+                    let end = from.saturating_add(*len);
+
+                    if end > document_len || to.saturating_add(*len) > document_len {
+                        return Err(Error::LengthMismatch {
+                            index,
+                            expected: document_len,
+                            actual: end.max(to.saturating_add(*len)),
+                        });
+                    }
+
+                    let mut range = document.clone();
+                    range.apply(
+                        Delta::new()
+                            .delete(*from)
+                            .retain(*len, None)
+                            .delete(document_len - end),
+                    );
+
+                    document.apply(Delta::new().retain(*from, None).delete(*len));
+
+                    let mut insert = Delta::new().retain(*to, None);
+                    insert.extend(range.into_delta());
+                    document.apply(insert);
+                }
+            }
+        }
+
+        *self = document;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::{Compose, Delta, Document, Transform};
+
+    use super::{Move, MoveDelta};
+
+    type Text = Document<String, Attributes>;
+
+    fn document(text: &str) -> Text {
+        Document::from_delta(Delta::new().insert(text.to_owned(), None)).unwrap()
+    }
+
+    fn bold() -> Attributes {
+        [("bold".to_owned(), AttrValue::Bool(true))]
+            .into_iter()
+            .collect()
+    }
+
+    fn changes(len: usize) -> Vec<MoveDelta<String, Attributes>> {
+        let mut changes = vec![];
+
+        for position in 0..=len {
+            changes.push(MoveDelta::from(
+                Delta::new()
+                    .retain(position, None)
+                    .insert("x".to_owned(), None),
+            ));
+        }
+
+        for from in 0..len {
+            for end in from + 1..=len {
+                changes.push(MoveDelta::from(
+                    Delta::new().retain(from, None).delete(end - from),
+                ));
+                changes.push(MoveDelta::from(
+                    Delta::new().retain(from, None).retain(end - from, bold()),
+                ));
+
+                for to in 0..=len - (end - from) {
+                    changes.push(MoveDelta::new().move_range(from, end - from, to));
+                }
+            }
+        }
+
+        changes
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut text = document("abcdef");
+
+        text.try_apply_moves(&MoveDelta::new().move_range(1, 2, 3))
+            .unwrap();
+        assert_eq!(text, document("adebcf"));
+
+        assert!(text
+            .try_apply_moves(&MoveDelta::new().move_range(5, 2, 0))
+            .is_err());
+        assert_eq!(text, document("adebcf"));
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut text = document("abcdef");
+        let change = Move::new(0, 2, 3);
+
+        text.try_apply_moves(&MoveDelta::new().move_range(0, 2, 3))
+            .unwrap();
+        text.try_apply_moves(&MoveDelta::new().move_range(
+            change.invert().from,
+            change.len,
+            change.invert().to,
+        ))
+        .unwrap();
+        assert_eq!(text, document("abcdef"));
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs = MoveDelta::<String, ()>::from(Delta::new().insert("a".to_owned(), None));
+        let rhs = MoveDelta::from(Delta::new().retain(1, None).insert("b".to_owned(), None));
+
+        assert_eq!(
+            lhs.compose(rhs),
+            MoveDelta::from(Delta::new().insert("ab".to_owned(), None))
+        );
+    }
+
+    #[test]
+    fn test_transform_edit_follows_move() {
+        let alice = MoveDelta::new().move_range(0, 2, 2);
+        let bob = MoveDelta::from(Delta::new().retain(1, None).insert("x".to_owned(), None));
+
+        let mut text = document("abcd");
+        text.try_apply_moves(&alice).unwrap();
+        text.try_apply_moves(&alice.transform(bob, true)).unwrap();
+
+        assert_eq!(text, document("cdaxb"));
+    }
+
+    #[test]
+    fn test_transform_converges() {
+        let before = document("abcd");
+
+        for alice in changes(4) {
+            for bob in changes(4) {
+                for priority in [true, false] {
+                    let mut left = before.clone();
+                    left.try_apply_moves(&alice).unwrap();
+                    left.try_apply_moves(&alice.clone().transform(bob.clone(), priority))
+                        .unwrap();
+
+                    let mut right = before.clone();
+                    right.try_apply_moves(&bob).unwrap();
+                    right
+                        .try_apply_moves(&bob.clone().transform(alice.clone(), !priority))
+                        .unwrap();
+
+                    assert_eq!(left, right, "{alice:?} {bob:?} {priority}");
+                }
+            }
+        }
+    }
+}
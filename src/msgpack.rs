@@ -0,0 +1,116 @@
+//! Conversions between deltas and [MessagePack](https://msgpack.org).
+//!
+//! This module is only available with the `rmp-serde` feature enabled. Deltas
+//! are encoded with the same structure as their JSON representation, so that
+//! any MessagePack implementation (e.g. `@msgpack/msgpack` on the client) can
+//! decode them into the same objects that Quill uses.
+//!
+//! Use these functions rather than `rmp_serde::to_vec`, which encodes structs
+//! as arrays without their field names. Since ops are untagged, that makes a
+//! `retain` indistinguishable from a `delete` of the same length (both become
+//! `[n]`), so such deltas don't round-trip.
+
+use rmp_serde::{decode, encode};
+use serde::{Deserialize, Serialize};
+
+use super::{Delta, Len};
+
+/// Encodes the given delta as MessagePack, with the same structure as its JSON
+/// representation (i.e. a map with an `ops` field).
+pub fn to_vec<T, A>(delta: &Delta<T, A>) -> Result<Vec<u8>, encode::Error>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    rmp_serde::to_vec_named(delta)
+}
+
+/// Decodes a delta from the given MessagePack, which may use any of the
+/// structures that a delta deserializes from.
+pub fn from_slice<'a, T, A>(bytes: &'a [u8]) -> Result<Delta<T, A>, decode::Error>
+where
+    T: Len + Deserialize<'a>,
+    A: Deserialize<'a>,
+{
+    rmp_serde::from_slice(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use crate::attributes::Attributes;
+    use crate::{Delta, Len};
+
+    use super::{from_slice, to_vec};
+
+    /// Value of an insert that's either text or an embed, like in Quill.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Text(String),
+        Embed(Attributes),
+    }
+
+    impl Len for Value {
+        fn len(&self) -> usize {
+            match self {
+                Value::Text(text) => text.len(),
+                Value::Embed(_) => 1,
+            }
+        }
+    }
+
+    fn attributes() -> Attributes {
+        serde_json::from_value(json!({
+            "bold": true,
+            "header": 1,
+            "size": 1.5,
+            "font": null,
+            "link": { "href": "https://example.com", "target": -1 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let delta = Delta::new()
+            .retain(3, None)
+            .delete(3)
+            .retain(2, attributes())
+            .insert("Hello".to_owned(), attributes())
+            .insert(" World".to_owned(), None);
+
+        let bytes = to_vec(&delta).unwrap();
+
+        assert_eq!(from_slice::<String, Attributes>(&bytes).unwrap(), delta);
+        assert_eq!(
+            rmp_serde::from_slice::<serde_json::Value>(&bytes).unwrap(),
+            serde_json::to_value(&delta).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_embeds() {
+        let delta: Delta<Value, Attributes> = serde_json::from_value(json!([
+            { "insert": "Hello" },
+            { "insert": { "image": "cat.png" }, "attributes": { "width": 100 } },
+            { "retain": 1, "attributes": attributes() },
+            { "delete": 1 },
+        ]))
+        .unwrap();
+
+        let bytes = to_vec(&delta).unwrap();
+
+        assert_eq!(from_slice::<Value, Attributes>(&bytes).unwrap(), delta);
+    }
+
+    #[test]
+    fn test_positional() {
+        let delta = Delta::<String, ()>::new().retain(3, None).delete(3);
+        let bytes = rmp_serde::to_vec(&delta).unwrap();
+
+        assert_ne!(from_slice::<String, ()>(&bytes).ok(), Some(delta));
+    }
+}
@@ -0,0 +1,343 @@
+//! Nested deltas for hierarchical documents.
+//!
+//! A [`NestedDelta`] is a delta whose elements are sub-documents (e.g. table
+//! cells, captions or comments), each of which is represented by a delta of
+//! its own. Sub-documents are inserted and deleted like any other element. A
+//! sub-document is edited by retaining it with an [`Edit`], which contains the
+//! change to apply to that sub-document.
+//!
+//! The core [`Compose`] and [`Transform`] implementations treat edits as
+//! opaque attributes. Use [`compose`] and [`transform`] instead to recursively
+//! compose and transform the changes to sub-documents.
+
+use std::fmt::Debug;
+use std::iter::Cloned;
+use std::ops::Range;
+use std::slice::Iter;
+
+use serde::{Deserialize, Serialize};
+
+use super::ops::{Insert, Retain};
+use super::{Compose, Delta, Len, Op, Seq, Split, Transform};
+
+/// Delta whose elements are sub-documents that can be edited with an [`Edit`].
+pub type NestedDelta<T, A> = Delta<Nested<T, A>, Edit<T, A>>;
+
+/// Sequence of sub-documents, each of which counts as a single element.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+#[serde(bound(
+    serialize = "Delta<T, A>: Serialize",
+    deserialize = "Delta<T, A>: Deserialize<'de>"
+))]
+pub struct Nested<T, A> {
+    items: Vec<Delta<T, A>>,
+}
+
+impl<T, A> Nested<T, A> {
+    /// Returns a sequence that consists of the given sub-document.
+    pub fn new(item: Delta<T, A>) -> Nested<T, A> {
+        Nested { items: vec![item] }
+    }
+
+    /// Returns the sub-documents in this sequence.
+    pub fn items(&self) -> &[Delta<T, A>] {
+        &self.items
+    }
+
+    /// Returns the sub-documents in this sequence.
+    pub fn into_items(self) -> Vec<Delta<T, A>> {
+        self.items
+    }
+}
+
+impl<T, A> Default for Nested<T, A> {
+    fn default() -> Self {
+        Nested { items: vec![] }
+    }
+}
+
+impl<T, A> Len for Nested<T, A> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+impl<T, A> Seq for Nested<T, A>
+where
+    T: Clone + 'static,
+    A: Clone + 'static,
+{
+    type Iterator<'a> = Cloned<Iter<'a, Delta<T, A>>>;
+
+    fn iter(&self) -> Self::Iterator<'_> {
+        <[Delta<T, A>]>::iter(&self.items).cloned()
+    }
+}
+
+impl<T, A> Split for Nested<T, A> {
+    fn split(&mut self, len: usize) -> Self {
+        let rest = self.items.split_off(len.min(self.items.len()));
+
+        Nested {
+            items: std::mem::replace(&mut self.items, rest),
+        }
+    }
+}
+
+impl<T, A> Extend<Nested<T, A>> for Nested<T, A> {
+    fn extend<I: IntoIterator<Item = Nested<T, A>>>(&mut self, iter: I) {
+        for nested in iter {
+            self.items.extend(nested.items);
+        }
+    }
+}
+
+impl<T, A> FromIterator<Delta<T, A>> for Nested<T, A> {
+    fn from_iter<I: IntoIterator<Item = Delta<T, A>>>(iter: I) -> Self {
+        Nested {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T, A> From<Vec<Delta<T, A>>> for Nested<T, A> {
+    fn from(items: Vec<Delta<T, A>>) -> Self {
+        Nested { items }
+    }
+}
+
+/// Change to the sub-documents that a retain of a [`NestedDelta`] retains.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+#[serde(bound(
+    serialize = "Delta<T, A>: Serialize",
+    deserialize = "Delta<T, A>: Deserialize<'de>"
+))]
+pub struct Edit<T, A>(pub Delta<T, A>);
+
+impl<T, A> Default for Edit<T, A> {
+    fn default() -> Self {
+        Edit(Delta::default())
+    }
+}
+
+/// Composes the changes to the sub-documents.
+impl<T, A> Compose<Edit<T, A>> for Edit<T, A>
+where
+    T: Clone + Default + Seq + Extend<T> + Debug,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = Edit<T, A>;
+
+    fn compose(self, rhs: Edit<T, A>) -> Self::Output {
+        Edit(self.0.compose(rhs.0))
+    }
+}
+
+/// Composes the given deltas like [`Compose`], but applies the edits that the
+/// second delta makes to sub-documents that the first delta inserts to those
+/// sub-documents (instead of storing them as attributes of the insert).
+pub fn compose<T, A>(lhs: NestedDelta<T, A>, rhs: NestedDelta<T, A>) -> NestedDelta<T, A>
+where
+    T: Clone + Default + PartialEq + Seq + Extend<T> + Debug,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A> + 'static,
+{
+    lhs.compose(rhs)
+        .into_iter()
+        .map(|op| match op {
+            Op::Insert(insert) => match insert.into_parts() {
+                (insert, Some(Edit(change))) => Op::Insert(Insert::new(
+                    insert
+                        .into_items()
+                        .into_iter()
+                        .map(|item| item.compose(change.clone()))
+                        .collect(),
+                    None,
+                )),
+                (insert, attributes) => Op::Insert(Insert::new(insert, attributes)),
+            },
+            op => op,
+        })
+        .collect()
+}
+
+/// Transforms the given delta with another delta like [`Transform`], but
+/// recursively transforms the edits that both deltas make to the same
+/// sub-documents (instead of keeping only one of them).
+pub fn transform<T, A>(
+    lhs: NestedDelta<T, A>,
+    rhs: NestedDelta<T, A>,
+    priority: bool,
+) -> NestedDelta<T, A>
+where
+    T: Clone + Default + PartialEq + Seq + Extend<T> + Debug,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A> + 'static,
+{
+    let lhs_edits = edits(&lhs);
+    let rhs_edits = edits(&rhs);
+
+    let mut result = lhs.clone().transform(rhs, priority);
+
+    for (lhs_range, lhs_edit) in &lhs_edits {
+        for (rhs_range, rhs_edit) in &rhs_edits {
+            let start = lhs_range.start.max(rhs_range.start);
+            let end = lhs_range.end.min(rhs_range.end);
+
+            if start >= end {
+                continue;
+            }
+
+            let edit = Edit(lhs_edit.0.clone().transform(rhs_edit.0.clone(), priority));
+            let start = (&lhs).transform(start, false);
+            result = set_edit(result, start..start + (end - start), edit);
+        }
+    }
+
+    result
+}
+
+/// Returns the edits that the given delta makes, along with the ranges of the
+/// sub-documents that these edits apply to.
+fn edits<T, A>(delta: &NestedDelta<T, A>) -> Vec<(Range<usize>, Edit<T, A>)>
+where
+    T: Clone,
+    A: Clone,
+{
+    let mut edits = vec![];
+    let mut position = 0usize;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(_) => {}
+            Op::Retain(Retain { retain, attributes }) => {
+                let end = position.saturating_add(*retain);
+
+                if let Some(edit) = attributes {
+                    edits.push((position..end, edit.clone()));
+                }
+
+                position = end;
+            }
+            Op::Delete(delete) => position = position.saturating_add(delete.len()),
+        }
+    }
+
+    edits
+}
+
+/// Returns the given delta where the retains in the given range carry the
+/// given edit.
+fn set_edit<T, A>(
+    delta: NestedDelta<T, A>,
+    range: Range<usize>,
+    edit: Edit<T, A>,
+) -> NestedDelta<T, A>
+where
+    T: Clone + Default + PartialEq + Seq + Extend<T> + Debug,
+    A: Clone + Default + PartialEq + Debug + 'static,
+{
+    let mut result = Delta::new();
+    let mut position = 0usize;
+
+    for op in delta {
+        match op {
+            Op::Retain(mut retain) => {
+                let before = retain.split(range.start.saturating_sub(position).min(retain.len()));
+                position = position.saturating_add(before.len());
+
+                let mut inside = retain.split(range.end.saturating_sub(position).min(retain.len()));
+                position = position.saturating_add(inside.len());
+                inside.attributes = Some(edit.clone());
+
+                position = position.saturating_add(retain.len());
+
+                result.push(before.into());
+                result.push(inside.into());
+                result.push(retain.into());
+            }
+            Op::Delete(delete) => {
+                position = position.saturating_add(delete.len());
+                result.push(delete.into());
+            }
+            op => result.push(op),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compose, Delta, Transform};
+
+    use super::{compose, transform, Edit, Nested, NestedDelta};
+
+    fn cell(text: &str) -> Delta<String, ()> {
+        Delta::new().insert(text.to_owned(), None)
+    }
+
+    #[test]
+    fn test_compose() {
+        let before: NestedDelta<String, ()> = Delta::new()
+            .insert(Nested::new(cell("a")), None)
+            .insert(Nested::new(cell("b")), None);
+        let change = Delta::new().retain(1, None).retain(
+            1,
+            Edit(Delta::new().retain(1, None).insert("c".to_owned(), None)),
+        );
+
+        assert_eq!(
+            compose(before, change),
+            Delta::new().insert(Nested::from(vec![cell("a"), cell("bc")]), None)
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let before: NestedDelta<String, ()> = Delta::new().insert(Nested::new(cell("ab")), None);
+
+        let alice = Delta::new().retain(1, Edit(Delta::new().insert("x".to_owned(), None)));
+        let bob = Delta::new().retain(
+            1,
+            Edit(Delta::new().retain(2, None).insert("y".to_owned(), None)),
+        );
+
+        let alice_bob = compose(
+            compose(before.clone(), alice.clone()),
+            transform(alice.clone(), bob.clone(), true),
+        );
+        let bob_alice = compose(
+            compose(before.clone(), bob.clone()),
+            transform(bob.clone(), alice.clone(), false),
+        );
+
+        assert_eq!(alice_bob, bob_alice);
+        assert_eq!(
+            alice_bob,
+            Delta::new().insert(Nested::new(cell("xaby")), None)
+        );
+    }
+
+    #[test]
+    fn test_transform_insert() {
+        let alice: NestedDelta<String, ()> = Delta::new().insert(Nested::new(cell("a")), None);
+        let bob = Delta::new().retain(1, Edit(Delta::new().insert("b".to_owned(), None)));
+
+        assert_eq!(
+            transform(alice.clone(), bob.clone(), true),
+            Transform::transform(alice, bob, true)
+        );
+    }
+
+    #[test]
+    fn test_edit_compose() {
+        let lhs = Edit(Delta::<String, ()>::new().insert("a".to_owned(), None));
+        let rhs = Edit(Delta::new().retain(1, None).insert("b".to_owned(), None));
+
+        assert_eq!(
+            lhs.compose(rhs),
+            Edit(Delta::new().insert("ab".to_owned(), None))
+        );
+    }
+}
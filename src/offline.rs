@@ -0,0 +1,227 @@
+use std::fmt::Debug;
+use std::mem::take;
+
+use super::{Compose, Delta, Metrics, Seq, Transform};
+
+/// Queue of local changes that are made while a client is disconnected.
+///
+/// Changes are composed into a single delta as they're pushed. Once the client
+/// reconnects, it should [`rebase`](OfflineQueue::rebase) the queue onto every
+/// delta that the server applied in the meantime (in order) and then submit the
+/// result of [`take`](OfflineQueue::take) as a regular change.
+///
+/// The queue reports its depth, the size of its pending delta and every rebase
+/// to the given [`Metrics`] (which don't record anything by default).
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfflineQueue<T, A, M = ()> {
+    pending: Delta<T, A>,
+    len: usize,
+    metrics: M,
+}
+
+impl<T, A> OfflineQueue<T, A> {
+    /// Returns a new empty queue.
+    pub fn new() -> OfflineQueue<T, A> {
+        OfflineQueue::with_metrics(())
+    }
+}
+
+impl<T, A, M> OfflineQueue<T, A, M> {
+    /// Returns a new empty queue that reports to the given metrics.
+    pub fn with_metrics(metrics: M) -> OfflineQueue<T, A, M> {
+        OfflineQueue {
+            pending: Delta::default(),
+            len: 0,
+            metrics,
+        }
+    }
+
+    /// Returns the metrics that this queue reports to.
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    /// Returns the number of changes that have been pushed to this queue since
+    /// it was last taken.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no changes have been pushed to this queue since it was
+    /// last taken.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the composition of all changes in this queue.
+    pub fn pending(&self) -> &Delta<T, A> {
+        &self.pending
+    }
+
+    /// Returns the composition of all changes in this queue (or `None` if it's
+    /// empty) and empties this queue.
+    pub fn take(&mut self) -> Option<Delta<T, A>>
+    where
+        M: Metrics,
+    {
+        match take(&mut self.len) {
+            0 => None,
+            _ => {
+                self.metrics.queue_depth(0);
+                Some(take(&mut self.pending))
+            }
+        }
+    }
+}
+
+impl<T, A, M> OfflineQueue<T, A, M>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+    M: Metrics,
+{
+    /// Adds the given local change, which applies after all changes that are
+    /// already in this queue, to this queue.
+    pub fn push(&mut self, change: Delta<T, A>) {
+        self.metrics.ops_processed(change.ops_len());
+        self.pending = take(&mut self.pending).compose(change);
+        self.len += 1;
+        self.metrics.queue_depth(self.len);
+        self.metrics.delta_size(self.pending.ops_len());
+    }
+
+    /// Transforms this queue so that it applies after the given delta, which
+    /// the server applied while this client was disconnected. Returns the given
+    /// delta transformed to apply after the changes in this queue, i.e. the
+    /// change that the client should apply to its local document.
+    ///
+    /// The server's delta has priority, because the server already applied it
+    /// before it will receive the changes in this queue.
+    pub fn rebase(&mut self, missed: Delta<T, A>) -> Delta<T, A> {
+        let pending = take(&mut self.pending);
+
+        self.metrics.ops_processed(missed.ops_len());
+
+        if self.len > 0 {
+            self.metrics.transform_retried();
+        }
+
+        self.pending = missed.clone().transform(pending.clone(), true);
+        pending.transform(missed, false)
+    }
+}
+
+impl<T, A, M> Default for OfflineQueue<T, A, M>
+where
+    M: Default,
+{
+    fn default() -> Self {
+        OfflineQueue::with_metrics(M::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::OfflineQueue;
+    use crate::{Compose, Delta, Metrics};
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        ops: Cell<usize>,
+        retries: Cell<usize>,
+        depth: Cell<usize>,
+        size: Cell<usize>,
+    }
+
+    impl Metrics for Counts {
+        fn ops_processed(&self, ops: usize) {
+            self.ops.set(self.ops.get() + ops);
+        }
+
+        fn transform_retried(&self) {
+            self.retries.set(self.retries.get() + 1);
+        }
+
+        fn queue_depth(&self, depth: usize) {
+            self.depth.set(depth);
+        }
+
+        fn delta_size(&self, ops: usize) {
+            self.size.set(ops);
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        let mut queue = OfflineQueue::<String, ()>::new();
+
+        assert_eq!(queue.take(), None);
+
+        queue.push(Delta::new().insert("Hello".to_owned(), None));
+        queue.push(
+            Delta::new()
+                .retain(5, None)
+                .insert(" World".to_owned(), None),
+        );
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(
+            queue.take(),
+            Some(Delta::new().insert("Hello World".to_owned(), None))
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_rebase() {
+        let base = Delta::<String, ()>::new().insert("Hello World".to_owned(), None);
+
+        let mut queue = OfflineQueue::new();
+        queue.push(Delta::new().retain(5, None).insert(",".to_owned(), None));
+        queue.push(Delta::new().retain(12, None).insert("!".to_owned(), None));
+
+        let local = base.clone().compose(queue.pending().clone());
+
+        let missed = [
+            Delta::new().insert("Oh, ".to_owned(), None),
+            Delta::new().retain(10, None).delete(5),
+        ];
+
+        let local = missed.iter().fold(local, |local, missed| {
+            local.compose(queue.rebase(missed.clone()))
+        });
+        let server = missed
+            .into_iter()
+            .fold(base, |server, missed| server.compose(missed))
+            .compose(queue.take().unwrap());
+
+        assert_eq!(local, server);
+        assert_eq!(server, Delta::new().insert("Oh, Hello, !".to_owned(), None));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let counts = Counts::default();
+        let mut queue = OfflineQueue::<String, (), _>::with_metrics(&counts);
+
+        queue.rebase(Delta::new().insert("Hello".to_owned(), None));
+        queue.push(Delta::new().retain(5, None).insert("!".to_owned(), None));
+        queue.push(Delta::new().insert("Oh, ".to_owned(), None));
+
+        assert_eq!(counts.ops.get(), 4);
+        assert_eq!(counts.retries.get(), 0);
+        assert_eq!(counts.depth.get(), 2);
+        assert_eq!(counts.size.get(), 3);
+
+        queue.rebase(Delta::new().delete(2));
+
+        assert_eq!(counts.ops.get(), 5);
+        assert_eq!(counts.retries.get(), 1);
+
+        queue.take();
+
+        assert_eq!(counts.depth.get(), 0);
+    }
+}
@@ -1,7 +1,7 @@
 use std::cmp::min;
 
 use arbitrary::Arbitrary;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
 use super::ops::{Delete, Insert, Retain};
 use super::{Len, Seq};
@@ -22,9 +22,13 @@ where
     (lhs.split(len), rhs.split(len))
 }
 
-/// Individual insert, retain or delete operation.
-#[derive(Arbitrary, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Individual insert, retain or delete operation. When deserializing, an
+/// operation is an insert if it has an `insert` field, a retain if it has a
+/// `retain` field and a delete otherwise.
+#[derive(Arbitrary, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged)]
+#[arbitrary(bound = "T: Arbitrary<'arbitrary> + Len, A: Arbitrary<'arbitrary>")]
 pub enum Op<T, A = ()> {
     /// Represents an insert-operation with a value and optional attributes.
     /// [Click here](Insert) to read more about insert operations.
@@ -39,6 +43,20 @@ pub enum Op<T, A = ()> {
     Delete(Delete),
 }
 
+impl<T, A> Op<T, A> {
+    /// Returns a new insert operation of the given single element (e.g. a
+    /// `char` for a `String`) with the given optional attributes, without the
+    /// caller having to build the value first. Whether this allocates depends on
+    /// `T`: a `String` does, while an [`InlineString`](crate::InlineString)
+    /// stores short text inline.
+    pub fn insert_one<I>(item: I, attributes: impl Into<Option<A>>) -> Op<T, A>
+    where
+        T: FromIterator<I> + Len,
+    {
+        Op::Insert(Insert::new(T::from_iter([item]), attributes))
+    }
+}
+
 impl<T, A> Len for Op<T, A>
 where
     T: Len,
@@ -50,6 +68,14 @@ where
             Self::Delete(delete) => delete.len(),
         }
     }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Insert(insert) => insert.is_empty(),
+            Self::Retain(retain) => retain.is_empty(),
+            Self::Delete(delete) => delete.is_empty(),
+        }
+    }
 }
 
 impl<T, A> Default for Op<T, A> {
@@ -95,105 +121,46 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Delete, Insert, Op, Split};
+    use crate::Len;
 
     #[test]
     fn test_split_insert_start() {
-        let mut a = Op::Insert(Insert {
-            insert: "abc".to_owned(),
-            attributes: Some(()),
-        });
+        let mut a = Op::Insert(Insert::new("abc".to_owned(), ()));
         let b = a.split(0);
 
-        assert_eq!(
-            a,
-            Op::Insert(Insert {
-                insert: "abc".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(a, Op::Insert(Insert::new("abc".to_owned(), ())));
 
-        assert_eq!(
-            b,
-            Op::Insert(Insert {
-                insert: "".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(b, Op::Insert(Insert::new("".to_owned(), ())));
     }
 
     #[test]
     fn test_split_insert_mid() {
-        let mut a = Op::Insert(Insert {
-            insert: "abc".to_owned(),
-            attributes: Some(()),
-        });
+        let mut a = Op::Insert(Insert::new("abc".to_owned(), ()));
         let b = a.split(1);
 
-        assert_eq!(
-            a,
-            Op::Insert(Insert {
-                insert: "bc".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(a, Op::Insert(Insert::new("bc".to_owned(), ())));
 
-        assert_eq!(
-            b,
-            Op::Insert(Insert {
-                insert: "a".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(b, Op::Insert(Insert::new("a".to_owned(), ())));
     }
 
     #[test]
     fn test_split_insert_end() {
-        let mut a = Op::Insert(Insert {
-            insert: "abc".to_owned(),
-            attributes: Some(()),
-        });
+        let mut a = Op::Insert(Insert::new("abc".to_owned(), ()));
         let b = a.split(3);
 
-        assert_eq!(
-            a,
-            Op::Insert(Insert {
-                insert: "".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(a, Op::Insert(Insert::new("".to_owned(), ())));
 
-        assert_eq!(
-            b,
-            Op::Insert(Insert {
-                insert: "abc".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(b, Op::Insert(Insert::new("abc".to_owned(), ())));
     }
 
     #[test]
     fn test_split_insert_oob() {
-        let mut a = Op::Insert(Insert {
-            insert: "abc".to_owned(),
-            attributes: Some(()),
-        });
+        let mut a = Op::Insert(Insert::new("abc".to_owned(), ()));
         let b = a.split(4);
 
-        assert_eq!(
-            a,
-            Op::Insert(Insert {
-                insert: "".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(a, Op::Insert(Insert::new("".to_owned(), ())));
 
-        assert_eq!(
-            b,
-            Op::Insert(Insert {
-                insert: "abc".to_owned(),
-                attributes: Some(()),
-            })
-        );
+        assert_eq!(b, Op::Insert(Insert::new("abc".to_owned(), ())));
     }
 
     #[test]
@@ -222,4 +189,20 @@ mod tests {
         assert_eq!(a, Op::Delete(Delete { delete: 0 }));
         assert_eq!(b, Op::Delete(Delete { delete: 3 }));
     }
+
+    #[test]
+    fn test_insert_len() {
+        let mut insert = Insert::<_, ()>::new("Hé🌍llo".to_owned(), None);
+
+        assert_eq!(insert.len(), 6);
+        assert_eq!(insert.split(3).len(), 3);
+        assert_eq!((insert.len(), insert.insert().as_str()), (3, "llo"));
+
+        insert.push('!');
+        insert.extend(["ÿ".to_owned()]);
+        assert_eq!((insert.len(), insert.insert().as_str()), (5, "llo!ÿ"));
+
+        let insert: Insert<String, ()> = serde_json::from_str(r#"{"insert":"Hé🌍"}"#).unwrap();
+        assert_eq!(insert.len(), 3);
+    }
 }
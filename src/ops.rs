@@ -1,6 +1,6 @@
 //! Types that represent the insert, retain and delete operations within Kyte.
 
-use arbitrary::Arbitrary;
+use arbitrary::{Arbitrary, Unstructured};
 use serde::{Deserialize, Serialize};
 
 use super::{Len, Seq, Split};
@@ -24,14 +24,18 @@ use super::{Len, Seq, Split};
 /// [6]: #impl-Transform<%26mut+Delete>-for-%26mut+Insert<T,+A>
 ///
 /// Apart from these traits, [`Insert<T, A>`] also implements [`Len`] and
-/// [`Split`].
-#[derive(Arbitrary, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// [`Split`]. An insert stores the length of its value next to it, so that
+/// compose, transform and split (which query that length repeatedly) don't
+/// have to count the elements of that value (e.g. the characters of a
+/// `String`) every time. The value can therefore only be changed through
+/// [`Split`], [`Extend`] and [`Insert::push`], which keep the length in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Insert<T, A> {
-    /// Contains the value that this operation inserts into a
-    /// [`Delta`](super::Delta). Note that this doesn't necessarily need to be
-    /// text. Any type that conforms to [`Value`](super::Value) (i.e. any
-    /// countable type) is suitable as value.
-    pub insert: T,
+    insert: T,
+
+    #[serde(skip)]
+    len: usize,
 
     /// Optionally contains the attributes of the elements in this insert
     /// sequence. If this field is `None`, it will eagerly assume other
@@ -47,22 +51,74 @@ impl<T, A> Insert<T, A>
 where
     T: Len,
 {
+    /// Returns a new insert operation of the given value with the given
+    /// optional attributes. The length of the value is counted once.
+    pub fn new(insert: T, attributes: impl Into<Option<A>>) -> Insert<T, A> {
+        Insert {
+            len: insert.len(),
+            insert,
+            attributes: attributes.into(),
+        }
+    }
+}
+
+impl<T, A> Insert<T, A> {
+    /// Returns the value that this operation inserts into a
+    /// [`Delta`](super::Delta). Note that this doesn't necessarily need to be
+    /// text. Any type that conforms to [`Seq`] (i.e. any countable type) is
+    /// suitable as value.
+    pub fn insert(&self) -> &T {
+        &self.insert
+    }
+
+    /// Returns the value and attributes of this operation.
+    pub fn into_parts(self) -> (T, Option<A>) {
+        (self.insert, self.attributes)
+    }
+
+    /// Appends the given single element (e.g. a `char` for a `String`) to the
+    /// value of this operation.
+    pub fn push<I>(&mut self, item: I)
+    where
+        T: Extend<I>,
+    {
+        self.insert.extend([item]);
+        self.len += 1;
+    }
+
     /// Returns a new retain operation with the same length as this insert's
     /// sequence's.
     pub fn as_retain(&self) -> Retain<A> {
         Retain {
-            retain: self.len(),
+            retain: self.len,
             attributes: None,
         }
     }
 }
 
-impl<T, A> Len for Insert<T, A>
+impl<T, A> Default for Insert<T, A>
 where
-    T: Len,
+    T: Default + Len,
+{
+    fn default() -> Self {
+        Insert::new(T::default(), None)
+    }
+}
+
+impl<'a, T, A> Arbitrary<'a> for Insert<T, A>
+where
+    T: Arbitrary<'a> + Len,
+    A: Arbitrary<'a>,
 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Insert::new(T::arbitrary(u)?, Option::<A>::arbitrary(u)?))
+    }
+}
+
+/// Returns the cached length of this insert's value.
+impl<T, A> Len for Insert<T, A> {
     fn len(&self) -> usize {
-        self.insert.len()
+        self.len
     }
 }
 
@@ -72,16 +128,30 @@ where
     A: Clone,
 {
     fn split(&mut self, len: usize) -> Self {
-        let remainder = self.insert.iter().take(len).collect();
-        self.insert = self.insert.iter().skip(len).collect();
+        let len = len.min(self.len);
+        self.len -= len;
 
         Insert {
-            insert: remainder,
+            insert: self.insert.split_prefix(len),
+            len,
             attributes: self.attributes.clone(),
         }
     }
 }
 
+/// Appends the given values to the value of this operation.
+impl<T, A> Extend<T> for Insert<T, A>
+where
+    T: Extend<T> + Len,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for insert in iter {
+            self.len += insert.len();
+            self.insert.extend([insert]);
+        }
+    }
+}
+
 /// Represents an operation that retains a sequence and optionally updates its
 /// attributes.
 ///
@@ -104,8 +174,12 @@ where
 /// Apart from these traits, [`Retain<T, A>`] also implements [`Len`] and
 /// [`Split`].
 #[derive(Arbitrary, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Retain<A> {
-    /// Contains the number of elements to retain.
+    /// Contains the number of elements to retain. When deserializing, an object
+    /// (which Quill uses to retain an embed and apply changes to it) is
+    /// accepted as a retain of a single element that keeps its attributes.
+    #[serde(deserialize_with = "super::de::retain")]
     pub retain: usize,
 
     /// Optionally contains the attributes that the elements in this sequence
@@ -169,8 +243,10 @@ where
 /// Apart from these traits, [`Delete<T, A>`] also implements [`Len`] and
 /// [`Split`].
 #[derive(Arbitrary, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Delete {
     /// Contains the number of elements to delete.
+    #[serde(deserialize_with = "super::de::delete")]
     pub delete: usize,
 }
 
@@ -0,0 +1,168 @@
+//! [Proptest](https://docs.rs/proptest) strategies for documents and deltas.
+//!
+//! This module is only available with the `proptest` feature enabled. Unlike
+//! the [`Arbitrary`](arbitrary::Arbitrary) impls (which are meant for fuzzing
+//! and may produce deltas that extend past the end of any document), the
+//! strategies in this module produce deltas that fit together: [`document`]
+//! produces a document (i.e. a delta that only contains inserts), and
+//! [`change`] produces a delta that applies to a document of a given length.
+//! All strategies are generic over the strategies of values and attributes,
+//! and shrink towards fewer and shorter ops.
+//!
+//! ```
+//! use kyte::{prop, Compose, Document};
+//! use proptest::prelude::*;
+//!
+//! proptest!(|((doc, a, b) in prop::concurrent("[a-z]{1,4}", Just(None::<()>), 0..8))| {
+//!     let len = doc.target_len(0);
+//!
+//!     prop_assert!(a.check_applicable(len).is_ok());
+//!     prop_assert!(b.check_applicable(len).is_ok());
+//!     prop_assert!(Document::from_delta(doc.compose(a)).is_ok());
+//! });
+//! ```
+
+use std::fmt::Debug;
+
+use proptest::collection::{vec, SizeRange};
+use proptest::prelude::*;
+
+use super::{Delta, Seq};
+
+/// Single step of a generated change, whose length is only resolved once the
+/// number of elements that are left in the document is known.
+#[derive(Clone, Debug)]
+enum Step<T, A> {
+    Insert(T, Option<A>),
+    Retain(usize, Option<A>),
+    Delete(usize),
+}
+
+/// Returns a strategy that produces documents (i.e. deltas that only contain
+/// inserts) with the given number of inserts, whose values and attributes are
+/// produced by the given strategies.
+pub fn document<T, A>(
+    value: impl Strategy<Value = T>,
+    attributes: impl Strategy<Value = Option<A>>,
+    ops: impl Into<SizeRange>,
+) -> impl Strategy<Value = Delta<T, A>>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug,
+{
+    vec((value, attributes), ops).prop_map(|inserts| {
+        inserts
+            .into_iter()
+            .fold(Delta::new(), |delta, (value, attributes)| {
+                delta.insert(value, attributes)
+            })
+    })
+}
+
+/// Returns a strategy that produces deltas with the given number of ops that
+/// apply to a document of the given length, i.e. whose retains and deletes
+/// don't extend past the end of that document. The values and attributes of
+/// inserts (and the attributes of retains) are produced by the given
+/// strategies.
+pub fn change<T, A>(
+    base_len: usize,
+    value: impl Strategy<Value = T>,
+    attributes: impl Strategy<Value = Option<A>> + Clone,
+    ops: impl Into<SizeRange>,
+) -> impl Strategy<Value = Delta<T, A>>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug,
+{
+    let step = prop_oneof![
+        (value, attributes.clone()).prop_map(|(value, attributes)| Step::Insert(value, attributes)),
+        (any::<usize>(), attributes).prop_map(|(len, attributes)| Step::Retain(len, attributes)),
+        any::<usize>().prop_map(Step::Delete),
+    ];
+
+    vec(step, ops).prop_map(move |steps| {
+        let mut delta = Delta::new();
+        let mut remaining = base_len;
+
+        for step in steps {
+            delta = match step {
+                Step::Insert(value, attributes) => delta.insert(value, attributes),
+                _ if remaining == 0 => continue,
+                Step::Retain(len, attributes) => {
+                    let len = 1 + len % remaining;
+                    remaining -= len;
+                    delta.retain(len, attributes)
+                }
+                Step::Delete(len) => {
+                    let len = 1 + len % remaining;
+                    remaining -= len;
+                    delta.delete(len)
+                }
+            };
+        }
+
+        delta
+    })
+}
+
+/// Returns a strategy that produces a document and two concurrent changes to
+/// that document (see [`document`] and [`change`]), e.g. to check that
+/// transforming them converges. Each delta has the given number of ops.
+pub fn concurrent<T, A, V, S>(
+    value: V,
+    attributes: S,
+    ops: impl Into<SizeRange>,
+) -> impl Strategy<Value = (Delta<T, A>, Delta<T, A>, Delta<T, A>)>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug,
+    V: Strategy<Value = T> + Clone,
+    S: Strategy<Value = Option<A>> + Clone,
+{
+    let ops = ops.into();
+
+    document(value.clone(), attributes.clone(), ops.clone()).prop_flat_map(move |document| {
+        let len = document.target_len(0);
+
+        (
+            Just(document),
+            change(len, value.clone(), attributes.clone(), ops.clone()),
+            change(len, value.clone(), attributes.clone(), ops.clone()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{change, concurrent, document};
+    use crate::{Compose, LastWriteWins, Transform};
+
+    fn attributes() -> impl Strategy<Value = Option<LastWriteWins<u8>>> + Clone {
+        proptest::option::of(any::<u8>().prop_map(LastWriteWins))
+    }
+
+    proptest! {
+        #[test]
+        fn test_document(doc in document("[a-z]{0,4}", attributes(), 0..8)) {
+            prop_assert_eq!(doc.base_len(), 0);
+        }
+
+        #[test]
+        fn test_change(delta in change(5, "[a-z]{0,4}", attributes(), 0..8)) {
+            prop_assert!(delta.check_applicable(5).is_ok());
+        }
+
+        #[test]
+        fn test_concurrent((before, a, b) in concurrent("[a-z]{1,4}", attributes(), 0..8)) {
+            let a_b = before
+                .clone()
+                .compose(a.clone())
+                .compose(a.clone().transform(b.clone(), true));
+            let b_a = before.compose(b.clone()).compose(b.transform(a, false));
+
+            prop_assert!(a_b.semantic_eq(&b_a));
+        }
+    }
+}
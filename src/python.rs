@@ -0,0 +1,185 @@
+//! [PyO3](https://pyo3.rs) bindings that expose [`QuillDelta`] to Python.
+//!
+//! This module is only available with the `python` feature enabled. Build the
+//! library as a `cdylib` (e.g. with [maturin](https://www.maturin.rs)) to get
+//! an extension module named `kyte` with a `Delta` class, so that scripts and
+//! notebooks that replay stored op logs compose and transform them exactly
+//! like a Rust server does. Deltas and attributes are exchanged as JSON strings
+//! in the format that Quill produces.
+//!
+//! ```python
+//! from kyte import Delta
+//!
+//! doc = Delta.from_json('{"ops":[{"insert":"Hello\\n"}]}')
+//! change = Delta().retain(5).insert("!", '{"bold":true}')
+//!
+//! print(doc.apply(change).to_json())
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use super::json::JsonAttributes;
+use super::quill::QuillDelta;
+use super::{Compose, Document, Transform};
+
+/// Immutable delta with text values and JSON attributes, which is exported to
+/// Python as `Delta`. Every method that changes a delta returns a new one.
+#[pyclass(name = "Delta", module = "kyte", frozen, eq, skip_from_py_object)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PyDelta(QuillDelta);
+
+#[pymethods]
+impl PyDelta {
+    /// Returns a new empty delta.
+    #[new]
+    pub fn new() -> PyDelta {
+        PyDelta::default()
+    }
+
+    /// Parses a delta from the JSON that Quill produces (i.e. an object with an
+    /// `ops` field or an array of ops).
+    #[staticmethod]
+    pub fn from_json(json: &str) -> PyResult<PyDelta> {
+        serde_json::from_str(json).map(PyDelta).map_err(value_error)
+    }
+
+    /// Returns the JSON representation of this delta, which Quill can parse.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("delta serializes")
+    }
+
+    /// Returns this delta followed by an insert of the given text with the
+    /// given attributes (as a JSON object).
+    #[pyo3(signature = (text, attributes = None))]
+    pub fn insert(&self, text: String, attributes: Option<&str>) -> PyResult<PyDelta> {
+        let attributes = parse_attributes(attributes)?;
+        Ok(PyDelta(self.0.clone().insert(text, attributes)))
+    }
+
+    /// Returns this delta followed by a retain of the given length with the
+    /// given attributes (as a JSON object).
+    #[pyo3(signature = (len, attributes = None))]
+    pub fn retain(&self, len: usize, attributes: Option<&str>) -> PyResult<PyDelta> {
+        let attributes = parse_attributes(attributes)?;
+        Ok(PyDelta(self.0.clone().retain(len, attributes)))
+    }
+
+    /// Returns this delta followed by a delete of the given length.
+    pub fn delete(&self, len: usize) -> PyDelta {
+        PyDelta(self.0.clone().delete(len))
+    }
+
+    /// Returns the composition of this delta and the given delta, which
+    /// applies after this delta.
+    pub fn compose(&self, other: &PyDelta) -> PyDelta {
+        PyDelta(self.0.clone().compose(other.0.clone()))
+    }
+
+    /// Returns the given delta transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    #[pyo3(signature = (other, priority = false))]
+    pub fn transform(&self, other: &PyDelta, priority: bool) -> PyDelta {
+        PyDelta(self.0.clone().transform(other.0.clone(), priority))
+    }
+
+    /// Returns the given index transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    #[pyo3(signature = (index, priority = false))]
+    pub fn transform_position(&self, index: usize, priority: bool) -> usize {
+        (&self.0).transform(index, priority)
+    }
+
+    /// Returns the delta that turns this document into the given document.
+    /// Raises `ValueError` if either delta isn't a document.
+    pub fn diff(&self, other: &PyDelta) -> PyResult<PyDelta> {
+        let lhs = Document::from_delta(self.0.clone()).map_err(value_error)?;
+        let rhs = Document::from_delta(other.0.clone()).map_err(value_error)?;
+
+        Ok(PyDelta(lhs.as_delta().diff(rhs.as_delta())))
+    }
+
+    /// Returns the document that results from applying the given change to
+    /// this document. Raises `ValueError` if this delta isn't a document or the
+    /// change extends past its end, rather than silently ignoring the excess
+    /// like `compose` does.
+    pub fn apply(&self, change: &PyDelta) -> PyResult<PyDelta> {
+        let mut document = Document::from_delta(self.0.clone()).map_err(value_error)?;
+        document.try_apply(change.0.clone()).map_err(value_error)?;
+
+        Ok(PyDelta(document.into_delta()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Delta.from_json({:?})", self.to_json())
+    }
+}
+
+impl PyDelta {
+    /// Returns the delta that this binding wraps.
+    pub fn into_inner(self) -> QuillDelta {
+        self.0
+    }
+}
+
+impl From<QuillDelta> for PyDelta {
+    fn from(delta: QuillDelta) -> Self {
+        PyDelta(delta)
+    }
+}
+
+/// Extension module that contains [`PyDelta`] as `kyte.Delta`.
+#[pymodule]
+pub fn kyte(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDelta>()
+}
+
+fn parse_attributes(attributes: Option<&str>) -> PyResult<Option<JsonAttributes>> {
+    attributes
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(value_error)
+}
+
+fn value_error(error: impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PyDelta;
+
+    fn delta(json: &str) -> PyDelta {
+        PyDelta::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_compose_transform() {
+        let a = PyDelta::new().insert("Hello".to_owned(), None).unwrap();
+        let b = PyDelta::new()
+            .retain(5, None)
+            .unwrap()
+            .insert("!".to_owned(), Some(r#"{"bold":true}"#))
+            .unwrap();
+
+        assert_eq!(
+            a.compose(&b),
+            delta(r#"[{"insert":"Hello"},{"insert":"!","attributes":{"bold":true}}]"#)
+        );
+        assert_eq!(
+            a.transform(&b, true),
+            delta(r#"[{"retain":10},{"insert":"!","attributes":{"bold":true}}]"#)
+        );
+        assert_eq!(a.transform_position(0, false), 5);
+    }
+
+    #[test]
+    fn test_diff_apply() {
+        let before = delta(r#"[{"insert":"Hello\n"}]"#);
+        let after = delta(r#"[{"insert":"Hello!\n"}]"#);
+        let change = before.diff(&after).unwrap();
+
+        assert_eq!(change, delta(r#"[{"retain":5},{"insert":"!"}]"#));
+        assert_eq!(before.apply(&change).unwrap(), after);
+    }
+}
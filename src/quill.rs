@@ -0,0 +1,518 @@
+//! Types for exchanging deltas with [Quill](https://quilljs.com/docs/delta/).
+//!
+//! This module is only available with the `quill` feature enabled. It exposes
+//! [`QuillDelta`], which uses the same value and attribute types as Quill does
+//! for text documents, so that deltas can be (de)serialized from and to the
+//! JSON that Quill produces without any custom types. Applications that only
+//! use Quill's standard formats can use [`QuillAttributes`] instead, which
+//! provides typed access to these formats.
+
+use serde::{Deserialize, Serialize};
+
+use super::de::double_option;
+use super::json::JsonAttributes;
+use super::rich::Scope;
+use super::validate::AttributeKeys;
+use super::{Compose, Delta, Diff};
+
+/// Delta with text values and JSON attributes, as used by Quill.
+pub type QuillDelta = Delta<String, JsonAttributes>;
+
+/// Value of the `script` format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    /// Renders text as subscript.
+    Sub,
+
+    /// Renders text as superscript.
+    Super,
+}
+
+/// Value of the `list` format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum List {
+    /// Renders a line as an item of an ordered list.
+    Ordered,
+
+    /// Renders a line as an item of a bulleted list.
+    Bullet,
+
+    /// Renders a line as a checked item of a checklist.
+    Checked,
+
+    /// Renders a line as an unchecked item of a checklist.
+    Unchecked,
+}
+
+/// Value of the `align` format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    /// Aligns a line to the left. Quill omits this format instead.
+    Left,
+
+    /// Centers a line.
+    Center,
+
+    /// Aligns a line to the right.
+    Right,
+
+    /// Justifies a line.
+    Justify,
+}
+
+/// Value of the `direction` format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Renders a line from right to left.
+    Rtl,
+}
+
+/// Value of the `code-block` format. Quill 1 uses `true`, whereas Quill 2 uses
+/// the language of the code block.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum CodeBlock {
+    /// Renders a line as code without a specific language.
+    Plain(bool),
+
+    /// Renders a line as code in the given language.
+    Language(String),
+}
+
+/// Attributes that cover Quill's standard formats.
+///
+/// Each format is represented by a nested option: `None` leaves the format
+/// unchanged (and is omitted when serialized), `Some(None)` removes the format
+/// (and is serialized as `null`) and `Some(Some(_))` sets the format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct QuillAttributes {
+    /// Contains the `bold` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<Option<bool>>,
+
+    /// Contains the `italic` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<Option<bool>>,
+
+    /// Contains the `underline` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underline: Option<Option<bool>>,
+
+    /// Contains the `strike` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strike: Option<Option<bool>>,
+
+    /// Contains the (inline) `code` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Option<bool>>,
+
+    /// Contains the `link` format, i.e. the URL of a link.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<Option<String>>,
+
+    /// Contains the `color` format, i.e. the CSS color of text.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Option<String>>,
+
+    /// Contains the `background` format, i.e. the CSS background color of text.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Option<String>>,
+
+    /// Contains the `font` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<Option<String>>,
+
+    /// Contains the `size` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<Option<String>>,
+
+    /// Contains the `script` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<Option<Script>>,
+
+    /// Contains the `header` format, i.e. the level of a heading.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<Option<u8>>,
+
+    /// Contains the `list` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<Option<List>>,
+
+    /// Contains the `indent` format, i.e. the indentation level of a line.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indent: Option<Option<u8>>,
+
+    /// Contains the `align` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<Option<Align>>,
+
+    /// Contains the `direction` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Option<Direction>>,
+
+    /// Contains the `blockquote` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blockquote: Option<Option<bool>>,
+
+    /// Contains the `code-block` format.
+    #[serde(default, deserialize_with = "double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_block: Option<Option<CodeBlock>>,
+}
+
+fn invert_format<T>(format: &Option<Option<T>>, base: &Option<Option<T>>) -> Option<Option<T>>
+where
+    T: Clone + PartialEq,
+{
+    match format {
+        Some(_) if format != base => Some(base.clone().flatten()),
+        Some(_) | None => None,
+    }
+}
+
+fn diff_format<T>(format: &Option<Option<T>>, other: &Option<Option<T>>) -> Option<Option<T>>
+where
+    T: Clone + PartialEq,
+{
+    match (format.clone().flatten(), other.clone().flatten()) {
+        (format, other) if format != other => Some(other),
+        _ => None,
+    }
+}
+
+impl QuillAttributes {
+    /// Returns the attributes that undo applying these attributes to elements
+    /// that had the given `base` attributes.
+    pub fn invert(&self, base: &QuillAttributes) -> QuillAttributes {
+        QuillAttributes {
+            bold: invert_format(&self.bold, &base.bold),
+            italic: invert_format(&self.italic, &base.italic),
+            underline: invert_format(&self.underline, &base.underline),
+            strike: invert_format(&self.strike, &base.strike),
+            code: invert_format(&self.code, &base.code),
+            link: invert_format(&self.link, &base.link),
+            color: invert_format(&self.color, &base.color),
+            background: invert_format(&self.background, &base.background),
+            font: invert_format(&self.font, &base.font),
+            size: invert_format(&self.size, &base.size),
+            script: invert_format(&self.script, &base.script),
+            header: invert_format(&self.header, &base.header),
+            list: invert_format(&self.list, &base.list),
+            indent: invert_format(&self.indent, &base.indent),
+            align: invert_format(&self.align, &base.align),
+            direction: invert_format(&self.direction, &base.direction),
+            blockquote: invert_format(&self.blockquote, &base.blockquote),
+            code_block: invert_format(&self.code_block, &base.code_block),
+        }
+    }
+}
+
+/// Returns the formats whose values differ, where formats that are only set in
+/// these attributes are removed (i.e. set to `Some(None)`).
+impl Diff for QuillAttributes {
+    fn diff(&self, other: &Self) -> Option<Self> {
+        let attributes = QuillAttributes {
+            bold: diff_format(&self.bold, &other.bold),
+            italic: diff_format(&self.italic, &other.italic),
+            underline: diff_format(&self.underline, &other.underline),
+            strike: diff_format(&self.strike, &other.strike),
+            code: diff_format(&self.code, &other.code),
+            link: diff_format(&self.link, &other.link),
+            color: diff_format(&self.color, &other.color),
+            background: diff_format(&self.background, &other.background),
+            font: diff_format(&self.font, &other.font),
+            size: diff_format(&self.size, &other.size),
+            script: diff_format(&self.script, &other.script),
+            header: diff_format(&self.header, &other.header),
+            list: diff_format(&self.list, &other.list),
+            indent: diff_format(&self.indent, &other.indent),
+            align: diff_format(&self.align, &other.align),
+            direction: diff_format(&self.direction, &other.direction),
+            blockquote: diff_format(&self.blockquote, &other.blockquote),
+            code_block: diff_format(&self.code_block, &other.code_block),
+        };
+
+        Some(attributes).filter(|attributes| attributes != &QuillAttributes::default())
+    }
+}
+
+/// Merges the attributes format-wise, where the given attributes take
+/// precedence. Removed formats (i.e. `Some(None)`) are kept so that they
+/// continue to remove formats when the composed attributes are applied.
+impl Compose<QuillAttributes> for QuillAttributes {
+    type Output = QuillAttributes;
+
+    fn compose(self, rhs: QuillAttributes) -> Self::Output {
+        QuillAttributes {
+            bold: rhs.bold.or(self.bold),
+            italic: rhs.italic.or(self.italic),
+            underline: rhs.underline.or(self.underline),
+            strike: rhs.strike.or(self.strike),
+            code: rhs.code.or(self.code),
+            link: rhs.link.or(self.link),
+            color: rhs.color.or(self.color),
+            background: rhs.background.or(self.background),
+            font: rhs.font.or(self.font),
+            size: rhs.size.or(self.size),
+            script: rhs.script.or(self.script),
+            header: rhs.header.or(self.header),
+            list: rhs.list.or(self.list),
+            indent: rhs.indent.or(self.indent),
+            align: rhs.align.or(self.align),
+            direction: rhs.direction.or(self.direction),
+            blockquote: rhs.blockquote.or(self.blockquote),
+            code_block: rhs.code_block.or(self.code_block),
+        }
+    }
+}
+
+/// Uses the (kebab-case) names of the formats in Quill's JSON as keys.
+impl AttributeKeys for QuillAttributes {
+    fn keys(&self) -> Vec<&str> {
+        [
+            ("bold", self.bold.is_some()),
+            ("italic", self.italic.is_some()),
+            ("underline", self.underline.is_some()),
+            ("strike", self.strike.is_some()),
+            ("code", self.code.is_some()),
+            ("link", self.link.is_some()),
+            ("color", self.color.is_some()),
+            ("background", self.background.is_some()),
+            ("font", self.font.is_some()),
+            ("size", self.size.is_some()),
+            ("script", self.script.is_some()),
+            ("header", self.header.is_some()),
+            ("list", self.list.is_some()),
+            ("indent", self.indent.is_some()),
+            ("align", self.align.is_some()),
+            ("direction", self.direction.is_some()),
+            ("blockquote", self.blockquote.is_some()),
+            ("code-block", self.code_block.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(key, set)| set.then_some(key))
+        .collect()
+    }
+
+    fn remove_key(&mut self, key: &str) {
+        match key {
+            "bold" => self.bold = None,
+            "italic" => self.italic = None,
+            "underline" => self.underline = None,
+            "strike" => self.strike = None,
+            "code" => self.code = None,
+            "link" => self.link = None,
+            "color" => self.color = None,
+            "background" => self.background = None,
+            "font" => self.font = None,
+            "size" => self.size = None,
+            "script" => self.script = None,
+            "header" => self.header = None,
+            "list" => self.list = None,
+            "indent" => self.indent = None,
+            "align" => self.align = None,
+            "direction" => self.direction = None,
+            "blockquote" => self.blockquote = None,
+            "code-block" => self.code_block = None,
+            _ => {}
+        }
+    }
+}
+
+/// Scope that declares Quill's block formats (i.e. `header`, `list`, `indent`,
+/// `align`, `direction`, `blockquote` and `code-block`) as block formats and all
+/// other formats as inline formats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuillScope;
+
+impl Scope<QuillAttributes> for QuillScope {
+    fn block(&self, attributes: &QuillAttributes) -> Option<QuillAttributes> {
+        Some(QuillAttributes {
+            header: attributes.header,
+            list: attributes.list,
+            indent: attributes.indent,
+            align: attributes.align,
+            direction: attributes.direction,
+            blockquote: attributes.blockquote,
+            code_block: attributes.code_block.clone(),
+            ..Default::default()
+        })
+        .filter(|block| block != &QuillAttributes::default())
+    }
+
+    fn inline(&self, attributes: &QuillAttributes) -> Option<QuillAttributes> {
+        Some(QuillAttributes {
+            header: None,
+            list: None,
+            indent: None,
+            align: None,
+            direction: None,
+            blockquote: None,
+            code_block: None,
+            ..attributes.clone()
+        })
+        .filter(|inline| inline != &QuillAttributes::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{CodeBlock, List, QuillAttributes, QuillScope};
+    use crate::rich::Scope;
+    use crate::validate::{AttributeFilter, AttributeKeys};
+    use crate::{Compose, Delta};
+
+    #[test]
+    fn test_serde() {
+        let attributes: QuillAttributes = serde_json::from_value(json!({
+            "bold": true,
+            "link": null,
+            "list": "bullet",
+            "code-block": "rust",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            attributes,
+            QuillAttributes {
+                bold: Some(Some(true)),
+                link: Some(None),
+                list: Some(Some(List::Bullet)),
+                code_block: Some(Some(CodeBlock::Language("rust".to_owned()))),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            serde_json::to_value(&attributes).unwrap(),
+            json!({
+                "bold": true,
+                "link": null,
+                "list": "bullet",
+                "code-block": "rust",
+            })
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs = QuillAttributes {
+            bold: Some(Some(true)),
+            color: Some(Some("red".to_owned())),
+            ..Default::default()
+        };
+        let rhs = QuillAttributes {
+            color: Some(None),
+            italic: Some(Some(true)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lhs.compose(rhs),
+            QuillAttributes {
+                bold: Some(Some(true)),
+                color: Some(None),
+                italic: Some(Some(true)),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_invert() {
+        let base = QuillAttributes {
+            bold: Some(Some(true)),
+            color: Some(Some("red".to_owned())),
+            ..Default::default()
+        };
+        let applied = QuillAttributes {
+            bold: Some(Some(true)),
+            color: Some(Some("blue".to_owned())),
+            italic: Some(Some(true)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            applied.invert(&base),
+            QuillAttributes {
+                color: Some(Some("red".to_owned())),
+                italic: Some(None),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_scope() {
+        let attributes = QuillAttributes {
+            bold: Some(Some(true)),
+            list: Some(Some(List::Bullet)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            QuillScope.block(&attributes),
+            Some(QuillAttributes {
+                list: Some(Some(List::Bullet)),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            QuillScope.inline(&attributes),
+            Some(QuillAttributes {
+                bold: Some(Some(true)),
+                ..Default::default()
+            })
+        );
+        assert_eq!(QuillScope.block(&QuillAttributes::default()), None);
+    }
+
+    #[test]
+    fn test_attribute_keys() {
+        let mut attributes = QuillAttributes {
+            bold: Some(Some(true)),
+            code_block: Some(None),
+            ..Default::default()
+        };
+
+        assert_eq!(attributes.keys(), ["bold", "code-block"]);
+
+        attributes.remove_key("code-block");
+        assert_eq!(attributes.keys(), ["bold"]);
+
+        let change = Delta::<String, _>::new().retain(2, attributes.clone());
+        let filter = AttributeFilter::strip(|key: &str| key != "bold");
+
+        assert_eq!(filter.filter(change), Ok(Delta::new().retain(2, None)));
+    }
+}
@@ -0,0 +1,571 @@
+//! Line-aware mechanics for rich-text documents.
+//!
+//! Rich-text editors like Quill distinguish between inline formats (e.g. bold
+//! or links), which apply to the characters they're set on, and block formats
+//! (e.g. headings or lists), which apply to an entire line and are stored on
+//! the newline that ends that line. A [`Scope`] declares which formats are
+//! block formats, so that this module can keep block formats consistent when
+//! lines are merged or split by concurrent edits.
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::ops::Range;
+
+use super::attributes::Attributes;
+use super::ops::{Delete, Retain};
+use super::{seq, Compose, Delta, Document, Len, Op, Transform};
+
+/// Implemented by types that declare which formats of attributes of type `A`
+/// are block formats and which formats are inline formats.
+pub trait Scope<A> {
+    /// Returns the block formats of the given attributes, or `None` if the
+    /// given attributes don't contain any block formats.
+    fn block(&self, attributes: &A) -> Option<A>;
+
+    /// Returns the inline formats of the given attributes, or `None` if the
+    /// given attributes don't contain any inline formats.
+    fn inline(&self, attributes: &A) -> Option<A>;
+}
+
+/// Scope for attribute maps that declares the formats with the given keys as
+/// block formats and all other formats as inline formats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockKeys {
+    keys: BTreeSet<String>,
+}
+
+impl BlockKeys {
+    /// Returns a scope that declares the formats with the given keys as block
+    /// formats.
+    pub fn new<I, K>(keys: I) -> BlockKeys
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        BlockKeys {
+            keys: keys.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns a scope that declares the block formats of Quill (i.e.
+    /// `header`, `list`, `indent`, `align`, `direction`, `blockquote` and
+    /// `code-block`) as block formats.
+    pub fn quill() -> BlockKeys {
+        BlockKeys::new([
+            "header",
+            "list",
+            "indent",
+            "align",
+            "direction",
+            "blockquote",
+            "code-block",
+        ])
+    }
+
+    /// Returns true if the format with the given key is a block format.
+    pub fn is_block(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+impl Scope<Attributes> for BlockKeys {
+    fn block(&self, attributes: &Attributes) -> Option<Attributes> {
+        Some(
+            attributes
+                .iter()
+                .filter(|(key, _)| self.is_block(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Attributes>(),
+        )
+        .filter(|block| !block.is_empty())
+    }
+
+    fn inline(&self, attributes: &Attributes) -> Option<Attributes> {
+        Some(
+            attributes
+                .iter()
+                .filter(|(key, _)| !self.is_block(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Attributes>(),
+        )
+        .filter(|inline| !inline.is_empty())
+    }
+}
+
+#[cfg(feature = "json")]
+impl Scope<super::json::JsonAttributes> for BlockKeys {
+    fn block(
+        &self,
+        attributes: &super::json::JsonAttributes,
+    ) -> Option<super::json::JsonAttributes> {
+        Some(
+            attributes
+                .iter()
+                .filter(|(key, _)| self.is_block(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<super::json::JsonAttributes>(),
+        )
+        .filter(|block| !block.is_empty())
+    }
+
+    fn inline(
+        &self,
+        attributes: &super::json::JsonAttributes,
+    ) -> Option<super::json::JsonAttributes> {
+        Some(
+            attributes
+                .iter()
+                .filter(|(key, _)| !self.is_block(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<super::json::JsonAttributes>(),
+        )
+        .filter(|inline| !inline.is_empty())
+    }
+}
+
+/// Returns the given delta with its inserts split at newlines, such that
+/// inserted newlines only carry block formats and all other inserted text only
+/// carries inline formats. Retains and deletes are left unchanged.
+pub fn normalize_formats<S, A>(scope: &S, delta: Delta<String, A>) -> Delta<String, A>
+where
+    S: Scope<A>,
+    A: Clone + Default + PartialEq,
+{
+    let mut result = Delta::new();
+
+    for op in delta {
+        match op {
+            Op::Insert(insert) => {
+                let (insert, attributes) = insert.into_parts();
+
+                for line in insert.split_inclusive('\n') {
+                    let (text, newline) = match line.strip_suffix('\n') {
+                        Some(text) => (text, true),
+                        None => (line, false),
+                    };
+
+                    if !text.is_empty() {
+                        let inline = attributes.as_ref().and_then(|attrs| scope.inline(attrs));
+                        result = result.insert(text.to_owned(), inline);
+                    }
+
+                    if newline {
+                        let block = attributes.as_ref().and_then(|attrs| scope.block(attrs));
+                        result = result.insert("\n".to_owned(), block);
+                    }
+                }
+            }
+            op => result.push(op),
+        }
+    }
+
+    result
+}
+
+/// Transforms the given delta with another delta like [`Transform`], where both
+/// deltas are applied to the given document, but keeps block formats
+/// consistent when either delta merges or splits lines.
+///
+/// A line is merged with the next line by deleting the newline in between,
+/// after which the merged line ends with the newline of the next line. If one
+/// delta sets block formats on a newline that the other delta deletes, those
+/// block formats are moved to the newline that ends the merged line, instead of
+/// being lost in one order of application and kept in the other.
+///
+/// A line is split by inserting a newline into it. If one delta sets block
+/// formats on the newline of a line that the other delta splits, those block
+/// formats are also set on the inserted newlines, so that they apply to every
+/// part of the split line (like Quill, which formats every line in a selection
+/// rather than only the newline at its end).
+pub fn transform<S, A>(
+    scope: &S,
+    document: &Document<String, A>,
+    lhs: &Delta<String, A>,
+    rhs: Delta<String, A>,
+    priority: bool,
+) -> Delta<String, A>
+where
+    S: Scope<A>,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let newlines = newlines(document);
+    let lhs_deleted = deleted(lhs);
+    let rhs_deleted = deleted(&rhs);
+
+    let lhs_blocks = block_formats(scope, lhs, &newlines);
+    let rhs_blocks = block_formats(scope, &rhs, &newlines);
+
+    let splits = split_formats(lhs, &rhs_blocks, &lhs_deleted, &newlines);
+    let rhs = split_inserts(rhs, &lhs_blocks, &rhs_deleted, &newlines);
+
+    let lhs_moves = merged_formats(scope, lhs, &rhs_deleted, &lhs_deleted, &newlines);
+    let rhs_moves = merged_formats(scope, &rhs, &lhs_deleted, &rhs_deleted, &newlines);
+
+    // The formats of the delta with priority are applied last, so that both
+    // orders of application produce the same formats.
+    let moves = match priority {
+        true => rhs_moves.into_iter().chain(lhs_moves),
+        false => lhs_moves.into_iter().chain(rhs_moves),
+    };
+
+    let mut result = lhs.clone().transform(rhs, priority);
+
+    for (newline, block) in moves {
+        let position = lhs.transform(newline, false);
+        let position = (&result).transform(position, false);
+        result = format(result, position, block);
+    }
+
+    for (newline, block) in splits {
+        let position = (&result).transform(newline, false);
+        result = format(result, position, block);
+    }
+
+    result
+}
+
+/// Returns the positions of all newlines in the given document.
+fn newlines<A>(document: &Document<String, A>) -> Vec<usize> {
+    seq::newlines(&document.as_delta().plain_text()).collect()
+}
+
+/// Returns the ranges of the document that the given delta deletes.
+fn deleted<A>(delta: &Delta<String, A>) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut position = 0usize;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(_) => {}
+            Op::Retain(retain) => position = position.saturating_add(retain.len()),
+            Op::Delete(delete) => {
+                let end = position.saturating_add(delete.len());
+                ranges.push(position..end);
+                position = end;
+            }
+        }
+    }
+
+    ranges
+}
+
+fn is_deleted(ranges: &[Range<usize>], position: usize) -> bool {
+    ranges.iter().any(|range| range.contains(&position))
+}
+
+/// Returns the block formats that the given delta sets on newlines, along with
+/// the positions of these newlines.
+fn block_formats<S, A>(scope: &S, delta: &Delta<String, A>, newlines: &[usize]) -> Vec<(usize, A)>
+where
+    S: Scope<A>,
+    A: Clone,
+{
+    let mut formats = vec![];
+    let mut position = 0usize;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(_) => {}
+            Op::Retain(Retain { retain, attributes }) => {
+                let end = position.saturating_add(*retain);
+
+                if let Some(block) = attributes.as_ref().and_then(|attrs| scope.block(attrs)) {
+                    formats.extend(
+                        newlines
+                            .iter()
+                            .filter(|newline| (position..end).contains(newline))
+                            .map(|&newline| (newline, block.clone())),
+                    );
+                }
+
+                position = end;
+            }
+            Op::Delete(Delete { delete }) => position = position.saturating_add(*delete),
+        }
+    }
+
+    formats
+}
+
+/// Returns the block formats that `delta` sets on newlines that `other_deleted`
+/// deletes, along with the position of the next newline that neither delta
+/// deletes and therefore ends the merged line.
+fn merged_formats<S, A>(
+    scope: &S,
+    delta: &Delta<String, A>,
+    other_deleted: &[Range<usize>],
+    deleted: &[Range<usize>],
+    newlines: &[usize],
+) -> Vec<(usize, A)>
+where
+    S: Scope<A>,
+    A: Clone,
+{
+    block_formats(scope, delta, newlines)
+        .into_iter()
+        .filter(|&(newline, _)| is_deleted(other_deleted, newline))
+        .filter_map(|(newline, block)| {
+            newlines
+                .iter()
+                .copied()
+                .find(|&next| {
+                    next > newline && !is_deleted(other_deleted, next) && !is_deleted(deleted, next)
+                })
+                .map(|next| (next, block))
+        })
+        .collect()
+}
+
+/// Returns the newline that ends the line containing the given position, if
+/// any.
+fn line_end(newlines: &[usize], position: usize) -> Option<usize> {
+    newlines
+        .iter()
+        .copied()
+        .find(|&newline| newline >= position)
+}
+
+/// Returns the block formats that `blocks` sets on the newlines of lines that
+/// the given delta splits (unless the given delta also deletes that newline),
+/// along with the positions of the inserted newlines after applying the given
+/// delta.
+fn split_formats<A>(
+    delta: &Delta<String, A>,
+    blocks: &[(usize, A)],
+    deleted: &[Range<usize>],
+    newlines: &[usize],
+) -> Vec<(usize, A)>
+where
+    A: Clone,
+{
+    let mut formats = vec![];
+    let mut position = 0usize;
+    let mut offset = 0usize;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(insert) => {
+                let block = line_end(newlines, position)
+                    .filter(|&newline| !is_deleted(deleted, newline))
+                    .and_then(|newline| blocks.iter().find(|(other, _)| *other == newline));
+
+                if let Some((_, block)) = block {
+                    formats.extend(
+                        seq::newlines(insert.insert())
+                            .map(|index| (offset.saturating_add(index), block.clone())),
+                    );
+                }
+
+                offset = offset.saturating_add(insert.len());
+            }
+            Op::Retain(retain) => {
+                position = position.saturating_add(retain.len());
+                offset = offset.saturating_add(retain.len());
+            }
+            Op::Delete(delete) => position = position.saturating_add(delete.len()),
+        }
+    }
+
+    formats
+}
+
+/// Returns the given delta where the newlines that it inserts into lines whose
+/// newlines `blocks` sets block formats on (unless the given delta deletes that
+/// newline) are composed with those block formats.
+fn split_inserts<A>(
+    delta: Delta<String, A>,
+    blocks: &[(usize, A)],
+    deleted: &[Range<usize>],
+    newlines: &[usize],
+) -> Delta<String, A>
+where
+    A: Clone + Default + PartialEq + Compose<A, Output = A>,
+{
+    let mut result = Delta::new();
+    let mut position = 0usize;
+
+    for op in delta {
+        let len = op.len();
+
+        match op {
+            Op::Insert(insert) => {
+                let block = line_end(newlines, position)
+                    .filter(|&newline| !is_deleted(deleted, newline))
+                    .and_then(|newline| blocks.iter().find(|(other, _)| *other == newline));
+
+                let Some((_, block)) = block else {
+                    result.push(Op::Insert(insert));
+                    continue;
+                };
+                let (insert, attributes) = insert.into_parts();
+
+                for line in insert.split_inclusive('\n') {
+                    let (text, newline) = match line.strip_suffix('\n') {
+                        Some(text) => (text, true),
+                        None => (line, false),
+                    };
+
+                    if !text.is_empty() {
+                        result = result.insert(text.to_owned(), attributes.clone());
+                    }
+
+                    if newline {
+                        let attributes = attributes.clone().compose(Some(block.clone()));
+                        result = result.insert("\n".to_owned(), attributes);
+                    }
+                }
+            }
+            op => {
+                position = position.saturating_add(len);
+                result.push(op);
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the given delta composed with a delta that sets the given attributes
+/// on the element at the given position.
+fn format<A>(delta: Delta<String, A>, position: usize, attributes: A) -> Delta<String, A>
+where
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    delta.compose(Delta::new().retain(position, None).retain(1, attributes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::{Delta, Document, Transform};
+
+    use super::{normalize_formats, transform, BlockKeys, Scope};
+
+    fn attrs<const N: usize>(entries: [(&str, AttrValue); N]) -> Attributes {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect()
+    }
+
+    fn document(delta: Delta<String, Attributes>) -> Document<String, Attributes> {
+        Document::from_delta(delta).unwrap()
+    }
+
+    #[test]
+    fn test_block_keys() {
+        let scope = BlockKeys::quill();
+        let attributes = attrs([("bold", true.into()), ("header", 1i64.into())]);
+
+        assert_eq!(
+            scope.block(&attributes),
+            Some(attrs([("header", 1i64.into())]))
+        );
+        assert_eq!(
+            scope.inline(&attributes),
+            Some(attrs([("bold", true.into())]))
+        );
+        assert_eq!(scope.block(&attrs([("bold", true.into())])), None);
+    }
+
+    #[test]
+    fn test_normalize_formats() {
+        let delta = Delta::new().retain(1, None).insert(
+            "a\nb".to_owned(),
+            attrs([("bold", true.into()), ("header", 1i64.into())]),
+        );
+
+        assert_eq!(
+            normalize_formats(&BlockKeys::quill(), delta),
+            Delta::new()
+                .retain(1, None)
+                .insert("a".to_owned(), attrs([("bold", true.into())]))
+                .insert("\n".to_owned(), attrs([("header", 1i64.into())]))
+                .insert("b".to_owned(), attrs([("bold", true.into())]))
+        );
+    }
+
+    #[test]
+    fn test_transform_heading_and_join() {
+        let scope = BlockKeys::quill();
+        let before = document(Delta::new().insert("a\nb\n".to_owned(), None));
+        let heading = attrs([("header", 1i64.into())]);
+
+        let alice = Delta::new().retain(1, None).delete(1);
+        let bob = Delta::new().retain(1, None).retain(1, heading.clone());
+
+        let expected = Delta::new()
+            .insert("ab".to_owned(), None)
+            .insert("\n".to_owned(), heading);
+
+        for priority in [true, false] {
+            let alice_bob = transform(&scope, &before, &alice, bob.clone(), priority);
+            let bob_alice = transform(&scope, &before, &bob, alice.clone(), !priority);
+
+            let mut left = before.clone();
+            left.apply(alice.clone());
+            left.apply(alice_bob);
+
+            let mut right = before.clone();
+            right.apply(bob.clone());
+            right.apply(bob_alice);
+
+            assert_eq!(left.as_delta(), &expected);
+            assert_eq!(right.as_delta(), &expected);
+        }
+    }
+
+    #[test]
+    fn test_transform_heading_and_split() {
+        let scope = BlockKeys::quill();
+        let before = document(
+            Delta::new()
+                .insert("ab".to_owned(), None)
+                .insert("\n".to_owned(), attrs([("header", 1i64.into())])),
+        );
+        let heading = attrs([("header", 2i64.into())]);
+
+        let alice = Delta::new()
+            .retain(1, None)
+            .insert("\n".to_owned(), attrs([("header", 1i64.into())]));
+        let bob = Delta::new().retain(2, None).retain(1, heading.clone());
+
+        let expected = Delta::new()
+            .insert("a".to_owned(), None)
+            .insert("\n".to_owned(), heading.clone())
+            .insert("b".to_owned(), None)
+            .insert("\n".to_owned(), heading);
+
+        for priority in [true, false] {
+            let alice_bob = transform(&scope, &before, &alice, bob.clone(), priority);
+            let bob_alice = transform(&scope, &before, &bob, alice.clone(), !priority);
+
+            let mut left = before.clone();
+            left.apply(alice.clone());
+            left.apply(alice_bob);
+
+            let mut right = before.clone();
+            right.apply(bob.clone());
+            right.apply(bob_alice);
+
+            assert_eq!(left.as_delta(), &expected);
+            assert_eq!(right.as_delta(), &expected);
+        }
+    }
+
+    #[test]
+    fn test_transform_without_merge() {
+        let scope = BlockKeys::quill();
+        let before = document(Delta::new().insert("a\nb\n".to_owned(), None));
+
+        let alice = Delta::new().insert("x".to_owned(), None);
+        let bob = Delta::new()
+            .retain(1, None)
+            .retain(1, attrs([("header", 1i64.into())]));
+
+        assert_eq!(
+            transform(&scope, &before, &alice, bob.clone(), true),
+            alice.clone().transform(bob, true)
+        );
+    }
+}
@@ -7,6 +7,14 @@ use std::str::Chars;
 pub trait Len {
     /// Should return the exact length of the receiver.
     fn len(&self) -> usize;
+
+    /// Returns true if the receiver has a length of zero. Types whose length
+    /// has to be counted (e.g. the characters of a `String`) should override
+    /// this, because it's called for every step of [`Compose`](super::Compose)
+    /// and [`Transform`](super::Transform).
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 /// Implemented by any sequence used as the value of a [`Delta`](super::Delta)
@@ -21,26 +29,101 @@ pub trait Seq:
 
     /// Should return an iterator over the items in this sequence.
     fn iter(&self) -> Self::Iterator<'_>;
+
+    /// Removes the first `len` items from this sequence (or all of them if it
+    /// has fewer) and returns them. The default implementation collects both
+    /// halves from [`Seq::iter`], so implementations should override this if
+    /// they can split in place.
+    fn split_prefix(&mut self, len: usize) -> Self
+    where
+        Self: Sized,
+    {
+        let prefix = self.iter().take(len).collect();
+        *self = self.iter().skip(len).collect();
+        prefix
+    }
 }
 
+/// Counts the characters in a string with SIMD instructions (if the `simd`
+/// feature is enabled and the CPU supports them).
 impl Len for String {
     fn len(&self) -> usize {
-        self.chars().count()
+        char_count(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        str::is_empty(self)
     }
 }
 
+/// Returns the number of characters in the given text.
+#[cfg(feature = "simd")]
+pub(crate) fn char_count(text: &str) -> usize {
+    bytecount::num_chars(text.as_bytes())
+}
+
+/// Returns the number of characters in the given text.
+#[cfg(not(feature = "simd"))]
+pub(crate) fn char_count(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Returns the (character) positions of the newlines in the given text.
+#[cfg(feature = "simd")]
+pub(crate) fn newlines(text: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut position = 0usize;
+    let mut offset = 0usize;
+
+    memchr::memchr_iter(b'\n', text.as_bytes()).map(move |index| {
+        position += char_count(&text[offset..index]);
+        offset = index;
+        position
+    })
+}
+
+/// Returns the (character) positions of the newlines in the given text.
+#[cfg(not(feature = "simd"))]
+pub(crate) fn newlines(text: &str) -> impl Iterator<Item = usize> + '_ {
+    text.chars()
+        .enumerate()
+        .filter(|&(_, char)| char == '\n')
+        .map(|(position, _)| position)
+}
+
 impl Seq for String {
     type Iterator<'a> = Chars<'a>;
 
     fn iter(&self) -> Self::Iterator<'_> {
         self.chars()
     }
+
+    /// Copies the prefix into a new string and removes it from this string in
+    /// place, so that the (usually much longer) rest isn't reallocated.
+    fn split_prefix(&mut self, len: usize) -> Self {
+        let prefix = split_at(self, len).0.to_owned();
+        self.replace_range(..prefix.len(), "");
+        prefix
+    }
+}
+
+/// Splits the given string after the given number of characters.
+pub(crate) fn split_at(text: &str, len: usize) -> (&str, &str) {
+    let index = text
+        .char_indices()
+        .nth(len)
+        .map_or(text.len(), |(index, _)| index);
+
+    text.split_at(index)
 }
 
 impl<T> Len for Vec<T> {
     fn len(&self) -> usize {
         Vec::len(self)
     }
+
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
 }
 
 impl<T> Seq for Vec<T>
@@ -52,4 +135,47 @@ where
     fn iter(&self) -> Self::Iterator<'_> {
         <[T]>::iter(self).cloned()
     }
+
+    /// Moves the prefix into a new vector without cloning its items.
+    fn split_prefix(&mut self, len: usize) -> Self {
+        self.drain(..len.min(self.len())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{char_count, newlines, Len, Seq};
+
+    #[test]
+    fn test_char_count() {
+        let text = "Hé\nllo 🌍\n\nwörld".repeat(100);
+
+        assert_eq!(char_count(&text), text.chars().count());
+        assert_eq!(Len::len(&text), 1500);
+    }
+
+    #[test]
+    fn test_newlines() {
+        let text = "\nHé\nllo 🌍\n\nwörld";
+
+        assert_eq!(newlines(text).collect::<Vec<_>>(), vec![0, 3, 9, 10]);
+        assert_eq!(newlines("").count(), 0);
+    }
+
+    #[test]
+    fn test_split_prefix() {
+        let mut text = "Hé🌍llo".to_owned();
+
+        assert_eq!(text.split_prefix(3), "Hé🌍");
+        assert_eq!(text, "llo");
+        assert_eq!(text.split_prefix(5), "llo");
+        assert_eq!(text, "");
+
+        let mut items = vec![1, 2, 3];
+
+        assert_eq!(items.split_prefix(2), [1, 2]);
+        assert_eq!(items, [3]);
+        assert_eq!(items.split_prefix(2), [3]);
+        assert!(items.is_empty());
+    }
 }
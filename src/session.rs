@@ -0,0 +1,588 @@
+//! Client state machine for keeping a local document in sync with a server.
+//!
+//! A [`Session`] applies local changes to its document optimistically and
+//! sends them to the server one at a time: the change that was sent is kept
+//! *in flight* until the server acknowledges it, while the changes that are
+//! made in the meantime are *pending* and composed into a single delta (see
+//! [`OfflineQueue`]). Changes that the server broadcasts from other clients
+//! were applied before the change in flight, so they're transformed against the
+//! change in flight and then against the pending changes before they're applied
+//! to the local document. In turn, the change in flight and the pending changes
+//! are transformed to apply after them.
+//!
+//! A [`Workspace`] manages the sessions of many documents that share a single
+//! connection, and exchanges [`ClientMessage`]s and [`ServerMessage`]s with the
+//! server.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::mem::{replace, take};
+
+use super::{Compose, Delta, Document, Error, OfflineQueue, Seq, Transform};
+
+/// Client state machine that keeps a local document in sync with a server.
+///
+/// Local changes are applied with [`edit`](Session::edit) and sent with
+/// [`take_pending`](Session::take_pending), which returns nothing while the
+/// previously sent change hasn't been [`ack`](Session::ack)ed yet. Changes of
+/// other clients are applied with [`receive`](Session::receive).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session<T, A> {
+    document: Document<T, A>,
+    inflight: Option<Delta<T, A>>,
+    pending: OfflineQueue<T, A>,
+}
+
+impl<T, A> Session<T, A> {
+    /// Returns a new session for a local copy of the given document.
+    pub fn new(document: Document<T, A>) -> Session<T, A> {
+        Session {
+            document,
+            inflight: None,
+            pending: OfflineQueue::new(),
+        }
+    }
+
+    /// Returns the local document, including the changes that haven't been
+    /// acknowledged yet.
+    pub fn document(&self) -> &Document<T, A> {
+        &self.document
+    }
+
+    /// Returns the change that was sent to the server but hasn't been
+    /// acknowledged yet (if any), e.g. to send it again after reconnecting.
+    pub fn inflight(&self) -> Option<&Delta<T, A>> {
+        self.inflight.as_ref()
+    }
+
+    /// Returns the composition of the local changes that haven't been sent
+    /// yet.
+    pub fn pending(&self) -> &Delta<T, A> {
+        self.pending.pending()
+    }
+
+    /// Returns the change in flight (if any) and marks it as acknowledged, so
+    /// that the pending changes can be sent. The server acknowledges a change
+    /// after it has applied it, and after broadcasting every change that it
+    /// applied before it.
+    pub fn ack(&mut self) -> Option<Delta<T, A>> {
+        self.inflight.take()
+    }
+}
+
+impl<T, A> Session<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given local change to the document and queues it to be
+    /// sent to the server, or returns an error if it doesn't apply to the
+    /// document (see [`Document::try_apply`]).
+    pub fn edit(&mut self, change: Delta<T, A>) -> Result<(), Error> {
+        self.document.try_apply(change.clone())?;
+        self.pending.push(change);
+        Ok(())
+    }
+
+    /// Transforms the given change of another client (which the server applied
+    /// before the change in flight) against the change in flight and the
+    /// pending changes, applies it to the local document and returns it as it
+    /// was applied, e.g. to update the editor. Returns an error (and leaves
+    /// this session unchanged) if the transformed change doesn't apply to the
+    /// document.
+    pub fn receive(&mut self, change: Delta<T, A>) -> Result<Delta<T, A>, Error> {
+        let (inflight, change) = match &self.inflight {
+            Some(inflight) => (
+                Some(change.clone().transform(inflight.clone(), true)),
+                inflight.clone().transform(change, false),
+            ),
+            None => (None, change),
+        };
+
+        let mut pending = self.pending.clone();
+        let change = pending.rebase(change);
+
+        self.document.try_apply(change.clone())?;
+        self.inflight = inflight;
+        self.pending = pending;
+
+        Ok(change)
+    }
+
+    /// Returns the composition of the pending changes and keeps it in flight
+    /// until it's acknowledged. Returns nothing if there are no pending changes
+    /// or if the previously sent change hasn't been acknowledged yet.
+    pub fn take_pending(&mut self) -> Option<Delta<T, A>> {
+        if self.inflight.is_some() {
+            return None;
+        }
+
+        self.inflight = self.pending.take();
+        self.inflight.clone()
+    }
+}
+
+/// Message that a [`Workspace`] sends to the server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientMessage<T, A> {
+    /// Asks the server to send the contents of the given document, followed by
+    /// the changes that other clients make to it.
+    Subscribe {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+
+    /// Asks the server to stop sending the changes to the given document.
+    Unsubscribe {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+
+    /// Contains the local changes to the given document, which the server
+    /// should acknowledge with [`ServerMessage::Ack`] once it has applied them
+    /// (see [`Session::take_pending`]).
+    Change {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the change.
+        change: Delta<T, A>,
+    },
+}
+
+/// Message that the server sends to a [`Workspace`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerMessage<T, A> {
+    /// Contains the contents of a document that the workspace subscribed to.
+    Snapshot {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the contents of the document, which may only contain
+        /// inserts.
+        contents: Delta<T, A>,
+    },
+
+    /// Contains a change that another client made to a document (see
+    /// [`Session::receive`]).
+    Change {
+        /// Contains the id of the document.
+        document_id: String,
+
+        /// Contains the change.
+        change: Delta<T, A>,
+    },
+
+    /// Acknowledges the last change that the workspace sent for a document
+    /// (see [`Session::ack`]).
+    Ack {
+        /// Contains the id of the document.
+        document_id: String,
+    },
+}
+
+/// Client that keeps the documents it opened in sync with a server over a
+/// single connection.
+///
+/// Documents are subscribed to lazily: the first call to
+/// [`open`](Workspace::open) queues a subscription, and the document's
+/// [`Session`] becomes available once the server responds with a snapshot of
+/// its contents. Messages from the server are routed to the sessions by
+/// document id with [`receive`](Workspace::receive), and the messages that
+/// should be sent to the server are taken with
+/// [`take_outgoing`](Workspace::take_outgoing).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workspace<T, A> {
+    documents: BTreeMap<String, DocumentState<T, A>>,
+    outgoing: Vec<ClientMessage<T, A>>,
+}
+
+/// State of a document in a workspace.
+#[derive(Clone, Debug, PartialEq)]
+enum DocumentState<T, A> {
+    /// The subscription is queued or sent, but the server hasn't sent a
+    /// snapshot yet.
+    Subscribing,
+
+    /// The server sent a snapshot, which the session was created from.
+    Open(Session<T, A>),
+
+    /// The document was closed, but its session still has local changes that
+    /// need to be sent or acknowledged before it's unsubscribed from.
+    Closing(Session<T, A>),
+}
+
+impl<T, A> Workspace<T, A> {
+    /// Returns a new workspace without any open documents.
+    pub fn new() -> Workspace<T, A> {
+        Workspace {
+            documents: BTreeMap::new(),
+            outgoing: vec![],
+        }
+    }
+
+    /// Returns the session of the given document, or `None` if the server
+    /// hasn't sent its contents yet. The first call for a document (or the
+    /// first call after it was closed and unsubscribed from) queues a
+    /// subscription to it.
+    pub fn open(&mut self, document_id: &str) -> Option<&mut Session<T, A>> {
+        let document = self
+            .documents
+            .entry(document_id.to_owned())
+            .or_insert_with(|| {
+                self.outgoing.push(ClientMessage::Subscribe {
+                    document_id: document_id.to_owned(),
+                });
+                DocumentState::Subscribing
+            });
+
+        if let DocumentState::Closing(_) = document {
+            if let DocumentState::Closing(session) = replace(document, DocumentState::Subscribing) {
+                *document = DocumentState::Open(session);
+            }
+        }
+
+        match document {
+            DocumentState::Open(session) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Returns the session of the given document if it's open.
+    pub fn session(&self, document_id: &str) -> Option<&Session<T, A>> {
+        match self.documents.get(document_id) {
+            Some(DocumentState::Open(session)) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Returns the session of the given document if it's open.
+    pub fn session_mut(&mut self, document_id: &str) -> Option<&mut Session<T, A>> {
+        match self.documents.get_mut(document_id) {
+            Some(DocumentState::Open(session)) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Closes the given document. If its session still has local changes, it's
+    /// only unsubscribed from once they've been sent and acknowledged. Does
+    /// nothing if the document isn't open.
+    pub fn close(&mut self, document_id: &str) {
+        match self.documents.remove(document_id) {
+            Some(DocumentState::Subscribing) => self.outgoing.push(ClientMessage::Unsubscribe {
+                document_id: document_id.to_owned(),
+            }),
+            Some(DocumentState::Open(session) | DocumentState::Closing(session)) => {
+                self.documents
+                    .insert(document_id.to_owned(), DocumentState::Closing(session));
+            }
+            None => {}
+        }
+    }
+}
+
+impl<T, A> Workspace<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Routes the given message to the session of its document and returns
+    /// the change to its contents as it was applied (i.e. the entire contents
+    /// for a snapshot), e.g. to update the editor. Returns `None` if the
+    /// message is an acknowledgement, is for a document that isn't open (e.g.
+    /// because it was closed while the message was underway) or is a snapshot
+    /// of a document that already has a session.
+    pub fn receive(&mut self, message: ServerMessage<T, A>) -> Result<Option<Delta<T, A>>, Error> {
+        match message {
+            ServerMessage::Snapshot {
+                document_id,
+                contents,
+            } => match self.documents.get_mut(&document_id) {
+                Some(document @ DocumentState::Subscribing) => {
+                    let session = Session::new(Document::from_delta(contents.clone())?);
+                    *document = DocumentState::Open(session);
+                    Ok(Some(contents))
+                }
+                _ => Ok(None),
+            },
+            ServerMessage::Change {
+                document_id,
+                change,
+            } => match self.documents.get_mut(&document_id) {
+                Some(DocumentState::Open(session)) => session.receive(change).map(Some),
+                Some(DocumentState::Closing(session)) => session.receive(change).map(|_| None),
+                _ => Ok(None),
+            },
+            ServerMessage::Ack { document_id } => {
+                if let Some(DocumentState::Open(session) | DocumentState::Closing(session)) =
+                    self.documents.get_mut(&document_id)
+                {
+                    session.ack();
+                }
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the messages that should be sent to the server and empties the
+    /// queue: subscriptions and unsubscriptions in the order in which the
+    /// documents were opened and closed, followed by the local changes to each
+    /// document that haven't been sent yet (see [`Session::take_pending`]) and
+    /// the unsubscriptions of closed documents whose changes have all been
+    /// acknowledged.
+    pub fn take_outgoing(&mut self) -> Vec<ClientMessage<T, A>> {
+        let mut outgoing = take(&mut self.outgoing);
+        let mut closed = vec![];
+
+        for (document_id, document) in &mut self.documents {
+            let (DocumentState::Open(session) | DocumentState::Closing(session)) = document else {
+                continue;
+            };
+
+            if let Some(change) = session.take_pending() {
+                outgoing.push(ClientMessage::Change {
+                    document_id: document_id.clone(),
+                    change,
+                });
+            }
+
+            if matches!(document, DocumentState::Closing(session) if session.inflight().is_none()) {
+                outgoing.push(ClientMessage::Unsubscribe {
+                    document_id: document_id.clone(),
+                });
+                closed.push(document_id.clone());
+            }
+        }
+
+        for document_id in closed {
+            self.documents.remove(&document_id);
+        }
+
+        outgoing
+    }
+}
+
+impl<T, A> Default for Workspace<T, A> {
+    fn default() -> Self {
+        Workspace::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClientMessage, ServerMessage, Session, Workspace};
+    use crate::{Delta, Document, Error, Transform};
+
+    fn session(text: &str) -> Session<String, ()> {
+        Session::new(Document::from_delta(Delta::new().insert(text.to_owned(), ())).unwrap())
+    }
+
+    fn contents(session: &Session<String, ()>) -> Delta<String, ()> {
+        session.document().as_delta().clone()
+    }
+
+    #[test]
+    fn test_receive_inflight() {
+        // The server applies Y's insert at the end of "ab" before X's insert at
+        // the start, so both replicas end up with "XabY".
+        let mut session = session("ab");
+        let x = Delta::new().insert("X".to_owned(), ());
+        let y = Delta::new().retain(2, None).insert("Y".to_owned(), ());
+
+        session.edit(x.clone()).unwrap();
+        assert_eq!(session.take_pending(), Some(x.clone()));
+        assert_eq!(session.take_pending(), None);
+
+        assert_eq!(
+            session.receive(y.clone()).unwrap(),
+            Delta::new().retain(3, None).insert("Y".to_owned(), ())
+        );
+        assert_eq!(session.inflight(), Some(&y.transform(x, true)));
+        assert_eq!(
+            contents(&session),
+            Delta::new().insert("XabY".to_owned(), ())
+        );
+        assert!(session.ack().is_some());
+        assert_eq!(session.take_pending(), None);
+    }
+
+    #[test]
+    fn test_receive_pending() {
+        let mut session = session("ab");
+
+        session
+            .edit(Delta::new().insert("X".to_owned(), ()))
+            .unwrap();
+        session.take_pending().unwrap();
+        session
+            .edit(Delta::new().retain(3, None).insert("Z".to_owned(), ()))
+            .unwrap();
+
+        assert_eq!(
+            session
+                .receive(Delta::new().retain(2, None).insert("Y".to_owned(), ()))
+                .unwrap(),
+            Delta::new().retain(3, None).insert("Y".to_owned(), ())
+        );
+        assert_eq!(
+            contents(&session),
+            Delta::new().insert("XabYZ".to_owned(), ())
+        );
+
+        // The pending change is only sent once the change in flight has been
+        // acknowledged, transformed to apply after the received change.
+        assert_eq!(session.take_pending(), None);
+        session.ack();
+        assert_eq!(
+            session.take_pending(),
+            Some(Delta::new().retain(4, None).insert("Z".to_owned(), ()))
+        );
+    }
+
+    #[test]
+    fn test_receive_invalid() {
+        let mut session = session("ab");
+
+        session
+            .edit(Delta::new().insert("X".to_owned(), ()))
+            .unwrap();
+        session.take_pending().unwrap();
+
+        let before = session.clone();
+        assert!(matches!(
+            session.receive(Delta::new().delete(3)),
+            Err(Error::LengthMismatch { .. })
+        ));
+        assert_eq!(session, before);
+    }
+
+    #[test]
+    fn test_workspace() {
+        let mut workspace = Workspace::new();
+        let contents = Delta::new().insert("Hello\n".to_owned(), ());
+        let change = |change: Delta<String, ()>| ServerMessage::Change {
+            document_id: "a".to_owned(),
+            change,
+        };
+
+        assert!(workspace.open("a").is_none());
+        assert!(workspace.open("a").is_none());
+        assert_eq!(
+            workspace.take_outgoing(),
+            vec![ClientMessage::Subscribe {
+                document_id: "a".to_owned()
+            }]
+        );
+
+        let oh = Delta::new().insert("Oh, ".to_owned(), ());
+        assert_eq!(workspace.receive(change(oh.clone())), Ok(None));
+        assert_eq!(
+            workspace.receive(ServerMessage::Snapshot {
+                document_id: "a".to_owned(),
+                contents: contents.clone(),
+            }),
+            Ok(Some(contents))
+        );
+
+        let exclaim = Delta::new().retain(5, None).insert("!".to_owned(), ());
+        workspace.open("a").unwrap().edit(exclaim.clone()).unwrap();
+        assert_eq!(workspace.receive(change(oh.clone())), Ok(Some(oh.clone())));
+
+        let sent = Delta::new().retain(9, None).insert("!".to_owned(), ());
+        assert_eq!(
+            workspace.take_outgoing(),
+            vec![ClientMessage::Change {
+                document_id: "a".to_owned(),
+                change: sent.clone(),
+            }]
+        );
+
+        // The document is only unsubscribed from once its last change has
+        // been acknowledged, and keeps receiving changes until then.
+        workspace.open("a").unwrap().edit(exclaim.clone()).unwrap();
+        workspace.close("a");
+        workspace.close("b");
+        assert!(workspace.session("a").is_none());
+        assert_eq!(workspace.receive(change(oh.clone())), Ok(None));
+        assert!(workspace.take_outgoing().is_empty());
+
+        workspace
+            .receive(ServerMessage::Ack {
+                document_id: "a".to_owned(),
+            })
+            .unwrap();
+        assert_eq!(
+            workspace.take_outgoing(),
+            vec![ClientMessage::Change {
+                document_id: "a".to_owned(),
+                change: Delta::new().retain(9, None).insert("!".to_owned(), ()),
+            }]
+        );
+
+        workspace
+            .receive(ServerMessage::Ack {
+                document_id: "a".to_owned(),
+            })
+            .unwrap();
+        assert_eq!(
+            workspace.take_outgoing(),
+            vec![ClientMessage::Unsubscribe {
+                document_id: "a".to_owned()
+            }]
+        );
+        assert!(workspace.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn test_workspace_reopen() {
+        let mut workspace = Workspace::<String, ()>::new();
+
+        workspace.open("a");
+        workspace.close("a");
+        workspace
+            .receive(ServerMessage::Snapshot {
+                document_id: "a".to_owned(),
+                contents: Delta::new().insert("a".to_owned(), ()),
+            })
+            .unwrap();
+        assert_eq!(
+            workspace.take_outgoing(),
+            vec![
+                ClientMessage::Subscribe {
+                    document_id: "a".to_owned()
+                },
+                ClientMessage::Unsubscribe {
+                    document_id: "a".to_owned()
+                },
+            ]
+        );
+
+        workspace.open("a");
+        workspace
+            .receive(ServerMessage::Snapshot {
+                document_id: "a".to_owned(),
+                contents: Delta::new().insert("a".to_owned(), ()),
+            })
+            .unwrap();
+        workspace
+            .open("a")
+            .unwrap()
+            .edit(Delta::new().delete(1))
+            .unwrap();
+        workspace.take_outgoing();
+        workspace.close("a");
+
+        // Reopening a document that's still closing keeps its session.
+        assert_eq!(
+            workspace.open("a").unwrap().inflight(),
+            Some(&Delta::new().delete(1))
+        );
+        workspace
+            .receive(ServerMessage::Ack {
+                document_id: "a".to_owned(),
+            })
+            .unwrap();
+        assert!(workspace.take_outgoing().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::mem::size_of;
+
+use super::{Delta, LastWriteWins, Op};
+
+/// Implemented by values and attributes whose memory usage is reported by
+/// [`Delta::heap_size`].
+pub trait HeapSize {
+    /// Returns the number of bytes that this value owns on the heap, excluding
+    /// the size of the value itself. The result may be an estimate for types
+    /// whose allocations aren't exposed (e.g. the nodes of a `BTreeMap`).
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_heap_size_inline {
+    ($($ty:ty),*) => {
+        $(
+            impl HeapSize for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_heap_size_inline!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    f32,
+    f64
+);
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T> HeapSize for Option<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T> HeapSize for Vec<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+/// Estimates the size of the nodes of this map as the size of its entries.
+impl<K, V> HeapSize for BTreeMap<K, V>
+where
+    K: HeapSize,
+    V: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(key, value)| {
+                size_of::<K>() + size_of::<V>() + key.heap_size() + value.heap_size()
+            })
+            .sum()
+    }
+}
+
+impl<T> HeapSize for LastWriteWins<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+/// Wire format whose size is measured by [`Delta::encoded_size`]. Each format
+/// is only available with the feature of the corresponding module enabled (and
+/// this enum only with at least one of them).
+#[cfg(any(feature = "json", feature = "ciborium", feature = "rmp-serde"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// Compact JSON, as produced by `serde_json::to_vec`.
+    #[cfg(feature = "json")]
+    Json,
+
+    /// CBOR, as produced by [`cbor::to_vec`](crate::cbor::to_vec).
+    #[cfg(feature = "ciborium")]
+    Cbor,
+
+    /// MessagePack, as produced by [`msgpack::to_vec`](crate::msgpack::to_vec).
+    #[cfg(feature = "rmp-serde")]
+    MessagePack,
+}
+
+impl<T, A> Delta<T, A> {
+    /// Returns the number of bytes that this delta occupies on the heap: the
+    /// capacity of its ops and everything that their values and attributes own
+    /// (see [`HeapSize`]).
+    pub fn heap_size(&self) -> usize
+    where
+        T: HeapSize,
+        A: HeapSize,
+    {
+        self.ops_capacity() * size_of::<Op<T, A>>()
+            + self
+                .ops()
+                .map(|op| match op {
+                    Op::Insert(insert) => {
+                        insert.insert().heap_size() + insert.attributes.heap_size()
+                    }
+                    Op::Retain(retain) => retain.attributes.heap_size(),
+                    Op::Delete(_) => 0,
+                })
+                .sum::<usize>()
+    }
+
+    /// Returns the number of bytes that this delta occupies when it's encoded
+    /// in the given format, or `None` if it can't be encoded in that format
+    /// (e.g. because its attributes contain a map with non-string keys).
+    ///
+    /// The delta is serialized into a writer that only counts bytes, so this
+    /// doesn't allocate a buffer for the encoded delta.
+    #[cfg(any(feature = "json", feature = "ciborium", feature = "rmp-serde"))]
+    pub fn encoded_size(&self, encoding: Encoding) -> Option<usize>
+    where
+        T: serde::Serialize,
+        A: serde::Serialize,
+    {
+        let mut counter = ByteCounter(0);
+
+        let encoded = match encoding {
+            #[cfg(feature = "json")]
+            Encoding::Json => serde_json::to_writer(&mut counter, self).is_ok(),
+            #[cfg(feature = "ciborium")]
+            Encoding::Cbor => ciborium::into_writer(self, &mut counter).is_ok(),
+            #[cfg(feature = "rmp-serde")]
+            Encoding::MessagePack => rmp_serde::encode::write_named(&mut counter, self).is_ok(),
+        };
+
+        encoded.then_some(counter.0)
+    }
+}
+
+/// Writer that discards its input and counts the number of bytes written.
+#[cfg(any(feature = "json", feature = "ciborium", feature = "rmp-serde"))]
+struct ByteCounter(usize);
+
+#[cfg(any(feature = "json", feature = "ciborium", feature = "rmp-serde"))]
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use serde_json::json;
+
+    use super::HeapSize;
+    use crate::attributes::Attributes;
+    use crate::{Delta, Op};
+
+    fn delta() -> Delta<String, Attributes> {
+        serde_json::from_value(json!({
+            "ops": [
+                { "retain": 2, "attributes": { "bold": true } },
+                { "insert": "Hello World", "attributes": { "color": "#ff0000" } },
+                { "delete": 3 },
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let delta = Delta::<String, ()>::new()
+            .insert("Hello".to_owned(), None)
+            .delete(1);
+        let ops = delta.ops_capacity() * size_of::<Op<String, ()>>();
+
+        assert_eq!(delta.heap_size(), ops + 5);
+
+        let delta = self::delta();
+        let ops = delta.ops_capacity() * size_of::<Op<String, Attributes>>();
+
+        assert!(delta.heap_size() > ops + "Hello World".to_owned().heap_size());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_encoded_size_json() {
+        let delta = delta();
+
+        assert_eq!(
+            delta.encoded_size(super::Encoding::Json),
+            Some(serde_json::to_vec(&delta).unwrap().len())
+        );
+    }
+
+    #[cfg(feature = "ciborium")]
+    #[test]
+    fn test_encoded_size_cbor() {
+        let delta = delta();
+
+        assert_eq!(
+            delta.encoded_size(super::Encoding::Cbor),
+            Some(crate::cbor::to_vec(&delta).unwrap().len())
+        );
+    }
+
+    #[cfg(feature = "rmp-serde")]
+    #[test]
+    fn test_encoded_size_msgpack() {
+        let delta = delta();
+
+        assert_eq!(
+            delta.encoded_size(super::Encoding::MessagePack),
+            Some(crate::msgpack::to_vec(&delta).unwrap().len())
+        );
+    }
+}
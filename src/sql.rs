@@ -0,0 +1,119 @@
+//! Support for storing deltas and documents in a database with
+//! [sqlx](https://docs.rs/sqlx).
+//!
+//! This module is only available with the `sqlx` feature enabled. Deltas and
+//! documents are stored as their JSON representation, in the same way as
+//! `sqlx::types::Json`: as `JSONB` in Postgres (which also decodes from `JSON`)
+//! and as `TEXT` in SQLite. They support every database that `Json` supports,
+//! as long as the corresponding feature of sqlx is enabled.
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::types::Json;
+use sqlx::{Database, Decode, Encode, Type};
+
+use super::{Delta, Document};
+
+macro_rules! impl_sqlx {
+    ($ty:ident) => {
+        impl<T, A, DB> Type<DB> for $ty<T, A>
+        where
+            DB: Database,
+            Json<Self>: Type<DB>,
+        {
+            fn type_info() -> DB::TypeInfo {
+                <Json<Self> as Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <Json<Self> as Type<DB>>::compatible(ty)
+            }
+        }
+
+        impl<'q, T, A, DB> Encode<'q, DB> for $ty<T, A>
+        where
+            DB: Database,
+            for<'a> Json<&'a Self>: Encode<'q, DB>,
+        {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+            ) -> Result<IsNull, BoxDynError> {
+                Json(self).encode_by_ref(buf)
+            }
+        }
+
+        impl<'r, T, A, DB> Decode<'r, DB> for $ty<T, A>
+        where
+            DB: Database,
+            Json<Self>: Decode<'r, DB>,
+        {
+            fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                Json::<Self>::decode(value).map(|json| json.0)
+            }
+        }
+    };
+}
+
+impl_sqlx!(Delta);
+impl_sqlx!(Document);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::{Connection, Row, SqliteConnection};
+
+    use crate::attributes::Attributes;
+    use crate::{Delta, Document};
+
+    fn delta() -> Delta<String, Attributes> {
+        serde_json::from_value(json!([
+            { "retain": 5, "attributes": { "bold": true } },
+            { "insert": "Hello" },
+            { "delete": 3 },
+        ]))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+
+        sqlx::query("CREATE TABLE ops (version INTEGER, delta TEXT, snapshot TEXT)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let document = Document::try_from(Delta::new().insert("Hello".to_owned(), None)).unwrap();
+
+        sqlx::query("INSERT INTO ops VALUES (1, ?, ?)")
+            .bind(delta())
+            .bind(&document)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let row = sqlx::query("SELECT delta, snapshot FROM ops WHERE version = 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(row.get::<Delta<String, Attributes>, _>("delta"), delta());
+        assert_eq!(row.get::<Document<String, ()>, _>("snapshot"), document);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(row.get("delta")).unwrap(),
+            serde_json::to_value(delta()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_invalid() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        let row = sqlx::query("SELECT '[{\"retain\": 1}]' AS snapshot")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+
+        assert!(row.try_get::<Document<String, ()>, _>("snapshot").is_err());
+    }
+}
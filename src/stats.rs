@@ -0,0 +1,80 @@
+use super::ops::Retain;
+use super::{Delta, Len, Op};
+
+/// Summary of the number of elements that a [`Delta`] inserts, deletes,
+/// retains and formats, as returned by [`Delta::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Contains the number of elements inserted by this delta.
+    pub inserted: usize,
+
+    /// Contains the number of elements deleted by this delta.
+    pub deleted: usize,
+
+    /// Contains the number of elements retained by this delta, regardless of
+    /// whether their attributes are updated.
+    pub retained: usize,
+
+    /// Contains the number of retained elements whose attributes are updated
+    /// by this delta (i.e. retains with `Some(_)` attributes).
+    pub formatted: usize,
+
+    /// Contains the number of operations in this delta.
+    pub ops: usize,
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Len,
+{
+    /// Returns a summary of the number of elements that this delta inserts,
+    /// deletes, retains and formats. Counts saturate at `usize::MAX`.
+    pub fn stats(&self) -> Stats {
+        self.ops().fold(Stats::default(), |mut stats, op| {
+            match op {
+                Op::Insert(insert) => stats.inserted = stats.inserted.saturating_add(insert.len()),
+                Op::Retain(Retain { retain, attributes }) => {
+                    stats.retained = stats.retained.saturating_add(*retain);
+
+                    if attributes.is_some() {
+                        stats.formatted = stats.formatted.saturating_add(*retain);
+                    }
+                }
+                Op::Delete(delete) => stats.deleted = stats.deleted.saturating_add(delete.len()),
+            }
+
+            stats.ops += 1;
+            stats
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Delta, Stats};
+
+    #[test]
+    fn test_stats() {
+        let delta = Delta::new()
+            .retain(2, None)
+            .insert("abc".to_owned(), None)
+            .retain(4, true)
+            .delete(5);
+
+        assert_eq!(
+            delta.stats(),
+            Stats {
+                inserted: 3,
+                deleted: 5,
+                retained: 6,
+                formatted: 4,
+                ops: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        assert_eq!(Delta::<String, ()>::new().stats(), Stats::default());
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Debug;
+
+use super::{Compose, Delta, Op, Seq, Transform};
+
+/// Overrides the composition or transformation of specific pairs of ops, e.g.
+/// to change how concurrent deletes of the same region or conflicting
+/// attributes are resolved, without reimplementing [`Compose`] and
+/// [`Transform`] for [`Delta`]. Pass a strategy to
+/// [`Delta::compose_using`] or [`Delta::transform_using`].
+///
+/// Both functions are called for every pair of ops and return `None` to fall
+/// back to the built-in behavior for that pair. An override has the same
+/// contract as an [`Extension`](crate::ext::Extension): it may consume either
+/// op partially (by [splitting](crate::Split) it), but must consume at least
+/// one element of either op, and returns the op that results from the part
+/// that was consumed.
+pub trait Strategy<T, A> {
+    /// Composes the given op with the op that is applied after it, or returns
+    /// `None` to use [`Compose`].
+    fn compose(&self, lhs: &mut Op<T, A>, rhs: &mut Op<T, A>) -> Option<Op<T, A>> {
+        let _ = (lhs, rhs);
+        None
+    }
+
+    /// Transforms the given `rhs` op with the `lhs` op that was applied
+    /// concurrently, or returns `None` to use [`Transform`].
+    fn transform(
+        &self,
+        lhs: &mut Op<T, A>,
+        rhs: &mut Op<T, A>,
+        priority: bool,
+    ) -> Option<Op<T, A>> {
+        let _ = (lhs, rhs, priority);
+        None
+    }
+}
+
+/// Default strategy that doesn't override any pair of ops.
+impl<T, A> Strategy<T, A> for () {}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Composes the given delta with this delta like [`Compose`], but lets the
+    /// given strategy override the composition of each pair of ops.
+    pub fn compose_using<S>(self, rhs: Delta<T, A>, strategy: &S) -> Delta<T, A>
+    where
+        S: Strategy<T, A> + ?Sized,
+    {
+        let mut self_iter = self.into_iter();
+        let mut other_iter = rhs.into_iter();
+
+        let mut result = Delta::new();
+
+        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| {
+            strategy.compose(a, b).unwrap_or_else(|| a.compose(b))
+        }));
+        result.extend(self_iter.chain(other_iter));
+
+        result.chop()
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Default + Clone + PartialEq + Compose<A, Output = A>,
+{
+    /// Transforms the given delta with this delta like [`Transform`], but lets
+    /// the given strategy override the transformation of each pair of ops.
+    pub fn transform_using<S>(self, rhs: Delta<T, A>, priority: bool, strategy: &S) -> Delta<T, A>
+    where
+        S: Strategy<T, A> + ?Sized,
+    {
+        let mut self_iter = self.into_iter();
+        let mut other_iter = rhs.into_iter();
+
+        let mut result = Delta::new();
+
+        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| {
+            strategy
+                .transform(a, b, priority)
+                .unwrap_or_else(|| a.transform(b, priority))
+        }));
+        result.extend(other_iter);
+
+        result.chop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::min;
+
+    use super::Strategy;
+    use crate::{Compose, Delta, Len, Op, Split, Transform};
+
+    /// Keeps inserted text that a later delta deletes (e.g. to review deleted
+    /// suggestions before they're dropped).
+    struct KeepInserts;
+
+    impl Strategy<String, ()> for KeepInserts {
+        fn compose(
+            &self,
+            lhs: &mut Op<String, ()>,
+            rhs: &mut Op<String, ()>,
+        ) -> Option<Op<String, ()>> {
+            match (&*lhs, &*rhs) {
+                (Op::Insert(_), Op::Delete(_)) => {
+                    let len = min(lhs.len(), rhs.len());
+                    rhs.split(len);
+                    Some(lhs.split(len))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Always orders concurrent inserts at the same position as if the
+    /// transformed delta came first.
+    struct RhsFirst;
+
+    impl Strategy<String, ()> for RhsFirst {
+        fn transform(
+            &self,
+            lhs: &mut Op<String, ()>,
+            rhs: &mut Op<String, ()>,
+            _priority: bool,
+        ) -> Option<Op<String, ()>> {
+            match (&*lhs, &*rhs) {
+                (Op::Insert(_), Op::Insert(_)) => Some(lhs.transform(rhs, false)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_compose_using() {
+        let a = Delta::new().insert("abc".to_owned(), None);
+        let b = Delta::new()
+            .retain(1, None)
+            .delete(1)
+            .insert("!".to_owned(), None);
+
+        assert_eq!(
+            a.clone().compose_using(b.clone(), &()),
+            a.clone().compose(b.clone())
+        );
+        assert_eq!(
+            a.compose_using(b, &KeepInserts),
+            Delta::new().insert("a!bc".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_transform_using() {
+        let a = Delta::<String, ()>::new().insert("a".to_owned(), None);
+        let b = Delta::new().insert("b".to_owned(), None);
+
+        assert_eq!(
+            a.clone().transform_using(b.clone(), true, &()),
+            a.clone().transform(b.clone(), true)
+        );
+        assert_eq!(
+            a.transform_using(b, true, &RhsFirst),
+            Delta::new().insert("b".to_owned(), None)
+        );
+    }
+}
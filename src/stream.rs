@@ -0,0 +1,214 @@
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::mem::take;
+
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use super::anchor::Shift;
+use super::{Compose, Delta, Document, Iter, Len, Op, Seq};
+
+impl<T, A> Document<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the delta that the given deserializer produces to this document
+    /// like [`Document::apply`], but applies each operation as soon as it's
+    /// deserialized. Unlike deserializing the delta first, this never holds
+    /// all of its operations in memory, which matters when importing large
+    /// deltas (e.g. with `serde_json::Deserializer::from_reader`).
+    ///
+    /// The delta may use either representation that [`Delta`] deserializes
+    /// from (an object with an `ops` field or a bare sequence of operations).
+    /// This document is consumed, because it's only partially applied if
+    /// deserialization fails.
+    pub fn apply_from<'de, D>(mut self, deserializer: D) -> Result<Document<T, A>, D::Error>
+    where
+        T: Deserialize<'de>,
+        A: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let mut anchors = take(self.anchors_mut());
+        let mut apply = Apply {
+            base: self.into_delta().into_iter(),
+            result: Delta::new(),
+            shift: anchors.shift(),
+        };
+
+        (&mut apply).deserialize(deserializer)?;
+        apply.result.extend(apply.base);
+        anchors.finish(apply.shift);
+
+        let mut document = Document::from_delta(apply.result).map_err(de::Error::custom)?;
+        *document.anchors_mut() = anchors;
+        Ok(document)
+    }
+}
+
+/// State of a document while a delta is applied to it one operation at a time.
+struct Apply<T, A> {
+    base: Iter<T, A>,
+    result: Delta<T, A>,
+    shift: Shift,
+}
+
+impl<T, A> Apply<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Composes the given operation with the remainder of the base document,
+    /// like a single step of [`Compose`] followed by [`Document::apply`]'s
+    /// removal of retains and deletes.
+    fn step(&mut self, mut op: Op<T, A>) {
+        if op.is_empty() {
+            return;
+        }
+
+        self.shift.step(&op);
+
+        while let Some(base) = self.base.next_mut() {
+            let composed = base.compose(&mut op);
+            self.push(composed);
+
+            if op.is_empty() {
+                return;
+            }
+        }
+
+        self.push(op);
+    }
+
+    fn push(&mut self, op: Op<T, A>) {
+        if matches!(op, Op::Insert(_)) {
+            self.result.push(op);
+        }
+    }
+}
+
+impl<'de, T, A> DeserializeSeed<'de> for &mut Apply<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + Deserialize<'de>,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A> + Deserialize<'de>,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ApplyVisitor(self, PhantomData))
+    }
+}
+
+struct ApplyVisitor<'a, 'de, T, A>(&'a mut Apply<T, A>, PhantomData<&'de ()>);
+
+impl<'de, T, A> Visitor<'de> for ApplyVisitor<'_, 'de, T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + Deserialize<'de>,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A> + Deserialize<'de>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of operations or an object with an `ops` field")
+    }
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        while let Some(op) = seq.next_element::<Op<T, A>>()? {
+            self.0.step(op);
+        }
+
+        Ok(())
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut ops = false;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "ops" if !ops => {
+                    map.next_value_seed(&mut *self.0)?;
+                    ops = true;
+                }
+                "ops" => return Err(de::Error::duplicate_field("ops")),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        match ops {
+            true => Ok(()),
+            false => Err(de::Error::missing_field("ops")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::attributes::Attributes;
+    use crate::{Delta, Document};
+
+    fn document() -> Document<String, Attributes> {
+        Document::from_delta(
+            Delta::new()
+                .insert("Hello".to_owned(), None)
+                .insert(" World\n".to_owned(), attributes(json!({ "bold": true }))),
+        )
+        .unwrap()
+    }
+
+    fn attributes(value: serde_json::Value) -> Attributes {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_apply_from() {
+        let change = json!([
+            { "retain": 3, "attributes": { "italic": true } },
+            { "delete": 4 },
+            { "insert": "y", "attributes": { "bold": null } },
+            { "retain": 0 },
+            { "retain": 2, "attributes": { "bold": null } },
+            { "insert": "!" },
+        ]);
+
+        let mut expected = document();
+        expected.apply(serde_json::from_value(change.clone()).unwrap());
+
+        let bytes = serde_json::to_vec(&change).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_reader(bytes.as_slice());
+
+        assert_eq!(document().apply_from(&mut deserializer).unwrap(), expected);
+
+        let change = json!({ "ops": change, "version": 3 });
+
+        assert_eq!(document().apply_from(change).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_apply_from_past_end() {
+        let change = json!([{ "retain": 20 }, { "delete": 3 }, { "insert": "!" }]);
+
+        let mut expected = document();
+        expected.apply(serde_json::from_value(change.clone()).unwrap());
+
+        assert_eq!(document().apply_from(change).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_apply_from_invalid() {
+        assert!(document().apply_from(json!({ "version": 3 })).is_err());
+        assert!(document().apply_from(json!([{ "retain": -1 }])).is_err());
+    }
+}
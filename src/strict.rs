@@ -0,0 +1,200 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use super::{Delta, Len, Op, Seq};
+
+/// Maximum length of a single operation accepted by
+/// [`Delta::deserialize_strict`]. This corresponds to the largest integer that
+/// JavaScript can represent exactly (i.e. `Number.MAX_SAFE_INTEGER`), so any
+/// longer operation can't have been produced by a Quill client.
+pub const MAX_STRICT_LEN: usize = (1 << 53) - 1;
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"))]
+struct StrictDelta<T, A> {
+    ops: StrictOps<T, A>,
+}
+
+struct StrictOps<T, A>(Vec<Op<T, A>>);
+
+struct StrictOpsVisitor<T, A>(PhantomData<(T, A)>);
+
+impl<'de, T, A> Visitor<'de> for StrictOpsVisitor<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    type Value = StrictOps<T, A>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of insert, retain and delete operations")
+    }
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        let mut ops = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(4096));
+        let mut base_len = 0usize;
+        let mut target_len = 0usize;
+
+        loop {
+            let index = ops.len();
+
+            let op = match seq.next_element::<Op<T, A>>() {
+                Ok(Some(op)) => op,
+                Ok(None) => break,
+                Err(error) => return Err(de::Error::custom(format!("op {index}: {error}"))),
+            };
+
+            match op.len() {
+                0 => {
+                    return Err(de::Error::custom(format!(
+                        "op {index}: operation has a length of zero"
+                    )))
+                }
+                len if len > MAX_STRICT_LEN => {
+                    return Err(de::Error::custom(format!(
+                        "op {index}: operation has a length of {len}, which exceeds the maximum of {MAX_STRICT_LEN}"
+                    )))
+                }
+                len => {
+                    if !matches!(op, Op::Insert(_)) {
+                        base_len = base_len.saturating_add(len);
+                    }
+
+                    if !matches!(op, Op::Delete(_)) {
+                        target_len = target_len.saturating_add(len);
+                    }
+
+                    if base_len.max(target_len) > MAX_STRICT_LEN {
+                        return Err(de::Error::custom(format!(
+                            "op {index}: delta has a total length of more than {MAX_STRICT_LEN}"
+                        )));
+                    }
+
+                    ops.push(op)
+                }
+            }
+        }
+
+        Ok(StrictOps(ops))
+    }
+}
+
+impl<'de, T, A> Deserialize<'de> for StrictOps<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(StrictOpsVisitor(PhantomData))
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Clone + PartialEq,
+{
+    /// Deserializes a delta from an untrusted source. Unlike the regular
+    /// [`Deserialize`] implementation, this function rejects operations with a
+    /// length of zero (including empty inserts), operations that are longer
+    /// than [`MAX_STRICT_LEN`] and malformed operations, and reports the index
+    /// of the offending operation in its error. It also rejects deltas whose
+    /// retains and deletes (or inserts and retains) add up to more than
+    /// [`MAX_STRICT_LEN`], so merging their operations never overflows. The
+    /// resulting delta is in
+    /// canonical form (see [`Delta::normalize`]).
+    ///
+    /// This function can be used with `#[serde(deserialize_with = "...")]`.
+    pub fn deserialize_strict<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+        A: Deserialize<'de>,
+    {
+        let StrictDelta {
+            ops: StrictOps(ops),
+        } = StrictDelta::deserialize(deserializer)?;
+
+        let mut delta = Delta::new();
+
+        for op in ops {
+            delta.push(op);
+        }
+
+        Ok(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Delta;
+
+    fn strict(value: serde_json::Value) -> Result<Delta<String, bool>, String> {
+        Delta::deserialize_strict(value).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_strict_valid() {
+        assert_eq!(
+            strict(json!({
+                "ops": [
+                    { "retain": 2 },
+                    { "insert": "a", "attributes": true },
+                    { "insert": "b", "attributes": true },
+                    { "delete": 1 },
+                ]
+            })),
+            Ok(Delta::new()
+                .retain(2, None)
+                .insert("ab".to_owned(), true)
+                .delete(1))
+        );
+    }
+
+    #[test]
+    fn test_strict_zero_length() {
+        assert_eq!(
+            strict(json!({ "ops": [{ "retain": 2 }, { "insert": "" }] })),
+            Err("op 1: operation has a length of zero".to_owned())
+        );
+        assert_eq!(
+            strict(json!({ "ops": [{ "delete": 0 }] })),
+            Err("op 0: operation has a length of zero".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_strict_absurd_length() {
+        assert!(strict(json!({ "ops": [{ "retain": 1u64 << 60 }] }))
+            .unwrap_err()
+            .starts_with("op 0: operation has a length of"));
+        assert_eq!(
+            strict(json!({ "ops": [{ "retain": 1u64 << 52 }, { "delete": 1u64 << 52 }] })),
+            Err(format!(
+                "op 1: delta has a total length of more than {}",
+                (1u64 << 53) - 1
+            ))
+        );
+    }
+
+    #[test]
+    fn test_strict_malformed() {
+        assert!(
+            strict(json!({ "ops": [{ "delete": 1 }, { "attributes": true }] }))
+                .unwrap_err()
+                .starts_with("op 1: ")
+        );
+        assert!(strict(json!({})).is_err());
+    }
+}
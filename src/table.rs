@@ -0,0 +1,504 @@
+//! Operational transformation of tables.
+//!
+//! A [`Table`] consists of rows and columns of cells, each of which is a
+//! [`Document`] of its own. A [`TableDelta`] is a sequence of [`TableOp`]s that
+//! insert or delete (empty) rows and columns or edit the contents of a cell.
+//! Like [`Delta`], table deltas implement [`Compose`] and [`Transform`], so
+//! that inserting and deleting rows and columns converges with concurrent
+//! edits of cells.
+
+use std::fmt::Debug;
+use std::mem::take;
+
+use serde::{Deserialize, Serialize};
+
+use super::tree::{insert_after_insert, insert_after_remove};
+use super::{Compose, Delta, Document, Error, Len, Seq, Transform};
+
+/// Table with rows and columns of cells.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Clone + Serialize, A: Clone + Serialize",
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub struct Table<T, A> {
+    columns: usize,
+    rows: Vec<Vec<Document<T, A>>>,
+}
+
+impl<T, A> Table<T, A> {
+    /// Returns a new table with the given number of rows and columns of empty
+    /// cells.
+    pub fn new(rows: usize, columns: usize) -> Table<T, A> {
+        Table {
+            columns,
+            rows: (0..rows).map(|_| empty_row(columns)).collect(),
+        }
+    }
+
+    /// Returns the number of rows in this table.
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the number of columns in this table.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the cell at the given row and column, if any.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&Document<T, A>> {
+        self.rows.get(row)?.get(column)
+    }
+}
+
+impl<T, A> Default for Table<T, A> {
+    fn default() -> Self {
+        Table::new(0, 0)
+    }
+}
+
+fn empty_row<T, A>(columns: usize) -> Vec<Document<T, A>> {
+    (0..columns).map(|_| Document::new()).collect()
+}
+
+impl<T, A> Table<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change to this table, or returns an error if one of
+    /// its operations refers to a row, column or cell that doesn't exist. In
+    /// that case, this table is left unchanged.
+    pub fn apply(&mut self, change: &TableDelta<T, A>) -> Result<(), Error> {
+        let mut table = self.clone();
+
+        for (index, op) in change.ops().iter().enumerate() {
+            table.apply_op(op).ok_or(Error::OutOfBounds { index })?;
+        }
+
+        *self = table;
+        Ok(())
+    }
+
+    fn apply_op(&mut self, op: &TableOp<T, A>) -> Option<()> {
+        match op {
+            TableOp::InsertRow { row } => {
+                if *row > self.rows.len() {
+                    return None;
+                }
+
+                self.rows.insert(*row, empty_row(self.columns));
+            }
+            TableOp::DeleteRow { row } => {
+                if *row >= self.rows.len() {
+                    return None;
+                }
+
+                self.rows.remove(*row);
+            }
+            TableOp::InsertColumn { column } => {
+                if *column > self.columns {
+                    return None;
+                }
+
+                self.columns += 1;
+
+                for cells in &mut self.rows {
+                    cells.insert(*column, Document::new());
+                }
+            }
+            TableOp::DeleteColumn { column } => {
+                if *column >= self.columns {
+                    return None;
+                }
+
+                self.columns -= 1;
+
+                for cells in &mut self.rows {
+                    cells.remove(*column);
+                }
+            }
+            TableOp::Edit { row, column, delta } => {
+                self.rows
+                    .get_mut(*row)?
+                    .get_mut(*column)?
+                    .apply(delta.clone());
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// Individual operation on a table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "op",
+    rename_all = "kebab-case",
+    bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>")
+)]
+pub enum TableOp<T, A> {
+    /// Inserts a row of empty cells at the given index.
+    InsertRow {
+        /// Contains the index that the inserted row will have.
+        row: usize,
+    },
+
+    /// Deletes the row at the given index.
+    DeleteRow {
+        /// Contains the index of the row to delete.
+        row: usize,
+    },
+
+    /// Inserts a column of empty cells at the given index.
+    InsertColumn {
+        /// Contains the index that the inserted column will have.
+        column: usize,
+    },
+
+    /// Deletes the column at the given index.
+    DeleteColumn {
+        /// Contains the index of the column to delete.
+        column: usize,
+    },
+
+    /// Applies the given delta to the contents of the cell at the given row
+    /// and column.
+    Edit {
+        /// Contains the row of the cell to edit.
+        row: usize,
+
+        /// Contains the column of the cell to edit.
+        column: usize,
+
+        /// Contains the change to apply to the contents of the cell.
+        delta: Delta<T, A>,
+    },
+}
+
+/// Sequence of operations on a table that are applied in order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"))]
+pub struct TableDelta<T, A> {
+    ops: Vec<TableOp<T, A>>,
+}
+
+impl<T, A> TableDelta<T, A> {
+    /// Returns a new empty table delta.
+    pub fn new() -> TableDelta<T, A> {
+        TableDelta { ops: vec![] }
+    }
+
+    /// Inserts a row of empty cells at the given index.
+    pub fn insert_row(mut self, row: usize) -> Self {
+        self.ops.push(TableOp::InsertRow { row });
+        self
+    }
+
+    /// Deletes the row at the given index.
+    pub fn delete_row(mut self, row: usize) -> Self {
+        self.ops.push(TableOp::DeleteRow { row });
+        self
+    }
+
+    /// Inserts a column of empty cells at the given index.
+    pub fn insert_column(mut self, column: usize) -> Self {
+        self.ops.push(TableOp::InsertColumn { column });
+        self
+    }
+
+    /// Deletes the column at the given index.
+    pub fn delete_column(mut self, column: usize) -> Self {
+        self.ops.push(TableOp::DeleteColumn { column });
+        self
+    }
+
+    /// Applies the given delta to the contents of the cell at the given row and
+    /// column.
+    pub fn edit(mut self, row: usize, column: usize, delta: Delta<T, A>) -> Self {
+        self.ops.push(TableOp::Edit { row, column, delta });
+        self
+    }
+
+    /// Returns the operations of this table delta.
+    pub fn ops(&self) -> &[TableOp<T, A>] {
+        &self.ops
+    }
+}
+
+impl<T, A> Default for TableDelta<T, A> {
+    fn default() -> Self {
+        TableDelta::new()
+    }
+}
+
+/// Appends the operations of the given table delta to this table delta, where
+/// consecutive edits of the same cell are composed into a single edit.
+impl<T, A> Compose<TableDelta<T, A>> for TableDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = TableDelta<T, A>;
+
+    fn compose(mut self, rhs: TableDelta<T, A>) -> Self::Output {
+        for op in rhs.ops {
+            match (self.ops.last_mut(), op) {
+                (
+                    Some(TableOp::Edit { row, column, delta }),
+                    TableOp::Edit {
+                        row: rhs_row,
+                        column: rhs_column,
+                        delta: rhs_delta,
+                    },
+                ) if (*row, *column) == (rhs_row, rhs_column) => {
+                    *delta = take(delta).compose(rhs_delta);
+                }
+                (_, op) => self.ops.push(op),
+            }
+        }
+
+        self
+    }
+}
+
+/// Transforms each operation of the given table delta with the operations of
+/// this table delta (that are themselves transformed with the preceding
+/// operations of the given table delta). Edits of cells in rows or columns
+/// that this table delta deletes are dropped.
+impl<T, A> Transform<TableDelta<T, A>> for TableDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = TableDelta<T, A>;
+
+    fn transform(self, rhs: TableDelta<T, A>, priority: bool) -> Self::Output {
+        let mut lhs = self.ops;
+        let mut result = TableDelta::new();
+
+        for op in rhs.ops {
+            let mut op = Some(op);
+            let mut next = Vec::with_capacity(lhs.len());
+
+            for lhs_op in lhs {
+                match op.take() {
+                    Some(rhs_op) => {
+                        next.extend(transform_op(&rhs_op, lhs_op.clone(), !priority));
+                        op = transform_op(&lhs_op, rhs_op, priority);
+                    }
+                    None => next.push(lhs_op),
+                }
+            }
+
+            lhs = next;
+            result.ops.extend(op);
+        }
+
+        result
+    }
+}
+
+/// Dimension of a table that an operation inserts into or deletes from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Row,
+    Column,
+}
+
+/// Returns the axis and index of the row or column that the given operation
+/// inserts (`true`) or deletes (`false`), if any.
+fn structural<T, A>(op: &TableOp<T, A>) -> Option<(Axis, usize, bool)> {
+    match op {
+        TableOp::InsertRow { row } => Some((Axis::Row, *row, true)),
+        TableOp::DeleteRow { row } => Some((Axis::Row, *row, false)),
+        TableOp::InsertColumn { column } => Some((Axis::Column, *column, true)),
+        TableOp::DeleteColumn { column } => Some((Axis::Column, *column, false)),
+        TableOp::Edit { .. } => None,
+    }
+}
+
+/// Returns the index of an existing row or column after applying the given
+/// operation, or `None` if the given operation deletes it.
+fn transform_index<T, A>(op: &TableOp<T, A>, axis: Axis, index: usize) -> Option<usize> {
+    match structural(op) {
+        Some((other_axis, other, true)) if other_axis == axis => {
+            Some(index + (other <= index) as usize)
+        }
+        Some((other_axis, other, false)) if other_axis == axis => match other == index {
+            true => None,
+            false => Some(index - (other < index) as usize),
+        },
+        _ => Some(index),
+    }
+}
+
+/// Transforms the given operation with the given (already applied) operation,
+/// or returns `None` if the given operation no longer has any effect.
+fn transform_op<T, A>(
+    lhs: &TableOp<T, A>,
+    rhs: TableOp<T, A>,
+    priority: bool,
+) -> Option<TableOp<T, A>>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let insert = |axis: Axis, index: usize| match structural(lhs) {
+        Some((other_axis, other, true)) if other_axis == axis => {
+            insert_after_insert(index, other, priority)
+        }
+        Some((other_axis, other, false)) if other_axis == axis => insert_after_remove(index, other),
+        _ => index,
+    };
+
+    match rhs {
+        TableOp::InsertRow { row } => Some(TableOp::InsertRow {
+            row: insert(Axis::Row, row),
+        }),
+        TableOp::InsertColumn { column } => Some(TableOp::InsertColumn {
+            column: insert(Axis::Column, column),
+        }),
+        TableOp::DeleteRow { row } => Some(TableOp::DeleteRow {
+            row: transform_index(lhs, Axis::Row, row)?,
+        }),
+        TableOp::DeleteColumn { column } => Some(TableOp::DeleteColumn {
+            column: transform_index(lhs, Axis::Column, column)?,
+        }),
+        TableOp::Edit { row, column, delta } => {
+            let row = transform_index(lhs, Axis::Row, row)?;
+            let column = transform_index(lhs, Axis::Column, column)?;
+
+            let delta = match lhs {
+                TableOp::Edit {
+                    row: other_row,
+                    column: other_column,
+                    delta: other_delta,
+                } if (*other_row, *other_column) == (row, column) => {
+                    other_delta.clone().transform(delta, priority)
+                }
+                _ => delta,
+            };
+
+            Some(TableOp::Edit { row, column, delta })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compose, Delta, Transform};
+
+    use super::{Table, TableDelta};
+
+    fn text(text: &str) -> Delta<String, ()> {
+        Delta::new().insert(text.to_owned(), None)
+    }
+
+    fn changes(rows: usize, columns: usize) -> Vec<TableDelta<String, ()>> {
+        let mut changes = vec![];
+
+        for row in 0..=rows {
+            changes.push(TableDelta::new().insert_row(row));
+        }
+
+        for column in 0..=columns {
+            changes.push(TableDelta::new().insert_column(column));
+        }
+
+        for row in 0..rows {
+            changes.push(TableDelta::new().delete_row(row));
+
+            for column in 0..columns {
+                changes.push(TableDelta::new().edit(row, column, text("x")));
+            }
+        }
+
+        for column in 0..columns {
+            changes.push(TableDelta::new().delete_column(column));
+        }
+
+        changes
+    }
+
+    fn table() -> Table<String, ()> {
+        let mut table = Table::new(2, 2);
+
+        table
+            .apply(
+                &TableDelta::new()
+                    .edit(0, 0, text("a"))
+                    .edit(0, 1, text("b"))
+                    .edit(1, 0, text("c"))
+                    .edit(1, 1, text("d")),
+            )
+            .unwrap();
+
+        table
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut table = table();
+
+        table
+            .apply(&TableDelta::new().insert_row(1).delete_column(0))
+            .unwrap();
+
+        assert_eq!((table.rows(), table.columns()), (3, 1));
+        assert_eq!(
+            table.cell(2, 0).map(|cell| cell.as_delta()),
+            Some(&text("d"))
+        );
+        assert!(table.apply(&TableDelta::new().delete_row(3)).is_err());
+        assert_eq!(table.rows(), 3);
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs = TableDelta::new().edit(0, 0, text("a"));
+        let rhs = TableDelta::new().edit(
+            0,
+            0,
+            Delta::new().retain(1, None).insert("b".to_owned(), None),
+        );
+
+        assert_eq!(lhs.compose(rhs), TableDelta::new().edit(0, 0, text("ab")));
+    }
+
+    #[test]
+    fn test_transform_converges() {
+        let before = table();
+
+        for alice in changes(2, 2) {
+            for bob in changes(2, 2) {
+                for priority in [true, false] {
+                    let mut left = before.clone();
+                    left.apply(&alice).unwrap();
+                    left.apply(&alice.clone().transform(bob.clone(), priority))
+                        .unwrap();
+
+                    let mut right = before.clone();
+                    right.apply(&bob).unwrap();
+                    right
+                        .apply(&bob.clone().transform(alice.clone(), !priority))
+                        .unwrap();
+
+                    assert_eq!(left, right, "{alice:?} {bob:?} {priority}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_edit() {
+        let alice = TableDelta::new().insert_row(0).insert_column(0);
+        let bob = TableDelta::new().edit(1, 1, text("x"));
+
+        assert_eq!(
+            alice.transform(bob, true),
+            TableDelta::new().edit(2, 2, text("x"))
+        );
+    }
+}
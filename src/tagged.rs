@@ -0,0 +1,254 @@
+//! Versioned, externally tagged serde representation of deltas for binary
+//! formats.
+//!
+//! This module is only available with the `tagged` feature enabled. The default
+//! representation of a delta matches Quill's JSON, which relies on
+//! `#[serde(untagged)]` to tell inserts, retains and deletes apart. Formats that
+//! aren't self-describing (e.g. bincode and postcard) can't deserialize untagged
+//! enums, so this module provides a representation that names each op
+//! explicitly instead:
+//!
+//! ```text
+//! (version: u8, ops: [Insert(value, attributes?) | Retain(len, attributes?) | Delete(len)])
+//! ```
+//!
+//! where lengths are `u64`s and the version is [`VERSION`]. Deserializing a
+//! delta with any other version fails, so that the representation can evolve
+//! without misinterpreting stored deltas.
+//!
+//! Use [`Tagged`] to (de)serialize a delta on its own, or this module with
+//! `#[serde(with = "kyte::tagged")]` for a delta that's part of another type.
+//! Values and attributes are (de)serialized with their own implementations, so
+//! they also need to support the format. In particular,
+//! [`Attributes`](crate::attributes::Attributes) are untagged as well, and can
+//! only be used with self-describing formats.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::de::DeltaRepr;
+use super::ops::{Delete, Insert, Retain};
+use super::{Delta, Len, Op};
+
+/// Version of the representation that this module produces.
+pub const VERSION: u8 = 1;
+
+/// Wrapper that (de)serializes a delta with the representation of this module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tagged<T, A>(pub Delta<T, A>);
+
+impl<T, A> Serialize for Tagged<T, A>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T, A> Deserialize<'de> for Tagged<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Tagged)
+    }
+}
+
+#[derive(Serialize)]
+enum TaggedOpRef<'a, T, A> {
+    Insert(&'a T, Option<&'a A>),
+    Retain(u64, Option<&'a A>),
+    Delete(u64),
+}
+
+#[derive(Deserialize)]
+enum TaggedOp<T, A> {
+    Insert(T, Option<A>),
+    Retain(u64, Option<A>),
+    Delete(u64),
+}
+
+/// Serializes the given delta with the representation of this module.
+pub fn serialize<T, A, S>(delta: &Delta<T, A>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    A: Serialize,
+    S: Serializer,
+{
+    let ops = delta
+        .ops()
+        .map(|op| match op {
+            Op::Insert(insert) => TaggedOpRef::Insert(insert.insert(), insert.attributes.as_ref()),
+            Op::Retain(retain) => {
+                TaggedOpRef::Retain(retain.retain as u64, retain.attributes.as_ref())
+            }
+            Op::Delete(delete) => TaggedOpRef::Delete(delete.delete as u64),
+        })
+        .collect::<Vec<_>>();
+
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&VERSION)?;
+    tuple.serialize_element(&ops)?;
+    tuple.end()
+}
+
+/// Deserializes a delta with the representation of this module. Returns an
+/// error if the delta has a different version than [`VERSION`] or contains an
+/// op that's longer than `usize::MAX`.
+pub fn deserialize<'de, T, A, D>(deserializer: D) -> Result<Delta<T, A>, D::Error>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple(2, TaggedVisitor(PhantomData))
+}
+
+struct TaggedVisitor<T, A>(PhantomData<(T, A)>);
+
+impl<'de, T, A> Visitor<'de> for TaggedVisitor<T, A>
+where
+    T: Len + Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    type Value = Delta<T, A>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a version followed by a sequence of tagged operations")
+    }
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        let version = seq
+            .next_element::<u8>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        if version != VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported version {version} (expected {VERSION})"
+            )));
+        }
+
+        let ops = seq
+            .next_element::<Vec<TaggedOp<T, A>>>()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?
+            .into_iter()
+            .map(|op| {
+                Ok(match op {
+                    TaggedOp::Insert(insert, attributes) => Insert::new(insert, attributes).into(),
+                    TaggedOp::Retain(retain, attributes) => Retain {
+                        retain: len(retain)?,
+                        attributes,
+                    }
+                    .into(),
+                    TaggedOp::Delete(delete) => Delete {
+                        delete: len(delete)?,
+                    }
+                    .into(),
+                })
+            })
+            .collect::<Result<Vec<Op<T, A>>, S::Error>>()?;
+
+        Ok(DeltaRepr::Seq(ops).into())
+    }
+}
+
+fn len<E>(len: u64) -> Result<usize, E>
+where
+    E: de::Error,
+{
+    usize::try_from(len).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(len), &"a length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::Delta;
+
+    use super::Tagged;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Format {
+        bold: bool,
+        color: Option<String>,
+    }
+
+    fn delta() -> Delta<String, Format> {
+        let format = Format {
+            bold: true,
+            color: Some("red".to_owned()),
+        };
+
+        Delta::new()
+            .retain(5, format.clone())
+            .insert("Hello".to_owned(), None)
+            .delete(3)
+            .insert(" World".to_owned(), format)
+    }
+
+    #[test]
+    fn test_bincode() {
+        let bytes = bincode::serialize(&Tagged(delta())).unwrap();
+
+        assert_eq!(bytes[0], super::VERSION);
+        assert_eq!(
+            bincode::deserialize::<Tagged<String, Format>>(&bytes).unwrap(),
+            Tagged(delta())
+        );
+    }
+
+    #[test]
+    fn test_postcard() {
+        let bytes = postcard::to_allocvec(&Tagged(delta())).unwrap();
+
+        assert_eq!(
+            postcard::from_bytes::<Tagged<String, Format>>(&bytes).unwrap(),
+            Tagged(delta())
+        );
+    }
+
+    #[test]
+    fn test_with() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Change {
+            version: usize,
+            #[serde(with = "crate::tagged")]
+            delta: Delta<String, Format>,
+        }
+
+        let change = Change {
+            version: 42,
+            delta: delta(),
+        };
+        let bytes = bincode::serialize(&change).unwrap();
+
+        assert_eq!(bincode::deserialize::<Change>(&bytes).unwrap(), change);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut bytes = bincode::serialize(&Tagged(delta())).unwrap();
+        bytes[0] = 2;
+
+        assert!(bincode::deserialize::<Tagged<String, Format>>(&bytes)
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported version 2"));
+    }
+}
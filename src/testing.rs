@@ -0,0 +1,579 @@
+//! Utilities for verifying that custom sequence and attribute types converge.
+//!
+//! This module is only available with the `testing` feature enabled, which is
+//! meant to be enabled for the `dev-dependencies` of crates that implement
+//! [`Seq`] or [`Compose`] and [`Transform`] for attributes of their own. The
+//! main check is [`assert_tp1`], which asserts the same property as kyte's own
+//! fuzz target, and [`check_tp2`] reports whether the stronger TP2 property
+//! holds for a given triple of deltas. The `arbitrary_*` functions generate
+//! deltas that fit together from (fuzzer or pseudo-random) bytes:
+//!
+//! ```
+//! use arbitrary::Unstructured;
+//! use kyte::testing::{arbitrary_change, arbitrary_document, assert_tp1};
+//! use kyte::LastWriteWins;
+//!
+//! let bytes = (0..4096).map(|i: u32| i.wrapping_mul(2654435761) as u8).collect::<Vec<_>>();
+//! let mut u = Unstructured::new(&bytes);
+//!
+//! let before = arbitrary_document::<String, LastWriteWins<u8>>(&mut u).unwrap();
+//! let len = before.target_len(0);
+//!
+//! assert_tp1(
+//!     &before,
+//!     &arbitrary_change(&mut u, len).unwrap(),
+//!     &arbitrary_change(&mut u, len).unwrap(),
+//! );
+//! ```
+
+pub mod invariants;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use super::attributes::{AttrValue, Attributes};
+use super::{generate, Compose, Delta, Document, Len, Op, Seq, Transform};
+
+/// Asserts that the given concurrent deltas converge when they're applied to
+/// the given document in either order (i.e. the TP1 property of operational
+/// transformation):
+///
+/// ```text
+/// before ∘ a ∘ a.transform(b, true) == before ∘ b ∘ b.transform(a, false)
+/// ```
+///
+/// Both results are compared with [`Delta::semantic_eq`].
+///
+/// # Panics
+///
+/// Panics with both results if they don't converge.
+#[track_caller]
+pub fn assert_tp1<T, A>(before: &Delta<T, A>, a: &Delta<T, A>, b: &Delta<T, A>)
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + PartialEq,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let a_b = before
+        .clone()
+        .compose(a.clone())
+        .compose(a.clone().transform(b.clone(), true));
+
+    let b_a = before
+        .clone()
+        .compose(b.clone())
+        .compose(b.clone().transform(a.clone(), false));
+
+    assert!(
+        a_b.semantic_eq(&b_a),
+        "deltas don't converge: {a_b:?} != {b_a:?} (before: {before:?}, a: {a:?}, b: {b:?})"
+    );
+}
+
+/// Delta that an element of the documents that [`check_tp2`] simulates was
+/// inserted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Origin {
+    /// The first delta (`a`).
+    A,
+
+    /// The second delta (`b`).
+    B,
+
+    /// The third delta (`c`).
+    C,
+}
+
+/// Identity of an element of the documents that [`check_tp2`] simulates, which
+/// stays the same while the element is moved around by other inserts and
+/// deletes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ElementId {
+    /// The element at the given index of the document that the deltas apply
+    /// to.
+    Base(usize),
+
+    /// The element that the given delta inserted, at the given index among
+    /// the elements that it inserted. Transforming a delta never drops or
+    /// reorders its inserts, so this identifies the same element in every
+    /// transformed version of that delta.
+    Inserted(Origin, usize),
+}
+
+/// Outcome of [`check_tp2`]: the two ways of transforming the third delta
+/// against the first two, and where (if anywhere) they diverge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tp2Report<T, A> {
+    /// Contains `c` transformed against `a`, and then against `b` transformed
+    /// against `a`.
+    pub via_a: Delta<T, A>,
+
+    /// Contains `c` transformed against `b`, and then against `a` transformed
+    /// against `b`.
+    pub via_b: Delta<T, A>,
+
+    /// Contains the index of the first op at which the canonical forms (see
+    /// [`Delta::normalize`]) of both results differ, or `None` if they're
+    /// equivalent.
+    pub divergence: Option<usize>,
+
+    /// Contains the elements of the document that results from applying `a`,
+    /// `b` transformed against `a` and [`via_a`](Tp2Report::via_a) to the
+    /// document that the deltas apply to.
+    pub elements_via_a: Vec<ElementId>,
+
+    /// Contains the elements of the document that results from applying `b`,
+    /// `a` transformed against `b` and [`via_b`](Tp2Report::via_b) to the
+    /// document that the deltas apply to.
+    pub elements_via_b: Vec<ElementId>,
+
+    /// Contains the elements that are at different positions in (or missing
+    /// from either of) both documents, in the order in which they appear in
+    /// [`elements_via_a`](Tp2Report::elements_via_a) and then in
+    /// [`elements_via_b`](Tp2Report::elements_via_b).
+    pub misplaced: Vec<ElementId>,
+}
+
+impl<T, A> Tp2Report<T, A> {
+    /// Returns true if both results are equivalent, i.e. TP2 holds for the
+    /// deltas that were checked.
+    pub fn holds(&self) -> bool {
+        self.divergence.is_none() && self.misplaced.is_empty()
+    }
+}
+
+/// Checks whether transforming `c` against the concurrent deltas `a` and `b`
+/// yields the same delta regardless of the order in which `a` and `b` are
+/// applied (i.e. the TP2 property of operational transformation):
+///
+/// ```text
+/// let b_a = a.transform(b, true);
+/// let a_b = b.transform(a, false);
+///
+/// b_a.transform(a.transform(c, true), true) == a_b.transform(b.transform(c, true), true)
+/// ```
+///
+/// i.e. `a` takes priority over `b`, and both take priority over `c`.
+///
+/// Kyte only guarantees TP1 (see [`assert_tp1`]). TP1 suffices for topologies
+/// where a single server totally orders all changes, e.g. a central server
+/// with any number of clients. Topologies where a change may be transformed
+/// against the same concurrent changes in different orders (e.g. multiple
+/// servers that exchange changes peer-to-peer, or clients that exchange
+/// changes without a server) also require TP2, which doesn't hold in general
+/// (e.g. for concurrent inserts around a deleted element), so this function
+/// returns a report instead of panicking.
+///
+/// Besides comparing both results op by op, this function tracks the identity
+/// of each element (see [`ElementId`]) through both orders, starting from a
+/// document that's as long as the longest of the deltas' base lengths, and
+/// reports which elements end up at different positions.
+pub fn check_tp2<T, A>(a: &Delta<T, A>, b: &Delta<T, A>, c: &Delta<T, A>) -> Tp2Report<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + PartialEq,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let b_a = a.clone().transform(b.clone(), true);
+    let a_b = b.clone().transform(a.clone(), false);
+
+    let via_a = b_a
+        .clone()
+        .transform(a.clone().transform(c.clone(), true), true);
+    let via_b = a_b
+        .clone()
+        .transform(b.clone().transform(c.clone(), true), true);
+
+    let lhs = via_a.clone().normalize().chop();
+    let rhs = via_b.clone().normalize().chop();
+
+    let divergence = match lhs.ops().zip(rhs.ops()).position(|(lhs, rhs)| lhs != rhs) {
+        Some(index) => Some(index),
+        None if lhs.ops_len() != rhs.ops_len() => Some(lhs.ops_len().min(rhs.ops_len())),
+        None => None,
+    };
+
+    let base = (0..a.base_len().max(b.base_len()).max(c.base_len()))
+        .map(ElementId::Base)
+        .collect::<Vec<_>>();
+
+    let elements_via_a = [(a, Origin::A), (&b_a, Origin::B), (&via_a, Origin::C)]
+        .into_iter()
+        .fold(base.clone(), |elements, (delta, origin)| {
+            apply_ids(elements, delta, origin)
+        });
+    let elements_via_b = [(b, Origin::B), (&a_b, Origin::A), (&via_b, Origin::C)]
+        .into_iter()
+        .fold(base, |elements, (delta, origin)| {
+            apply_ids(elements, delta, origin)
+        });
+
+    let misplaced = misplaced(&elements_via_a, &elements_via_b);
+
+    Tp2Report {
+        via_a,
+        via_b,
+        divergence,
+        elements_via_a,
+        elements_via_b,
+        misplaced,
+    }
+}
+
+/// Applies the given delta, which was derived from a delta of the given
+/// origin, to a document with the given elements.
+fn apply_ids<T, A>(elements: Vec<ElementId>, delta: &Delta<T, A>, origin: Origin) -> Vec<ElementId>
+where
+    T: Seq,
+{
+    let mut elements = elements.into_iter();
+    let mut result = vec![];
+    let mut inserted = 0;
+
+    for op in delta.ops() {
+        match op {
+            Op::Insert(insert) => {
+                let len = insert.len();
+                result.extend(
+                    (inserted..inserted + len).map(|index| ElementId::Inserted(origin, index)),
+                );
+                inserted += len;
+            }
+            Op::Retain(retain) => result.extend(elements.by_ref().take(retain.len())),
+            Op::Delete(delete) => elements.by_ref().take(delete.len()).for_each(drop),
+        }
+    }
+
+    result.extend(elements);
+    result
+}
+
+/// Returns the elements whose positions differ between both documents.
+fn misplaced(lhs: &[ElementId], rhs: &[ElementId]) -> Vec<ElementId> {
+    let lhs_positions = lhs
+        .iter()
+        .enumerate()
+        .map(|(index, &id)| (id, index))
+        .collect::<BTreeMap<_, _>>();
+    let rhs_positions = rhs
+        .iter()
+        .enumerate()
+        .map(|(index, &id)| (id, index))
+        .collect::<BTreeMap<_, _>>();
+
+    let lhs = lhs
+        .iter()
+        .filter(|id| rhs_positions.get(id) != lhs_positions.get(id));
+    let rhs = rhs.iter().filter(|id| !lhs_positions.contains_key(id));
+
+    lhs.chain(rhs).copied().collect()
+}
+
+/// Generates a document, i.e. a delta that only contains inserts, with
+/// arbitrary values and attributes.
+pub fn arbitrary_document<'a, T, A>(u: &mut Unstructured<'a>) -> arbitrary::Result<Delta<T, A>>
+where
+    T: Arbitrary<'a> + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    generate::document(u)
+}
+
+/// Generates a delta that applies to a document of the given length, i.e.
+/// one whose retains and deletes don't extend past the end of that document.
+/// Deltas that are generated for the same length are compatible and can be
+/// passed to [`assert_tp1`] together.
+pub fn arbitrary_change<'a, T, A>(
+    u: &mut Unstructured<'a>,
+    base_len: usize,
+) -> arbitrary::Result<Delta<T, A>>
+where
+    T: Arbitrary<'a> + Default + Clone + Seq + Extend<T>,
+    A: Arbitrary<'a> + Default + Clone + PartialEq,
+{
+    generate::change(u, base_len)
+}
+
+/// Words that simulated typing and pasting are made of.
+const WORDS: &[&str] = &[
+    "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "lorem", "ipsum", "dolor",
+    "sit", "amet", "sync", "server", "document", "change", "kyte",
+];
+
+/// Formats that simulated formatting toggles.
+const FORMATS: &[&str] = &["bold", "italic", "underline"];
+
+/// Seedable generator of changes that resemble human editing traffic, for load
+/// and soak testing servers.
+///
+/// The generator keeps a document and a cursor, and each change applies to the
+/// document that results from the previous changes. Most changes type a word
+/// or a newline at the cursor, and the rest delete characters before the
+/// cursor (like backspace), paste larger chunks of text, delete a selection or
+/// toggle a format on a selection. Occasionally, the cursor jumps elsewhere
+/// first. The same seed always produces the same changes.
+#[derive(Clone, Debug)]
+pub struct DeltaGenerator {
+    state: u64,
+    document: Document<String, Attributes>,
+    cursor: usize,
+}
+
+impl DeltaGenerator {
+    /// Returns a new generator with the given seed that starts with an empty
+    /// document.
+    pub fn new(seed: u64) -> DeltaGenerator {
+        DeltaGenerator::with_document(seed, Document::new())
+    }
+
+    /// Returns a new generator with the given seed that starts with the given
+    /// document, with its cursor at the end of that document.
+    pub fn with_document(seed: u64, document: Document<String, Attributes>) -> DeltaGenerator {
+        DeltaGenerator {
+            // Scrambles the seed (like SplitMix64), so that similar seeds
+            // produce unrelated changes and zero is a valid seed.
+            state: (seed ^ 0x9e3779b97f4a7c15).wrapping_mul(0xbf58476d1ce4e5b9) | 1,
+            cursor: document.len(),
+            document,
+        }
+    }
+
+    /// Returns the document that the next change applies to.
+    pub fn document(&self) -> &Document<String, Attributes> {
+        &self.document
+    }
+
+    /// Returns the next change and applies it to the document of this
+    /// generator.
+    pub fn next_change(&mut self) -> Delta<String, Attributes> {
+        let len = self.document.len();
+
+        if self.below(10) == 0 {
+            self.cursor = self.below(len + 1);
+        }
+
+        let change = match self.below(100) {
+            10..=24 if self.cursor > 0 => {
+                let delete = 1 + self.below(self.cursor.min(3));
+                self.cursor -= delete;
+                Delta::new().retain(self.cursor, None).delete(delete)
+            }
+            25..=29 => {
+                let words = 4 + self.below(40);
+                let text = self.text(words);
+                let change = Delta::new().retain(self.cursor, None);
+                self.cursor += text.chars().count();
+                change.insert(text, None)
+            }
+            30..=34 if len > 0 => {
+                let (start, end) = self.selection(len);
+                self.cursor = start;
+                Delta::new().retain(start, None).delete(end - start)
+            }
+            35..=39 if len > 0 => {
+                let (start, end) = self.selection(len);
+                let format = FORMATS[self.below(FORMATS.len())];
+                let value = match self.below(2) {
+                    0 => AttrValue::Bool(true),
+                    _ => AttrValue::Null,
+                };
+                let attributes: Attributes = [(format.to_owned(), value)].into_iter().collect();
+
+                Delta::new()
+                    .retain(start, None)
+                    .retain(end - start, attributes)
+            }
+            _ => {
+                let text = match self.below(10) {
+                    0 => "\n".to_owned(),
+                    _ => self.text(1),
+                };
+                let change = Delta::new().retain(self.cursor, None);
+                self.cursor += text.chars().count();
+                change.insert(text, None)
+            }
+        };
+
+        self.document.apply(change.clone());
+
+        change
+    }
+
+    /// Returns the next pseudo-random number from a xorshift generator.
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Returns a pseudo-random number below the given (non-zero) bound.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns the given number of words, each followed by a space.
+    fn text(&mut self, words: usize) -> String {
+        (0..words)
+            .map(|_| WORDS[self.below(WORDS.len())])
+            .fold(String::new(), |text, word| text + word + " ")
+    }
+
+    /// Returns the start and end of a non-empty selection of at most 50
+    /// elements in a document of the given (non-zero) length.
+    fn selection(&mut self, len: usize) -> (usize, usize) {
+        let start = self.below(len);
+        let end = start + 1 + self.below((len - start).min(50));
+
+        (start, end)
+    }
+}
+
+/// Generates changes indefinitely (see [`DeltaGenerator::next_change`]).
+impl Iterator for DeltaGenerator {
+    type Item = Delta<String, Attributes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_change())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::Unstructured;
+
+    use super::{
+        arbitrary_change, arbitrary_document, assert_tp1, check_tp2, DeltaGenerator, ElementId,
+        Origin,
+    };
+    use crate::{Compose, Delta, LastWriteWins};
+
+    /// Returns bytes from a xorshift generator, which deterministically feed
+    /// the `arbitrary_*` functions in place of a fuzzer.
+    fn pseudo_random_bytes(mut state: u64) -> Vec<u8> {
+        (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_assert_tp1() {
+        let bytes = pseudo_random_bytes(0x9e3779b97f4a7c15);
+        let mut u = Unstructured::new(&bytes);
+
+        for _ in 0..1000 {
+            let before = arbitrary_document::<String, LastWriteWins<usize>>(&mut u).unwrap();
+            let len = before.target_len(0);
+            let a = arbitrary_change(&mut u, len).unwrap();
+            let b = arbitrary_change(&mut u, len).unwrap();
+
+            a.check_applicable(len).unwrap();
+            b.check_applicable(len).unwrap();
+            assert_tp1(&before, &a, &b);
+        }
+    }
+
+    /// Attribute that (incorrectly) composes by adding, so concurrent updates
+    /// of the same element don't converge.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Sum(u8);
+
+    impl Compose<Sum> for Sum {
+        type Output = Sum;
+
+        fn compose(self, rhs: Sum) -> Sum {
+            Sum(self.0 + rhs.0)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "deltas don't converge")]
+    fn test_assert_tp1_diverged() {
+        let before = Delta::new().insert("ab".to_owned(), None);
+        let a = Delta::new().retain(1, Sum(1));
+        let b = Delta::new().retain(2, Sum(2));
+
+        assert_tp1(&before, &a, &b);
+    }
+
+    #[test]
+    fn test_check_tp2() {
+        let a = Delta::<String, ()>::new().insert("a".to_owned(), None);
+        let b = Delta::new().retain(2, None).delete(1);
+        let c = Delta::new().retain(1, None).insert("c".to_owned(), None);
+
+        let report = check_tp2(&a, &b, &c);
+
+        assert!(report.holds());
+        assert_eq!(
+            report.via_a,
+            Delta::new().retain(2, None).insert("c".to_owned(), None)
+        );
+        assert_eq!(
+            report.elements_via_a,
+            [
+                ElementId::Inserted(Origin::A, 0),
+                ElementId::Base(0),
+                ElementId::Inserted(Origin::C, 0),
+                ElementId::Base(1),
+            ]
+        );
+        assert_eq!(report.elements_via_a, report.elements_via_b);
+    }
+
+    #[test]
+    fn test_check_tp2_diverged() {
+        // Inserts on either side of a concurrently deleted element.
+        let a = Delta::<String, ()>::new()
+            .retain(1, None)
+            .insert("a".to_owned(), None);
+        let b = Delta::new().delete(1);
+        let c = Delta::new().insert("c".to_owned(), None);
+
+        let report = check_tp2(&a, &b, &c);
+
+        assert_eq!(report.divergence, Some(0));
+        assert_eq!(report.via_a, Delta::new().insert("c".to_owned(), None));
+        assert_eq!(
+            report.via_b,
+            Delta::new().retain(1, None).insert("c".to_owned(), None)
+        );
+        assert_eq!(
+            report.misplaced,
+            [
+                ElementId::Inserted(Origin::C, 0),
+                ElementId::Inserted(Origin::A, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delta_generator() {
+        let mut generator = DeltaGenerator::new(0);
+        let mut document = generator.document().clone();
+
+        for change in generator.by_ref().take(1000) {
+            assert_eq!(change.check_applicable(document.len()), Ok(()));
+            document.apply(change);
+        }
+
+        assert_eq!(&document, generator.document());
+        assert!(document.len() > 1000);
+
+        let changes = DeltaGenerator::new(42).take(100).collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            DeltaGenerator::new(42).take(100).collect::<Vec<_>>()
+        );
+        assert_ne!(
+            changes,
+            DeltaGenerator::new(43).take(100).collect::<Vec<_>>()
+        );
+    }
+}
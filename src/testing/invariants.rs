@@ -0,0 +1,175 @@
+//! Checks of invariants that every combination of value and attribute types
+//! should uphold, for fuzz targets and test suites of new ops and attributes.
+//!
+//! Like [`assert_tp1`](super::assert_tp1), each check panics with the deltas
+//! involved if its invariant doesn't hold. The checks expect deltas that apply
+//! to the documents they're given (e.g. as generated by
+//! [`arbitrary_change`](super::arbitrary_change)), because lenient composition
+//! (see [`ApplyMode`](crate::ApplyMode)) doesn't uphold all of them for deltas
+//! that extend past the end of their document.
+
+use std::fmt::Debug;
+
+use crate::ops::Retain;
+use crate::{Compose, Delta, Document, Op, Seq, Transform};
+
+/// Asserts that composition is associative, i.e. that `(a ∘ b) ∘ c` is
+/// equivalent to `a ∘ (b ∘ c)` (see [`Delta::semantic_eq`]).
+#[track_caller]
+pub fn assert_compose_associative<T, A>(a: &Delta<T, A>, b: &Delta<T, A>, c: &Delta<T, A>)
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + PartialEq,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let lhs = a.clone().compose(b.clone()).compose(c.clone());
+    let rhs = a.clone().compose(b.clone().compose(c.clone()));
+
+    assert!(
+        lhs.semantic_eq(&rhs),
+        "composition isn't associative: {lhs:?} != {rhs:?} (a: {a:?}, b: {b:?}, c: {c:?})"
+    );
+}
+
+/// Asserts that the given inverse of a change undoes that change, i.e. that
+/// applying `change` and then `inverse` to `base` yields `base` again.
+#[track_caller]
+pub fn assert_invert_roundtrip<T, A>(
+    base: &Document<T, A>,
+    change: &Delta<T, A>,
+    inverse: &Delta<T, A>,
+) where
+    T: Default + Clone + Seq + Extend<T> + Debug + PartialEq,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let mut document = base.clone();
+    document.apply(change.clone());
+    document.apply(inverse.clone());
+
+    assert!(
+        document.as_delta().semantic_eq(base.as_delta()),
+        "inverse doesn't undo change: {document:?} != {base:?} (change: {change:?}, inverse: {inverse:?})"
+    );
+}
+
+/// Asserts that transforming the position of each element of `base` that
+/// `change` doesn't delete (without priority, see [`Transform<usize>`]) yields
+/// the position of the same element after `change` is applied.
+#[track_caller]
+pub fn assert_transform_position<T, A>(base: &Document<T, A>, change: &Delta<T, A>)
+where
+    T: Default + Clone + Seq + Extend<T> + Debug + PartialEq,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let mut after = base.clone();
+    after.apply(change.clone());
+
+    for index in (0..base.len()).filter(|&index| !deletes(change, index)) {
+        let transformed = change.transform(index, false);
+
+        assert_eq!(
+            element(base, index),
+            element(&after, transformed),
+            "position {index} transforms into {transformed}, which contains a different element (change: {change:?})"
+        );
+    }
+}
+
+/// Returns true if the given change deletes the element at the given index.
+fn deletes<T, A>(change: &Delta<T, A>, index: usize) -> bool
+where
+    T: Seq,
+{
+    let mut offset = 0usize;
+
+    for op in change.ops() {
+        match op {
+            Op::Insert(_) => continue,
+            Op::Retain(retain) => offset = offset.saturating_add(retain.retain),
+            Op::Delete(delete) => {
+                offset = offset.saturating_add(delete.delete);
+
+                if index < offset {
+                    return true;
+                }
+            }
+        }
+
+        if index < offset {
+            return false;
+        }
+    }
+
+    false
+}
+
+/// Returns the value of the element at the given index of the given document
+/// (without its attributes).
+fn element<T, A>(document: &Document<T, A>, index: usize) -> Option<T>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    let slice = document.as_delta().clone().compose(
+        Delta::new()
+            .delete(index)
+            .retain(1, None)
+            .delete(usize::MAX),
+    );
+
+    slice.into_iter().find_map(|op| match op {
+        Op::Insert(insert) => Some(insert.into_parts().0),
+        Op::Retain(Retain { .. }) | Op::Delete(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::Unstructured;
+
+    use super::{assert_compose_associative, assert_invert_roundtrip, assert_transform_position};
+    use crate::testing::{arbitrary_change, arbitrary_document};
+    use crate::{Delta, Document, LastWriteWins};
+
+    #[test]
+    fn test_invariants() {
+        let bytes = (0..1 << 18)
+            .map(|i: u32| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect::<Vec<_>>();
+
+        for chunk in bytes.chunks(256) {
+            let mut u = Unstructured::new(chunk);
+            let base = arbitrary_document::<String, LastWriteWins<u8>>(&mut u).unwrap();
+            let a = arbitrary_change(&mut u, base.target_len(0)).unwrap();
+            let b = arbitrary_change(&mut u, a.target_len(base.target_len(0))).unwrap();
+
+            assert_compose_associative(&base, &a, &b);
+            assert_transform_position(&Document::from_delta(base).unwrap(), &a);
+        }
+    }
+
+    #[test]
+    fn test_invert_roundtrip() {
+        let base =
+            Document::from_delta(Delta::<_, ()>::new().insert("abc".to_owned(), None)).unwrap();
+        let change = Delta::new()
+            .retain(1, None)
+            .insert("x".to_owned(), None)
+            .delete(1);
+        let inverse = Delta::new()
+            .retain(1, None)
+            .insert("b".to_owned(), None)
+            .delete(1);
+
+        assert_invert_roundtrip(&base, &change, &inverse);
+    }
+
+    #[test]
+    #[should_panic(expected = "inverse doesn't undo change")]
+    fn test_invert_roundtrip_wrong() {
+        let base =
+            Document::from_delta(Delta::<_, ()>::new().insert("abc".to_owned(), None)).unwrap();
+        let change = Delta::new().retain(1, None).delete(1);
+
+        assert_invert_roundtrip(&base, &change, &Delta::new());
+    }
+}
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use super::{Delta, Len, Op, Seq};
+
+impl<T, A> Delta<T, A>
+where
+    T: Seq,
+{
+    /// Concatenates the inserts of this (document) delta into a string, using
+    /// the given function to map each element to a character. Retains and
+    /// deletes are skipped. Embeds are usually mapped to the object replacement
+    /// character (`'\u{FFFC}'`) so that positions in the resulting string match
+    /// positions in the document.
+    pub fn plain_text_with<E, F>(&self, map: F) -> String
+    where
+        for<'a> T::Iterator<'a>: Iterator<Item = E>,
+        F: FnMut(E) -> char,
+    {
+        self.text_in_with(0..usize::MAX, map)
+    }
+
+    /// Concatenates the elements in the given range of this (document) delta
+    /// into a string, using the given function to map each element to a
+    /// character. See [`Delta::plain_text_with`].
+    pub fn text_in_with<E, F>(&self, range: Range<usize>, mut map: F) -> String
+    where
+        for<'a> T::Iterator<'a>: Iterator<Item = E>,
+        F: FnMut(E) -> char,
+    {
+        let mut text = String::new();
+        let mut position = 0usize;
+
+        for op in self.ops() {
+            if position >= range.end {
+                break;
+            }
+
+            let Op::Insert(insert) = op else {
+                continue;
+            };
+
+            let len = insert.len();
+
+            if position.saturating_add(len) > range.start {
+                let skip = range.start.saturating_sub(position);
+                let take = range.end.saturating_sub(position).min(len) - skip;
+
+                text.extend(insert.insert().iter().skip(skip).take(take).map(&mut map));
+            }
+
+            position = position.saturating_add(len);
+        }
+
+        text
+    }
+}
+
+impl<A> Delta<String, A> {
+    /// Concatenates the inserts of this (document) delta into a string.
+    /// Retains and deletes are skipped.
+    pub fn plain_text(&self) -> String {
+        self.plain_text_with(|c| c)
+    }
+
+    /// Returns the text in the given range of this (document) delta, where the
+    /// range is measured in characters.
+    pub fn text_in(&self, range: Range<usize>) -> String {
+        self.text_in_with(range, |c| c)
+    }
+
+    /// Returns the ranges (measured in characters) of the non-overlapping
+    /// occurrences of the given needle in this (document) delta, from left to
+    /// right. Occurrences may span multiple inserts, regardless of their
+    /// attributes, and retains and deletes are skipped. Embeds that are
+    /// represented by an object replacement character (`'\u{FFFC}'`) only
+    /// match a needle that contains that character, so occurrences of plain
+    /// text never span an embed. An empty needle has no occurrences.
+    ///
+    /// Unlike searching the result of [`Delta::plain_text`], this doesn't
+    /// materialize the text of the document.
+    pub fn find<'a>(&'a self, needle: &str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let needle = needle.chars().collect::<Vec<_>>();
+        let table = failure_table(&needle);
+        let mut chars = self
+            .ops()
+            .filter_map(|op| match op {
+                Op::Insert(insert) => Some(insert.insert().chars()),
+                Op::Retain(_) | Op::Delete(_) => None,
+            })
+            .flatten()
+            .enumerate();
+        let mut matched = 0;
+
+        std::iter::from_fn(move || {
+            if needle.is_empty() {
+                return None;
+            }
+
+            for (position, c) in chars.by_ref() {
+                while matched > 0 && needle[matched] != c {
+                    matched = table[matched - 1];
+                }
+
+                if needle[matched] == c {
+                    matched += 1;
+                }
+
+                if matched == needle.len() {
+                    matched = 0;
+                    return Some(position + 1 - needle.len()..position + 1);
+                }
+            }
+
+            None
+        })
+    }
+}
+
+/// Returns the Knuth-Morris-Pratt failure table of the given needle, i.e. the
+/// length of the longest proper prefix of `needle[..=i]` that is also a suffix
+/// of it, for every `i`.
+fn failure_table(needle: &[char]) -> Vec<usize> {
+    let mut table = vec![0; needle.len()];
+    let mut len = 0;
+
+    for i in 1..needle.len() {
+        while len > 0 && needle[i] != needle[len] {
+            len = table[len - 1];
+        }
+
+        if needle[i] == needle[len] {
+            len += 1;
+        }
+
+        table[i] = len;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Delta;
+
+    #[test]
+    fn test_plain_text() {
+        let delta = Delta::new()
+            .insert("Hello ".to_owned(), None)
+            .insert("Wörld".to_owned(), true)
+            .insert("\n".to_owned(), None);
+
+        assert_eq!(delta.plain_text(), "Hello Wörld\n");
+        assert_eq!(delta.text_in(4..8), "o Wö");
+        assert_eq!(delta.text_in(10..100), "d\n");
+        assert_eq!(delta.text_in(20..30), "");
+    }
+
+    #[test]
+    fn test_plain_text_with() {
+        let delta = Delta::<_, ()>::new().insert("a\u{FFFC}b".to_owned(), None);
+
+        let map = |c| match c {
+            '\u{FFFC}' => '*',
+            c => c,
+        };
+
+        assert_eq!(delta.plain_text_with(map), "a*b");
+        assert_eq!(delta.text_in_with(1..2, map), "*");
+    }
+
+    #[test]
+    fn test_find() {
+        let delta = Delta::new()
+            .insert("aab".to_owned(), None)
+            .insert("ab".to_owned(), true)
+            .insert("\u{FFFC}".to_owned(), None)
+            .insert("aaab\n".to_owned(), None);
+
+        assert_eq!(delta.find("ab").collect::<Vec<_>>(), [1..3, 3..5, 8..10]);
+        assert_eq!(delta.find("aaa").collect::<Vec<_>>(), vec![6..9]);
+        assert_eq!(delta.find("b\u{FFFC}a").collect::<Vec<_>>(), vec![4..7]);
+        assert_eq!(delta.find("aa").collect::<Vec<_>>(), [0..2, 6..8]);
+        assert_eq!(delta.find("").count(), 0);
+        assert_eq!(delta.find("c").count(), 0);
+    }
+
+    #[test]
+    fn test_find_non_ascii() {
+        let delta = Delta::<_, ()>::new()
+            .retain(3, None)
+            .insert("Wö".to_owned(), None)
+            .delete(2)
+            .insert("rld Wörld".to_owned(), None);
+
+        assert_eq!(delta.find("Wörld").collect::<Vec<_>>(), [0..5, 6..11]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::Debug;
+
+use super::{Compose, Delta, Document, Error, Seq};
+
+/// Group of changes that are applied to a [`Document`] atomically: either all
+/// of them or none of them.
+///
+/// Each change applies after the changes that precede it in the transaction
+/// (just like consecutive calls to [`Document::apply`]). Applying a
+/// transaction returns the composition of its changes, which can be broadcast
+/// to other clients and recorded in an undo stack as a single change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transaction<T, A> {
+    changes: Vec<Delta<T, A>>,
+}
+
+impl<T, A> Transaction<T, A> {
+    /// Returns a new empty transaction.
+    pub fn new() -> Transaction<T, A> {
+        Transaction {
+            changes: Vec::new(),
+        }
+    }
+
+    /// Adds the given change to this transaction, which applies after the
+    /// changes that are already in this transaction.
+    pub fn push(&mut self, change: Delta<T, A>) {
+        self.changes.push(change);
+    }
+
+    /// Returns the number of changes in this transaction.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns true if this transaction doesn't contain any changes.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<T, A> Transaction<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the changes in this transaction to the given document and
+    /// returns their composition. If any change isn't well-formed or extends
+    /// past the end of the document that it applies to (see
+    /// [`Delta::check_applicable`]), returns the error of the first such change
+    /// and leaves the given document unchanged.
+    pub fn apply(self, document: &mut Document<T, A>) -> Result<Delta<T, A>, Error> {
+        let mut len = document.len();
+
+        for change in &self.changes {
+            change.check_applicable(len)?;
+            len = change.target_len(len);
+        }
+
+        let change = self
+            .changes
+            .into_iter()
+            .reduce(|lhs, rhs| lhs.compose(rhs))
+            .unwrap_or_default();
+
+        document.apply(change.clone());
+
+        Ok(change)
+    }
+}
+
+impl<T, A> Default for Transaction<T, A> {
+    fn default() -> Self {
+        Transaction::new()
+    }
+}
+
+impl<T, A> FromIterator<Delta<T, A>> for Transaction<T, A> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Delta<T, A>>,
+    {
+        Transaction {
+            changes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T, A> Extend<Delta<T, A>> for Transaction<T, A> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = Delta<T, A>>,
+    {
+        self.changes.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transaction;
+    use crate::attributes::{attributes, Attributes};
+    use crate::{Delta, Document, Error};
+
+    fn document() -> Document<String, Attributes> {
+        Document::from_delta(Delta::new().insert("Hello World\n".to_owned(), None)).unwrap()
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut document = document();
+        let transaction = Transaction::from_iter([
+            Delta::new().retain(5, None).delete(6),
+            Delta::new().retain(5, None).insert("!".to_owned(), None),
+            Delta::new().retain(6, attributes("bold", true)),
+        ]);
+
+        assert_eq!(
+            transaction.apply(&mut document),
+            Ok(Delta::new()
+                .retain(5, attributes("bold", true))
+                .insert("!".to_owned(), attributes("bold", true))
+                .delete(6))
+        );
+        assert_eq!(
+            document.as_delta(),
+            &Delta::new()
+                .insert("Hello!".to_owned(), attributes("bold", true))
+                .insert("\n".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_apply_atomic() {
+        let mut document = document();
+        let mut transaction = Transaction::new();
+        transaction.push(Delta::new().retain(5, None).delete(6));
+        transaction.push(Delta::new().retain(7, None).insert("!".to_owned(), None));
+
+        assert_eq!(
+            transaction.apply(&mut document),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 6,
+                actual: 7
+            })
+        );
+        assert_eq!(document, self::document());
+    }
+}
@@ -1,10 +1,11 @@
 use std::cmp::min;
 use std::fmt::Debug;
+use std::iter::from_fn;
 use std::mem::take;
 
 use super::op::split;
 use super::ops::{Delete, Insert, Retain};
-use super::{Delta, Len, Op, Seq};
+use super::{ApplyMode, Compose, Cursor, Delta, Error, Len, Op, OpPairCursor, Seq};
 
 /// Implemented by types that can transform another operation to make them
 /// behave commutatively (i.e. order-independent).
@@ -68,7 +69,7 @@ where
     type Output = Retain<A>;
 
     fn transform(self, _rhs: &mut Retain<A>, _priority: bool) -> Self::Output {
-        take(self).as_retain().into()
+        take(self).as_retain()
     }
 }
 
@@ -102,19 +103,20 @@ where
 }
 
 /// Alice and Bob are both retaining the same selection. We can simply retain
-/// Bob's retain.
+/// Bob's retain, with its attributes transformed against Alice's attributes
+/// (see [`Compose::transform_retain`]).
 impl<A> Transform<&mut Retain<A>> for &mut Retain<A>
 where
-    A: Clone + Default,
+    A: Clone + Default + Compose<A, Output = A>,
 {
     type Output = Retain<A>;
 
     fn transform(self, rhs: &mut Retain<A>, priority: bool) -> Self::Output {
         let (lhs, rhs) = split(self, rhs);
 
-        match priority {
-            true => lhs.or(rhs),
-            false => rhs.or(lhs),
+        Retain {
+            retain: rhs.retain,
+            attributes: A::transform_retain(lhs.attributes, rhs.attributes, priority),
         }
     }
 }
@@ -137,7 +139,7 @@ where
 /// first.
 impl<T, A> Transform<&mut Insert<T, A>> for &mut Delete
 where
-    T: Default,
+    T: Default + Len,
     A: Default,
 {
     type Output = Insert<T, A>;
@@ -177,14 +179,14 @@ impl Transform<&mut Delete> for &mut Delete {
 impl<T, A> Transform<&mut Op<T, A>> for &mut Op<T, A>
 where
     T: Clone + Default + Seq + Extend<T>,
-    A: Clone + Default + PartialEq,
+    A: Clone + Default + PartialEq + Compose<A, Output = A>,
 {
     type Output = Op<T, A>;
 
     fn transform(self, rhs: &mut Op<T, A>, priority: bool) -> Self::Output {
         match self {
             Op::Insert(lhs) => match rhs {
-                Op::Insert(rhs) => lhs.transform(rhs, priority).into(),
+                Op::Insert(rhs) => lhs.transform(rhs, priority),
                 Op::Retain(rhs) => lhs.transform(rhs, priority).into(),
                 Op::Delete(rhs) => lhs.transform(rhs, priority).into(),
             },
@@ -205,20 +207,303 @@ where
 impl<T, A> Transform<Delta<T, A>> for Delta<T, A>
 where
     T: Clone + Default + Seq + Extend<T> + Debug,
-    A: Clone + Default + PartialEq + Debug,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
 {
     type Output = Delta<T, A>;
 
     fn transform(self, rhs: Delta<T, A>, priority: bool) -> Self::Output {
-        let mut self_iter = self.into_iter();
-        let mut other_iter = rhs.into_iter();
+        let mut result = Delta::new();
+        self.transform_into(rhs, priority, &mut result);
+        result
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Clone + Default + Seq + Extend<T> + Debug,
+    A: Clone + Default + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Transforms this delta with the given delta in place, i.e. replaces this
+    /// delta with `against.transform(self, priority)` (so `priority` is that
+    /// of the given delta). Both deltas apply to the same document.
+    ///
+    /// The operations of this delta that precede the position of the first
+    /// change of the given delta (i.e. those covered by its leading retain
+    /// without attributes) are left in place, and only the rest of this delta
+    /// is transformed. This makes rebasing a long delta (e.g. an offline
+    /// queue) onto a change near its end cheap. The given delta is walked with
+    /// a [`RefIter`](crate::RefIter), so only the ops that are reached are
+    /// cloned.
+    pub fn transform_assign(&mut self, against: &Delta<T, A>, priority: bool) {
+        let mut against_iter = against.cursor();
+
+        let retain = match against_iter.next_mut() {
+            Some(Op::Retain(Retain {
+                retain,
+                attributes: None,
+            })) => *retain,
+            _ => 0,
+        };
+
+        let mut kept = 0usize;
+        let mut index = 0;
+
+        for op in self.ops() {
+            let fits = match op {
+                Op::Insert(_) => kept < retain,
+                op => match kept.checked_add(op.len()) {
+                    Some(len) if len <= retain => {
+                        kept = len;
+                        true
+                    }
+                    _ => false,
+                },
+            };
+
+            if !fits {
+                break;
+            }
+
+            index += 1;
+        }
+
+        if let Some(Op::Retain(against)) = against_iter.next_mut() {
+            against.retain -= kept;
+        }
+
+        let mut cursor = OpPairCursor::new(against_iter, self.split_off(index).into_iter());
+
+        self.extend(from_fn(|| {
+            cursor
+                .next_mut()
+                .map(|(against, op)| against.transform(op, priority))
+        }));
+        self.extend(cursor.into_inner().1);
+        self.chop_in_place();
+    }
+
+    /// Transforms this delta with each of the given deltas in order, where
+    /// this delta and the first given delta apply to the same document and
+    /// each given delta applies after the one before it (e.g. the changes that
+    /// a server accepted since the revision that this delta is based on). Like
+    /// [`Delta::transform_assign`], `priority` is that of the given deltas.
+    ///
+    /// This streams through the given deltas with
+    /// [`Delta::transform_assign`], so this delta is the only intermediate
+    /// and its ops before the first change of each given delta are left in
+    /// place. To transform many deltas through the same history, transform
+    /// them with a [`LazyDelta`](crate::LazyDelta) of that history instead,
+    /// which composes it only once.
+    pub fn transform_through(mut self, history: &[Delta<T, A>], priority: bool) -> Delta<T, A> {
+        for delta in history {
+            self.transform_assign(delta, priority);
+        }
+
+        self
+    }
+
+    /// Transforms the given delta with this delta like [`Transform`], but
+    /// writes the result into the given delta, whose previous operations are
+    /// discarded. Like [`Delta::compose_into`], this reuses the buffer of that
+    /// delta.
+    pub fn transform_into(self, rhs: Delta<T, A>, priority: bool, out: &mut Delta<T, A>) {
+        self.transform_untrimmed_into(rhs, priority, out);
+        out.chop_in_place();
+    }
 
+    /// Transforms the given delta with this delta like [`Transform`], but keeps
+    /// trailing retains that don't update any attributes (see
+    /// [`Delta::compose_untrimmed`]).
+    pub fn transform_untrimmed(self, rhs: Delta<T, A>, priority: bool) -> Delta<T, A> {
         let mut result = Delta::new();
+        self.transform_untrimmed_into(rhs, priority, &mut result);
+        result
+    }
+
+    fn transform_untrimmed_into(self, rhs: Delta<T, A>, priority: bool, out: &mut Delta<T, A>) {
+        out.clear();
+        transform_ops(self.into_iter(), rhs.into_iter(), priority, out);
+    }
+
+    /// Strict counterpart of [`Transform`] that transforms the given delta with
+    /// this delta, where both deltas are applied to a document of the given
+    /// length. Unlike [`Transform`], this function returns an error if either
+    /// delta consumes more elements than that document contains or isn't
+    /// well-formed (see [`Delta::check_applicable`]).
+    pub fn try_transform(
+        self,
+        rhs: Delta<T, A>,
+        priority: bool,
+        base_len: usize,
+    ) -> Result<Delta<T, A>, Error> {
+        self.check_applicable(base_len)?;
+        rhs.check_applicable(base_len)?;
+
+        Ok(self.transform(rhs, priority))
+    }
+
+    /// Transforms the given delta with this delta using the given mode, where
+    /// both deltas are applied to a document of the given length. In
+    /// [`ApplyMode::Strict`], this is equivalent to [`Delta::try_transform`].
+    /// In [`ApplyMode::Lenient`], this is equivalent to [`Transform`] and never
+    /// returns an error.
+    pub fn transform_with(
+        self,
+        rhs: Delta<T, A>,
+        priority: bool,
+        base_len: usize,
+        mode: ApplyMode,
+    ) -> Result<Delta<T, A>, Error> {
+        match mode {
+            ApplyMode::Strict => self.try_transform(rhs, priority, base_len),
+            ApplyMode::Lenient => Ok(self.transform(rhs, priority)),
+        }
+    }
+}
+
+/// Transforms the ops of `rhs` with the ops of `lhs` (see [`Transform`]) and
+/// appends the untrimmed result to the given ops.
+pub(crate) fn transform_ops<T, A, I, E>(
+    mut lhs: Cursor<Op<T, A>, I>,
+    mut rhs: Cursor<Op<T, A>, I>,
+    priority: bool,
+    out: &mut E,
+) where
+    T: Clone + Default + Seq + Extend<T>,
+    A: Clone + Default + PartialEq + Compose<A, Output = A>,
+    I: Iterator<Item = Op<T, A>>,
+    E: Extend<Op<T, A>>,
+{
+    out.extend(lhs.zip_mut(&mut rhs, |a, b| a.transform(b, priority)));
+    out.extend(rhs);
+}
+
+#[cfg(feature = "rayon")]
+impl<T, A> Delta<T, A>
+where
+    T: Clone + Default + Seq + Extend<T> + Debug + Send + Sync,
+    A: Clone + Default + PartialEq + Debug + Send + Sync + Compose<A, Output = A>,
+{
+    /// Transforms each of the given deltas with this delta in parallel, where
+    /// this delta and each of the given deltas apply to the same document.
+    /// This is equivalent to (but faster than) replacing each delta with
+    /// `self.clone().transform(delta, priority)`.
+    ///
+    /// This function is only available with the `rayon` feature enabled.
+    pub fn transform_all(&self, deltas: &mut [Delta<T, A>], priority: bool) {
+        use rayon::prelude::*;
+
+        deltas.par_iter_mut().for_each(|delta| {
+            *delta = self.clone().transform(take(delta), priority);
+        });
+    }
+}
+
+impl<T, A> Delta<T, A>
+where
+    T: Clone + Default + Seq + Extend<T>,
+    A: Clone + Default + PartialEq,
+{
+    /// Transforms the given position through each of the given deltas in
+    /// order like [`Transform<usize>`], where the first delta applies to the
+    /// document that the position refers to and each other delta applies
+    /// after the one before it (e.g. to restore a cursor after replaying the
+    /// history that a client missed). See [`Delta::transform_positions_through`]
+    /// to transform many positions at once.
+    pub fn transform_position_through(
+        history: &[Delta<T, A>],
+        position: usize,
+        priority: bool,
+    ) -> usize {
+        let mut positions = [position];
+        Delta::transform_positions_through(history, &mut positions, priority);
+        positions[0]
+    }
+
+    /// Transforms each of the given positions in place through each of the
+    /// given deltas in order, like [`Delta::transform_position_through`]. The
+    /// positions are sorted once, after which each delta takes a single pass
+    /// over its ops and the positions (because transforming positions
+    /// preserves their order), rather than a pass per position.
+    pub fn transform_positions_through(
+        history: &[Delta<T, A>],
+        positions: &mut [usize],
+        priority: bool,
+    ) {
+        let mut order = (0..positions.len()).collect::<Vec<_>>();
+        order.sort_unstable_by_key(|&index| positions[index]);
+
+        // The positions are tracked as `i128`s so that the offsets of deltas
+        // with overly long retains and deletes don't overflow.
+        let mut sorted = Vec::with_capacity(order.len());
+
+        for &index in &order {
+            sorted.push(positions[index] as i128);
+        }
+
+        for delta in history {
+            delta.transform_sorted_positions(&mut sorted, priority);
+        }
+
+        for (index, position) in order.into_iter().zip(sorted) {
+            positions[index] = position as usize;
+        }
+    }
+
+    /// Transforms the given positions (which must be sorted) in place like
+    /// [`Transform<usize>`] in a single pass over the ops of this delta.
+    fn transform_sorted_positions(&self, positions: &mut [i128], priority: bool) {
+        // Positions before `done` are final. The others are still positions in
+        // the original document, and map to the transformed document by adding
+        // `new - old`, where `old` and `new` are the offsets that the ops so far
+        // span in both documents.
+        let mut done = 0;
+        let mut old = 0i128;
+        let mut new = 0i128;
+
+        for op in self.ops() {
+            if done == positions.len() {
+                return;
+            }
+
+            let len = op.len() as i128;
+
+            match op {
+                Op::Insert(_) => {
+                    if priority && len > 0 {
+                        while done < positions.len() && positions[done] == old {
+                            positions[done] = new;
+                            done += 1;
+                        }
+                    }
 
-        result.extend(self_iter.zip_mut(&mut other_iter, |a, b| a.transform(b, priority)));
-        result.extend(other_iter);
+                    new += len;
+                }
+                Op::Retain(_) => {
+                    while done < positions.len() && positions[done] < old + len {
+                        positions[done] += new - old;
+                        done += 1;
+                    }
+
+                    old += len;
+                    new += len;
+                }
+                Op::Delete(_) => {
+                    for position in positions[done..].iter_mut() {
+                        match *position <= old + len {
+                            true => *position = old + len,
+                            false => break,
+                        }
+                    }
+
+                    old += len;
+                }
+            }
+        }
 
-        result.chop()
+        for position in positions[done..].iter_mut() {
+            *position += new - old;
+        }
     }
 }
 
@@ -232,10 +517,8 @@ where
     fn transform(self, rhs: usize, priority: bool) -> Self::Output {
         let mut index = rhs;
         let mut offset = 0;
-        let mut iter = self.ops();
-
-        while let Some(op) = iter.next() {
-            if offset > rhs {
+        for op in self.ops() {
+            if offset > index {
                 break;
             }
 
@@ -262,7 +545,244 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::{Delta, Transform};
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::{Delta, Error, Transform};
+    use crate::LastWriteWins;
+
+    #[test]
+    fn test_try_transform() {
+        let a = Delta::<_, ()>::new()
+            .retain(2, None)
+            .insert("A".to_owned(), None);
+        let b = Delta::new().retain(1, None).delete(2);
+
+        assert_eq!(
+            a.clone().try_transform(b.clone(), true, 3),
+            Ok(Delta::new()
+                .retain(1, None)
+                .delete(1)
+                .retain(1, None)
+                .delete(1))
+        );
+        assert_eq!(
+            a.try_transform(b, true, 2),
+            Err(Error::LengthMismatch {
+                index: 1,
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_transform_into() {
+        let a = Delta::<_, ()>::new().insert("A".to_owned(), None);
+        let b = Delta::new().insert("B".to_owned(), None).retain(1, None);
+
+        let mut out = Delta::new().delete(3);
+        a.clone().transform_into(b.clone(), true, &mut out);
+
+        assert_eq!(out, a.transform(b, true));
+        assert_eq!(
+            out,
+            Delta::new().retain(1, None).insert("B".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn test_transform_untrimmed() {
+        let a = Delta::<_, ()>::new().insert("A".to_owned(), None);
+        let b = Delta::new().retain(1, None).delete(1).retain(2, None);
+
+        let mut transformed = a.clone().transform_untrimmed(b.clone(), true);
+
+        assert_eq!(
+            transformed,
+            Delta::new().retain(2, None).delete(1).retain(2, None)
+        );
+
+        transformed.trim();
+        assert_eq!(transformed, a.transform(b, true));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_transform_all() {
+        let incoming = Delta::<_, ()>::new().insert("Hello ".to_owned(), None);
+        let queued = (0..100)
+            .map(|index| {
+                Delta::new()
+                    .retain(index, None)
+                    .insert(index.to_string(), None)
+            })
+            .collect::<Vec<_>>();
+
+        let mut transformed = queued.clone();
+        incoming.transform_all(&mut transformed, true);
+
+        for (queued, transformed) in queued.into_iter().zip(transformed) {
+            assert_eq!(transformed, incoming.clone().transform(queued, true));
+        }
+    }
+
+    #[test]
+    fn test_transform_through() {
+        let history = [
+            Delta::<_, ()>::new()
+                .retain(1, None)
+                .insert("X".to_owned(), None),
+            Delta::new().retain(3, None).delete(1),
+            Delta::new().insert("Y".to_owned(), None),
+        ];
+        let delta = Delta::new().retain(2, None).insert("Z".to_owned(), None);
+
+        let stepwise = history.iter().fold(delta.clone(), |delta, past| {
+            past.clone().transform(delta, true)
+        });
+
+        assert_eq!(
+            delta.clone().transform_through(&history, true),
+            Delta::new().retain(4, None).insert("Z".to_owned(), None)
+        );
+        assert_eq!(delta.clone().transform_through(&history, true), stepwise);
+        assert_eq!(delta.clone().transform_through(&[], true), delta);
+    }
+
+    #[test]
+    fn test_transform_assign() {
+        let mut delta = Delta::<_, ()>::new()
+            .insert("ab".to_owned(), None)
+            .retain(3, None)
+            .delete(2)
+            .insert("c".to_owned(), None)
+            .retain(1, None);
+
+        let against = [
+            Delta::new().retain(5, None).insert("x".to_owned(), None),
+            Delta::new().retain(3, None).insert("y".to_owned(), None),
+            Delta::new().retain(2, None).delete(5),
+            Delta::new().insert("z".to_owned(), None),
+            Delta::new().retain(100, None),
+        ];
+
+        for against in against {
+            for priority in [false, true] {
+                let expected = against.clone().transform(delta.clone(), priority);
+
+                let mut actual = delta.clone();
+                actual.transform_assign(&against, priority);
+
+                assert_eq!(actual, expected);
+            }
+
+            delta.transform_assign(&against, true);
+        }
+    }
+
+    #[test]
+    fn test_transform_positions_through() {
+        let history = [
+            Delta::<_, ()>::new()
+                .retain(1, None)
+                .insert("X".to_owned(), None),
+            Delta::new().retain(2, None).delete(2),
+            Delta::new().retain(2, None).insert("YZ".to_owned(), None),
+        ];
+
+        let mut positions = [4, 0, 1, 3, 2];
+        Delta::transform_positions_through(&history, &mut positions, false);
+        assert_eq!(positions, [5, 0, 4, 4, 4]);
+
+        let mut positions = [4, 0, 1, 3, 2];
+        Delta::transform_positions_through(&history, &mut positions, true);
+        assert_eq!(positions, [5, 0, 1, 2, 2]);
+
+        assert_eq!(Delta::transform_position_through(&history, 5, true), 6);
+        assert_eq!(
+            Delta::<String, ()>::transform_position_through(&[], 5, true),
+            5
+        );
+    }
+
+    #[test]
+    fn test_transform_positions_arbitrary() {
+        let mut state = 0xbb67ae8584caa73bu64;
+        let bytes = (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let mut data = Unstructured::new(&bytes);
+
+        for _ in 0..1000 {
+            // Arbitrary deltas can retain up to `usize::MAX` items, which
+            // overflows the offsets of the per-position transform that we
+            // compare against, so the lengths are kept small here.
+            let history = [(); 3].map(|_| {
+                (0..u8::arbitrary(&mut data).unwrap() % 8).fold(
+                    Delta::<String, ()>::new(),
+                    |delta, _| {
+                        let len = u8::arbitrary(&mut data).unwrap() as usize % 4 + 1;
+
+                        match u8::arbitrary(&mut data).unwrap() % 3 {
+                            0 => delta.insert("x".repeat(len), None),
+                            1 => delta.retain(len, None),
+                            _ => delta.delete(len),
+                        }
+                    },
+                )
+            });
+            let priority = bool::arbitrary(&mut data).unwrap();
+            let mut positions = (0..8)
+                .map(|_| u8::arbitrary(&mut data).unwrap() as usize % 32)
+                .collect::<Vec<_>>();
+
+            let expected = positions
+                .iter()
+                .map(|&position| {
+                    history.iter().fold(position, |position, delta| {
+                        delta.transform(position, priority)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            Delta::transform_positions_through(&history, &mut positions, priority);
+
+            assert_eq!(positions, expected);
+        }
+    }
+
+    #[test]
+    fn test_transform_assign_arbitrary() {
+        let mut state = 0x6a09e667f3bcc908u64;
+        let bytes = (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let mut data = Unstructured::new(&bytes);
+
+        for _ in 0..1000 {
+            let [mut lhs, rhs] = [(); 2].map(|_| {
+                Delta::<String, LastWriteWins<usize>>::arbitrary(&mut data)
+                    .unwrap()
+                    .normalize()
+            });
+            let priority = bool::arbitrary(&mut data).unwrap();
+
+            let expected = rhs.clone().transform(lhs.clone(), priority);
+            lhs.transform_assign(&rhs, priority);
+
+            assert_eq!(lhs, expected);
+        }
+    }
 
     #[test]
     fn test_insert_before_position() {
@@ -287,4 +807,16 @@ mod test {
         assert_eq!((&delta).transform(2, true), 2);
         assert_eq!((&delta).transform(2, false), 3);
     }
+
+    #[test]
+    fn test_delete_before_position() {
+        let delta = Delta::<String, ()>::new()
+            .retain(1, None)
+            .delete(1)
+            .retain(1, None)
+            .delete(1);
+
+        assert_eq!((&delta).transform(2, true), 1);
+        assert_eq!((&delta).transform(4, true), 2);
+    }
 }
@@ -0,0 +1,587 @@
+//! Operational transformation of trees.
+//!
+//! Structured documents (e.g. outlines or slide decks) are trees of [`Node`]s,
+//! each of which has contents (a [`Document`]) and an ordered list of children.
+//! Nodes are addressed by paths, i.e. the indices of the children to descend
+//! into starting from the root, so the empty path addresses the root itself.
+//!
+//! A [`TreeDelta`] is a sequence of [`TreeOp`]s that insert, remove, move or
+//! edit nodes. Like [`Delta`], tree deltas implement [`Compose`] and
+//! [`Transform`].
+
+use std::fmt::Debug;
+use std::mem::take;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Compose, Delta, Document, Error, Len, Seq, Transform};
+
+/// Path of a node, i.e. the indices of the children to descend into starting
+/// from the root.
+pub type Path = Vec<usize>;
+
+/// Node of a tree with contents and an ordered list of children.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Clone + Serialize, A: Clone + Serialize",
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub struct Node<T, A> {
+    /// Contains the contents of this node.
+    #[serde(default)]
+    pub content: Document<T, A>,
+
+    /// Contains the children of this node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node<T, A>>,
+}
+
+impl<T, A> Node<T, A> {
+    /// Returns a new node with the given contents and without any children.
+    pub fn new(content: Document<T, A>) -> Node<T, A> {
+        Node {
+            content,
+            children: vec![],
+        }
+    }
+
+    /// Returns the node at the given path, if any.
+    pub fn get(&self, path: &[usize]) -> Option<&Node<T, A>> {
+        match path.split_first() {
+            Some((index, rest)) => self.children.get(*index)?.get(rest),
+            None => Some(self),
+        }
+    }
+
+    fn get_mut(&mut self, path: &[usize]) -> Option<&mut Node<T, A>> {
+        match path.split_first() {
+            Some((index, rest)) => self.children.get_mut(*index)?.get_mut(rest),
+            None => Some(self),
+        }
+    }
+}
+
+impl<T, A> Default for Node<T, A> {
+    fn default() -> Self {
+        Node::new(Document::new())
+    }
+}
+
+impl<T, A> Node<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change to the tree rooted at this node, or returns an
+    /// error if one of its operations refers to a node that doesn't exist. In
+    /// that case, this tree is left unchanged.
+    pub fn apply(&mut self, change: &TreeDelta<T, A>) -> Result<(), Error> {
+        let mut node = self.clone();
+
+        for (index, op) in change.ops().iter().enumerate() {
+            node.apply_op(op).ok_or(Error::InvalidPath { index })?;
+        }
+
+        *self = node;
+        Ok(())
+    }
+
+    fn apply_op(&mut self, op: &TreeOp<T, A>) -> Option<()> {
+        match op {
+            TreeOp::Insert { path, node } => {
+                let (index, parent) = path.split_last()?;
+                let parent = self.get_mut(parent)?;
+
+                if *index > parent.children.len() {
+                    return None;
+                }
+
+                parent.children.insert(*index, node.clone());
+            }
+            TreeOp::Remove { path } => {
+                let (index, parent) = path.split_last()?;
+                let parent = self.get_mut(parent)?;
+
+                if *index >= parent.children.len() {
+                    return None;
+                }
+
+                parent.children.remove(*index);
+            }
+            TreeOp::Move { path, to } => {
+                let (index, parent) = path.split_last()?;
+                let parent = self.get_mut(parent)?;
+
+                if *index >= parent.children.len() || *to >= parent.children.len() {
+                    return None;
+                }
+
+                let node = parent.children.remove(*index);
+                parent.children.insert(*to, node);
+            }
+            TreeOp::Edit { path, delta } => {
+                self.get_mut(path)?.content.apply(delta.clone());
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// Individual operation on a tree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+#[serde(bound(
+    serialize = "T: Clone + Serialize, A: Clone + Serialize",
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub enum TreeOp<T, A> {
+    /// Inserts the given node at the given path.
+    Insert {
+        /// Contains the path that the inserted node will have.
+        path: Path,
+
+        /// Contains the node to insert.
+        node: Node<T, A>,
+    },
+
+    /// Removes the node at the given path (including its children).
+    Remove {
+        /// Contains the path of the node to remove.
+        path: Path,
+    },
+
+    /// Moves the node at the given path to another position among its
+    /// siblings. To move a node to another parent, remove it and insert it
+    /// again.
+    Move {
+        /// Contains the path of the node to move.
+        path: Path,
+
+        /// Contains the index that the node will have among its siblings after
+        /// it has been moved.
+        to: usize,
+    },
+
+    /// Applies the given delta to the contents of the node at the given path.
+    Edit {
+        /// Contains the path of the node to edit.
+        path: Path,
+
+        /// Contains the change to apply to the contents of the node.
+        delta: Delta<T, A>,
+    },
+}
+
+/// Sequence of operations on a tree that are applied in order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Clone + Serialize, A: Clone + Serialize",
+    deserialize = "T: Len + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub struct TreeDelta<T, A> {
+    ops: Vec<TreeOp<T, A>>,
+}
+
+impl<T, A> TreeDelta<T, A> {
+    /// Returns a new empty tree delta.
+    pub fn new() -> TreeDelta<T, A> {
+        TreeDelta { ops: vec![] }
+    }
+
+    /// Inserts the given node at the given path.
+    pub fn insert(mut self, path: Path, node: Node<T, A>) -> Self {
+        self.ops.push(TreeOp::Insert { path, node });
+        self
+    }
+
+    /// Removes the node at the given path.
+    pub fn remove(mut self, path: Path) -> Self {
+        self.ops.push(TreeOp::Remove { path });
+        self
+    }
+
+    /// Moves the node at the given path to the given index among its siblings.
+    pub fn move_to(mut self, path: Path, to: usize) -> Self {
+        self.ops.push(TreeOp::Move { path, to });
+        self
+    }
+
+    /// Applies the given delta to the contents of the node at the given path.
+    pub fn edit(mut self, path: Path, delta: Delta<T, A>) -> Self {
+        self.ops.push(TreeOp::Edit { path, delta });
+        self
+    }
+
+    /// Returns the operations of this tree delta.
+    pub fn ops(&self) -> &[TreeOp<T, A>] {
+        &self.ops
+    }
+}
+
+impl<T, A> Default for TreeDelta<T, A> {
+    fn default() -> Self {
+        TreeDelta::new()
+    }
+}
+
+/// Appends the operations of the given tree delta to this tree delta, where
+/// consecutive edits of the same node are composed into a single edit.
+impl<T, A> Compose<TreeDelta<T, A>> for TreeDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = TreeDelta<T, A>;
+
+    fn compose(mut self, rhs: TreeDelta<T, A>) -> Self::Output {
+        for op in rhs.ops {
+            match (self.ops.last_mut(), op) {
+                (
+                    Some(TreeOp::Edit { path, delta }),
+                    TreeOp::Edit {
+                        path: rhs_path,
+                        delta: rhs_delta,
+                    },
+                ) if path == &rhs_path => {
+                    *delta = take(delta).compose(rhs_delta);
+                }
+                (_, op) => self.ops.push(op),
+            }
+        }
+
+        self
+    }
+}
+
+/// Transforms each operation of the given tree delta with the operations of
+/// this tree delta (that are themselves transformed with the preceding
+/// operations of the given tree delta). Operations on nodes that this tree
+/// delta removes are dropped.
+impl<T, A> Transform<TreeDelta<T, A>> for TreeDelta<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    type Output = TreeDelta<T, A>;
+
+    fn transform(self, rhs: TreeDelta<T, A>, priority: bool) -> Self::Output {
+        let mut lhs = self.ops;
+        let mut result = TreeDelta::new();
+
+        for op in rhs.ops {
+            let mut op = Some(op);
+            let mut next = Vec::with_capacity(lhs.len());
+
+            for lhs_op in lhs {
+                match op.take() {
+                    Some(rhs_op) => {
+                        next.extend(transform_op(&rhs_op, lhs_op.clone(), !priority));
+                        op = transform_op(&lhs_op, rhs_op, priority);
+                    }
+                    None => next.push(lhs_op),
+                }
+            }
+
+            lhs = next;
+            result.ops.extend(op);
+        }
+
+        result
+    }
+}
+
+/// Returns the given index of a child of a list after removing the child at
+/// index `from` from that list and inserting it at index `to`.
+fn move_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        return to;
+    }
+
+    let index = index - (index > from) as usize;
+    index + (index >= to) as usize
+}
+
+/// Returns true if the given path addresses a child (or a descendant of a
+/// child) of the same list as the given path of a sibling.
+fn same_list(path: &[usize], sibling: &[usize]) -> bool {
+    !sibling.is_empty()
+        && path.len() >= sibling.len()
+        && path[..sibling.len() - 1] == sibling[..sibling.len() - 1]
+}
+
+/// Returns the path of the node at the given path after applying the given
+/// operation, or `None` if the given operation removes that node.
+fn transform_path<T, A>(op: &TreeOp<T, A>, mut path: Path) -> Option<Path> {
+    match op {
+        TreeOp::Insert { path: other, .. } => {
+            let depth = other.len().saturating_sub(1);
+
+            if same_list(&path, other) && path[depth] >= other[depth] {
+                path[depth] += 1;
+            }
+        }
+        TreeOp::Remove { path: other } => {
+            let depth = other.len().saturating_sub(1);
+
+            if !other.is_empty() && path.starts_with(other) {
+                return None;
+            }
+
+            if same_list(&path, other) && path[depth] > other[depth] {
+                path[depth] -= 1;
+            }
+        }
+        TreeOp::Move { path: other, to } => {
+            let depth = other.len().saturating_sub(1);
+
+            if same_list(&path, other) {
+                path[depth] = move_index(path[depth], other[depth], *to);
+            }
+        }
+        TreeOp::Edit { .. } => {}
+    }
+
+    Some(path)
+}
+
+/// Returns the given index at which a child is inserted into a list after
+/// inserting another child at index `other`.
+pub(crate) fn insert_after_insert(index: usize, other: usize, priority: bool) -> usize {
+    index + (other < index || (other == index && priority)) as usize
+}
+
+/// Returns the given index at which a child is inserted into a list after
+/// removing the child at index `other`.
+pub(crate) fn insert_after_remove(index: usize, other: usize) -> usize {
+    index - (other < index) as usize
+}
+
+/// Transforms the given operation with the given (already applied) operation,
+/// or returns `None` if the given operation no longer has any effect.
+///
+/// Within a single list, a move is treated as removing a child followed by
+/// inserting it again, so that moves are transformed with the same rules as
+/// inserts and removes.
+fn transform_op<T, A>(lhs: &TreeOp<T, A>, rhs: TreeOp<T, A>, priority: bool) -> Option<TreeOp<T, A>>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    match rhs {
+        TreeOp::Insert { mut path, node } => {
+            let depth = path.len().saturating_sub(1);
+
+            match lhs {
+                TreeOp::Insert { path: other, .. }
+                    if other.len() == path.len() && same_list(&path, other) =>
+                {
+                    path[depth] = insert_after_insert(path[depth], other[depth], priority);
+                }
+                TreeOp::Remove { path: other }
+                    if other.len() == path.len() && same_list(&path, other) =>
+                {
+                    path[depth] = insert_after_remove(path[depth], other[depth]);
+                }
+                TreeOp::Move { path: other, to }
+                    if other.len() == path.len() && same_list(&path, other) =>
+                {
+                    let index = insert_after_remove(path[depth], other[depth]);
+                    path[depth] = insert_after_insert(index, *to, priority);
+                }
+                lhs => {
+                    let (index, parent) = path.split_last()?;
+                    let mut parent = transform_path(lhs, parent.to_vec())?;
+                    parent.push(*index);
+                    path = parent;
+                }
+            }
+
+            Some(TreeOp::Insert { path, node })
+        }
+        TreeOp::Remove { path } => {
+            let path = transform_path(lhs, path)?;
+            Some(TreeOp::Remove { path })
+        }
+        TreeOp::Move { path, to } => {
+            let depth = path.len().saturating_sub(1);
+            let from = *path.last()?;
+
+            let to = match lhs {
+                TreeOp::Insert { path: other, .. }
+                    if other.len() == path.len() && same_list(&path, other) =>
+                {
+                    let other = insert_after_remove(other[depth], from);
+                    insert_after_insert(to, other, priority)
+                }
+                TreeOp::Remove { path: other }
+                    if other.len() == path.len() && same_list(&path, other) =>
+                {
+                    let other = other[depth];
+
+                    if other == from {
+                        return None;
+                    }
+
+                    insert_after_remove(to, other - (other > from) as usize)
+                }
+                TreeOp::Move {
+                    path: other,
+                    to: other_to,
+                } if other.len() == path.len() && same_list(&path, other) => {
+                    let other_from = other[depth];
+
+                    if other_from == from {
+                        return match priority {
+                            true => None,
+                            false => {
+                                let mut path = path;
+                                path[depth] = *other_to;
+                                Some(TreeOp::Move { path, to })
+                            }
+                        };
+                    }
+
+                    let removed = other_from - (other_from > from) as usize;
+                    let other_to =
+                        insert_after_remove(*other_to, from - (from > other_from) as usize);
+                    let to = insert_after_remove(to, removed);
+                    insert_after_insert(to, other_to, priority)
+                }
+                _ => to,
+            };
+
+            let path = transform_path(lhs, path)?;
+            Some(TreeOp::Move { path, to })
+        }
+        TreeOp::Edit { path, delta } => {
+            let path = transform_path(lhs, path)?;
+
+            let delta = match lhs {
+                TreeOp::Edit {
+                    path: other,
+                    delta: other_delta,
+                } if other == &path => other_delta.clone().transform(delta, priority),
+                _ => delta,
+            };
+
+            Some(TreeOp::Edit { path, delta })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compose, Delta, Document, Transform};
+
+    use super::{Node, TreeDelta};
+
+    type Tree = Node<String, ()>;
+
+    fn node(text: &str) -> Tree {
+        Node::new(Document::from_delta(Delta::new().insert(text.to_owned(), None)).unwrap())
+    }
+
+    fn list(len: usize) -> Tree {
+        Node {
+            children: (0..len).map(|index| node(&index.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn changes(len: usize) -> Vec<TreeDelta<String, ()>> {
+        let mut changes = vec![];
+
+        for index in 0..=len {
+            changes.push(TreeDelta::new().insert(vec![index], node("x")));
+        }
+
+        for index in 0..len {
+            changes.push(TreeDelta::new().remove(vec![index]));
+            changes.push(
+                TreeDelta::new().edit(vec![index], Delta::new().insert("y".to_owned(), None)),
+            );
+
+            for to in 0..len {
+                changes.push(TreeDelta::new().move_to(vec![index], to));
+            }
+        }
+
+        changes
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut tree = list(3);
+
+        tree.apply(
+            &TreeDelta::new()
+                .move_to(vec![0], 2)
+                .insert(vec![0, 0], node("x"))
+                .edit(vec![0, 0], Delta::new().insert("y".to_owned(), None)),
+        )
+        .unwrap();
+
+        assert_eq!(tree.children.len(), 3);
+        assert_eq!(tree.get(&[2]), Some(&node("0")));
+        assert_eq!(tree.get(&[0, 0]), Some(&node("yx")));
+        assert!(tree.apply(&TreeDelta::new().remove(vec![5])).is_err());
+        assert_eq!(tree.children.len(), 3);
+    }
+
+    #[test]
+    fn test_compose() {
+        let lhs =
+            TreeDelta::<String, ()>::new().edit(vec![0], Delta::new().insert("a".to_owned(), None));
+        let rhs = TreeDelta::new().edit(
+            vec![0],
+            Delta::new().retain(1, None).insert("b".to_owned(), None),
+        );
+
+        assert_eq!(
+            lhs.compose(rhs),
+            TreeDelta::new().edit(vec![0], Delta::new().insert("ab".to_owned(), None))
+        );
+    }
+
+    #[test]
+    fn test_transform_converges() {
+        let before = list(3);
+
+        for alice in changes(3) {
+            for bob in changes(3) {
+                for priority in [true, false] {
+                    let mut left = before.clone();
+                    left.apply(&alice).unwrap();
+                    left.apply(&alice.clone().transform(bob.clone(), priority))
+                        .unwrap();
+
+                    let mut right = before.clone();
+                    right.apply(&bob).unwrap();
+                    right
+                        .apply(&bob.clone().transform(alice.clone(), !priority))
+                        .unwrap();
+
+                    assert_eq!(left, right, "{alice:?} {bob:?} {priority}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_nested() {
+        let before = Node {
+            children: vec![list(2), list(2)],
+            ..Default::default()
+        };
+
+        let alice = TreeDelta::new().move_to(vec![0], 1);
+        let bob = TreeDelta::new().edit(vec![0, 1], Delta::new().insert("y".to_owned(), None));
+
+        let mut tree = before.clone();
+        tree.apply(&alice).unwrap();
+        tree.apply(&alice.transform(bob, true)).unwrap();
+
+        assert_eq!(tree.get(&[1, 1]), Some(&node("y1")));
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt::Debug;
+
+use unicode_normalization::{is_nfc, is_nfd, is_nfkc, is_nfkd, UnicodeNormalization};
+
+use super::ops::Insert;
+use super::{Compose, Delta, Document, Op};
+
+/// Unicode normalization form that inserted text can be converted into, so that
+/// clients on different platforms (which may produce composed or decomposed
+/// characters for the same input) don't create documents that look the same
+/// but differ in their bytes, and therefore in their
+/// [content hash](Document::content_hash).
+///
+/// Normalization is applied to each insert on its own. Text that's inserted
+/// next to existing text (e.g. a combining accent after a letter) isn't merged
+/// with that text, because that would change the length of the document behind
+/// the back of every concurrent change.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Normalization {
+    /// Canonical decomposition followed by canonical composition, which is
+    /// what most platforms produce and what the web recommends.
+    #[default]
+    Nfc,
+
+    /// Canonical decomposition.
+    Nfd,
+
+    /// Compatibility decomposition followed by canonical composition.
+    Nfkc,
+
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+impl Normalization {
+    /// Returns true if the given text is already in this normalization form.
+    pub fn is_normalized(self, text: &str) -> bool {
+        match self {
+            Normalization::Nfc => is_nfc(text),
+            Normalization::Nfd => is_nfd(text),
+            Normalization::Nfkc => is_nfkc(text),
+            Normalization::Nfkd => is_nfkd(text),
+        }
+    }
+
+    /// Returns the given text in this normalization form. Text that's already
+    /// normalized is returned as is, without reallocating.
+    pub fn normalize(self, text: String) -> String {
+        if self.is_normalized(&text) {
+            return text;
+        }
+
+        match self {
+            Normalization::Nfc => text.nfc().collect(),
+            Normalization::Nfd => text.nfd().collect(),
+            Normalization::Nfkc => text.nfkc().collect(),
+            Normalization::Nfkd => text.nfkd().collect(),
+        }
+    }
+}
+
+impl<A> Delta<String, A>
+where
+    A: Clone + Default + PartialEq,
+{
+    /// Returns this delta with the text of every insert converted into the
+    /// given normalization form. Retains and deletes are left unchanged, so the
+    /// result applies to the same documents as this delta.
+    pub fn normalize_unicode(self, form: Normalization) -> Self {
+        self.into_iter()
+            .map(|op| match op {
+                Op::Insert(insert) => {
+                    let (insert, attributes) = insert.into_parts();
+                    Op::Insert(Insert::new(form.normalize(insert), attributes))
+                }
+                op => op,
+            })
+            .collect()
+    }
+
+    /// Returns a new delta with one insert operation with the given text in the
+    /// given normalization form. See [`Delta::insert`].
+    pub fn insert_normalized(
+        self,
+        insert: String,
+        attributes: impl Into<Option<A>>,
+        form: Normalization,
+    ) -> Self {
+        self.insert(form.normalize(insert), attributes)
+    }
+}
+
+impl<A> Document<String, A>
+where
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Applies the given change to this document like [`Document::apply`],
+    /// after converting the text of its inserts into the given normalization
+    /// form (see [`Delta::normalize_unicode`]).
+    ///
+    /// Returns the normalized change, which should be sent to other clients
+    /// instead of the given change so that they converge.
+    pub fn apply_normalized(
+        &mut self,
+        change: Delta<String, A>,
+        form: Normalization,
+    ) -> Delta<String, A> {
+        let change = change.normalize_unicode(form);
+        self.apply(change.clone());
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Normalization;
+    use crate::{Delta, Document};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(Normalization::Nfc.normalize("e\u{301}".to_owned()), "é");
+        assert_eq!(Normalization::Nfd.normalize("é".to_owned()), "e\u{301}");
+        assert_eq!(Normalization::Nfkc.normalize("ﬁ".to_owned()), "fi");
+        assert!(Normalization::Nfkd.is_normalized("fi"));
+        assert!(!Normalization::Nfc.is_normalized("e\u{301}"));
+    }
+
+    #[test]
+    fn test_apply_normalized() {
+        let mut mac =
+            Document::from_delta(Delta::<_, ()>::new().insert("Cafe\n".to_owned(), None)).unwrap();
+        let mut windows = mac.clone();
+
+        let change = mac.apply_normalized(
+            Delta::new()
+                .retain(3, None)
+                .delete(1)
+                .insert("e\u{301}".to_owned(), None),
+            Normalization::Nfc,
+        );
+
+        assert_eq!(
+            change,
+            Delta::new()
+                .retain(3, None)
+                .insert("é".to_owned(), None)
+                .delete(1)
+        );
+
+        windows.apply_normalized(
+            Delta::new()
+                .retain(3, None)
+                .insert_normalized("é".to_owned(), None, Normalization::Nfc)
+                .delete(1),
+            Normalization::Nfc,
+        );
+
+        assert_eq!(mac.content_hash(), windows.content_hash());
+        assert_eq!(mac.len(), 5);
+    }
+}
@@ -0,0 +1,810 @@
+//! Validation of incoming deltas before a server transforms and applies them.
+//!
+//! A server usually needs a few checks on every submitted delta: that it
+//! applies to the document at all, that it stays within size limits, that it
+//! doesn't touch protected parts of the document, and any number of business
+//! rules. Each check is a [`DeltaValidator`] that either rejects a delta with
+//! an [`Error`] or returns it (possibly rewritten, e.g. with some attributes
+//! removed). A [`ValidatorChain`] runs several validators in order, so a server
+//! loop only needs to call [`DeltaValidator::validate`] once per submission,
+//! or [`Document::apply_validated`] to validate and apply it in one go.
+//!
+//! ```
+//! use kyte::validate::{DeltaValidator, LengthCheck, Limits, ValidatorChain};
+//! use kyte::{Delta, Document};
+//!
+//! let chain = ValidatorChain::new().with(LengthCheck).with(Limits {
+//!     max_document_len: 10,
+//!     ..Limits::default()
+//! });
+//!
+//! let document = Document::from_delta(Delta::<_, ()>::new().insert("Hello".to_owned(), None))
+//!     .unwrap();
+//!
+//! assert!(chain
+//!     .validate(&document, Delta::new().retain(5, None).insert("!".to_owned(), None))
+//!     .is_ok());
+//! assert!(chain
+//!     .validate(&document, Delta::new().insert("Hello World".to_owned(), None))
+//!     .is_err());
+//! ```
+
+use std::fmt::{self, Debug};
+use std::ops::Range;
+
+use super::ops::Insert;
+use super::{Compose, Delta, Document, Error, HeapSize, Len, Op, Seq};
+
+mod schema;
+
+pub use schema::{AttributeSchema, AttributeType, AttributeValues, UnknownAttributes, ValueRef};
+
+/// Implemented by checks that an incoming delta must pass before it's applied
+/// to a document.
+pub trait DeltaValidator<T, A> {
+    /// Returns the given change, which applies to the given document, if it
+    /// passes this check. A validator may also rewrite the change, in which
+    /// case the server should apply (and broadcast) the returned delta instead.
+    fn validate(
+        &self,
+        document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error>;
+}
+
+/// Business rules can be written as closures.
+impl<T, A, F> DeltaValidator<T, A> for F
+where
+    F: Fn(&Document<T, A>, Delta<T, A>) -> Result<Delta<T, A>, Error>,
+{
+    fn validate(
+        &self,
+        document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        self(document, change)
+    }
+}
+
+/// Sequence of validators that runs each validator on the delta returned by
+/// the previous one, and stops at the first one that rejects it.
+pub struct ValidatorChain<T, A> {
+    validators: Vec<Box<dyn DeltaValidator<T, A> + Send + Sync>>,
+}
+
+impl<T, A> ValidatorChain<T, A> {
+    /// Returns a new chain without any validators, which accepts every delta.
+    pub fn new() -> ValidatorChain<T, A> {
+        ValidatorChain {
+            validators: Vec::new(),
+        }
+    }
+
+    /// Returns this chain with the given validator appended to it.
+    pub fn with<V>(mut self, validator: V) -> Self
+    where
+        V: DeltaValidator<T, A> + Send + Sync + 'static,
+    {
+        self.push(validator);
+        self
+    }
+
+    /// Appends the given validator to this chain.
+    pub fn push<V>(&mut self, validator: V)
+    where
+        V: DeltaValidator<T, A> + Send + Sync + 'static,
+    {
+        self.validators.push(Box::new(validator));
+    }
+
+    /// Returns the number of validators in this chain.
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Returns true if this chain doesn't contain any validators.
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+}
+
+impl<T, A> DeltaValidator<T, A> for ValidatorChain<T, A> {
+    fn validate(
+        &self,
+        document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        self.validators
+            .iter()
+            .try_fold(change, |change, validator| {
+                validator.validate(document, change)
+            })
+    }
+}
+
+impl<T, A> Default for ValidatorChain<T, A> {
+    fn default() -> Self {
+        ValidatorChain::new()
+    }
+}
+
+impl<T, A> fmt::Debug for ValidatorChain<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidatorChain")
+            .field("len", &self.validators.len())
+            .finish()
+    }
+}
+
+/// Validator that rejects deltas that aren't well-formed or extend past the
+/// end of the document (see [`Delta::check_applicable`]). This should usually
+/// be the first validator of a chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LengthCheck;
+
+impl<T, A> DeltaValidator<T, A> for LengthCheck
+where
+    T: Len,
+{
+    fn validate(
+        &self,
+        document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        change.check_applicable(document.len())?;
+        Ok(change)
+    }
+}
+
+/// Validator that rejects deltas that exceed any of the given limits with
+/// [`Error::LimitExceeded`]. Every limit defaults to `usize::MAX`, i.e. no
+/// limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Contains the maximum number of operations of a delta.
+    pub max_ops: usize,
+
+    /// Contains the maximum length of a single insert.
+    pub max_insert_len: usize,
+
+    /// Contains the maximum length of the document after the delta is applied.
+    pub max_document_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_ops: usize::MAX,
+            max_insert_len: usize::MAX,
+            max_document_len: usize::MAX,
+        }
+    }
+}
+
+impl<T, A> DeltaValidator<T, A> for Limits
+where
+    T: Len,
+{
+    fn validate(
+        &self,
+        document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        if change.ops_len() > self.max_ops {
+            return Err(Error::LimitExceeded {
+                index: self.max_ops,
+            });
+        }
+
+        let mut last_insert = None;
+
+        for (index, op) in change.ops().enumerate() {
+            if let Op::Insert(insert) = op {
+                if insert.len() > self.max_insert_len {
+                    return Err(Error::LimitExceeded { index });
+                }
+
+                last_insert = Some(index);
+            }
+        }
+
+        // Deltas without inserts never grow the document, so they're accepted
+        // even if the document already exceeds the limit.
+        match last_insert {
+            Some(index) if change.target_len(document.len()) > self.max_document_len => {
+                Err(Error::LimitExceeded { index })
+            }
+            _ => Ok(change),
+        }
+    }
+}
+
+/// Validator that rejects deltas that delete, insert into or update the
+/// attributes of any of the given ranges of the document with
+/// [`Error::Protected`]. Inserts at either end of a range are allowed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtectedRanges {
+    /// Contains the protected ranges of the document.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl ProtectedRanges {
+    /// Returns a validator that protects the given ranges.
+    pub fn new(ranges: impl IntoIterator<Item = Range<usize>>) -> ProtectedRanges {
+        ProtectedRanges {
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    fn overlaps(&self, range: Range<usize>) -> bool {
+        self.ranges
+            .iter()
+            .filter(|protected| !protected.is_empty())
+            .any(|protected| protected.start < range.end && range.start < protected.end)
+    }
+
+    fn contains_boundary(&self, position: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|protected| protected.start < position && position < protected.end)
+    }
+}
+
+impl<T, A> DeltaValidator<T, A> for ProtectedRanges
+where
+    T: Len,
+{
+    fn validate(
+        &self,
+        _document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        let mut position = 0usize;
+
+        for (index, op) in change.ops().enumerate() {
+            let end = position.saturating_add(op.len());
+
+            let protected = match op {
+                Op::Insert(_) => self.contains_boundary(position),
+                Op::Retain(retain) => retain.attributes.is_some() && self.overlaps(position..end),
+                Op::Delete(_) => self.overlaps(position..end),
+            };
+
+            if protected {
+                return Err(Error::Protected { index });
+            }
+
+            if !matches!(op, Op::Insert(_)) {
+                position = end;
+            }
+        }
+
+        Ok(change)
+    }
+}
+
+/// Implemented by attribute types whose formats are identified by keys (e.g.
+/// attribute maps or structs with a field per format), so that validators can
+/// inspect and remove formats by name.
+pub trait AttributeKeys {
+    /// Returns the keys of the formats that these attributes set or remove.
+    fn keys(&self) -> Vec<&str>;
+
+    /// Removes the format with the given key from these attributes, so that
+    /// they leave that format unchanged.
+    fn remove_key(&mut self, key: &str);
+}
+
+/// Determines what an [`AttributeFilter`] does with formats that aren't
+/// allowed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    /// Removes the formats from the delta, so that the rest of the delta is
+    /// still applied.
+    #[default]
+    Strip,
+
+    /// Rejects the entire delta with [`Error::ForbiddenAttribute`].
+    Reject,
+}
+
+/// Validator that only lets the submitting client set the formats whose keys
+/// the given function allows (e.g. only moderators may set `locked`) on
+/// inserts and retains, and strips or rejects all others. A server usually
+/// creates a filter per submission, based on the permissions of the client
+/// that submitted it.
+#[derive(Clone, Copy, Debug)]
+pub struct AttributeFilter<F> {
+    allowed: F,
+    mode: FilterMode,
+}
+
+impl<F> AttributeFilter<F>
+where
+    F: Fn(&str) -> bool,
+{
+    /// Returns a filter that allows the formats with the keys for which the
+    /// given function returns true and handles other formats with the given
+    /// mode.
+    pub fn new(allowed: F, mode: FilterMode) -> AttributeFilter<F> {
+        AttributeFilter { allowed, mode }
+    }
+
+    /// Returns a filter that removes formats that aren't allowed.
+    pub fn strip(allowed: F) -> AttributeFilter<F> {
+        AttributeFilter::new(allowed, FilterMode::Strip)
+    }
+
+    /// Returns a filter that rejects deltas that set formats that aren't
+    /// allowed.
+    pub fn reject(allowed: F) -> AttributeFilter<F> {
+        AttributeFilter::new(allowed, FilterMode::Reject)
+    }
+
+    /// Returns the given delta with the formats that aren't allowed removed,
+    /// or an error if this filter rejects them. Attributes without any allowed
+    /// formats are removed entirely. This doesn't need the document, so
+    /// clients can also apply it before submitting a delta.
+    pub fn filter<T, A>(&self, change: Delta<T, A>) -> Result<Delta<T, A>, Error>
+    where
+        T: Default + Clone + Seq + Extend<T>,
+        A: AttributeKeys + Clone + Default + PartialEq,
+    {
+        map_attributes(change, |index, attributes| {
+            self.filter_attributes(index, attributes)
+        })
+    }
+
+    fn filter_attributes<A>(&self, index: usize, attributes: Option<A>) -> Result<Option<A>, Error>
+    where
+        A: AttributeKeys,
+    {
+        let Some(mut attributes) = attributes else {
+            return Ok(None);
+        };
+
+        let forbidden = attributes
+            .keys()
+            .into_iter()
+            .filter(|key| !(self.allowed)(key))
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+
+        match (self.mode, forbidden.first()) {
+            (_, None) => return Ok(Some(attributes)),
+            (FilterMode::Reject, Some(key)) => {
+                return Err(Error::ForbiddenAttribute {
+                    index,
+                    key: key.clone(),
+                })
+            }
+            (FilterMode::Strip, Some(_)) => {}
+        }
+
+        for key in forbidden {
+            attributes.remove_key(&key);
+        }
+
+        Ok(Some(attributes).filter(|attributes| !attributes.keys().is_empty()))
+    }
+}
+
+impl<T, A, F> DeltaValidator<T, A> for AttributeFilter<F>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: AttributeKeys + Clone + Default + PartialEq,
+    F: Fn(&str) -> bool,
+{
+    fn validate(
+        &self,
+        _document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        self.filter(change)
+    }
+}
+
+/// Implemented by hooks that clean up (or reject) the inserts of incoming
+/// deltas before they're stored, e.g. to strip control characters, limit the
+/// size of embeds or rewrite the URLs of links. A sanitizer runs as a
+/// validator when it's wrapped in [`Sanitize`].
+pub trait InsertSanitizer<T, A> {
+    /// Returns the cleaned up version of the given insert, which is the op with
+    /// the given index in its delta, or an error if it must be rejected.
+    fn sanitize(&self, index: usize, insert: Insert<T, A>) -> Result<Insert<T, A>, Error>;
+}
+
+impl<T, A, F> InsertSanitizer<T, A> for F
+where
+    F: Fn(usize, Insert<T, A>) -> Result<Insert<T, A>, Error>,
+{
+    fn sanitize(&self, index: usize, insert: Insert<T, A>) -> Result<Insert<T, A>, Error> {
+        self(index, insert)
+    }
+}
+
+/// Validator that passes every insert of a delta through the given
+/// [`InsertSanitizer`]. Inserts that a sanitizer empties are removed, and
+/// adjacent inserts that end up with equal attributes are merged.
+///
+/// ```
+/// use kyte::attributes::{AttrValue, Attributes};
+/// use kyte::ops::Insert;
+/// use kyte::validate::Sanitize;
+/// use kyte::{Delta, Error};
+///
+/// // Routes external links through a redirect page.
+/// let links = Sanitize(|_, mut insert: Insert<String, Attributes>| {
+///     if let Some(AttrValue::String(url)) = insert
+///         .attributes
+///         .as_mut()
+///         .and_then(|attributes| attributes.get_mut("link"))
+///     {
+///         *url = format!("https://example.com/redirect?to={url}");
+///     }
+///
+///     Ok::<_, Error>(insert)
+/// });
+///
+/// let link = |url: &str| Attributes::from([("link".to_owned(), AttrValue::from(url))]);
+///
+/// assert_eq!(
+///     links.sanitize(Delta::new().insert("kyte".to_owned(), link("https://kyte.dev"))),
+///     Ok(Delta::new().insert(
+///         "kyte".to_owned(),
+///         link("https://example.com/redirect?to=https://kyte.dev")
+///     ))
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sanitize<S>(pub S);
+
+impl<S> Sanitize<S> {
+    /// Returns the given delta with every insert sanitized, or the first error
+    /// that the sanitizer returns. Like [`AttributeFilter::filter`], this
+    /// doesn't need the document.
+    pub fn sanitize<T, A>(&self, change: Delta<T, A>) -> Result<Delta<T, A>, Error>
+    where
+        T: Default + Clone + Seq + Extend<T>,
+        A: Clone + Default + PartialEq,
+        S: InsertSanitizer<T, A>,
+    {
+        change
+            .into_iter()
+            .enumerate()
+            .map(|(index, op)| match op {
+                Op::Insert(insert) => self.0.sanitize(index, insert).map(Op::Insert),
+                op => Ok(op),
+            })
+            .collect()
+    }
+}
+
+impl<T, A, S> DeltaValidator<T, A> for Sanitize<S>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Clone + Default + PartialEq,
+    S: InsertSanitizer<T, A>,
+{
+    fn validate(
+        &self,
+        _document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        self.sanitize(change)
+    }
+}
+
+/// Sanitizer that removes control characters other than newlines and tabs
+/// from inserted text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StripControl;
+
+impl<A> InsertSanitizer<String, A> for StripControl {
+    fn sanitize(
+        &self,
+        _index: usize,
+        insert: Insert<String, A>,
+    ) -> Result<Insert<String, A>, Error> {
+        let (mut insert, attributes) = insert.into_parts();
+        insert.retain(|c| !c.is_control() || c == '\n' || c == '\t');
+        Ok(Insert::new(insert, attributes))
+    }
+}
+
+/// Sanitizer that rejects inserts whose values own more than the given number
+/// of bytes on the heap (see [`HeapSize`]) with [`Error::LimitExceeded`], e.g.
+/// to limit the size of embedded images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxHeapSize(pub usize);
+
+impl<T, A> InsertSanitizer<T, A> for MaxHeapSize
+where
+    T: HeapSize,
+{
+    fn sanitize(&self, index: usize, insert: Insert<T, A>) -> Result<Insert<T, A>, Error> {
+        match insert.insert().heap_size() > self.0 {
+            true => Err(Error::LimitExceeded { index }),
+            false => Ok(insert),
+        }
+    }
+}
+
+impl<T, A> Document<T, A>
+where
+    T: Default + Clone + Seq + Extend<T> + Debug,
+    A: Default + Clone + PartialEq + Debug + Compose<A, Output = A>,
+{
+    /// Passes the given change through the given validator and applies the
+    /// result to this document like [`Document::try_apply`], so that nothing
+    /// is stored before it's validated and sanitized.
+    ///
+    /// Returns the applied change, which should be sent to other clients
+    /// instead of the given change. If the validator rejects the change or it
+    /// doesn't apply to this document, this document is left unchanged.
+    pub fn apply_validated<V>(
+        &mut self,
+        change: Delta<T, A>,
+        validator: &V,
+    ) -> Result<Delta<T, A>, Error>
+    where
+        V: DeltaValidator<T, A> + ?Sized,
+    {
+        let change = validator.validate(self, change)?;
+        self.try_apply(change.clone())?;
+        Ok(change)
+    }
+}
+
+/// Returns the given delta with the attributes of its inserts and retains
+/// replaced by the result of the given function, which is called with the
+/// index of each op.
+fn map_attributes<T, A, F>(change: Delta<T, A>, mut f: F) -> Result<Delta<T, A>, Error>
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: Clone + Default + PartialEq,
+    F: FnMut(usize, Option<A>) -> Result<Option<A>, Error>,
+{
+    change
+        .into_iter()
+        .enumerate()
+        .map(|(index, op)| match op {
+            Op::Insert(mut insert) => {
+                insert.attributes = f(index, insert.attributes)?;
+                Ok(Op::Insert(insert))
+            }
+            Op::Retain(mut retain) => {
+                retain.attributes = f(index, retain.attributes)?;
+                Ok(Op::Retain(retain))
+            }
+            op => Ok(op),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AttributeFilter, DeltaValidator, LengthCheck, Limits, MaxHeapSize, ProtectedRanges,
+        Sanitize, StripControl, ValidatorChain,
+    };
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::ops::Retain;
+    use crate::{Delta, Document, Error, Op};
+
+    fn document(text: &str) -> Document<String, bool> {
+        Document::from_delta(Delta::new().insert(text.to_owned(), None)).unwrap()
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits {
+            max_ops: 2,
+            max_insert_len: 3,
+            max_document_len: 6,
+        };
+        let document = document("abcd");
+
+        assert_eq!(
+            limits.validate(&document, Delta::new().insert("xy".to_owned(), None)),
+            Ok(Delta::new().insert("xy".to_owned(), None))
+        );
+        assert_eq!(
+            limits.validate(&document, Delta::new().insert("xyz".to_owned(), None)),
+            Err(Error::LimitExceeded { index: 0 })
+        );
+        assert_eq!(
+            limits.validate(
+                &document,
+                Delta::new().retain(1, None).insert("wxyz".to_owned(), None)
+            ),
+            Err(Error::LimitExceeded { index: 1 })
+        );
+        assert_eq!(
+            limits.validate(
+                &document,
+                Delta::new()
+                    .insert("x".to_owned(), None)
+                    .retain(1, true)
+                    .insert("y".to_owned(), None)
+            ),
+            Err(Error::LimitExceeded { index: 2 })
+        );
+        assert_eq!(
+            limits.validate(
+                &document,
+                Delta::new().retain(1, None).insert("xyz".to_owned(), None)
+            ),
+            Err(Error::LimitExceeded { index: 1 })
+        );
+        assert!(limits
+            .validate(
+                &document,
+                Delta::new().insert("xyz".to_owned(), None).delete(1)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_protected_ranges() {
+        let protected = ProtectedRanges::new([2..4, 10..12]);
+        let document = document("abcdef");
+        let validate =
+            |change: Delta<String, bool>| protected.validate(&document, change).map(|_| ());
+
+        assert_eq!(
+            validate(Delta::new().retain(2, None).insert("x".to_owned(), None)),
+            Ok(())
+        );
+        assert_eq!(
+            validate(Delta::new().retain(4, None).insert("x".to_owned(), None)),
+            Ok(())
+        );
+        assert_eq!(
+            validate(Delta::new().delete(2).retain(2, None).delete(2)),
+            Ok(())
+        );
+        assert_eq!(
+            validate(Delta::new().retain(4, true)),
+            Err(Error::Protected { index: 0 })
+        );
+        assert_eq!(
+            validate(Delta::new().retain(3, None).insert("x".to_owned(), None)),
+            Err(Error::Protected { index: 1 })
+        );
+        assert_eq!(
+            validate(Delta::new().retain(1, None).delete(2)),
+            Err(Error::Protected { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        let chain = ValidatorChain::new()
+            .with(LengthCheck)
+            .with(|_: &Document<String, bool>, change: Delta<String, bool>| {
+                Ok(change
+                    .into_iter()
+                    .map(|op| match op {
+                        Op::Insert(mut insert) => {
+                            insert.attributes = None;
+                            Op::Insert(insert)
+                        }
+                        Op::Retain(retain) => Op::Retain(Retain {
+                            attributes: None,
+                            ..retain
+                        }),
+                        op => op,
+                    })
+                    .collect())
+            })
+            .with(ProtectedRanges::new([0..1, 10..12]));
+        let document = document("abc");
+
+        assert_eq!(chain.len(), 3);
+        assert_eq!(
+            chain.validate(
+                &document,
+                Delta::new().retain(1, true).insert("x".to_owned(), true)
+            ),
+            Ok(Delta::new().retain(1, None).insert("x".to_owned(), None))
+        );
+        assert_eq!(
+            chain.validate(&document, Delta::new().delete(4)),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 3,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            chain.validate(&document, Delta::new().delete(1)),
+            Err(Error::Protected { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_attribute_filter() {
+        fn attributes(keys: &[&str]) -> Attributes {
+            keys.iter()
+                .map(|&key| (key.to_owned(), AttrValue::from(true)))
+                .collect()
+        }
+
+        let allowed = |key: &str| key != "locked";
+        let change = Delta::new()
+            .retain(1, attributes(&["bold", "locked"]))
+            .retain(1, attributes(&["locked"]))
+            .insert("x".to_owned(), attributes(&["italic"]));
+
+        assert_eq!(
+            AttributeFilter::strip(allowed).filter(change.clone()),
+            Ok(Delta::new()
+                .retain(1, attributes(&["bold"]))
+                .retain(1, None)
+                .insert("x".to_owned(), attributes(&["italic"])))
+        );
+        assert_eq!(
+            AttributeFilter::reject(allowed).filter(change),
+            Err(Error::ForbiddenAttribute {
+                index: 0,
+                key: "locked".to_owned()
+            })
+        );
+
+        let document = Document::from_delta(Delta::new().insert("ab".to_owned(), None)).unwrap();
+
+        assert_eq!(
+            AttributeFilter::reject(allowed).validate(
+                &document,
+                Delta::new().insert("x".to_owned(), attributes(&["bold"]))
+            ),
+            Ok(Delta::new().insert("x".to_owned(), attributes(&["bold"])))
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let mut document =
+            Document::from_delta(Delta::<_, ()>::new().insert("ab".to_owned(), None)).unwrap();
+        let chain = ValidatorChain::new()
+            .with(Sanitize(StripControl))
+            .with(Sanitize(MaxHeapSize(5)))
+            .with(LengthCheck);
+
+        assert_eq!(
+            document.apply_validated(
+                Delta::new()
+                    .insert("\u{7}\u{1b}[2J".to_owned(), ())
+                    .retain(1, None)
+                    .insert("c\td\n".to_owned(), ())
+                    .insert("\u{0}".to_owned(), None),
+                &chain
+            ),
+            Ok(Delta::new()
+                .insert("[2J".to_owned(), ())
+                .retain(1, None)
+                .insert("c\td\n".to_owned(), ()))
+        );
+        assert_eq!(document.len(), 9);
+
+        assert_eq!(
+            document.apply_validated(
+                Delta::new()
+                    .retain(1, None)
+                    .insert("hello!".to_owned(), None),
+                &chain
+            ),
+            Err(Error::LimitExceeded { index: 1 })
+        );
+        assert_eq!(
+            document.apply_validated(Delta::new().delete(10), &chain),
+            Err(Error::LengthMismatch {
+                index: 0,
+                expected: 9,
+                actual: 10
+            })
+        );
+        assert_eq!(document.len(), 9);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use super::{map_attributes, AttributeKeys, DeltaValidator};
+use crate::{Delta, Document, Error, Seq};
+
+/// Implemented by dynamically typed attributes (e.g.
+/// [`Attributes`](crate::attributes::Attributes)), so that an
+/// [`AttributeSchema`] can check their values. Attribute structs don't need
+/// this, because their fields already have the right types.
+pub trait AttributeValues: AttributeKeys {
+    /// Returns the value of the format with the given key, if it's set or
+    /// removed by these attributes.
+    fn value(&self, key: &str) -> Option<ValueRef<'_>>;
+}
+
+/// Borrowed view of a dynamically typed attribute value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    /// Represents a value that removes a format.
+    Null,
+
+    /// Represents a boolean value.
+    Bool(bool),
+
+    /// Represents an integer value.
+    Int(i64),
+
+    /// Represents a floating point value, or an integer that doesn't fit in an
+    /// `i64`.
+    Float(f64),
+
+    /// Represents a string value.
+    String(&'a str),
+
+    /// Represents an array of values.
+    Array,
+
+    /// Represents a nested map of values.
+    Map,
+}
+
+/// Type (and range) of the values that an [`AttributeSchema`] accepts for a
+/// format. Every type also accepts a null value, which removes the format.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum AttributeType {
+    /// Accepts booleans.
+    Bool,
+
+    /// Accepts integers between `min` and `max` (inclusive).
+    Int {
+        /// Contains the smallest accepted value.
+        min: i64,
+
+        /// Contains the largest accepted value.
+        max: i64,
+    },
+
+    /// Accepts integers and floating point numbers between `min` and `max`
+    /// (inclusive).
+    Float {
+        /// Contains the smallest accepted value.
+        min: f64,
+
+        /// Contains the largest accepted value.
+        max: f64,
+    },
+
+    /// Accepts strings of at most `max_len` characters.
+    String {
+        /// Contains the maximum number of characters.
+        max_len: usize,
+    },
+
+    /// Accepts any of the given strings (e.g. `left`, `center` and `right`).
+    OneOf(Vec<String>),
+
+    /// Accepts nested maps.
+    Map,
+
+    /// Accepts any value.
+    Any,
+}
+
+impl AttributeType {
+    /// Returns a type that accepts integers in the given range.
+    pub fn int(range: RangeInclusive<i64>) -> AttributeType {
+        AttributeType::Int {
+            min: *range.start(),
+            max: *range.end(),
+        }
+    }
+
+    /// Returns a type that accepts numbers in the given range.
+    pub fn float(range: RangeInclusive<f64>) -> AttributeType {
+        AttributeType::Float {
+            min: *range.start(),
+            max: *range.end(),
+        }
+    }
+
+    /// Returns a type that accepts strings of any length.
+    pub fn string() -> AttributeType {
+        AttributeType::String {
+            max_len: usize::MAX,
+        }
+    }
+
+    /// Returns a type that accepts any of the given strings.
+    pub fn one_of<I>(values: I) -> AttributeType
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        AttributeType::OneOf(values.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns true if this type accepts the given value.
+    pub fn matches(&self, value: ValueRef<'_>) -> bool {
+        match (self, value) {
+            (_, ValueRef::Null) | (AttributeType::Any, _) => true,
+            (AttributeType::Bool, ValueRef::Bool(_)) => true,
+            (AttributeType::Int { min, max }, ValueRef::Int(value)) => {
+                (*min..=*max).contains(&value)
+            }
+            (AttributeType::Float { min, max }, ValueRef::Int(value)) => {
+                (*min..=*max).contains(&(value as f64))
+            }
+            (AttributeType::Float { min, max }, ValueRef::Float(value)) => {
+                (*min..=*max).contains(&value)
+            }
+            (AttributeType::String { max_len }, ValueRef::String(value)) => {
+                value.chars().count() <= *max_len
+            }
+            (AttributeType::OneOf(values), ValueRef::String(value)) => {
+                values.iter().any(|allowed| allowed == value)
+            }
+            (AttributeType::Map, ValueRef::Map) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Determines what an [`AttributeSchema`] does with formats that aren't
+/// registered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UnknownAttributes {
+    /// Rejects the entire delta with [`Error::ForbiddenAttribute`].
+    #[default]
+    Reject,
+
+    /// Removes the formats from the delta.
+    Strip,
+
+    /// Passes the formats through unchecked.
+    Pass,
+}
+
+/// Validator that checks the attributes of inserts and retains against the
+/// registered formats, so that untrusted clients can't store arbitrary
+/// attribute payloads in a document. Values of the wrong type or out of range
+/// are rejected with [`Error::InvalidAttribute`], and formats that aren't
+/// registered are handled according to [`UnknownAttributes`].
+///
+/// ```
+/// use kyte::attributes::{AttrValue, Attributes};
+/// use kyte::validate::{AttributeSchema, AttributeType};
+/// use kyte::{Delta, Error};
+///
+/// let schema = AttributeSchema::new()
+///     .with("bold", AttributeType::Bool)
+///     .with("header", AttributeType::int(1..=6));
+///
+/// let header = |value: i64| Attributes::from([("header".to_owned(), AttrValue::Int(value))]);
+///
+/// assert!(schema
+///     .check(Delta::<String, _>::new().retain(5, header(2)))
+///     .is_ok());
+/// assert_eq!(
+///     schema.check(Delta::<String, _>::new().retain(5, header(7))),
+///     Err(Error::InvalidAttribute {
+///         index: 0,
+///         key: "header".to_owned()
+///     })
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttributeSchema {
+    types: BTreeMap<String, AttributeType>,
+    unknown: UnknownAttributes,
+}
+
+impl AttributeSchema {
+    /// Returns a new schema without any formats, which rejects unknown formats.
+    pub fn new() -> AttributeSchema {
+        AttributeSchema::default()
+    }
+
+    /// Returns this schema with the given format registered.
+    pub fn with(mut self, key: impl Into<String>, value: AttributeType) -> AttributeSchema {
+        self.register(key, value);
+        self
+    }
+
+    /// Registers the given format, replacing any earlier type of that format.
+    pub fn register(&mut self, key: impl Into<String>, value: AttributeType) {
+        self.types.insert(key.into(), value);
+    }
+
+    /// Returns this schema with the given policy for unknown formats.
+    pub fn with_unknown(mut self, unknown: UnknownAttributes) -> AttributeSchema {
+        self.unknown = unknown;
+        self
+    }
+
+    /// Returns the type of the given format, if it's registered.
+    pub fn get(&self, key: &str) -> Option<&AttributeType> {
+        self.types.get(key)
+    }
+
+    /// Returns the given delta if its attributes match this schema, with
+    /// unknown formats removed if this schema strips them, or an error
+    /// otherwise. Attributes without any remaining formats are removed
+    /// entirely. Like [`AttributeFilter::filter`](super::AttributeFilter::filter),
+    /// this doesn't need the document.
+    pub fn check<T, A>(&self, change: Delta<T, A>) -> Result<Delta<T, A>, Error>
+    where
+        T: Default + Clone + Seq + Extend<T>,
+        A: AttributeValues + Clone + Default + PartialEq,
+    {
+        map_attributes(change, |index, attributes| {
+            self.check_attributes(index, attributes)
+        })
+    }
+
+    fn check_attributes<A>(&self, index: usize, attributes: Option<A>) -> Result<Option<A>, Error>
+    where
+        A: AttributeValues,
+    {
+        let Some(mut attributes) = attributes else {
+            return Ok(None);
+        };
+
+        let mut unknown = vec![];
+
+        for key in attributes.keys() {
+            match (self.types.get(key), self.unknown) {
+                (Some(value), _) => {
+                    if !attributes
+                        .value(key)
+                        .is_none_or(|actual| value.matches(actual))
+                    {
+                        return Err(Error::InvalidAttribute {
+                            index,
+                            key: key.to_owned(),
+                        });
+                    }
+                }
+                (None, UnknownAttributes::Reject) => {
+                    return Err(Error::ForbiddenAttribute {
+                        index,
+                        key: key.to_owned(),
+                    })
+                }
+                (None, UnknownAttributes::Strip) => unknown.push(key.to_owned()),
+                (None, UnknownAttributes::Pass) => {}
+            }
+        }
+
+        if unknown.is_empty() {
+            return Ok(Some(attributes));
+        }
+
+        for key in unknown {
+            attributes.remove_key(&key);
+        }
+
+        Ok(Some(attributes).filter(|attributes| !attributes.keys().is_empty()))
+    }
+}
+
+impl<T, A> DeltaValidator<T, A> for AttributeSchema
+where
+    T: Default + Clone + Seq + Extend<T>,
+    A: AttributeValues + Clone + Default + PartialEq,
+{
+    fn validate(
+        &self,
+        _document: &Document<T, A>,
+        change: Delta<T, A>,
+    ) -> Result<Delta<T, A>, Error> {
+        self.check(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttributeSchema, AttributeType, UnknownAttributes, ValueRef};
+    use crate::attributes::{AttrValue, Attributes};
+    use crate::{Delta, Error};
+
+    fn attributes<const N: usize>(entries: [(&str, AttrValue); N]) -> Attributes {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let align = AttributeType::one_of(["left", "center", "right"]);
+
+        assert!(align.matches(ValueRef::String("center")));
+        assert!(!align.matches(ValueRef::String("justify")));
+        assert!(align.matches(ValueRef::Null));
+        assert!(AttributeType::float(0.5..=2.0).matches(ValueRef::Int(1)));
+        assert!(!AttributeType::float(0.5..=2.0).matches(ValueRef::Float(f64::NAN)));
+        assert!(!AttributeType::int(1..=6).matches(ValueRef::Float(2.0)));
+        assert!(AttributeType::String { max_len: 2 }.matches(ValueRef::String("éé")));
+        assert!(!AttributeType::String { max_len: 2 }.matches(ValueRef::String("abc")));
+        assert!(!AttributeType::Bool.matches(ValueRef::Map));
+        assert!(AttributeType::Any.matches(ValueRef::Array));
+    }
+
+    #[test]
+    fn test_check() {
+        let schema = AttributeSchema::new()
+            .with("bold", AttributeType::Bool)
+            .with("color", AttributeType::String { max_len: 7 });
+
+        let change = Delta::<String, _>::new()
+            .retain(
+                1,
+                attributes([("bold", true.into()), ("script", "<x>".into())]),
+            )
+            .insert("x".to_owned(), attributes([("color", AttrValue::Null)]))
+            .retain(1, attributes([("onclick", "alert()".into())]));
+
+        assert_eq!(
+            schema.check(change.clone()),
+            Err(Error::ForbiddenAttribute {
+                index: 0,
+                key: "script".to_owned()
+            })
+        );
+        assert_eq!(
+            schema
+                .clone()
+                .with_unknown(UnknownAttributes::Strip)
+                .check(change.clone()),
+            Ok(Delta::new()
+                .retain(1, attributes([("bold", true.into())]))
+                .insert("x".to_owned(), attributes([("color", AttrValue::Null)]))
+                .retain(1, None))
+        );
+        assert_eq!(
+            schema
+                .clone()
+                .with_unknown(UnknownAttributes::Pass)
+                .check(change.clone()),
+            Ok(change)
+        );
+        assert_eq!(
+            schema.check(
+                Delta::<String, _>::new()
+                    .retain(1, None)
+                    .retain(1, attributes([("color", "#ff0000".into())]))
+                    .insert("x".to_owned(), attributes([("color", "#ff000000".into())]))
+            ),
+            Err(Error::InvalidAttribute {
+                index: 2,
+                key: "color".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Reference vectors from Quill's `quill-delta` test suite and a harness that
+//! runs them.
+//!
+//! This module is only available with the `vectors` feature enabled. Each
+//! vector contains JSON deltas (as Quill serializes them) and the result that
+//! `quill-delta` produces for them, for [`Suite::Compose`],
+//! [`Suite::Transform`], [`Suite::TransformPosition`], [`Suite::Diff`] and
+//! [`Suite::Invert`]. Running the vectors with [`run`] checks that an
+//! implementation of [`Harness`] is wire-compatible with Quill. Kyte itself is
+//! checked with [`Kyte`], and forks can implement [`Harness`] for their own
+//! deltas.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::json::{self, JsonAttributes};
+use super::ops::Retain;
+use super::quill::QuillDelta;
+use super::{Compose, Delta, Document, Len, Op, Transform};
+
+/// Group of reference vectors that exercise the same operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Suite {
+    /// Vectors for `Delta.compose`.
+    Compose,
+
+    /// Vectors for `Delta.transform`.
+    Transform,
+
+    /// Vectors for `Delta.transformPosition`.
+    TransformPosition,
+
+    /// Vectors for `Delta.diff`.
+    Diff,
+
+    /// Vectors for `Delta.invert`.
+    Invert,
+}
+
+/// Implementation of Quill's delta operations on JSON deltas, which the
+/// reference vectors are run against.
+///
+/// Harnesses that don't implement an operation return
+/// [`HarnessError::Unsupported`], which skips the vector. [`Harness::diff`]
+/// and [`Harness::invert`] do so by default.
+pub trait Harness {
+    /// Returns the composition of `a` and `b`.
+    fn compose(&self, a: &Value, b: &Value) -> Result<Value, HarnessError>;
+
+    /// Returns `b` transformed against `a`, where `a` has priority if
+    /// `priority` is true.
+    fn transform(&self, a: &Value, b: &Value, priority: bool) -> Result<Value, HarnessError>;
+
+    /// Returns the given index transformed against the given delta.
+    fn transform_position(
+        &self,
+        delta: &Value,
+        index: usize,
+        priority: bool,
+    ) -> Result<usize, HarnessError>;
+
+    /// Returns the delta that turns document `a` into document `b`, or `None`
+    /// if either isn't a document.
+    fn diff(&self, a: &Value, b: &Value) -> Result<Option<Value>, HarnessError> {
+        let _ = (a, b);
+        Err(HarnessError::Unsupported)
+    }
+
+    /// Returns the delta that undoes applying `delta` to the document `base`.
+    fn invert(&self, delta: &Value, base: &Value) -> Result<Value, HarnessError> {
+        let _ = (delta, base);
+        Err(HarnessError::Unsupported)
+    }
+}
+
+/// Error that a [`Harness`] returns instead of the result of a vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HarnessError {
+    /// Indicates that the harness doesn't implement the operation of the
+    /// vector, which is skipped.
+    Unsupported,
+
+    /// Indicates that the harness can't handle the inputs of the vector (e.g.
+    /// a delta that it can't parse), which fails.
+    Invalid(String),
+}
+
+/// Vector whose expected result didn't match the result of a harness.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+    /// Contains the suite of this vector.
+    pub suite: Suite,
+
+    /// Contains the name of this vector.
+    pub name: String,
+
+    /// Contains the result that `quill-delta` produces.
+    pub expected: Value,
+
+    /// Contains the result that the harness produced, or the reason why it
+    /// couldn't handle the inputs of this vector.
+    pub actual: Result<Value, String>,
+}
+
+/// Outcome of running the reference vectors with [`run`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// Contains the number of vectors whose results matched.
+    pub passed: usize,
+
+    /// Contains the suites and names of the vectors that were skipped because
+    /// the harness doesn't implement their operation.
+    pub skipped: Vec<(Suite, String)>,
+
+    /// Contains the vectors whose results didn't match.
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    /// Returns true if none of the vectors failed.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct Vector<I, O> {
+    name: String,
+    #[serde(flatten)]
+    input: I,
+    expected: O,
+}
+
+#[derive(Deserialize)]
+struct Pair {
+    a: Value,
+    b: Value,
+}
+
+#[derive(Deserialize)]
+struct TransformInput {
+    a: Value,
+    b: Value,
+    priority: bool,
+}
+
+#[derive(Deserialize)]
+struct PositionInput {
+    delta: Value,
+    index: usize,
+    priority: bool,
+}
+
+#[derive(Deserialize)]
+struct InvertInput {
+    delta: Value,
+    base: Value,
+}
+
+/// Runs all reference vectors with the given harness.
+pub fn run<H>(harness: &H) -> Report
+where
+    H: Harness + ?Sized,
+{
+    let mut report = Report::default();
+
+    run_suite(
+        &mut report,
+        Suite::Compose,
+        include_str!("../tests/fixtures/quill/compose.json"),
+        |input: Pair| harness.compose(&input.a, &input.b),
+    );
+    run_suite(
+        &mut report,
+        Suite::Transform,
+        include_str!("../tests/fixtures/quill/transform.json"),
+        |input: TransformInput| harness.transform(&input.a, &input.b, input.priority),
+    );
+    run_suite(
+        &mut report,
+        Suite::TransformPosition,
+        include_str!("../tests/fixtures/quill/transform_position.json"),
+        |input: PositionInput| {
+            harness
+                .transform_position(&input.delta, input.index, input.priority)
+                .map(Value::from)
+        },
+    );
+    run_suite(
+        &mut report,
+        Suite::Diff,
+        include_str!("../tests/fixtures/quill/diff.json"),
+        |input: Pair| Ok(harness.diff(&input.a, &input.b)?.unwrap_or_default()),
+    );
+    run_suite(
+        &mut report,
+        Suite::Invert,
+        include_str!("../tests/fixtures/quill/invert.json"),
+        |input: InvertInput| harness.invert(&input.delta, &input.base),
+    );
+
+    report
+}
+
+fn run_suite<I, F>(report: &mut Report, suite: Suite, json: &str, run: F)
+where
+    I: DeserializeOwned,
+    F: Fn(I) -> Result<Value, HarnessError>,
+{
+    let vectors: Vec<Vector<I, Value>> =
+        serde_json::from_str(json).expect("reference vectors are valid");
+
+    for vector in vectors {
+        let actual = match run(vector.input) {
+            Ok(actual) if actual == vector.expected => {
+                report.passed += 1;
+                continue;
+            }
+            Ok(actual) => Ok(actual),
+            Err(HarnessError::Unsupported) => {
+                report.skipped.push((suite, vector.name));
+                continue;
+            }
+            Err(HarnessError::Invalid(error)) => Err(error),
+        };
+
+        report.failures.push(Failure {
+            suite,
+            name: vector.name,
+            expected: vector.expected,
+            actual,
+        });
+    }
+}
+
+/// Harness that runs the reference vectors against kyte's [`QuillDelta`].
+///
+/// [`Suite::Diff`] is only supported with the `similar` feature enabled, and
+/// skipped otherwise.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Kyte;
+
+impl Kyte {
+    fn parse(value: &Value) -> Result<QuillDelta, HarnessError> {
+        serde_json::from_value(value.clone())
+            .map_err(|error| HarnessError::Invalid(error.to_string()))
+    }
+
+    fn to_value(delta: QuillDelta) -> Result<Value, HarnessError> {
+        serde_json::to_value(delta).map_err(|error| HarnessError::Invalid(error.to_string()))
+    }
+}
+
+impl Harness for Kyte {
+    fn compose(&self, a: &Value, b: &Value) -> Result<Value, HarnessError> {
+        Kyte::to_value(Kyte::parse(a)?.compose(Kyte::parse(b)?))
+    }
+
+    fn transform(&self, a: &Value, b: &Value, priority: bool) -> Result<Value, HarnessError> {
+        Kyte::to_value(Kyte::parse(a)?.transform(Kyte::parse(b)?, priority))
+    }
+
+    fn transform_position(
+        &self,
+        delta: &Value,
+        index: usize,
+        priority: bool,
+    ) -> Result<usize, HarnessError> {
+        Ok((&Kyte::parse(delta)?).transform(index, priority))
+    }
+
+    fn diff(&self, a: &Value, b: &Value) -> Result<Option<Value>, HarnessError> {
+        let (Ok(a), Ok(b)) = (
+            Document::from_delta(Kyte::parse(a)?),
+            Document::from_delta(Kyte::parse(b)?),
+        ) else {
+            return Ok(None);
+        };
+
+        #[cfg(feature = "similar")]
+        return Kyte::to_value(a.as_delta().diff(b.as_delta())).map(Some);
+
+        #[cfg(not(feature = "similar"))]
+        {
+            let _ = (a, b);
+            Err(HarnessError::Unsupported)
+        }
+    }
+
+    fn invert(&self, delta: &Value, base: &Value) -> Result<Value, HarnessError> {
+        let base = Document::from_delta(Kyte::parse(base)?)
+            .map_err(|error| HarnessError::Invalid(error.to_string()))?;
+
+        Kyte::to_value(invert(Kyte::parse(delta)?, &base))
+    }
+}
+
+/// Returns the delta that undoes applying the given delta to the given
+/// document, like Quill's `Delta.invert`.
+fn invert(delta: QuillDelta, base: &Document<String, JsonAttributes>) -> QuillDelta {
+    let elements = base
+        .as_delta()
+        .ops()
+        .flat_map(|op| match op {
+            Op::Insert(insert) => insert
+                .insert()
+                .chars()
+                .map(|char| (char, insert.attributes.clone()))
+                .collect(),
+            Op::Retain(_) | Op::Delete(_) => vec![],
+        })
+        .collect::<Vec<_>>();
+
+    let mut elements = elements.into_iter();
+    let mut inverted = Delta::new();
+
+    for op in delta {
+        let len = op.len();
+
+        inverted = match op {
+            Op::Insert(_) => inverted.delete(len),
+            Op::Retain(Retain {
+                attributes: None, ..
+            }) => {
+                elements.by_ref().take(len).for_each(drop);
+                inverted.retain(len, None)
+            }
+            Op::Retain(Retain {
+                attributes: Some(attributes),
+                ..
+            }) => elements
+                .by_ref()
+                .take(len)
+                .fold(inverted, |inverted, (_, base)| {
+                    let base = base.unwrap_or_default();
+                    let attributes = json::invert(&attributes, &base);
+                    inverted.retain(
+                        1,
+                        Some(attributes).filter(|attributes| !attributes.is_empty()),
+                    )
+                }),
+            Op::Delete(_) => elements
+                .by_ref()
+                .take(len)
+                .fold(inverted, |inverted, (char, base)| {
+                    inverted.insert(char.to_string(), base)
+                }),
+        };
+    }
+
+    inverted.chop()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{run, Harness, HarnessError, Kyte, Suite};
+
+    #[test]
+    fn test_kyte() {
+        let report = run(&Kyte);
+
+        // QuillDelta only supports text inserts.
+        assert_eq!(
+            report
+                .failures
+                .iter()
+                .map(|failure| (failure.suite, failure.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Suite::Compose, "insert embed"),
+                (Suite::Compose, "retain empty embed"),
+                (Suite::Compose, "remove all embed attributes"),
+            ]
+        );
+        assert!(report.passed > 0);
+
+        #[cfg(feature = "similar")]
+        assert_eq!(report.skipped, vec![]);
+
+        #[cfg(not(feature = "similar"))]
+        assert!(report
+            .skipped
+            .iter()
+            .all(|(suite, _)| *suite == Suite::Diff));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        struct Compose;
+
+        impl Harness for Compose {
+            fn compose(&self, a: &Value, b: &Value) -> Result<Value, HarnessError> {
+                Kyte.compose(a, b)
+            }
+
+            fn transform(&self, _: &Value, _: &Value, _: bool) -> Result<Value, HarnessError> {
+                Err(HarnessError::Unsupported)
+            }
+
+            fn transform_position(
+                &self,
+                _: &Value,
+                _: usize,
+                _: bool,
+            ) -> Result<usize, HarnessError> {
+                Err(HarnessError::Invalid("not a delta".to_owned()))
+            }
+        }
+
+        let report = run(&Compose);
+
+        assert!(report.passed > 0);
+        assert!(report
+            .failures
+            .iter()
+            .filter(|failure| failure.suite != Suite::Compose)
+            .all(|failure| failure.suite == Suite::TransformPosition
+                && failure.actual == Err("not a delta".to_owned())));
+        assert!(report
+            .skipped
+            .iter()
+            .any(|(suite, _)| *suite == Suite::Invert));
+    }
+}
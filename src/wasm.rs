@@ -0,0 +1,198 @@
+//! Bindings that expose [`QuillDelta`] to JavaScript with
+//! [wasm-bindgen](https://docs.rs/wasm-bindgen).
+//!
+//! This module is only available with the `wasm` feature enabled. It exports a
+//! `Delta` class whose methods mirror those of `quill-delta` (`insert`,
+//! `retain`, `delete`, `compose`, `transform` and `transformPosition`), so that
+//! a browser client can run the same implementation of operational
+//! transformation as a Rust server. Deltas and attributes cross the boundary
+//! as JSON strings in the format that Quill produces, or directly as the
+//! JavaScript objects that Quill uses (see [`to_js`] and [`from_js`]).
+//!
+//! ```js
+//! import { Delta } from "kyte";
+//!
+//! const a = Delta.fromJson('{"ops":[{"insert":"Hello"}]}');
+//! const b = new Delta();
+//! b.retain(5);
+//! b.insert("!", '{"bold":true}');
+//!
+//! console.log(a.compose(b).toJson());
+//!
+//! // Quill's own delta objects don't need to be stringified.
+//! const c = Delta.fromJs(quill.getContents());
+//! quill.setContents(c.toJs());
+//! ```
+
+use std::mem::take;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::prelude::*;
+
+use super::json::JsonAttributes;
+use super::quill::QuillDelta;
+use super::{Compose, Delta, Len, Transform};
+
+/// Delta with text values and JSON attributes, which is exported to JavaScript
+/// as `Delta`.
+#[wasm_bindgen(js_name = Delta)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WasmDelta(QuillDelta);
+
+#[wasm_bindgen(js_class = Delta)]
+impl WasmDelta {
+    /// Returns a new empty delta.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmDelta {
+        WasmDelta::default()
+    }
+
+    /// Parses a delta from the JSON that Quill produces (i.e. an object with an
+    /// `ops` field or an array of ops).
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmDelta, JsError> {
+        Ok(WasmDelta(serde_json::from_str(json)?))
+    }
+
+    /// Returns the JSON representation of this delta, which Quill can parse.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("delta serializes")
+    }
+
+    /// Converts a delta from a JavaScript object with the same structure as
+    /// its JSON representation, such as a `Delta` of `quill-delta` (see
+    /// [`from_js`]).
+    #[wasm_bindgen(js_name = fromJs)]
+    pub fn from_js(value: JsValue) -> Result<WasmDelta, JsError> {
+        from_js(value).map(WasmDelta)
+    }
+
+    /// Converts this delta into a plain JavaScript object, which Quill accepts
+    /// wherever it accepts a `Delta` (see [`to_js`]).
+    #[wasm_bindgen(js_name = toJs)]
+    pub fn to_js(&self) -> Result<JsValue, JsError> {
+        to_js(&self.0)
+    }
+
+    /// Appends an insert of the given text with the given attributes (as a
+    /// JSON object) to this delta.
+    pub fn insert(&mut self, text: String, attributes: Option<String>) -> Result<(), JsError> {
+        let attributes = parse_attributes(attributes)?;
+        self.0 = take(&mut self.0).insert(text, attributes);
+        Ok(())
+    }
+
+    /// Appends a retain of the given length with the given attributes (as a
+    /// JSON object) to this delta.
+    pub fn retain(&mut self, len: usize, attributes: Option<String>) -> Result<(), JsError> {
+        let attributes = parse_attributes(attributes)?;
+        self.0 = take(&mut self.0).retain(len, attributes);
+        Ok(())
+    }
+
+    /// Appends a delete of the given length to this delta.
+    pub fn delete(&mut self, len: usize) {
+        self.0 = take(&mut self.0).delete(len);
+    }
+
+    /// Returns the composition of this delta and the given delta, which
+    /// applies after this delta.
+    pub fn compose(&self, other: &WasmDelta) -> WasmDelta {
+        WasmDelta(self.0.clone().compose(other.0.clone()))
+    }
+
+    /// Returns the given delta transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    pub fn transform(&self, other: &WasmDelta, priority: bool) -> WasmDelta {
+        WasmDelta(self.0.clone().transform(other.0.clone(), priority))
+    }
+
+    /// Returns the given index transformed against this delta, where this
+    /// delta has priority if `priority` is true.
+    #[wasm_bindgen(js_name = transformPosition)]
+    pub fn transform_position(&self, index: usize, priority: bool) -> usize {
+        (&self.0).transform(index, priority)
+    }
+}
+
+impl WasmDelta {
+    /// Returns the delta that this binding wraps.
+    pub fn into_inner(self) -> QuillDelta {
+        self.0
+    }
+}
+
+impl From<QuillDelta> for WasmDelta {
+    fn from(delta: QuillDelta) -> Self {
+        WasmDelta(delta)
+    }
+}
+
+/// Converts the given delta into a JavaScript object with the same structure as
+/// its JSON representation (i.e. `{ ops: [...] }`), without an intermediate
+/// JSON string. Attributes and embeds that serialize as maps become plain
+/// objects (rather than `Map`s), so that Quill can consume the result directly.
+pub fn to_js<T, A>(delta: &Delta<T, A>) -> Result<JsValue, JsError>
+where
+    T: Serialize,
+    A: Serialize,
+{
+    Ok(delta.serialize(&Serializer::json_compatible())?)
+}
+
+/// Converts a delta from a JavaScript object with the same structure as its
+/// JSON representation, such as a `Delta` of `quill-delta` or the result of
+/// `quill.getContents()`. Embeds (e.g. `{ insert: { image: "..." } }`) are
+/// supported if `T` deserializes from them.
+pub fn from_js<T, A>(value: JsValue) -> Result<Delta<T, A>, JsError>
+where
+    T: Len + DeserializeOwned,
+    A: DeserializeOwned,
+{
+    Ok(serde_wasm_bindgen::from_value(value)?)
+}
+
+fn parse_attributes(attributes: Option<String>) -> Result<Option<JsonAttributes>, JsError> {
+    Ok(attributes
+        .map(|attributes| serde_json::from_str(&attributes))
+        .transpose()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::WasmDelta;
+
+    #[test]
+    fn test_compose() {
+        let mut a = WasmDelta::new();
+        a.insert("Hello".to_owned(), None).unwrap();
+
+        let mut b = WasmDelta::new();
+        b.retain(5, None).unwrap();
+        b.insert("!".to_owned(), Some(r#"{"bold":true}"#.to_owned()))
+            .unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&a.compose(&b).to_json()).unwrap(),
+            json!({ "ops": [{ "insert": "Hello" }, { "insert": "!", "attributes": { "bold": true } }] })
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let a = WasmDelta::from_json(r#"{"ops":[{"insert":"A"}]}"#).unwrap();
+        let b = WasmDelta::from_json(r#"[{"retain":1},{"delete":1}]"#).unwrap();
+
+        assert_eq!(
+            a.transform(&b, true),
+            WasmDelta::from_json(r#"[{"retain":2},{"delete":1}]"#).unwrap()
+        );
+        assert_eq!(a.transform_position(0, false), 1);
+        assert_eq!(b.transform_position(2, false), 1);
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "quill")]
+
+use kyte::quill::QuillDelta;
+use kyte::{Compose, Transform};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Upstream compose and transform vectors that kyte is known to disagree
+/// with, together with the reason. These are checked to still fail, so that
+/// they're removed from these lists once kyte agrees with Quill.
+const KNOWN_COMPOSE_FAILURES: &[(&str, &str)] = &[
+    ("insert embed", "QuillDelta only supports text inserts"),
+    (
+        "retain empty embed",
+        "QuillDelta only supports text inserts",
+    ),
+    (
+        "remove all embed attributes",
+        "QuillDelta only supports text inserts",
+    ),
+];
+
+const KNOWN_TRANSFORM_FAILURES: &[(&str, &str)] = &[];
+
+#[derive(Deserialize)]
+struct ComposeFixture {
+    name: String,
+    a: Value,
+    b: Value,
+    expected: Value,
+}
+
+#[derive(Deserialize)]
+struct TransformFixture {
+    name: String,
+    a: Value,
+    b: Value,
+    priority: bool,
+    expected: Value,
+}
+
+fn parse(value: &Value) -> Result<QuillDelta, String> {
+    let delta = serde_json::from_value::<QuillDelta>(value.clone()).map_err(|e| e.to_string())?;
+
+    match &serde_json::to_value(&delta).unwrap() == value {
+        true => Ok(delta),
+        false => Err(format!("serde round-trip of {value}")),
+    }
+}
+
+fn check(known: &[(&str, &str)], name: &str, actual: Result<QuillDelta, String>, expected: &Value) {
+    let actual = actual.map(|delta| serde_json::to_value(delta).unwrap());
+
+    match known.iter().find(|(known, _)| *known == name) {
+        Some((_, reason)) => assert_ne!(
+            actual.as_ref(),
+            Ok(expected),
+            "{name}: known failure ({reason}) passes now"
+        ),
+        None => assert_eq!(actual.as_ref(), Ok(expected), "{name}"),
+    }
+}
+
+#[test]
+fn test_quill_compose() {
+    let fixtures: Vec<ComposeFixture> =
+        serde_json::from_str(include_str!("fixtures/quill/compose.json")).unwrap();
+
+    for fixture in fixtures {
+        let actual = parse(&fixture.a).and_then(|a| Ok(a.compose(parse(&fixture.b)?)));
+
+        check(
+            KNOWN_COMPOSE_FAILURES,
+            &fixture.name,
+            actual,
+            &fixture.expected,
+        );
+    }
+}
+
+#[test]
+fn test_quill_transform() {
+    let fixtures: Vec<TransformFixture> =
+        serde_json::from_str(include_str!("fixtures/quill/transform.json")).unwrap();
+
+    for fixture in fixtures {
+        let actual =
+            parse(&fixture.a).and_then(|a| Ok(a.transform(parse(&fixture.b)?, fixture.priority)));
+
+        check(
+            KNOWN_TRANSFORM_FAILURES,
+            &fixture.name,
+            actual,
+            &fixture.expected,
+        );
+    }
+}